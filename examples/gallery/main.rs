@@ -0,0 +1,317 @@
+//! Runs the single window examples in one app, sharing the device and window. Switch between
+//! them from the on-screen menu, with number keys or Tab.
+//!
+//! `multiple_windows` and `egui_gui` open windows of their own and `headless_compute` runs
+//! without one, so they aren't part of the gallery.
+
+#[allow(dead_code)]
+#[path = "../game_of_life/main.rs"]
+mod game_of_life;
+#[allow(dead_code)]
+#[path = "../hello_world.rs"]
+mod hello_world;
+#[allow(dead_code)]
+#[path = "../lines.rs"]
+mod lines;
+#[allow(dead_code)]
+#[path = "../mesh.rs"]
+mod mesh;
+#[allow(dead_code)]
+#[path = "../quad/main.rs"]
+mod quad;
+#[allow(dead_code)]
+#[path = "../sand/main.rs"]
+mod sand_sim;
+#[allow(dead_code)]
+#[path = "../shader_with_includes/main.rs"]
+mod shader_with_includes;
+#[allow(dead_code)]
+#[path = "../triangle/main.rs"]
+mod triangle;
+
+use std::{path::PathBuf, time::Duration};
+
+use glass::{
+    device_context::DeviceConfig, egui_utils::EguiGui, gpu_error::GpuError, gui::GuiBackend,
+    input::TextEvent, watchdog::HangInfo, window::WindowConfig, Glass, GlassApp, GlassConfig,
+    GlassContext, GlassError, RenderData,
+};
+use wgpu::{CommandBuffer, Features, Limits, TextureFormat};
+use winit::{
+    event::{DeviceEvent, DeviceId, ElementState, WindowEvent},
+    event_loop::ActiveEventLoop,
+    keyboard::{KeyCode, PhysicalKey},
+    window::WindowId,
+};
+
+const WIDTH: u32 = 1920;
+const HEIGHT: u32 = 1080;
+
+/// Name and constructor of an example
+type Example = (&'static str, fn(&mut GlassContext) -> Box<dyn GlassApp>);
+
+const EXAMPLES: &[Example] = &[
+    ("Hello World", |_| Box::new(hello_world::HelloWorld)),
+    ("Triangle", |_| Box::new(triangle::TriangleApp::default())),
+    ("Quad", |_| Box::new(quad::TreeApp::default())),
+    ("Line Draw", |_| Box::new(lines::LineApp::default())),
+    ("Mesh", |_| Box::new(mesh::MeshApp::default())),
+    ("Shader With Includes", |_| {
+        Box::new(shader_with_includes::TriangleApp::default())
+    }),
+    ("Game Of Life", |_| {
+        Box::new(game_of_life::GameOfLifeApp::default())
+    }),
+    ("Sand Sim", |_| Box::new(sand_sim::SandApp::default())),
+];
+
+fn config() -> GlassConfig {
+    GlassConfig {
+        // Union of what the bundled examples need
+        device_config: DeviceConfig {
+            limits: Limits {
+                max_push_constant_size: 128,
+                ..Default::default()
+            },
+            features: Features::PUSH_CONSTANTS | Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
+            ..DeviceConfig::performance()
+        },
+        window_configs: vec![WindowConfig {
+            width: WIDTH,
+            height: HEIGHT,
+            exit_on_esc: true,
+            depth_format: Some(TextureFormat::Depth32Float),
+            ..WindowConfig::default()
+        }],
+        fixed_timestep: Some(Duration::from_secs_f64(
+            1.0 / game_of_life::GENERATIONS_PER_SECOND,
+        )),
+        time_hotkeys: true,
+        ..GlassConfig::default()
+    }
+}
+
+fn main() -> Result<(), GlassError> {
+    Glass::run(config(), |_| {
+        Box::new(GalleryApp {
+            current: 0,
+            pending: None,
+            app: None,
+            menu: EguiGui::new(),
+        })
+    })
+}
+
+/// Forwards every [`GlassApp`] hook to the active example
+struct GalleryApp {
+    current: usize,
+    /// Picked from the menu during update, switched to once the event loop is at hand
+    pending: Option<usize>,
+    app: Option<Box<dyn GlassApp>>,
+    menu: EguiGui,
+}
+
+impl GalleryApp {
+    fn switch(&mut self, event_loop: &ActiveEventLoop, context: &mut GlassContext, index: usize) {
+        if let Some(mut app) = self.app.take() {
+            app.end(context);
+        }
+        self.current = index % EXAMPLES.len();
+        let (name, create) = EXAMPLES[self.current];
+        let mut app = create(context);
+        app.start(event_loop, context);
+        self.app = Some(app);
+        context.primary_render_window().window().set_title(&format!(
+            "Glass Gallery - {} ({}/{}, 1-{} or Tab to switch)",
+            name,
+            self.current + 1,
+            EXAMPLES.len(),
+            EXAMPLES.len()
+        ));
+    }
+
+    fn menu(&mut self, context: &GlassContext) {
+        let window_id = context.primary_render_window().window().id();
+        let Some(window) = self.menu.window(window_id) else {
+            return;
+        };
+        egui::Window::new("Examples")
+            .resizable(false)
+            .show(window.context(), |ui| {
+                for (index, (name, _)) in EXAMPLES.iter().enumerate() {
+                    let label = format!("{} {}", index + 1, name);
+                    if ui.selectable_label(index == self.current, label).clicked() {
+                        self.pending = Some(index);
+                    }
+                }
+            });
+    }
+
+    fn picked_example(&self, event: &WindowEvent) -> Option<usize> {
+        let WindowEvent::KeyboardInput {
+            event, ..
+        } = event
+        else {
+            return None;
+        };
+        if event.state != ElementState::Pressed || event.repeat {
+            return None;
+        }
+        let PhysicalKey::Code(key) = event.physical_key else {
+            return None;
+        };
+        let digits = [
+            KeyCode::Digit1,
+            KeyCode::Digit2,
+            KeyCode::Digit3,
+            KeyCode::Digit4,
+            KeyCode::Digit5,
+            KeyCode::Digit6,
+            KeyCode::Digit7,
+            KeyCode::Digit8,
+            KeyCode::Digit9,
+        ];
+        if key == KeyCode::Tab {
+            Some(self.current + 1)
+        } else {
+            digits
+                .iter()
+                .position(|digit| *digit == key)
+                .filter(|index| *index < EXAMPLES.len())
+        }
+    }
+}
+
+impl GlassApp for GalleryApp {
+    fn start(&mut self, event_loop: &ActiveEventLoop, context: &mut GlassContext) {
+        let window_id = context.primary_render_window().window().id();
+        self.menu.add_window(event_loop, context, window_id);
+        self.switch(event_loop, context, 0);
+    }
+
+    fn before_input(&mut self, context: &mut GlassContext, event_loop: &ActiveEventLoop) {
+        if let Some(index) = self.pending.take() {
+            self.switch(event_loop, context, index);
+        }
+        if let Some(app) = &mut self.app {
+            app.before_input(context, event_loop);
+        }
+    }
+
+    fn device_input(
+        &mut self,
+        context: &mut GlassContext,
+        event_loop: &ActiveEventLoop,
+        device_id: DeviceId,
+        event: &DeviceEvent,
+    ) {
+        if let Some(app) = &mut self.app {
+            app.device_input(context, event_loop, device_id, event);
+        }
+    }
+
+    fn window_input(
+        &mut self,
+        context: &mut GlassContext,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: &WindowEvent,
+    ) {
+        if let Some(index) = self.picked_example(event) {
+            self.switch(event_loop, context, index);
+            return;
+        }
+        if let Some(app) = &mut self.app {
+            app.window_input(context, event_loop, window_id, event);
+        }
+    }
+
+    fn text_input(&mut self, context: &mut GlassContext, window_id: WindowId, event: TextEvent) {
+        if let Some(app) = &mut self.app {
+            app.text_input(context, window_id, event);
+        }
+    }
+
+    // The gallery examples have no UI of their own, the runner renders the menu over them
+    fn gui(&mut self) -> Option<&mut dyn GuiBackend> {
+        Some(&mut self.menu)
+    }
+
+    fn file_dropped(&mut self, context: &mut GlassContext, window_id: WindowId, path: PathBuf) {
+        if let Some(app) = &mut self.app {
+            app.file_dropped(context, window_id, path);
+        }
+    }
+
+    fn file_hovered(&mut self, context: &mut GlassContext, window_id: WindowId, path: PathBuf) {
+        if let Some(app) = &mut self.app {
+            app.file_hovered(context, window_id, path);
+        }
+    }
+
+    fn file_hover_cancelled(&mut self, context: &mut GlassContext, window_id: WindowId) {
+        if let Some(app) = &mut self.app {
+            app.file_hover_cancelled(context, window_id);
+        }
+    }
+
+    fn fixed_update(&mut self, context: &mut GlassContext) {
+        if let Some(app) = &mut self.app {
+            app.fixed_update(context);
+        }
+    }
+
+    fn update(&mut self, context: &mut GlassContext) {
+        self.menu(context);
+        if let Some(app) = &mut self.app {
+            app.update(context);
+        }
+    }
+
+    fn render(
+        &mut self,
+        context: &GlassContext,
+        render_data: RenderData,
+    ) -> Option<Vec<CommandBuffer>> {
+        self.app
+            .as_mut()
+            .and_then(|app| app.render(context, render_data))
+    }
+
+    fn on_hang(&mut self, context: &mut GlassContext, hang: &HangInfo) {
+        if let Some(app) = &mut self.app {
+            app.on_hang(context, hang);
+        }
+    }
+
+    fn gpu_error(&mut self, context: &mut GlassContext, error: &GpuError) {
+        if let Some(app) = &mut self.app {
+            app.gpu_error(context, error);
+        }
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        self.app
+            .as_ref()
+            .map(|app| app.save_state())
+            .unwrap_or_default()
+    }
+
+    fn load_state(&mut self, state: &[u8]) {
+        if let Some(app) = &mut self.app {
+            app.load_state(state);
+        }
+    }
+
+    fn end_of_frame(&mut self, context: &mut GlassContext) {
+        if let Some(app) = &mut self.app {
+            app.end_of_frame(context);
+        }
+    }
+
+    fn end(&mut self, context: &mut GlassContext) {
+        if let Some(app) = &mut self.app {
+            app.end(context);
+        }
+    }
+}
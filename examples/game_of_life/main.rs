@@ -1,21 +1,23 @@
-use std::{borrow::Cow, time::Instant};
+use std::{borrow::Cow, sync::Arc, time::Duration};
 
 use bytemuck::{Pod, Zeroable};
 use glam::Vec2;
 use glass::{
+    camera::Camera2D,
     device_context::DeviceConfig,
-    pipelines::QuadPipeline,
-    texture::Texture,
-    window::{GlassWindow, WindowConfig},
+    pipelines::{FrameBlendPipeline, QuadPipeline},
+    texture::{PingPong, PingPongBindGroups, Texture},
+    window::{Viewport, WindowConfig},
     Glass, GlassApp, GlassConfig, GlassContext, GlassError, RenderData,
 };
 use wgpu::{
-    Backends, BindGroup, BindGroupDescriptor, CommandBuffer, CommandEncoder, ComputePassDescriptor,
-    ComputePipeline, ComputePipelineDescriptor, Extent3d, InstanceFlags, Limits, MemoryHints,
-    PowerPreference, PresentMode, PushConstantRange, ShaderStages, StorageTextureAccess, StoreOp,
-    TextureFormat, TextureUsages,
+    Backends, BindGroup, BindGroupDescriptor, BindGroupLayout, CommandBuffer, CommandEncoder,
+    ComputePassDescriptor, ComputePipeline, ComputePipelineDescriptor, Extent3d, InstanceFlags,
+    Limits, MemoryHints, PowerPreference, PresentMode, PushConstantRange, ShaderStages,
+    StorageTextureAccess, StoreOp, TextureFormat, TextureUsages,
 };
 use winit::{
+    dpi::PhysicalPosition,
     event::{ElementState, MouseButton, WindowEvent},
     event_loop::ActiveEventLoop,
     window::WindowId,
@@ -23,15 +25,9 @@ use winit::{
 
 const WIDTH: u32 = 1024;
 const HEIGHT: u32 = 1024;
-const FPS_60: f32 = 16.0 / 1000.0;
-#[rustfmt::skip]
-const OPENGL_TO_WGPU: glam::Mat4 = glam::Mat4::from_cols_array(&[
-    1.0, 0.0, 0.0, 0.0,
-    0.0, 1.0, 0.0, 0.0,
-    0.0, 0.0, 0.5, 0.0,
-    0.0, 0.0, 0.5, 1.0,
-]);
-
+/// Generations per second. Presentation blends the two latest generations, so the simulation
+/// doesn't strobe on displays refreshing faster than this.
+pub const GENERATIONS_PER_SECOND: f64 = 60.0;
 fn config() -> GlassConfig {
     GlassConfig {
         device_config: DeviceConfig {
@@ -39,6 +35,7 @@ fn config() -> GlassConfig {
             memory_hints: MemoryHints::Performance,
             features: wgpu::Features::PUSH_CONSTANTS
                 | wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
+            optional_features: wgpu::Features::empty(),
             limits: Limits {
                 max_push_constant_size: 128,
                 ..Limits::default()
@@ -46,6 +43,8 @@ fn config() -> GlassConfig {
             backends: Backends::all(),
             instance_flags: InstanceFlags::from_build_config(),
             trace_path: None,
+            pipeline_cache_path: None,
+            default_sampler_anisotropy: 1,
         },
         window_configs: vec![WindowConfig {
             width: WIDTH,
@@ -54,6 +53,8 @@ fn config() -> GlassConfig {
             present_mode: PresentMode::AutoNoVsync,
             ..WindowConfig::default()
         }],
+        fixed_timestep: Some(Duration::from_secs_f64(1.0 / GENERATIONS_PER_SECOND)),
+        ..GlassConfig::default()
     }
 }
 
@@ -71,17 +72,24 @@ impl GlassApp for GameOfLifeApp {
         // Create pipelines
         let (init_pipeline, game_of_life_pipeline, draw_pipeline) =
             create_game_of_life_pipeline(context);
-        let quad_pipeline = QuadPipeline::new(context.device(), wgpu::ColorTargetState {
-            format: GlassWindow::default_surface_format(),
-            blend: Some(wgpu::BlendState {
-                color: wgpu::BlendComponent::OVER,
-                alpha: wgpu::BlendComponent::OVER,
-            }),
-            write_mask: wgpu::ColorWrites::ALL,
-        });
+        let quad_pipeline = QuadPipeline::new(
+            context.device_context(),
+            wgpu::ColorTargetState {
+                format: context.primary_render_window().surface_format(),
+                blend: Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent::OVER,
+                    alpha: wgpu::BlendComponent::OVER,
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            },
+            1,
+        );
+        let frame_blend_pipeline =
+            FrameBlendPipeline::new(context.device_context(), TextureFormat::Rgba16Float).unwrap();
         self.data = Some(create_canvas_data(
             context,
             &quad_pipeline,
+            &frame_blend_pipeline,
             &init_pipeline,
             &draw_pipeline,
         ));
@@ -89,6 +97,7 @@ impl GlassApp for GameOfLifeApp {
         self.game_of_life_pipeline = Some(game_of_life_pipeline);
         self.draw_pipeline = Some(draw_pipeline);
         self.quad_pipeline = Some(quad_pipeline);
+        self.frame_blend_pipeline = Some(frame_blend_pipeline);
         init_game_of_life(self, context);
     }
 
@@ -102,94 +111,70 @@ impl GlassApp for GameOfLifeApp {
         handle_inputs(self, event);
     }
 
+    fn fixed_update(&mut self, context: &mut GlassContext) {
+        let mut encoder =
+            context
+                .device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Generation"),
+                });
+        update_game_of_life(self, context, &mut encoder);
+        context.queue().submit(Some(encoder.finish()));
+    }
+
     fn update(&mut self, context: &mut GlassContext) {
         run_update(self, context);
     }
 
     fn render(
         &mut self,
-        _context: &GlassContext,
+        context: &GlassContext,
         render_data: RenderData,
     ) -> Option<Vec<CommandBuffer>> {
-        render(self, render_data)
+        render(self, context, render_data)
     }
 }
 
-struct GameOfLifeApp {
+#[derive(Default)]
+pub struct GameOfLifeApp {
     quad_pipeline: Option<QuadPipeline>,
+    frame_blend_pipeline: Option<FrameBlendPipeline>,
     init_pipeline: Option<ComputePipeline>,
     game_of_life_pipeline: Option<ComputePipeline>,
     draw_pipeline: Option<ComputePipeline>,
     data: Option<CanvasData>,
-    cursor_pos: Vec2,
-    prev_cursor_pos: Option<Vec2>,
+    cursor_pos: PhysicalPosition<f64>,
+    prev_cursor_pos: Option<PhysicalPosition<f64>>,
     draw: bool,
-    dt_sum: f32,
-    num_dts: f32,
-    time: Instant,
-    updated_time: Instant,
-    count: usize,
     commands: Option<CommandBuffer>,
 }
 
-impl Default for GameOfLifeApp {
-    fn default() -> Self {
-        Self {
-            quad_pipeline: None,
-            init_pipeline: None,
-            game_of_life_pipeline: None,
-            draw_pipeline: None,
-            data: None,
-            cursor_pos: Default::default(),
-            prev_cursor_pos: None,
-            draw: false,
-            dt_sum: 0.0,
-            num_dts: 0.0,
-            time: Instant::now(),
-            updated_time: Instant::now(),
-            count: 0,
-            commands: None,
-        }
-    }
-}
-
 impl GameOfLifeApp {
-    fn cursor_to_canvas(&self, width: f32, height: f32, scale_factor: f32) -> (Vec2, Vec2) {
-        let half_screen = Vec2::new(width, height) / scale_factor / 2.0;
-        let current_canvas_pos = self.cursor_pos / scale_factor - half_screen + WIDTH as f32 / 2.0;
-        let prev_canvas_pos = self.prev_cursor_pos.unwrap_or(current_canvas_pos) / scale_factor
-            - half_screen
-            + HEIGHT as f32 / 2.0;
+    fn cursor_to_canvas(&self, viewport: &Viewport) -> (Vec2, Vec2) {
+        let current_canvas_pos = Vec2::from(viewport.cursor_to_canvas(self.cursor_pos));
+        let prev_canvas_pos = self
+            .prev_cursor_pos
+            .map(|pos| Vec2::from(viewport.cursor_to_canvas(pos)))
+            .unwrap_or(current_canvas_pos);
         (current_canvas_pos, prev_canvas_pos)
     }
 }
 
 fn run_update(app: &mut GameOfLifeApp, context: &GlassContext) {
-    let now = Instant::now();
-    app.dt_sum += (now - app.time).as_secs_f32();
-    app.num_dts += 1.0;
-    if app.num_dts == 100.0 {
+    let frame_time = context.frame_time();
+    if frame_time.frame_index().is_multiple_of(100) {
         // Set fps
-        context.primary_render_window().window().set_title(&format!(
-            "Game Of Life: {:.2}",
-            1.0 / (app.dt_sum / app.num_dts)
-        ));
-        app.num_dts = 0.0;
-        app.dt_sum = 0.0;
+        context
+            .primary_render_window()
+            .window()
+            .set_title(&format!("Game Of Life: {:.2}", frame_time.fps()));
     }
-    app.time = Instant::now();
 
-    // Use only single command queue
     let mut encoder = context
         .device()
         .create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Computes"),
         });
-    // Update 60fps
-    if (app.time - app.updated_time).as_secs_f32() > FPS_60 {
-        update_game_of_life(app, context, &mut encoder);
-        app.updated_time = app.time;
-    }
     if app.draw {
         draw_game_of_life(app, context, &mut encoder);
     }
@@ -198,31 +183,37 @@ fn run_update(app: &mut GameOfLifeApp, context: &GlassContext) {
     app.commands = Some(encoder.finish());
 }
 
-fn render(app: &mut GameOfLifeApp, render_data: RenderData) -> Option<Vec<CommandBuffer>> {
+fn render(
+    app: &mut GameOfLifeApp,
+    context: &GlassContext,
+    render_data: RenderData,
+) -> Option<Vec<CommandBuffer>> {
     let GameOfLifeApp {
         data,
         quad_pipeline,
+        frame_blend_pipeline,
         ..
     } = app;
     let canvas_data = data.as_ref().unwrap();
     let quad_pipeline = quad_pipeline.as_ref().unwrap();
+    let frame_blend_pipeline = frame_blend_pipeline.as_ref().unwrap();
     let RenderData {
         encoder,
         frame,
         window,
         ..
     } = render_data;
-    let (width, height) = {
-        let scale_factor = window.window().scale_factor() as f32;
-        let size = window.window().inner_size();
-        (
-            size.width as f32 / scale_factor,
-            size.height as f32 / scale_factor,
-        )
-    };
+    let viewport = window.viewport([WIDTH, HEIGHT]);
     let view = frame
         .texture
         .create_view(&wgpu::TextureViewDescriptor::default());
+    // Blend from the previous generation towards the current one as time approaches the next
+    frame_blend_pipeline.blend(
+        encoder,
+        canvas_data.blend_bind_groups.get(&canvas_data.cells),
+        &canvas_data.blended.views[0],
+        context.time().fixed_alpha(),
+    );
 
     {
         let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -239,12 +230,13 @@ fn render(app: &mut GameOfLifeApp, render_data: RenderData) -> Option<Vec<Comman
             timestamp_writes: None,
             occlusion_query_set: None,
         });
+        viewport.apply(&mut rpass);
         quad_pipeline.draw(
             &mut rpass,
-            &canvas_data.canvas_bind_group,
+            &canvas_data.blended_bind_group,
             [0.0; 4],
-            camera_projection([width, height]).to_cols_array_2d(),
-            canvas_data.canvas.size,
+            Camera2D::projection([WIDTH as f32, HEIGHT as f32]),
+            canvas_data.blended.size,
             1.0,
         );
         Some(vec![app.commands.take().unwrap()])
@@ -256,7 +248,7 @@ fn handle_inputs(app: &mut GameOfLifeApp, event: &WindowEvent) {
         WindowEvent::CursorMoved {
             position, ..
         } => {
-            app.cursor_pos = Vec2::new(position.x as f32, position.y as f32);
+            app.cursor_pos = *position;
         }
         WindowEvent::MouseInput {
             button: MouseButton::Left,
@@ -274,12 +266,8 @@ fn draw_game_of_life(
     context: &GlassContext,
     encoder: &mut CommandEncoder,
 ) {
-    let scale_factor = context.primary_render_window().window().scale_factor() as f32;
-    let (width, height) = {
-        let size = context.primary_render_window().window().inner_size();
-        (size.width as f32, size.height as f32)
-    };
-    let (end, start) = app.cursor_to_canvas(width, height, scale_factor);
+    let viewport = context.primary_render_window().viewport([WIDTH, HEIGHT]);
+    let (end, start) = app.cursor_to_canvas(&viewport);
     let GameOfLifeApp {
         data,
         draw_pipeline,
@@ -294,7 +282,8 @@ fn draw_game_of_life(
     });
     let pc = GameOfLifePushConstants::new(start, end, 10.0);
     cpass.set_pipeline(draw_pipeline);
-    cpass.set_bind_group(0, &data.draw_bind_group, &[]);
+    let bind_group = data.draw_bind_group(context);
+    cpass.set_bind_group(0, &*bind_group, &[]);
     cpass.set_push_constants(0, bytemuck::cast_slice(&[pc]));
     cpass.dispatch_workgroups(WIDTH / 8, HEIGHT / 8, 1);
 }
@@ -309,38 +298,22 @@ fn update_game_of_life(
         game_of_life_pipeline,
         ..
     } = app;
-    let data = data.as_ref().unwrap();
+    let data = data.as_mut().unwrap();
     let game_of_life_pipeline = game_of_life_pipeline.as_ref().unwrap();
-    let (canvas, data_in) = if app.count % 2 == 0 {
-        (&data.canvas.views[0], &data.data_in.views[0])
-    } else {
-        (&data.data_in.views[0], &data.canvas.views[0])
-    };
-    let update_bind_group = context.device().create_bind_group(&BindGroupDescriptor {
-        label: Some("Update Bind Group"),
-        layout: &game_of_life_pipeline.get_bind_group_layout(0),
-        entries: &[
-            wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::TextureView(canvas),
-            },
-            wgpu::BindGroupEntry {
-                binding: 1,
-                resource: wgpu::BindingResource::TextureView(data_in),
-            },
-        ],
-    });
-    let mut cpass = encoder.begin_compute_pass(&ComputePassDescriptor {
-        label: Some("Update"),
-        timestamp_writes: None,
-    });
-    let pc = GameOfLifePushConstants::new(Vec2::ZERO, Vec2::ZERO, 0.0);
-    cpass.set_pipeline(game_of_life_pipeline);
-    cpass.set_bind_group(0, &update_bind_group, &[]);
-    cpass.set_push_constants(0, bytemuck::cast_slice(&[pc]));
-    cpass.dispatch_workgroups(WIDTH / 8, HEIGHT / 8, 1);
-
-    app.count += 1;
+    {
+        let mut cpass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("Update"),
+            timestamp_writes: None,
+        });
+        let pc = GameOfLifePushConstants::new(Vec2::ZERO, Vec2::ZERO, 0.0);
+        let bind_group = data.update_bind_group(context);
+        cpass.set_pipeline(game_of_life_pipeline);
+        cpass.set_bind_group(0, &*bind_group, &[]);
+        cpass.set_push_constants(0, bytemuck::cast_slice(&[pc]));
+        cpass.dispatch_workgroups(WIDTH / 8, HEIGHT / 8, 1);
+    }
+    // The new generation is read by drawing, rendering and the next update
+    data.cells.swap();
 }
 
 fn init_game_of_life(app: &mut GameOfLifeApp, context: &GlassContext) {
@@ -362,8 +335,9 @@ fn init_game_of_life(app: &mut GameOfLifeApp, context: &GlassContext) {
             label: Some("Init"),
             timestamp_writes: None,
         });
+        let bind_group = data.update_bind_group(context);
         cpass.set_pipeline(init_pipeline);
-        cpass.set_bind_group(0, &data.init_bind_group, &[]);
+        cpass.set_bind_group(0, &*bind_group, &[]);
         cpass.set_push_constants(
             0,
             bytemuck::cast_slice(&[GameOfLifePushConstants::new(Vec2::ZERO, Vec2::ZERO, 0.0)]),
@@ -374,11 +348,56 @@ fn init_game_of_life(app: &mut GameOfLifeApp, context: &GlassContext) {
 }
 
 struct CanvasData {
-    canvas: Texture,
-    data_in: Texture,
-    canvas_bind_group: BindGroup,
-    init_bind_group: BindGroup,
-    draw_bind_group: BindGroup,
+    /// Alive cells, the source holds the current generation
+    cells: PingPong,
+    /// Blend of the previous (destination) and current (source) generation, drawn to the window
+    blended: Texture,
+    blended_bind_group: BindGroup,
+    blend_bind_groups: PingPongBindGroups,
+    /// Init & update share their bind group layout
+    update_bind_group_layout: BindGroupLayout,
+    draw_bind_group_layout: BindGroupLayout,
+}
+
+impl CanvasData {
+    /// Update reads the source and writes the destination. Bind groups of both directions are
+    /// created once and reused from the context's cache.
+    fn update_bind_group(&self, context: &GlassContext) -> Arc<BindGroup> {
+        let (src, dst) = (self.cells.src(), self.cells.dst());
+        context
+            .bind_group_cache()
+            .bind_group(("gol_update", self.cells.index() as u64), || {
+                context.device().create_bind_group(&BindGroupDescriptor {
+                    label: Some("Update Bind Group"),
+                    layout: &self.update_bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&dst.views[0]),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::TextureView(&src.views[0]),
+                        },
+                    ],
+                })
+            })
+    }
+
+    fn draw_bind_group(&self, context: &GlassContext) -> Arc<BindGroup> {
+        context
+            .bind_group_cache()
+            .bind_group(("gol_draw", self.cells.index() as u64), || {
+                context.device().create_bind_group(&BindGroupDescriptor {
+                    label: Some("Draw Bind Group"),
+                    layout: &self.draw_bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&self.cells.src().views[0]),
+                    }],
+                })
+            })
+    }
 }
 
 #[repr(C)]
@@ -402,12 +421,13 @@ impl GameOfLifePushConstants {
 fn create_canvas_data(
     context: &GlassContext,
     quad_pipeline: &QuadPipeline,
+    frame_blend_pipeline: &FrameBlendPipeline,
     init_pipeline: &ComputePipeline,
     draw_pipeline: &ComputePipeline,
 ) -> CanvasData {
-    let canvas = Texture::empty(
+    let cells = PingPong::new(
         context.device(),
-        "canvas.png",
+        "cells",
         Extent3d {
             width: WIDTH,
             height: HEIGHT,
@@ -417,9 +437,9 @@ fn create_canvas_data(
         TextureFormat::Rgba16Float,
         TextureUsages::TEXTURE_BINDING | TextureUsages::STORAGE_BINDING,
     );
-    let data_in = Texture::empty(
+    let blended = Texture::empty(
         context.device(),
-        "data_in.png",
+        "blended_cells",
         Extent3d {
             width: WIDTH,
             height: HEIGHT,
@@ -427,45 +447,32 @@ fn create_canvas_data(
         },
         1,
         TextureFormat::Rgba16Float,
-        TextureUsages::TEXTURE_BINDING | TextureUsages::STORAGE_BINDING,
+        TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
     );
-    // Create bind groups to match pipeline layouts (except update, create that dynamically each frame)
-    let canvas_bind_group = quad_pipeline.create_bind_group(
+    let blended_bind_group = quad_pipeline.create_bind_group(
         context.device(),
-        &canvas.views[0],
+        &blended.views[0],
         context.sampler_linear_clamp_to_edge(),
     );
-    // These must match the bind group layout of our pipeline
-    let init_bind_group_layout = init_pipeline.get_bind_group_layout(0);
-    let init_bind_group = context.device().create_bind_group(&BindGroupDescriptor {
-        label: Some("Init Bind Group"),
-        layout: &init_bind_group_layout,
-        entries: &[
-            wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::TextureView(&canvas.views[0]),
-            },
-            wgpu::BindGroupEntry {
-                binding: 1,
-                resource: wgpu::BindingResource::TextureView(&data_in.views[0]),
-            },
-        ],
-    });
-    let draw_bing_group_layout = draw_pipeline.get_bind_group_layout(0);
-    let draw_bind_group = context.device().create_bind_group(&BindGroupDescriptor {
-        label: Some("Draw Bind Group"),
-        layout: &draw_bing_group_layout,
-        entries: &[wgpu::BindGroupEntry {
-            binding: 0,
-            resource: wgpu::BindingResource::TextureView(&data_in.views[0]),
-        }],
+    // Create bind groups for both directions to match pipeline layouts. After an update the
+    // destination holds the previous generation.
+    let blend_bind_groups = cells.create_bind_groups(|src, dst| {
+        frame_blend_pipeline.create_bind_group(
+            context.device(),
+            &dst.views[0],
+            &src.views[0],
+            context.sampler_linear_clamp_to_edge(),
+        )
     });
+    let update_bind_group_layout = init_pipeline.get_bind_group_layout(0);
+    let draw_bind_group_layout = draw_pipeline.get_bind_group_layout(0);
     CanvasData {
-        canvas,
-        data_in,
-        canvas_bind_group,
-        init_bind_group,
-        draw_bind_group,
+        cells,
+        blended,
+        blended_bind_group,
+        blend_bind_groups,
+        update_bind_group_layout,
+        draw_bind_group_layout,
     }
 }
 
@@ -595,17 +602,3 @@ fn create_game_of_life_pipeline(
 
     (init_pipeline, update_pipeline, draw_pipeline)
 }
-
-fn camera_projection(screen_size: [f32; 2]) -> glam::Mat4 {
-    let half_width = screen_size[0] / 2.0;
-    let half_height = screen_size[1] / 2.0;
-    OPENGL_TO_WGPU
-        * glam::Mat4::orthographic_rh(
-            -half_width,
-            half_width,
-            -half_height,
-            half_height,
-            0.0,
-            1000.0,
-        )
-}
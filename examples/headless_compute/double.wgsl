@@ -0,0 +1,13 @@
+@group(0) @binding(0)
+var<storage, read_write> values: array<f32>;
+
+override WORKGROUP_SIZE: u32 = 64u;
+
+@compute @workgroup_size(WORKGROUP_SIZE, 1, 1)
+fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let index = invocation_id.x;
+    if (index >= arrayLength(&values)) {
+        return;
+    }
+    values[index] = values[index] * 2.0;
+}
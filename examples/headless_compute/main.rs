@@ -0,0 +1,114 @@
+use std::borrow::Cow;
+
+use glass::{
+    device_context::DeviceConfig, utils::ComputePipelineBuilder, GlassCompute, GlassComputeApp,
+    GlassComputeContext, GlassError,
+};
+use wgpu::{util::DeviceExt, BindGroup, Buffer, ComputePipeline, Maintain, MapMode};
+
+const NUM_VALUES: usize = 1024;
+const NUM_STEPS: u64 = 10;
+const WORKGROUP_SIZE: usize = 64;
+
+fn main() -> Result<(), GlassError> {
+    GlassCompute::run(DeviceConfig::performance(), |context| {
+        Box::new(DoubleApp::new(context))
+    })
+}
+
+/// Doubles values in a storage buffer each step and prints the result at exit
+struct DoubleApp {
+    pipeline: ComputePipeline,
+    bind_group: BindGroup,
+    values: Buffer,
+    readback: Buffer,
+}
+
+impl DoubleApp {
+    fn new(context: &GlassComputeContext) -> DoubleApp {
+        let device = context.device();
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Double Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("double.wgsl"))),
+        });
+        let pipeline = ComputePipelineBuilder::new("Double Pipeline", &shader, "main")
+            .constant("WORKGROUP_SIZE", WORKGROUP_SIZE as f64)
+            .cache(context.device_context().pipeline_cache())
+            .build(device);
+        let initial = (0..NUM_VALUES).map(|i| i as f32).collect::<Vec<f32>>();
+        let values = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Values"),
+            contents: bytemuck::cast_slice(&initial),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        });
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback"),
+            size: values.size(),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Double Bind Group"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: values.as_entire_binding(),
+            }],
+        });
+        DoubleApp {
+            pipeline,
+            bind_group,
+            values,
+            readback,
+        }
+    }
+}
+
+impl GlassComputeApp for DoubleApp {
+    fn update(&mut self, context: &mut GlassComputeContext) {
+        let mut encoder =
+            context
+                .device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Compute Commands"),
+                });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Double Pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            pass.dispatch_workgroups(NUM_VALUES.div_ceil(WORKGROUP_SIZE) as u32, 1, 1);
+        }
+        context.queue().submit(Some(encoder.finish()));
+        if context.step() + 1 == NUM_STEPS {
+            context.exit();
+        }
+    }
+
+    fn end(&mut self, context: &mut GlassComputeContext) {
+        let mut encoder =
+            context
+                .device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Readback Commands"),
+                });
+        encoder.copy_buffer_to_buffer(&self.values, 0, &self.readback, 0, self.values.size());
+        context.queue().submit(Some(encoder.finish()));
+
+        let slice = self.readback.slice(..);
+        slice.map_async(MapMode::Read, |_| ());
+        context.device().poll(Maintain::Wait);
+        {
+            let data = slice.get_mapped_range();
+            let values: &[f32] = bytemuck::cast_slice(&data);
+            println!(
+                "After {} steps: {:?}",
+                NUM_STEPS,
+                &values[..8.min(values.len())]
+            );
+        }
+        self.readback.unmap();
+    }
+}
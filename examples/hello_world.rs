@@ -4,6 +4,6 @@ fn main() -> Result<(), GlassError> {
     Glass::run(GlassConfig::default(), |_| Box::new(HelloWorld))
 }
 
-struct HelloWorld;
+pub struct HelloWorld;
 
 impl GlassApp for HelloWorld {}
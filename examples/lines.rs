@@ -1,12 +1,14 @@
-use glam::{Mat4, Vec2, Vec3};
+use glam::Vec2;
 use glass::{
+    camera::Camera2D,
     device_context::DeviceConfig,
-    pipelines::{ColoredVertex, Line, LinePipeline},
-    window::{GlassWindow, WindowConfig},
+    gizmos::Gizmos,
+    pipelines::{ColoredVertex, Line, LinePipeline, LineWidth},
+    window::WindowConfig,
     Glass, GlassApp, GlassConfig, GlassContext, GlassError, RenderData,
 };
 use rapier2d::prelude::*;
-use wgpu::{util::DeviceExt, Buffer, CommandBuffer, Features, Limits, StoreOp};
+use wgpu::{Buffer, CommandBuffer, StoreOp};
 use winit::event_loop::ActiveEventLoop;
 
 const WIDTH: u32 = 1920;
@@ -14,58 +16,60 @@ const HEIGHT: u32 = 1080;
 /// Height of screen is 10 meters. This much we need to multiply positions in physics world
 /// to convert to pixels
 const PHYSICS_TO_PIXELS: f32 = HEIGHT as f32 / 10.0;
+const BALL_LINE_VERTICES: usize = 6;
 
 fn config() -> GlassConfig {
     GlassConfig {
-        device_config: DeviceConfig {
-            limits: Limits {
-                max_push_constant_size: 128,
-                ..Default::default()
-            },
-            features: Features::POLYGON_MODE_LINE,
-            ..DeviceConfig::performance()
-        },
+        device_config: DeviceConfig::performance(),
         window_configs: vec![WindowConfig {
             width: WIDTH,
             height: HEIGHT,
             exit_on_esc: true,
             ..WindowConfig::default()
         }],
+        ..GlassConfig::default()
     }
 }
 
 fn main() -> Result<(), GlassError> {
-    Glass::run(config(), |context| Box::new(LineApp::new(context)))
+    Glass::run(config(), |_| Box::new(LineApp::default()))
 }
 
-struct LineApp {
-    line_pipeline: LinePipeline,
+pub struct LineApp {
+    line_pipeline: Option<LinePipeline>,
     physics_pipeline: PhysicsPipeline,
     physics_world: PhysicsWorld,
-    view_proj: Mat4,
-    lines: DebugLines,
-    another_line_buffer: Option<Buffer>,
+    view_proj: [[f32; 4]; 4],
+    ball: RigidBodyHandle,
+    /// Lines to the ball, streamed through the upload belt each update
+    ball_lines: Option<Buffer>,
 }
 
-impl LineApp {
-    fn new(context: &mut GlassContext) -> LineApp {
+impl Default for LineApp {
+    fn default() -> LineApp {
         LineApp {
-            line_pipeline: LinePipeline::new(context.device(), wgpu::ColorTargetState {
-                format: GlassWindow::default_surface_format(),
-                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                write_mask: wgpu::ColorWrites::ALL,
-            }),
+            line_pipeline: None,
             physics_pipeline: PhysicsPipeline::new(),
             physics_world: PhysicsWorld::new(Vec2::new(0.0, -9.81)),
-            view_proj: camera_projection([WIDTH as f32, HEIGHT as f32]),
-            lines: DebugLines::new(),
-            another_line_buffer: None,
+            view_proj: Camera2D::projection([WIDTH as f32, HEIGHT as f32]),
+            ball: RigidBodyHandle::invalid(),
+            ball_lines: None,
         }
     }
 }
 
 impl GlassApp for LineApp {
     fn start(&mut self, _event_loop: &ActiveEventLoop, context: &mut GlassContext) {
+        self.line_pipeline = Some(LinePipeline::new(
+            context.device_context(),
+            wgpu::ColorTargetState {
+                format: context.primary_render_window().surface_format(),
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            },
+            1,
+        ));
+        context.gizmos_mut().set_width(LineWidth::Pixels(2.0));
         // Add ground level
         let y_pos = 0.0;
         let ground = self
@@ -140,47 +144,27 @@ impl GlassApp for LineApp {
             .translation(vector![0.0, 10.0])
             .build();
         let collider = ColliderBuilder::ball(0.5).restitution(1.2).build();
-        let ball_body_handle = self.physics_world.rigid_body_set.insert(rigid_body);
+        self.ball = self.physics_world.rigid_body_set.insert(rigid_body);
         self.physics_world.collider_set.insert_with_parent(
             collider,
-            ball_body_handle,
+            self.ball,
             &mut self.physics_world.rigid_body_set,
         );
 
-        let mut some_lines = vec![];
-        let line1 = Line::new([0.0, 0.0, 0.0], [512.0, 512.0, 0.0], [1.0, 0.0, 0.0, 1.0]);
-        let line2 = Line::new([512.0, 0.0, 0.0], [512.0, 512.0, 0.0], [0.0, 1.0, 0.0, 1.0]);
-        let line3 = Line::new([0.0, 0.0, 0.0], [512.0, 0.0, 0.0], [0.0, 0.0, 1.0, 1.0]);
-        for line in [line1, line2, line3] {
-            some_lines.push(ColoredVertex::new_2d(
-                [line.start[0], line.start[1]],
-                line.color,
-            ));
-            some_lines.push(ColoredVertex::new_2d(
-                [line.end[0], line.end[1]],
-                line.color,
-            ));
-        }
-        let line_vertices =
-            context
-                .device()
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Line Buffer"),
-                    contents: bytemuck::cast_slice(&some_lines),
-                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                });
-        self.another_line_buffer = Some(line_vertices);
+        self.ball_lines = Some(context.device().create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Ball Lines"),
+            size: (BALL_LINE_VERTICES * std::mem::size_of::<ColoredVertex>()) as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        }));
     }
 
-    fn update(&mut self, _context: &mut GlassContext) {
+    fn update(&mut self, context: &mut GlassContext) {
         let LineApp {
             physics_pipeline,
             physics_world,
-            lines,
             ..
         } = self;
-        // Clear lines
-        lines.clear();
         let PhysicsWorld {
             gravity,
             rigid_body_set,
@@ -213,32 +197,57 @@ impl GlassApp for LineApp {
 
         // Update lines
         debug_render.render(
-            lines,
+            &mut GizmoLines(context.gizmos_mut()),
             rigid_body_set,
             collider_set,
             impulse_joint_set,
             multibody_joint_set,
             narrow_phase,
         );
+
+        // Stream lines following the ball, the belt's writes are submitted before render
+        let ball = rigid_body_set[self.ball].translation();
+        let ball = [ball.x * PHYSICS_TO_PIXELS, ball.y * PHYSICS_TO_PIXELS, 0.0];
+        let lines = [
+            Line::new([0.0, 0.0, 0.0], ball, [1.0, 0.0, 0.0, 1.0]),
+            Line::new([512.0, 0.0, 0.0], ball, [0.0, 1.0, 0.0, 1.0]),
+            Line::new([0.0, 0.0, 0.0], [512.0, 0.0, 0.0], [0.0, 0.0, 1.0, 1.0]),
+        ];
+        let vertices = lines
+            .iter()
+            .flat_map(|line| {
+                [
+                    ColoredVertex::new_2d([line.start[0], line.start[1]], line.color),
+                    ColoredVertex::new_2d([line.end[0], line.end[1]], line.color),
+                ]
+            })
+            .collect::<Vec<_>>();
+        context.upload_belt().write_buffer(
+            context.device(),
+            self.ball_lines.as_ref().unwrap(),
+            0,
+            bytemuck::cast_slice(&vertices),
+        );
     }
 
     fn render(
         &mut self,
-        _context: &GlassContext,
+        context: &GlassContext,
         render_data: RenderData,
     ) -> Option<Vec<CommandBuffer>> {
         let LineApp {
             line_pipeline,
             view_proj,
-            lines,
-            another_line_buffer,
+            ball_lines,
             ..
         } = self;
         let RenderData {
             encoder,
             frame,
+            window,
             ..
         } = render_data;
+        let [width, height] = window.surface_size();
         let view = frame
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
@@ -256,41 +265,25 @@ impl GlassApp for LineApp {
             timestamp_writes: None,
             occlusion_query_set: None,
         });
-        for line in lines.lines.iter() {
-            line_pipeline.draw(&mut rpass, view_proj.to_cols_array_2d(), *line);
-        }
-        let another_line_buffer = another_line_buffer.as_ref().unwrap();
+        context
+            .gizmos()
+            .draw(&mut rpass, *view_proj, [width as f32, height as f32]);
+        let line_pipeline = line_pipeline.as_ref().unwrap();
+        let ball_lines = ball_lines.as_ref().unwrap();
         line_pipeline.draw_line_buffer(
             &mut rpass,
-            view_proj.to_cols_array_2d(),
-            another_line_buffer,
-            0..6,
+            *view_proj,
+            ball_lines,
+            0..BALL_LINE_VERTICES as u32,
         );
         None
     }
 }
 
-pub struct DebugLines {
-    lines: Vec<Line>,
-}
-
-impl DebugLines {
-    fn new() -> DebugLines {
-        DebugLines {
-            lines: vec![],
-        }
-    }
-
-    fn add_line(&mut self, line: Line) {
-        self.lines.push(line);
-    }
+/// Forwards rapier's debug lines to the context's gizmos
+struct GizmoLines<'a>(&'a mut Gizmos);
 
-    fn clear(&mut self) {
-        self.lines.clear();
-    }
-}
-
-impl DebugRenderBackend for DebugLines {
+impl DebugRenderBackend for GizmoLines<'_> {
     fn draw_line(
         &mut self,
         _object: DebugRenderObject,
@@ -298,12 +291,11 @@ impl DebugRenderBackend for DebugLines {
         b: Point<Real>,
         color: [f32; 4],
     ) {
-        let line = Line::new(
-            (Vec3::new(a.x, a.y, 0.0) * PHYSICS_TO_PIXELS).into(),
-            (Vec3::new(b.x, b.y, 0.0) * PHYSICS_TO_PIXELS).into(),
+        self.0.line(
+            (Vec2::new(a.x, a.y) * PHYSICS_TO_PIXELS).into(),
+            (Vec2::new(b.x, b.y) * PHYSICS_TO_PIXELS).into(),
             color,
         );
-        self.add_line(line);
     }
 }
 
@@ -341,25 +333,3 @@ impl PhysicsWorld {
         }
     }
 }
-
-#[rustfmt::skip]
-const OPENGL_TO_WGPU: glam::Mat4 = glam::Mat4::from_cols_array(&[
-    1.0, 0.0, 0.0, 0.0,
-    0.0, 1.0, 0.0, 0.0,
-    0.0, 0.0, 0.5, 0.0,
-    0.0, 0.0, 0.5, 1.0,
-]);
-
-fn camera_projection(screen_size: [f32; 2]) -> glam::Mat4 {
-    let half_width = screen_size[0] / 2.0;
-    let half_height = screen_size[1] / 2.0;
-    OPENGL_TO_WGPU
-        * Mat4::orthographic_rh(
-            -half_width,
-            half_width,
-            -half_height,
-            half_height,
-            0.0,
-            1000.0,
-        )
-}
@@ -0,0 +1,123 @@
+use glam::{Mat4, Vec3};
+use glass::{
+    camera::Camera3D,
+    device_context::DeviceConfig,
+    pipelines::{Mesh, MeshPipeline},
+    window::WindowConfig,
+    Glass, GlassApp, GlassConfig, GlassContext, GlassError, RenderData,
+};
+use wgpu::{CommandBuffer, Limits, StoreOp, TextureFormat};
+use winit::event_loop::ActiveEventLoop;
+
+const WIDTH: u32 = 1920;
+const HEIGHT: u32 = 1080;
+
+fn config() -> GlassConfig {
+    GlassConfig {
+        device_config: DeviceConfig {
+            limits: Limits {
+                max_push_constant_size: 128,
+                ..Default::default()
+            },
+            ..DeviceConfig::performance()
+        },
+        window_configs: vec![WindowConfig {
+            width: WIDTH,
+            height: HEIGHT,
+            exit_on_esc: true,
+            depth_format: Some(TextureFormat::Depth32Float),
+            ..WindowConfig::default()
+        }],
+        ..GlassConfig::default()
+    }
+}
+
+fn main() -> Result<(), GlassError> {
+    Glass::run(config(), |_| Box::new(MeshApp::default()))
+}
+
+#[derive(Default)]
+pub struct MeshApp {
+    mesh_pipeline: Option<MeshPipeline>,
+    cube: Option<Mesh>,
+    angle: f32,
+}
+
+impl GlassApp for MeshApp {
+    fn start(&mut self, _event_loop: &ActiveEventLoop, context: &mut GlassContext) {
+        let window = context.primary_render_window();
+        self.mesh_pipeline = Some(
+            MeshPipeline::new(
+                context.device_context(),
+                wgpu::ColorTargetState {
+                    format: window.surface_format(),
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                },
+                window.depth_format(),
+                window.msaa_samples(),
+            )
+            .unwrap(),
+        );
+        self.cube = Some(Mesh::cube(context.device(), 1.0));
+    }
+
+    fn update(&mut self, context: &mut GlassContext) {
+        self.angle += context.time().delta_secs();
+    }
+
+    fn render(
+        &mut self,
+        context: &GlassContext,
+        render_data: RenderData,
+    ) -> Option<Vec<CommandBuffer>> {
+        let RenderData {
+            encoder,
+            frame,
+            window,
+            ..
+        } = render_data;
+        let mesh_pipeline = self.mesh_pipeline.as_ref().unwrap();
+        let cube = self.cube.as_ref().unwrap();
+        let [width, height] = window.surface_size();
+        let camera = Camera3D::new([2.0, 1.5, 3.0], [0.0; 3], [width as f32, height as f32]);
+        mesh_pipeline.update_camera(context.queue(), camera.view_proj(), [0.4, 1.0, 0.6], 0.2);
+
+        let view = frame
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(window.color_attachment(&view, wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color {
+                    r: 0.1,
+                    g: 0.1,
+                    b: 0.15,
+                    a: 1.0,
+                }),
+                store: StoreOp::Store,
+            }))],
+            depth_stencil_attachment: window.depth_view().map(|view| {
+                wgpu::RenderPassDepthStencilAttachment {
+                    view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        for (i, x) in [-1.0f32, 1.0].into_iter().enumerate() {
+            let model = Mat4::from_translation(Vec3::new(x, 0.0, 0.0))
+                * Mat4::from_rotation_y(self.angle * (i as f32 + 1.0))
+                * Mat4::from_rotation_x(self.angle * 0.5);
+            mesh_pipeline.draw(&mut rpass, cube, None, model.to_cols_array_2d(), [
+                0.9, 0.5, 0.2, 1.0,
+            ]);
+        }
+        None
+    }
+}
@@ -1,23 +1,13 @@
 use glass::{
-    device_context::DeviceConfig,
-    pipelines::QuadPipeline,
-    texture::Texture,
-    window::{GlassWindow, WindowConfig},
-    Glass, GlassApp, GlassConfig, GlassContext, GlassError, RenderData,
+    camera::Camera2D, device_context::DeviceConfig, pipelines::QuadPipeline, texture::Texture,
+    utils::RenderBundleBuilder, window::WindowConfig, Glass, GlassApp, GlassConfig, GlassContext,
+    GlassError, RenderData,
 };
-use wgpu::{BindGroup, CommandBuffer, Limits, StoreOp, TextureFormat, TextureUsages};
+use wgpu::{BindGroup, CommandBuffer, RenderBundle, StoreOp, TextureFormat, TextureUsages};
 use winit::event_loop::ActiveEventLoop;
 
 const WIDTH: u32 = 1920;
 const HEIGHT: u32 = 1080;
-#[rustfmt::skip]
-const OPENGL_TO_WGPU: glam::Mat4 = glam::Mat4::from_cols_array(&[
-    1.0, 0.0, 0.0, 0.0,
-    0.0, 1.0, 0.0, 0.0,
-    0.0, 0.0, 0.5, 0.0,
-    0.0, 0.0, 0.5, 1.0,
-]);
-
 fn main() -> Result<(), GlassError> {
     Glass::run(config(), |_| Box::new(TreeApp::default()))
 }
@@ -25,11 +15,8 @@ fn main() -> Result<(), GlassError> {
 fn config() -> GlassConfig {
     GlassConfig {
         device_config: DeviceConfig {
-            limits: Limits {
-                // Needed for push constants
-                max_push_constant_size: 128,
-                ..Default::default()
-            },
+            // Reduces shimmering of the minified quads
+            default_sampler_anisotropy: 16,
             ..DeviceConfig::performance()
         },
         window_configs: vec![WindowConfig {
@@ -38,39 +25,46 @@ fn config() -> GlassConfig {
             exit_on_esc: true,
             ..WindowConfig::default()
         }],
+        ..GlassConfig::default()
     }
 }
 
 /// Example buffer data etc.
 #[derive(Default)]
-struct TreeApp {
+pub struct TreeApp {
     quad_pipeline: Option<QuadPipeline>,
     data: Option<ExampleData>,
+    /// Static row of small trees, recorded again when the window's size changes
+    forest: Option<(RenderBundle, [f32; 2])>,
 }
 
 impl GlassApp for TreeApp {
     fn start(&mut self, _event_loop: &ActiveEventLoop, context: &mut GlassContext) {
-        let quad_pipeline = QuadPipeline::new(context.device(), wgpu::ColorTargetState {
-            format: GlassWindow::default_surface_format(),
-            blend: Some(wgpu::BlendState {
-                color: wgpu::BlendComponent::OVER,
-                alpha: wgpu::BlendComponent::OVER,
-            }),
-            write_mask: wgpu::ColorWrites::ALL,
-        });
+        let quad_pipeline = QuadPipeline::new(
+            context.device_context(),
+            wgpu::ColorTargetState {
+                format: context.primary_render_window().surface_format(),
+                blend: Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent::OVER,
+                    alpha: wgpu::BlendComponent::OVER,
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            },
+            1,
+        );
         self.data = Some(create_example_data(context, &quad_pipeline));
         self.quad_pipeline = Some(quad_pipeline);
     }
 
     fn render(
         &mut self,
-        _context: &GlassContext,
+        context: &GlassContext,
         render_data: RenderData,
     ) -> Option<Vec<CommandBuffer>> {
         let TreeApp {
             quad_pipeline,
             data,
-            ..
+            forest,
         } = self;
         let quad_pipeline = quad_pipeline.as_ref().unwrap();
         let tree_data = data.as_ref().unwrap();
@@ -91,6 +85,16 @@ impl GlassApp for TreeApp {
         let view = frame
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
+        if forest.as_ref().map(|(_, size)| *size) != Some([width, height]) {
+            let bundle = record_forest(
+                context,
+                window.surface_format(),
+                quad_pipeline,
+                tree_data,
+                [width, height],
+            );
+            *forest = Some((bundle, [width, height]));
+        }
 
         {
             let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -107,11 +111,12 @@ impl GlassApp for TreeApp {
                 timestamp_writes: None,
                 occlusion_query_set: None,
             });
+            rpass.execute_bundles(forest.as_ref().map(|(bundle, _)| bundle));
             quad_pipeline.draw(
                 &mut rpass,
                 &tree_data.tree_bind_group,
                 [250.0, 250.0, 0.0, 0.0],
-                camera_projection([width, height]).to_cols_array_2d(),
+                Camera2D::projection([width, height]),
                 tree_data.tree.size,
                 1.0,
             );
@@ -120,6 +125,33 @@ impl GlassApp for TreeApp {
     }
 }
 
+/// Record the small trees into a bundle, which is replayed every frame without re-encoding
+fn record_forest(
+    context: &GlassContext,
+    format: TextureFormat,
+    quad_pipeline: &QuadPipeline,
+    tree_data: &ExampleData,
+    size: [f32; 2],
+) -> RenderBundle {
+    let view_proj = Camera2D::projection(size);
+    let tree_size = [tree_data.tree.size[0] / 4.0, tree_data.tree.size[1] / 4.0];
+    RenderBundleBuilder::new("forest")
+        .color(format)
+        .record(context.device(), |encoder| {
+            for i in 0..8 {
+                let x = -size[0] / 2.0 + (i as f32 + 0.5) * size[0] / 8.0;
+                quad_pipeline.draw_bundle(
+                    encoder,
+                    &tree_data.tree_bind_group,
+                    [x, -size[1] / 2.0 + tree_size[1], 0.0, 0.0],
+                    view_proj,
+                    tree_size,
+                    1.0,
+                );
+            }
+        })
+}
+
 struct ExampleData {
     tree: Texture,
     tree_bind_group: BindGroup,
@@ -141,27 +173,12 @@ fn create_example_data(context: &GlassContext, quad_pipeline: &QuadPipeline) ->
 
 fn create_tree_texture(app: &GlassContext) -> Texture {
     let diffuse_bytes = include_bytes!("tree.png");
-    Texture::from_bytes(
-        app.device(),
-        app.queue(),
-        diffuse_bytes,
-        "tree.png",
-        TextureFormat::Rgba8UnormSrgb,
-        TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
-    )
-    .unwrap()
-}
-
-fn camera_projection(screen_size: [f32; 2]) -> glam::Mat4 {
-    let half_width = screen_size[0] / 2.0;
-    let half_height = screen_size[1] / 2.0;
-    OPENGL_TO_WGPU
-        * glam::Mat4::orthographic_rh(
-            -half_width,
-            half_width,
-            -half_height,
-            half_height,
-            0.0,
-            1000.0,
+    app.texture_loader()
+        .from_bytes(
+            diffuse_bytes,
+            "tree.png",
+            TextureFormat::Rgba8UnormSrgb,
+            TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
         )
+        .unwrap()
 }
@@ -1,12 +1,12 @@
 use glam::{IVec2, Vec2};
-use glass::{pipelines::QuadPipeline, texture::Texture};
-use image::RgbaImage;
-use wgpu::{
-    BindGroup, Device, Extent3d, ImageCopyTexture, ImageDataLayout, Origin3d, Queue, Sampler,
-    TextureAspect, TextureFormat, TextureUsages,
+use glass::{
+    pipelines::QuadPipeline,
+    texture::{DirtyRect, Texture},
 };
+use image::RgbaImage;
+use wgpu::{BindGroup, Device, Extent3d, Queue, Sampler, TextureFormat, TextureUsages};
 
-use crate::sand::{Sand, SandType};
+use super::sand::{Sand, SandType};
 
 pub struct Grid {
     pub data: Vec<Sand>,
@@ -15,7 +15,7 @@ pub struct Grid {
     pub grid_bind_group: BindGroup,
     pub width: u32,
     pub height: u32,
-    changed: bool,
+    dirty: DirtyRect,
 }
 
 impl Grid {
@@ -48,7 +48,7 @@ impl Grid {
             grid_bind_group,
             width,
             height,
-            changed: false,
+            dirty: DirtyRect::new(),
         }
     }
 
@@ -77,13 +77,13 @@ impl Grid {
         if x >= 0 && x < self.width as i32 && y >= 0 && y < self.height as i32 {
             let index = self.index(x, y);
             self.data[index] = sand;
+            let image_y = self.height - y as u32 - 1;
             self.rgba.put_pixel(
                 x as u32,
-                self.height - y as u32 - 1,
+                image_y,
                 [sand.color[0], sand.color[1], sand.color[2], 255].into(),
             );
-
-            self.changed = true;
+            self.dirty.mark(x as u32, image_y);
         }
     }
 
@@ -159,23 +159,6 @@ impl Grid {
     }
 
     pub fn update_texture(&mut self, queue: &Queue) {
-        if self.changed {
-            queue.write_texture(
-                ImageCopyTexture {
-                    texture: &self.texture.texture,
-                    mip_level: 0,
-                    origin: Origin3d::ZERO,
-                    aspect: TextureAspect::All,
-                },
-                &self.rgba,
-                ImageDataLayout {
-                    offset: 0,
-                    bytes_per_row: Some(4 * self.width),
-                    rows_per_image: None,
-                },
-                self.texture.texture.size(),
-            );
-            self.changed = false;
-        }
+        self.texture.write_dirty(queue, &mut self.dirty, &self.rgba);
     }
 }
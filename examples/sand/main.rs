@@ -1,55 +1,82 @@
 mod grid;
 mod sand;
-mod timer;
 
-use glam::Vec2;
 use glass::{
+    camera::Camera2D,
     device_context::DeviceConfig,
-    pipelines::QuadPipeline,
-    window::{GlassWindow, WindowConfig},
+    input::InputMap,
+    pipelines::{CrtPipeline, CrtSettings, QuadPipeline},
+    recorder::RecorderConfig,
+    window::{
+        coords::{self, ScalingMode},
+        WindowConfig,
+    },
     Glass, GlassApp, GlassConfig, GlassContext, GlassError, RenderData,
 };
 use wgpu::{
-    Color, CommandBuffer, Limits, LoadOp, Operations, PresentMode, RenderPassColorAttachment,
-    RenderPassDescriptor, StoreOp, TextureViewDescriptor,
-};
-use winit::{
-    event::{ElementState, MouseButton, WindowEvent},
-    event_loop::ActiveEventLoop,
-    window::WindowId,
+    BindGroup, Color, CommandBuffer, Limits, LoadOp, Operations, PresentMode,
+    RenderPassColorAttachment, RenderPassDescriptor, StoreOp, TextureViewDescriptor,
 };
+use winit::{event::MouseButton, event_loop::ActiveEventLoop, keyboard::KeyCode};
 
-use crate::{grid::Grid, sand::SandType, timer::Timer};
+use self::{grid::Grid, sand::SandType};
 
 const CANVAS_SIZE: u32 = 512;
 const CANVAS_SCALE: u32 = 2;
 
 fn main() -> Result<(), GlassError> {
-    Glass::run(config(), |context| {
-        Box::new(SandSim::new(context)) as Box<dyn GlassApp>
+    // E.g. `cargo run --example sand -- --fullscreen --no-vsync`
+    Glass::run(config().with_args()?, |_| {
+        Box::new(SandApp::default()) as Box<dyn GlassApp>
     })
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+enum Action {
+    ToggleRecording,
+    CycleDisplay,
+    DrawSand,
+    DrawEmpty,
+    DrawWater,
+}
+
+/// How the canvas is scaled to the window, cycle with C
+#[derive(Copy, Clone, PartialEq)]
+enum Display {
+    Stretch,
+    PixelArt,
+    Crt,
+}
+
+/// Creates the simulation once the window's surface format is known
+#[derive(Default)]
+pub struct SandApp {
+    sim: Option<SandSim>,
+}
+
 struct SandSim {
     grid: Grid,
     quad_pipeline: QuadPipeline,
-    cursor_pos: Vec2,
-    draw_sand: bool,
-    draw_water: bool,
-    draw_empty: bool,
-    timer: Timer,
+    crt_pipeline: CrtPipeline,
+    crt_bind_group: BindGroup,
+    display: Display,
+    input_map: InputMap<Action>,
 }
 
 impl SandSim {
     pub fn new(context: &GlassContext) -> SandSim {
-        let quad_pipeline = QuadPipeline::new(context.device(), wgpu::ColorTargetState {
-            format: GlassWindow::default_surface_format(),
-            blend: Some(wgpu::BlendState {
-                color: wgpu::BlendComponent::OVER,
-                alpha: wgpu::BlendComponent::OVER,
-            }),
-            write_mask: wgpu::ColorWrites::ALL,
-        });
+        let quad_pipeline = QuadPipeline::new(
+            context.device_context(),
+            wgpu::ColorTargetState {
+                format: context.primary_render_window().surface_format(),
+                blend: Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent::OVER,
+                    alpha: wgpu::BlendComponent::OVER,
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            },
+            1,
+        );
         let grid = Grid::new(
             context.device(),
             &quad_pipeline,
@@ -57,93 +84,86 @@ impl SandSim {
             CANVAS_SIZE,
             CANVAS_SIZE,
         );
+        let crt_pipeline = CrtPipeline::new(
+            context.device_context(),
+            context.primary_render_window().surface_format(),
+            CrtSettings::pixel_art(),
+        )
+        .unwrap();
+        let crt_bind_group = crt_pipeline.create_bind_group(
+            context.device(),
+            &grid.texture.views[0],
+            context.sampler_linear_clamp_to_edge(),
+        );
         SandSim {
             grid,
             quad_pipeline,
-            cursor_pos: Vec2::ZERO,
-            draw_sand: false,
-            draw_water: false,
-            draw_empty: false,
-            timer: Timer::new(),
+            crt_pipeline,
+            crt_bind_group,
+            display: Display::Stretch,
+            input_map: input_map(),
         }
     }
-}
 
-impl GlassApp for SandSim {
-    fn window_input(
-        &mut self,
-        _context: &mut GlassContext,
-        _event_loop: &ActiveEventLoop,
-        _window_id: WindowId,
-        event: &WindowEvent,
-    ) {
-        match event {
-            WindowEvent::CursorMoved {
-                position, ..
-            } => {
-                self.cursor_pos = Vec2::new(position.x as f32, position.y as f32);
-            }
-            WindowEvent::MouseInput {
-                button: MouseButton::Left,
-                state,
-                ..
-            } => {
-                self.draw_sand = state == &ElementState::Pressed;
-            }
-            WindowEvent::MouseInput {
-                button: MouseButton::Right,
-                state,
-                ..
-            } => {
-                self.draw_empty = state == &ElementState::Pressed;
-            }
-            WindowEvent::MouseInput {
-                button: MouseButton::Middle,
-                state,
-                ..
-            } => {
-                self.draw_water = state == &ElementState::Pressed;
+    fn update(&mut self, context: &mut GlassContext) {
+        let input = context.input();
+        // Toggle recording frames to ./recording with R
+        if input.action_just_pressed(&self.input_map, Action::ToggleRecording) {
+            let window_id = context.primary_render_window().window().id();
+            if context.is_recording() {
+                context.stop_recording();
+            } else if let Err(e) = context.start_recording(window_id, RecorderConfig::default()) {
+                eprintln!("Failed to start recording: {}", e);
             }
-            _ => (),
         }
-    }
-
-    fn update(&mut self, context: &mut GlassContext) {
-        if self.draw_sand || self.draw_empty || self.draw_water {
-            let screen_size = context.primary_render_window().surface_size();
-            let scale_factor = context.primary_render_window().window().scale_factor() as f32;
-            let pos = cursor_to_canvas(
-                self.cursor_pos / scale_factor,
-                screen_size[0] as f32 / scale_factor,
-                screen_size[1] as f32 / scale_factor,
-            );
-            let rounded = pos.round().as_ivec2();
-            self.grid.draw_sand_radius(
-                rounded.x,
-                rounded.y,
-                if self.draw_sand {
-                    SandType::Sand
-                } else if self.draw_water {
-                    SandType::Water
+        let input = context.input();
+        if input.action_just_pressed(&self.input_map, Action::CycleDisplay) {
+            self.display = match self.display {
+                Display::Stretch => Display::PixelArt,
+                Display::PixelArt => Display::Crt,
+                Display::Crt => Display::Stretch,
+            };
+            self.crt_pipeline
+                .set_settings(if self.display == Display::Crt {
+                    CrtSettings::default()
                 } else {
-                    SandType::Empty
-                },
-                5.0,
+                    CrtSettings::pixel_art()
+                });
+        }
+        let sand_type = [
+            (Action::DrawSand, SandType::Sand),
+            (Action::DrawWater, SandType::Water),
+            (Action::DrawEmpty, SandType::Empty),
+        ]
+        .into_iter()
+        .find(|(action, _)| input.action_pressed(&self.input_map, *action))
+        .map(|(_, sand_type)| sand_type);
+        if let (Some(sand_type), Some(cursor)) = (sand_type, input.cursor_position()) {
+            // Canvas y axis points up
+            let [x, y] = coords::cursor_to_render_target_y_up(
+                cursor,
+                context.primary_render_window().surface_size(),
+                [CANVAS_SIZE, CANVAS_SIZE],
+                ScalingMode::Fit,
             );
+            self.grid
+                .draw_sand_radius(x.round() as i32, y.round() as i32, sand_type, 5.0);
+        }
+        // Pause with F9, step with F10
+        if context.time().is_advancing() {
+            self.grid.simulate();
+            self.grid.simulate();
         }
-        self.grid.simulate();
-        self.grid.simulate();
         self.grid.update_texture(context.queue());
     }
 
-    fn render(
-        &mut self,
-        _context: &GlassContext,
-        render_data: RenderData,
-    ) -> Option<Vec<CommandBuffer>> {
+    fn render(&mut self, render_data: RenderData) {
         let SandSim {
             grid,
             quad_pipeline,
+            crt_pipeline,
+            crt_bind_group,
+            display,
             ..
         } = self;
         let RenderData {
@@ -152,17 +172,12 @@ impl GlassApp for SandSim {
             window,
             ..
         } = render_data;
-        let (width, height) = {
-            let scale_factor = window.window().scale_factor() as f32;
-            let size = window.window().inner_size();
-            (
-                size.width as f32 / scale_factor,
-                size.height as f32 / scale_factor,
-            )
-        };
+        let viewport = window.viewport([CANVAS_SIZE, CANVAS_SIZE]);
         let view = frame.texture.create_view(&TextureViewDescriptor::default());
 
-        {
+        if *display != Display::Stretch {
+            crt_pipeline.upscale(encoder, crt_bind_group, &view, Some(viewport));
+        } else {
             let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
                 label: None,
                 color_attachments: &[Some(RenderPassColorAttachment {
@@ -177,57 +192,59 @@ impl GlassApp for SandSim {
                 timestamp_writes: None,
                 occlusion_query_set: None,
             });
+            viewport.apply(&mut rpass);
             quad_pipeline.draw(
                 &mut rpass,
                 &grid.grid_bind_group,
                 [0.0; 4],
-                camera_projection([width, height]).to_cols_array_2d(),
-                [
-                    grid.texture.size[0] * CANVAS_SCALE as f32,
-                    grid.texture.size[1] * CANVAS_SCALE as f32,
-                ],
+                Camera2D::projection([CANVAS_SIZE as f32, CANVAS_SIZE as f32]),
+                grid.texture.size,
                 1.0,
             );
         }
+    }
+}
+
+impl GlassApp for SandApp {
+    fn start(&mut self, _event_loop: &ActiveEventLoop, context: &mut GlassContext) {
+        self.sim = Some(SandSim::new(context));
+    }
+
+    fn update(&mut self, context: &mut GlassContext) {
+        if let Some(sim) = &mut self.sim {
+            sim.update(context);
+        }
+    }
+
+    fn render(
+        &mut self,
+        _context: &GlassContext,
+        render_data: RenderData,
+    ) -> Option<Vec<CommandBuffer>> {
+        self.sim.as_mut()?.render(render_data);
         None
     }
 
     fn end_of_frame(&mut self, context: &mut GlassContext) {
-        self.timer.update();
         if let Some(w) = context.primary_render_window_maybe() {
-            w.window()
-                .set_title(&format!("Sand Grid - FPS: {:.2}", self.timer.avg_fps()));
+            w.window().set_title(&format!(
+                "Sand Grid - FPS: {:.2}",
+                context.frame_time().fps()
+            ));
         }
     }
 }
 
-fn cursor_to_canvas(cursor: Vec2, screen_width: f32, screen_height: f32) -> Vec2 {
-    let half_screen = Vec2::new(screen_width, screen_height) / 2.0;
-    Vec2::new(1.0, -1.0) * (cursor - half_screen) / CANVAS_SCALE as f32 + CANVAS_SIZE as f32 * 0.5
+fn input_map() -> InputMap<Action> {
+    let mut map = InputMap::new();
+    map.bind(Action::ToggleRecording, KeyCode::KeyR)
+        .bind(Action::CycleDisplay, KeyCode::KeyC)
+        .bind(Action::DrawSand, MouseButton::Left)
+        .bind(Action::DrawEmpty, MouseButton::Right)
+        .bind(Action::DrawWater, MouseButton::Middle);
+    map
 }
 
-fn camera_projection(screen_size: [f32; 2]) -> glam::Mat4 {
-    let half_width = screen_size[0] / 2.0;
-    let half_height = screen_size[1] / 2.0;
-    OPENGL_TO_WGPU
-        * glam::Mat4::orthographic_rh(
-            -half_width,
-            half_width,
-            -half_height,
-            half_height,
-            0.0,
-            1000.0,
-        )
-}
-
-#[rustfmt::skip]
-pub const OPENGL_TO_WGPU: glam::Mat4 = glam::Mat4::from_cols_array(&[
-    1.0, 0.0, 0.0, 0.0,
-    0.0, 1.0, 0.0, 0.0,
-    0.0, 0.0, 0.5, 0.0,
-    0.0, 0.0, 0.5, 1.0,
-]);
-
 fn config() -> GlassConfig {
     GlassConfig {
         device_config: DeviceConfig {
@@ -245,5 +262,7 @@ fn config() -> GlassConfig {
             exit_on_esc: true,
             ..WindowConfig::default()
         }],
+        time_hotkeys: true,
+        ..GlassConfig::default()
     }
 }
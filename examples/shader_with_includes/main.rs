@@ -1,13 +1,20 @@
-use std::{borrow::Cow, collections::HashMap, path::PathBuf};
+use std::{borrow::Cow, path::PathBuf, time::Duration};
 
 use glass::{
-    utils::ShaderModule, Glass, GlassApp, GlassConfig, GlassContext, GlassError, RenderData,
+    hot_reload::HotReloaded,
+    utils::{ShaderModule, WatchedShaderModule},
+    Glass, GlassApp, GlassConfig, GlassContext, GlassError, RenderData,
 };
 use wgpu::{
-    CommandBuffer, MultisampleState, PipelineLayoutDescriptor, PrimitiveState, RenderPipeline,
-    RenderPipelineDescriptor, ShaderModuleDescriptor, StoreOp, TextureFormat,
+    CommandBuffer, Device, MultisampleState, PipelineLayoutDescriptor, PrimitiveState,
+    RenderPipeline, RenderPipelineDescriptor, ShaderModuleDescriptor, StoreOp, TextureFormat,
+};
+use winit::{
+    event::{ElementState, WindowEvent},
+    event_loop::ActiveEventLoop,
+    keyboard::{KeyCode, PhysicalKey},
+    window::WindowId,
 };
-use winit::event_loop::ActiveEventLoop;
 
 const WIDTH: u32 = 1920;
 const HEIGHT: u32 = 1080;
@@ -19,13 +26,57 @@ fn main() -> Result<(), GlassError> {
 }
 
 #[derive(Default)]
-struct TriangleApp {
-    triangle_pipeline: Option<RenderPipeline>,
+pub struct TriangleApp {
+    triangle_pipeline: Option<HotReloaded<RenderPipeline>>,
+    static_pipeline: Option<RenderPipeline>,
+    /// Draw with the embedded shader instead of the hot reloaded one, toggled with space
+    use_static: bool,
 }
 
 impl GlassApp for TriangleApp {
     fn start(&mut self, _event_loop: &ActiveEventLoop, context: &mut GlassContext) {
-        self.triangle_pipeline = Some(create_triangle_pipeline(context));
+        // Dynamic includes, hot reloaded when the shader or its includes change. Errors are
+        // logged and the previous pipeline is kept.
+        let mut watched = WatchedShaderModule::new(&PathBuf::from(
+            "examples/shader_with_includes/triangle_with_include.wgsl",
+        ))
+        .unwrap();
+        // Pick up new includes too, and wait for editors to finish writing
+        watched.watch_dir(&PathBuf::from("examples/shader_with_includes"));
+        watched.set_debounce(Duration::from_millis(50));
+        let format = context.primary_render_window().surface_format();
+        self.triangle_pipeline = Some(context.register_shader(
+            watched,
+            move |device, shader_module| {
+                let shader = shader_module.create_shader_module(device, None);
+                create_triangle_pipeline(device, &shader, format)
+            },
+        ));
+        // Static includes embedded into the binary, press space to draw with them
+        self.static_pipeline = Some(create_triangle_pipeline(
+            context.device(),
+            &static_shader(context),
+            format,
+        ));
+    }
+
+    fn window_input(
+        &mut self,
+        _context: &mut GlassContext,
+        _event_loop: &ActiveEventLoop,
+        _window_id: WindowId,
+        event: &WindowEvent,
+    ) {
+        if let WindowEvent::KeyboardInput {
+            event, ..
+        } = event
+        {
+            if event.physical_key == PhysicalKey::Code(KeyCode::Space)
+                && event.state == ElementState::Pressed
+            {
+                self.use_static = !self.use_static;
+            }
+        }
     }
 
     fn render(
@@ -55,76 +106,59 @@ impl GlassApp for TriangleApp {
             timestamp_writes: None,
             occlusion_query_set: None,
         });
-        let triangle_pipeline = self.triangle_pipeline.as_ref().unwrap();
-        rpass.set_pipeline(triangle_pipeline);
+        let triangle_pipeline = self.triangle_pipeline.as_ref().unwrap().get();
+        if self.use_static {
+            rpass.set_pipeline(self.static_pipeline.as_ref().unwrap());
+        } else {
+            rpass.set_pipeline(&triangle_pipeline);
+        }
         rpass.draw(0..3, 0..1);
         None
     }
 }
 
-fn create_triangle_pipeline(context: &GlassContext) -> RenderPipeline {
-    // Dynamic includes
-    let _shader_module = ShaderModule::new(&PathBuf::from(
-        "examples/shader_with_includes/triangle_with_include.wgsl",
-    ))
-    .unwrap();
-
-    // Static includes
-    let mut static_includes = HashMap::default();
-    // Include all files that you wish to refer to in your root shader. Tedious, but this ensures
-    // You can keep using includes while containing static shaders.
-    static_includes.insert(
-        "examples/shader_with_includes/triangle_with_include.wgsl",
-        include_str!("triangle_with_include.wgsl"),
-    );
-    static_includes.insert(
-        "examples/shader_with_includes/consts.wgsl",
-        include_str!("consts.wgsl"),
-    );
-    static_includes.insert(
-        "examples/triangle/triangle.wgsl",
-        include_str!("../triangle/triangle.wgsl"),
-    );
-    let shader_module = ShaderModule::new_with_static_sources(
-        "examples/shader_with_includes/triangle_with_include.wgsl",
-        &static_includes,
-    )
-    .unwrap();
-    let shader = context
+fn static_shader(context: &GlassContext) -> wgpu::ShaderModule {
+    // Embeds the root shader and all files it includes into the binary
+    let source =
+        glass::include_shader!("examples/shader_with_includes/triangle_with_include.wgsl").unwrap();
+    let shader_module = ShaderModule::new_from_source(source).unwrap();
+    context
         .device()
         .create_shader_module(ShaderModuleDescriptor {
             label: None,
             source: wgpu::ShaderSource::Naga(Cow::Owned(shader_module.into())),
-        });
-    let layout = context
-        .device()
-        .create_pipeline_layout(&PipelineLayoutDescriptor {
-            label: None,
-            bind_group_layouts: &[],
-            push_constant_ranges: &[],
-        });
-    let pipeline = context
-        .device()
-        .create_render_pipeline(&RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                compilation_options: Default::default(),
-                buffers: &[],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_main"),
-                compilation_options: Default::default(),
-                targets: &[Some(TextureFormat::Bgra8UnormSrgb.into())],
-            }),
-            primitive: PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: MultisampleState::default(),
-            multiview: None,
-            cache: None,
-        });
-    pipeline
+        })
+}
+
+fn create_triangle_pipeline(
+    device: &Device,
+    shader: &wgpu::ShaderModule,
+    format: TextureFormat,
+) -> RenderPipeline {
+    let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: None,
+        bind_group_layouts: &[],
+        push_constant_ranges: &[],
+    });
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: None,
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            compilation_options: Default::default(),
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            compilation_options: Default::default(),
+            targets: &[Some(format.into())],
+        }),
+        primitive: PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
 }
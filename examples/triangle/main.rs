@@ -3,9 +3,14 @@ use std::borrow::Cow;
 use glass::{Glass, GlassApp, GlassConfig, GlassContext, GlassError, RenderData};
 use wgpu::{
     CommandBuffer, MultisampleState, PipelineLayoutDescriptor, PrimitiveState, RenderPipeline,
-    RenderPipelineDescriptor, ShaderModuleDescriptor, StoreOp, TextureFormat,
+    RenderPipelineDescriptor, ShaderModuleDescriptor, StoreOp,
+};
+use winit::{
+    event::{ElementState, WindowEvent},
+    event_loop::ActiveEventLoop,
+    keyboard::{KeyCode, PhysicalKey},
+    window::WindowId,
 };
-use winit::event_loop::ActiveEventLoop;
 
 const WIDTH: u32 = 1920;
 const HEIGHT: u32 = 1080;
@@ -17,7 +22,7 @@ fn main() -> Result<(), GlassError> {
 }
 
 #[derive(Default)]
-struct TriangleApp {
+pub struct TriangleApp {
     triangle_pipeline: Option<RenderPipeline>,
 }
 
@@ -26,6 +31,30 @@ impl GlassApp for TriangleApp {
         self.triangle_pipeline = Some(create_triangle_pipeline(context));
     }
 
+    fn window_input(
+        &mut self,
+        context: &mut GlassContext,
+        _event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: &WindowEvent,
+    ) {
+        // Save a screenshot with F12
+        if let WindowEvent::KeyboardInput {
+            event, ..
+        } = event
+        {
+            if event.physical_key == PhysicalKey::Code(KeyCode::F12)
+                && event.state == ElementState::Pressed
+            {
+                context.capture_frame(window_id, |image| {
+                    if let Err(e) = image.save("triangle.png") {
+                        eprintln!("Failed to save screenshot: {}", e);
+                    }
+                });
+            }
+        }
+    }
+
     fn render(
         &mut self,
         _context: &GlassContext,
@@ -89,7 +118,9 @@ fn create_triangle_pipeline(context: &GlassContext) -> RenderPipeline {
                 module: &shader,
                 entry_point: Some("fs_main"),
                 compilation_options: Default::default(),
-                targets: &[Some(TextureFormat::Bgra8UnormSrgb.into())],
+                targets: &[Some(
+                    context.primary_render_window().surface_format().into(),
+                )],
             }),
             primitive: PrimitiveState::default(),
             depth_stencil: None,
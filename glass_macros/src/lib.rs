@@ -0,0 +1,107 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use proc_macro::{Literal, TokenStream, TokenTree};
+
+/// Embed a wgsl shader and the files it `#include`s, resolving includes at compile time. Paths
+/// are relative to the crate root, like the paths of runtime includes are relative to the
+/// working directory when run with cargo. Expands to
+/// `glass::utils::ShaderSource::new_with_static_sources` with a map of all reachable files, so
+/// the result is a `Result<ShaderSource, ShaderError>`. The path may be a normal or a raw string
+/// literal.
+///
+/// ```ignore
+/// let source = glass::include_shader!("shaders/sprite.wgsl")?;
+/// let module = ShaderModule::new_from_source(source)?;
+/// ```
+#[proc_macro]
+pub fn include_shader(input: TokenStream) -> TokenStream {
+    let root = match parse_path(input) {
+        Ok(root) => root,
+        Err(e) => return compile_error(&e),
+    };
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default());
+    let mut files = vec![];
+    let mut visited = HashSet::new();
+    if let Err(e) = collect_includes(&manifest_dir, &root, &mut visited, &mut files) {
+        return compile_error(&e);
+    }
+    let inserts = files
+        .iter()
+        .map(|(name, path)| {
+            format!(
+                "includes.insert({}, include_str!({}));",
+                Literal::string(name),
+                Literal::string(&path.display().to_string())
+            )
+        })
+        .collect::<String>();
+    format!(
+        "{{
+            let mut includes = ::std::collections::HashMap::<&'static str, &'static str>::new();
+            {}
+            ::glass::utils::ShaderSource::new_with_static_sources({}, &includes)
+        }}",
+        inserts,
+        Literal::string(&root)
+    )
+    .parse()
+    .unwrap()
+}
+
+fn parse_path(input: TokenStream) -> Result<String, String> {
+    let mut tokens = input.into_iter();
+    let (Some(TokenTree::Literal(literal)), None) = (tokens.next(), tokens.next()) else {
+        return Err("include_shader! expects a single path string".to_string());
+    };
+    let literal = literal.to_string();
+    let path = match literal.strip_prefix('r') {
+        // Raw strings, `r"..."` or `r#"..."#`, are taken as is
+        Some(raw) => {
+            let hashes = &raw[..raw.len() - raw.trim_start_matches('#').len()];
+            raw.strip_prefix(hashes)
+                .and_then(|s| s.strip_suffix(hashes))
+                .and_then(|s| s.strip_prefix('"'))
+                .and_then(|s| s.strip_suffix('"'))
+                .map(str::to_string)
+        }
+        None => literal
+            .strip_prefix('"')
+            .and_then(|s| s.strip_suffix('"'))
+            .map(|s| s.replace("\\\\", "\\")),
+    };
+    path.ok_or_else(|| "include_shader! expects a single path string".to_string())
+}
+
+/// Collect `name` and its includes as (include name, absolute path), following the include
+/// parsing of `ShaderSource`. Files included more than once are reported by
+/// `new_with_static_sources` at runtime, so they're only collected once here.
+fn collect_includes(
+    manifest_dir: &Path,
+    name: &str,
+    visited: &mut HashSet<String>,
+    files: &mut Vec<(String, PathBuf)>,
+) -> Result<(), String> {
+    if !visited.insert(name.to_string()) {
+        return Ok(());
+    }
+    let path = manifest_dir.join(name);
+    let source = std::fs::read_to_string(&path)
+        .map_err(|e| format!("include_shader!: {}: {}", path.display(), e))?;
+    files.push((name.to_string(), path));
+    for line in source.lines() {
+        if line.starts_with("#include") {
+            let included = line.trim_start_matches("#include ").trim();
+            collect_includes(manifest_dir, included, visited, files)?;
+        }
+    }
+    Ok(())
+}
+
+fn compile_error(message: &str) -> TokenStream {
+    format!("compile_error!({})", Literal::string(message))
+        .parse()
+        .unwrap()
+}
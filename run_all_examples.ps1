@@ -5,4 +5,6 @@ cargo run --example quad
 cargo run --example multiple_windows
 cargo run --example game_of_life
 cargo run --example lines
+cargo run --example headless_compute
 cargo run --example egui_gui --features "egui_gui"
+cargo run --example glass-gallery --features "egui_gui"
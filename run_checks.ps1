@@ -1,3 +1,4 @@
 cargo fmt -- --check --color always
 cargo clippy --all-targets -- -D warnings
-cargo test --workspace
\ No newline at end of file
+cargo test --workspace
+cargo clippy --all-targets --features glsl -- -D warnings
@@ -1,2 +1,3 @@
 cargo fmt -- --check --color always
-cargo clippy --all-targets -- -D warnings
\ No newline at end of file
+cargo clippy --all-targets -- -D warnings
+cargo clippy --all-targets --features glsl -- -D warnings
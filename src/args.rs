@@ -0,0 +1,261 @@
+use wgpu::{Backends, PresentMode};
+
+use crate::{
+    locale::{format_message, message, Message},
+    window::WindowPos,
+    GlassConfig, GlassError,
+};
+
+impl GlassConfig {
+    /// Default config with command line arguments applied, see [`GlassConfig::with_args`].
+    pub fn from_args() -> Result<GlassConfig, GlassError> {
+        GlassConfig::default().with_args()
+    }
+
+    /// Apply the process' command line arguments over this config, see
+    /// [`GlassConfig::merge_args`].
+    pub fn with_args(self) -> Result<GlassConfig, GlassError> {
+        self.merge_args(std::env::args().skip(1))
+    }
+
+    /// Apply standard glass options over this config. Window options apply to the first window,
+    /// vsync applies to all windows. Unknown arguments are ignored so apps can parse their own.
+    /// Both `--width 800` and `--width=800` are accepted.
+    ///
+    /// Options: `--windowed`, `--fullscreen`, `--width`, `--height`, `--backend`, `--vsync`,
+    /// `--no-vsync`, `--monitor`. `--fullscreen` with `--monitor` goes borderless fullscreen on
+    /// that monitor.
+    pub fn merge_args<I, S>(mut self, args: I) -> Result<GlassConfig, GlassError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut args = args.into_iter().map(Into::into).peekable();
+        // Window placement is applied after parsing so --monitor and --fullscreen combine
+        let mut fullscreen = None;
+        let mut monitor = None;
+        while let Some(arg) = args.next() {
+            let (flag, mut value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg, None),
+            };
+            let mut take_value = |value: &mut Option<String>| {
+                value
+                    .take()
+                    .or_else(|| args.next_if(|next| !next.starts_with("--")))
+                    .ok_or_else(|| {
+                        argument_error(format_message(Message::ArgumentRequiresValue, &[(
+                            "flag", &flag,
+                        )]))
+                    })
+            };
+            match flag.as_str() {
+                "--windowed" => fullscreen = Some(false),
+                "--fullscreen" => fullscreen = Some(true),
+                "--width" => {
+                    let width = parse_number(&flag, &take_value(&mut value)?)?;
+                    if let Some(window) = self.window_configs.first_mut() {
+                        window.width = width;
+                    }
+                }
+                "--height" => {
+                    let height = parse_number(&flag, &take_value(&mut value)?)?;
+                    if let Some(window) = self.window_configs.first_mut() {
+                        window.height = height;
+                    }
+                }
+                "--backend" => {
+                    self.device_config.backends = parse_backend(&take_value(&mut value)?)?;
+                }
+                "--vsync" => {
+                    let vsync = match value
+                        .take()
+                        .or_else(|| args.next_if(|next| matches!(next.as_str(), "on" | "off")))
+                    {
+                        None => true,
+                        Some(v) if v == "on" => true,
+                        Some(v) if v == "off" => false,
+                        Some(v) => {
+                            return Err(argument_error(format_message(
+                                Message::ArgumentInvalidValue,
+                                &[("value", &v), ("flag", &"--vsync")],
+                            )))
+                        }
+                    };
+                    self.set_vsync(vsync);
+                }
+                "--no-vsync" => self.set_vsync(false),
+                "--monitor" => {
+                    monitor = Some(parse_number(&flag, &take_value(&mut value)?)? as usize);
+                }
+                _ => (),
+            }
+        }
+        if let Some(window) = self.window_configs.first_mut() {
+            let fullscreen = fullscreen.unwrap_or_else(|| is_fullscreen(window.pos));
+            match (fullscreen, monitor) {
+                (true, Some(index)) => window.pos = WindowPos::FullScreenBorderlessOnMonitor(index),
+                (false, Some(index)) => {
+                    window.pos = WindowPos::OnMonitor {
+                        index,
+                        pos: None,
+                    }
+                }
+                (true, None) if !is_fullscreen(window.pos) => {
+                    window.pos = WindowPos::FullScreenBorderless
+                }
+                (false, None) if is_fullscreen(window.pos) => window.pos = WindowPos::Centered,
+                _ => (),
+            }
+        }
+        Ok(self)
+    }
+
+    fn set_vsync(&mut self, vsync: bool) {
+        for window in self.window_configs.iter_mut() {
+            window.present_mode = if vsync {
+                PresentMode::AutoVsync
+            } else {
+                PresentMode::AutoNoVsync
+            };
+        }
+    }
+}
+
+fn is_fullscreen(pos: WindowPos) -> bool {
+    matches!(
+        pos,
+        WindowPos::FullScreen
+            | WindowPos::SizedFullScreen
+            | WindowPos::FullScreenBorderless
+            | WindowPos::FullScreenBorderlessOnMonitor(_)
+    )
+}
+
+fn parse_number(flag: &str, value: &str) -> Result<u32, GlassError> {
+    value.parse().map_err(|_| {
+        argument_error(format_message(Message::ArgumentInvalidValue, &[
+            ("value", &value),
+            ("flag", &flag),
+        ]))
+    })
+}
+
+fn parse_backend(value: &str) -> Result<Backends, GlassError> {
+    Ok(match value.to_lowercase().as_str() {
+        "vulkan" | "vk" => Backends::VULKAN,
+        "metal" => Backends::METAL,
+        "dx12" | "d3d12" => Backends::DX12,
+        "gl" | "opengl" | "gles" => Backends::GL,
+        "webgpu" => Backends::BROWSER_WEBGPU,
+        "primary" => Backends::PRIMARY,
+        "all" => Backends::all(),
+        _ => {
+            return Err(argument_error(format_message(
+                Message::ArgumentUnknownBackend,
+                &[("value", &value)],
+            )))
+        }
+    })
+}
+
+fn argument_error(error: String) -> GlassError {
+    GlassError::ArgumentError(format!("{}\n{}", error, message(Message::ArgumentUsage)))
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu::{Backends, PresentMode};
+
+    use crate::{window::WindowPos, GlassConfig, GlassError};
+
+    fn parse(args: &[&str]) -> Result<GlassConfig, GlassError> {
+        GlassConfig::default().merge_args(args.iter().copied())
+    }
+
+    #[test]
+    fn test_value_forms() {
+        let config = parse(&["--width=800", "--height", "600"]).unwrap();
+        assert_eq!(config.window_configs[0].width, 800);
+        assert_eq!(config.window_configs[0].height, 600);
+        let config = parse(&["--width", "800", "--backend=vk"]).unwrap();
+        assert_eq!(config.window_configs[0].width, 800);
+        assert_eq!(config.device_config.backends, Backends::VULKAN);
+    }
+
+    #[test]
+    fn test_unknown_flags_ignored() {
+        let config = parse(&["--my-app-flag", "--width", "800", "input.txt"]).unwrap();
+        assert_eq!(config.window_configs[0].width, 800);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            parse(&["--width", "wide"]),
+            Err(GlassError::ArgumentError(_))
+        ));
+        assert!(matches!(
+            parse(&["--width"]),
+            Err(GlassError::ArgumentError(_))
+        ));
+        assert!(matches!(
+            parse(&["--width", "--height", "600"]),
+            Err(GlassError::ArgumentError(_))
+        ));
+        assert!(matches!(
+            parse(&["--vsync=sometimes"]),
+            Err(GlassError::ArgumentError(_))
+        ));
+        assert!(matches!(
+            parse(&["--backend", "glide"]),
+            Err(GlassError::ArgumentError(_))
+        ));
+    }
+
+    #[test]
+    fn test_vsync() {
+        let config = parse(&["--no-vsync"]).unwrap();
+        assert_eq!(
+            config.window_configs[0].present_mode,
+            PresentMode::AutoNoVsync
+        );
+        let config = parse(&["--no-vsync", "--vsync"]).unwrap();
+        assert_eq!(
+            config.window_configs[0].present_mode,
+            PresentMode::AutoVsync
+        );
+        let config = parse(&["--vsync", "off"]).unwrap();
+        assert_eq!(
+            config.window_configs[0].present_mode,
+            PresentMode::AutoNoVsync
+        );
+    }
+
+    #[test]
+    fn test_window_placement() {
+        let pos = |args: &[&str]| parse(args).unwrap().window_configs[0].pos;
+        assert!(matches!(
+            pos(&["--fullscreen"]),
+            WindowPos::FullScreenBorderless
+        ));
+        assert!(matches!(
+            pos(&["--fullscreen", "--monitor", "1"]),
+            WindowPos::FullScreenBorderlessOnMonitor(1)
+        ));
+        assert!(matches!(
+            pos(&["--monitor=1", "--fullscreen"]),
+            WindowPos::FullScreenBorderlessOnMonitor(1)
+        ));
+        assert!(matches!(pos(&["--monitor", "2"]), WindowPos::OnMonitor {
+            index: 2,
+            pos: None
+        }));
+        assert!(matches!(
+            pos(&["--fullscreen", "--windowed"]),
+            WindowPos::Centered
+        ));
+    }
+}
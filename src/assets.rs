@@ -0,0 +1,293 @@
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread::JoinHandle,
+};
+
+use flume::{unbounded, Receiver, Sender};
+use image::DynamicImage;
+use log::error;
+use wgpu::{Device, Queue, TextureFormat, TextureUsages};
+
+use crate::{texture::Texture, GlassError};
+
+enum AssetSource {
+    Path(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+struct LoadRequest {
+    handle: TextureHandle,
+    source: AssetSource,
+}
+
+struct LoadResult {
+    handle: TextureHandle,
+    image: Result<DynamicImage, GlassError>,
+}
+
+struct TextureParams {
+    label: String,
+    format: TextureFormat,
+    usage: TextureUsages,
+    mip_count: u32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LoadState {
+    Loading,
+    Loaded,
+    /// Loading failed and the handle resolved to [`Texture::missing`]
+    Failed,
+}
+
+struct HandleInner {
+    params: TextureParams,
+    texture: RwLock<(Arc<Texture>, LoadState)>,
+}
+
+/// A texture that is being loaded by an [`AssetLoader`]. Resolves from a 1x1 transparent
+/// placeholder to the loaded texture in [`AssetLoader::poll`]. Cheap to clone.
+#[derive(Clone)]
+pub struct TextureHandle(Arc<HandleInner>);
+
+impl TextureHandle {
+    /// The loaded texture, or the placeholder while loading. Bind groups created from the
+    /// placeholder must be recreated once the handle has resolved.
+    pub fn texture(&self) -> Arc<Texture> {
+        self.0.texture.read().unwrap().0.clone()
+    }
+
+    pub fn state(&self) -> LoadState {
+        self.0.texture.read().unwrap().1
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.state() == LoadState::Loaded
+    }
+
+    pub fn label(&self) -> &str {
+        &self.0.params.label
+    }
+
+    fn resolve(&self, texture: Texture, state: LoadState) {
+        *self.0.texture.write().unwrap() = (Arc::new(texture), state);
+    }
+}
+
+/// Loads and decodes images on background threads so large images don't block the frame. Decoded
+/// images are uploaded on the thread calling [`AssetLoader::poll`], usually once per frame
+/// before rendering.
+///
+/// ```ignore
+/// let handle = loader.load("assets/tree.png", "tree", format, usage, 1);
+/// // Each frame
+/// for resolved in loader.poll() {
+///     if resolved == handle {
+///         self.bind_group = quad.create_bind_group(device, &handle.texture().views[0], sampler);
+///     }
+/// }
+/// ```
+pub struct AssetLoader {
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    placeholder: Arc<Texture>,
+    requests: Option<Sender<LoadRequest>>,
+    results: Receiver<LoadResult>,
+    workers: Vec<JoinHandle<()>>,
+    /// Tells workers to skip remaining requests when the loader is dropped
+    stop: Arc<AtomicBool>,
+    pending: usize,
+}
+
+impl AssetLoader {
+    /// Start a loader decoding on `threads` worker threads
+    pub fn new(device: Arc<Device>, queue: Arc<Queue>, threads: usize) -> AssetLoader {
+        let (request_tx, request_rx) = unbounded::<LoadRequest>();
+        let (result_tx, result_rx) = unbounded::<LoadResult>();
+        let stop = Arc::new(AtomicBool::new(false));
+        let workers = (0..threads.max(1))
+            .map(|i| {
+                let requests = request_rx.clone();
+                let results = result_tx.clone();
+                let stop = stop.clone();
+                std::thread::Builder::new()
+                    .name(format!("glass_asset_loader_{}", i))
+                    .spawn(move || {
+                        for request in requests.iter() {
+                            if stop.load(Ordering::Relaxed) {
+                                break;
+                            }
+                            // A panicking decoder fails the load instead of leaving it pending
+                            let source = request.source;
+                            let image = catch_unwind(AssertUnwindSafe(|| decode(source)))
+                                .unwrap_or_else(|_| {
+                                    Err(GlassError::IoError(std::io::Error::other(
+                                        "Image decoding panicked",
+                                    )))
+                                });
+                            let result = LoadResult {
+                                handle: request.handle,
+                                image,
+                            };
+                            if results.send(result).is_err() {
+                                break;
+                            }
+                        }
+                    })
+                    .expect("Failed to spawn asset loader thread")
+            })
+            .collect();
+        let placeholder = Arc::new(Texture::from_image(
+            &device,
+            &queue,
+            &DynamicImage::ImageRgba8(image::RgbaImage::new(1, 1)),
+            "asset_placeholder",
+            TextureFormat::Rgba8UnormSrgb,
+            TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            1,
+        ));
+        AssetLoader {
+            device,
+            queue,
+            placeholder,
+            requests: Some(request_tx),
+            results: result_rx,
+            workers,
+            stop,
+            pending: 0,
+        }
+    }
+
+    /// Load an image file in the background
+    pub fn load(
+        &mut self,
+        path: impl Into<PathBuf>,
+        label: &str,
+        format: TextureFormat,
+        usage: TextureUsages,
+        mip_count: u32,
+    ) -> TextureHandle {
+        self.request(
+            AssetSource::Path(path.into()),
+            label,
+            format,
+            usage,
+            mip_count,
+        )
+    }
+
+    /// Decode encoded image bytes in the background
+    pub fn load_bytes(
+        &mut self,
+        bytes: Vec<u8>,
+        label: &str,
+        format: TextureFormat,
+        usage: TextureUsages,
+        mip_count: u32,
+    ) -> TextureHandle {
+        self.request(AssetSource::Bytes(bytes), label, format, usage, mip_count)
+    }
+
+    fn request(
+        &mut self,
+        source: AssetSource,
+        label: &str,
+        format: TextureFormat,
+        usage: TextureUsages,
+        mip_count: u32,
+    ) -> TextureHandle {
+        let handle = TextureHandle(Arc::new(HandleInner {
+            params: TextureParams {
+                label: label.to_string(),
+                format,
+                usage,
+                mip_count,
+            },
+            texture: RwLock::new((self.placeholder.clone(), LoadState::Loading)),
+        }));
+        self.requests
+            .as_ref()
+            .unwrap()
+            .send(LoadRequest {
+                handle: handle.clone(),
+                source,
+            })
+            .expect("Asset loader threads have stopped");
+        self.pending += 1;
+        handle
+    }
+
+    /// Upload images decoded since the last poll and resolve their handles. Failed loads are
+    /// logged and resolve to [`Texture::missing`]. Returns the resolved handles.
+    pub fn poll(&mut self) -> Vec<TextureHandle> {
+        let mut resolved = vec![];
+        for result in self.results.try_iter() {
+            let params = &result.handle.0.params;
+            match result.image {
+                Ok(image) => {
+                    let texture = Texture::from_image(
+                        &self.device,
+                        &self.queue,
+                        &image,
+                        &params.label,
+                        params.format,
+                        params.usage,
+                        params.mip_count,
+                    );
+                    result.handle.resolve(texture, LoadState::Loaded);
+                }
+                Err(e) => {
+                    error!("Failed to load texture {}: {}", params.label, e);
+                    let texture = Texture::missing(&self.device, &self.queue);
+                    result.handle.resolve(texture, LoadState::Failed);
+                }
+            }
+            resolved.push(result.handle);
+        }
+        self.pending -= resolved.len();
+        resolved
+    }
+
+    /// Number of requested textures that haven't resolved yet
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    /// The texture unloaded handles resolve to
+    pub fn placeholder(&self) -> &Arc<Texture> {
+        &self.placeholder
+    }
+}
+
+impl Drop for AssetLoader {
+    fn drop(&mut self) {
+        // Workers finish their current image and skip the queued ones, closing the channel wakes
+        // up idle workers
+        self.stop.store(true, Ordering::Relaxed);
+        self.requests = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl PartialEq for TextureHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for TextureHandle {}
+
+fn decode(source: AssetSource) -> Result<DynamicImage, GlassError> {
+    let bytes = match source {
+        AssetSource::Path(path) => std::fs::read(path).map_err(GlassError::IoError)?,
+        AssetSource::Bytes(bytes) => bytes,
+    };
+    image::load_from_memory(&bytes).map_err(GlassError::ImageError)
+}
@@ -0,0 +1,512 @@
+use wgpu::Device;
+use winit::{
+    event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent},
+    window::{Window, WindowId},
+};
+
+use crate::{
+    input::{Input, PIXELS_PER_SCROLL_LINE},
+    resize::Resizable,
+    transform::mul,
+};
+
+/// Orthographic 2D camera. `pos` is the world position at the center of the screen and `scale`
+/// is the number of world units per screen pixel, so larger scales zoom out. World y points up,
+/// screen y down.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Camera2D {
+    pub pos: [f32; 2],
+    pub scale: f32,
+    /// Counter-clockwise rotation of the camera in radians. The world appears rotated clockwise.
+    pub rotation: f32,
+    /// Round the position to whole screen pixels, so pixel art doesn't shimmer while the camera
+    /// moves. Meant for unrotated cameras.
+    pub pixel_snap: bool,
+    /// Screen size in physical pixels
+    pub size: [f32; 2],
+}
+
+impl Camera2D {
+    pub fn new(size: [f32; 2]) -> Camera2D {
+        Camera2D {
+            pos: [0.0; 2],
+            scale: 1.0,
+            rotation: 0.0,
+            pixel_snap: false,
+            size,
+        }
+    }
+
+    /// View projection of a screen sized area centered at the origin, one world unit per pixel
+    pub fn projection(size: [f32; 2]) -> [[f32; 4]; 4] {
+        Self::new(size).view_proj()
+    }
+
+    pub fn for_window(window: &Window) -> Camera2D {
+        let size = window.inner_size();
+        Self::new([size.width as f32, size.height as f32])
+    }
+
+    /// Position the view is centered at, rounded to whole screen pixels if
+    /// [`Camera2D::pixel_snap`] is set
+    pub fn view_pos(&self) -> [f32; 2] {
+        if !self.pixel_snap {
+            return self.pos;
+        }
+        // With an odd screen size the center is in the middle of a pixel
+        let snap = |pos: f32, size: f32| {
+            let offset = (size / 2.0).fract();
+            ((pos / self.scale - offset).round() + offset) * self.scale
+        };
+        [
+            snap(self.pos[0], self.size[0]),
+            snap(self.pos[1], self.size[1]),
+        ]
+    }
+
+    /// Column major view matrix, moving and rotating the world into camera space
+    pub fn view(&self) -> [[f32; 4]; 4] {
+        let [x, y] = self.view_pos();
+        let (sin, cos) = self.rotation.sin_cos();
+        [
+            [cos, -sin, 0.0, 0.0],
+            [sin, cos, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [-(cos * x + sin * y), sin * x - cos * y, 0.0, 1.0],
+        ]
+    }
+
+    /// Column major orthographic projection matrix with wgpu's depth range. Depth 0 maps to the
+    /// middle of the range, leaving room for layering in -1000..1000.
+    pub fn proj(&self) -> [[f32; 4]; 4] {
+        [
+            [2.0 / (self.size[0] * self.scale), 0.0, 0.0, 0.0],
+            [0.0, 2.0 / (self.size[1] * self.scale), 0.0, 0.0],
+            [0.0, 0.0, -1.0 / 2000.0, 0.0],
+            [0.0, 0.0, 0.5, 1.0],
+        ]
+    }
+
+    pub fn view_proj(&self) -> [[f32; 4]; 4] {
+        mul(self.proj(), self.view())
+    }
+
+    pub fn screen_to_world(&self, screen: [f32; 2]) -> [f32; 2] {
+        let pos = self.view_pos();
+        let offset = [
+            (screen[0] - self.size[0] / 2.0) * self.scale,
+            -(screen[1] - self.size[1] / 2.0) * self.scale,
+        ];
+        let [x, y] = rotate2(offset, self.rotation);
+        [pos[0] + x, pos[1] + y]
+    }
+
+    pub fn world_to_screen(&self, world: [f32; 2]) -> [f32; 2] {
+        let pos = self.view_pos();
+        let [x, y] = rotate2([world[0] - pos[0], world[1] - pos[1]], -self.rotation);
+        [
+            x / self.scale + self.size[0] / 2.0,
+            -y / self.scale + self.size[1] / 2.0,
+        ]
+    }
+
+    /// Move the camera so the world follows a drag of `delta` screen pixels
+    pub fn pan(&mut self, delta: [f32; 2]) {
+        let [x, y] = rotate2(
+            [-delta[0] * self.scale, delta[1] * self.scale],
+            self.rotation,
+        );
+        self.pos[0] += x;
+        self.pos[1] += y;
+    }
+}
+
+impl Resizable for Camera2D {
+    fn resize(&mut self, _device: &Device, size: [u32; 2]) {
+        self.size = [size[0] as f32, size[1] as f32];
+    }
+}
+
+/// Drives a [`Camera2D`]: drag to pan, wheel to zoom towards the cursor and smoothly follow a
+/// target. Either feed it events from [`GlassApp::window_input`](crate::GlassApp::window_input)
+/// with [`CameraController2D::handle_event`], or call [`CameraController2D::update`] once per
+/// frame with [`GlassContext::input`](crate::GlassContext::input).
+///
+/// ```ignore
+/// // In update
+/// self.controller.set_follow_target(Some(self.player.pos));
+/// self.controller.update(&mut self.camera, context.input(), window_id, dt);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CameraController2D {
+    pub pan_button: MouseButton,
+    /// Scale multiplier per wheel line
+    pub zoom_speed: f32,
+    pub min_scale: f32,
+    pub max_scale: f32,
+    /// Min and max world position the camera center is clamped to
+    pub bounds: Option<[[f32; 2]; 2]>,
+    /// How quickly the camera catches up with the follow target. The remaining distance shrinks
+    /// to `1 / e` in `1 / follow_speed` seconds.
+    pub follow_speed: f32,
+    cursor: Option<[f32; 2]>,
+    panning: bool,
+    follow_target: Option<[f32; 2]>,
+}
+
+impl Default for CameraController2D {
+    fn default() -> Self {
+        Self {
+            pan_button: MouseButton::Middle,
+            zoom_speed: 1.1,
+            min_scale: 0.01,
+            max_scale: 100.0,
+            bounds: None,
+            follow_speed: 5.0,
+            cursor: None,
+            panning: false,
+            follow_target: None,
+        }
+    }
+}
+
+impl CameraController2D {
+    pub fn new() -> CameraController2D {
+        Self::default()
+    }
+
+    /// Last cursor position in physical pixels
+    pub fn cursor(&self) -> Option<[f32; 2]> {
+        self.cursor
+    }
+
+    pub fn is_panning(&self) -> bool {
+        self.panning
+    }
+
+    /// World position [`CameraController2D::update`] moves the camera towards. Following pauses
+    /// while panning.
+    pub fn set_follow_target(&mut self, target: Option<[f32; 2]>) {
+        self.follow_target = target;
+    }
+
+    pub fn follow_target(&self) -> Option<[f32; 2]> {
+        self.follow_target
+    }
+
+    /// Pan while the pan button is held, zoom towards the cursor with the wheel and move towards
+    /// the follow target. Input is only used while the cursor is over `window_id`, the window
+    /// the camera renders to. `dt` is the frame time in seconds. Call once per frame in update.
+    /// Returns whether the camera changed.
+    pub fn update(
+        &mut self,
+        camera: &mut Camera2D,
+        input: &Input,
+        window_id: WindowId,
+        dt: f32,
+    ) -> bool {
+        let mut changed = false;
+        let cursor = input
+            .cursor_position()
+            .filter(|_| input.cursor_window() == Some(window_id));
+        let prev = std::mem::replace(&mut self.cursor, cursor);
+        self.panning = cursor.is_some() && input.pressed(self.pan_button);
+        if let (true, Some(pos), Some(prev)) = (self.panning, cursor, prev) {
+            if pos != prev {
+                camera.pan([pos[0] - prev[0], pos[1] - prev[1]]);
+                changed = true;
+            }
+        }
+        let lines = input.scroll_delta()[1] / PIXELS_PER_SCROLL_LINE;
+        if cursor.is_some() && lines != 0.0 {
+            self.zoom(camera, self.zoom_speed.powf(-lines));
+            changed = true;
+        }
+        if let (false, Some(target)) = (self.panning, self.follow_target) {
+            if target != camera.pos {
+                let t = 1.0 - (-self.follow_speed * dt).exp();
+                camera.pos[0] += (target[0] - camera.pos[0]) * t;
+                camera.pos[1] += (target[1] - camera.pos[1]) * t;
+                changed = true;
+            }
+        }
+        if changed {
+            self.clamp(camera);
+        }
+        changed
+    }
+
+    /// Update the camera from an event. Returns whether the camera changed.
+    pub fn handle_event(&mut self, camera: &mut Camera2D, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::CursorMoved {
+                position, ..
+            } => {
+                let pos = [position.x as f32, position.y as f32];
+                let prev = self.cursor.replace(pos);
+                if let (true, Some(prev)) = (self.panning, prev) {
+                    camera.pan([pos[0] - prev[0], pos[1] - prev[1]]);
+                    self.clamp(camera);
+                    return true;
+                }
+            }
+            WindowEvent::CursorLeft {
+                ..
+            } => {
+                self.cursor = None;
+                self.panning = false;
+            }
+            WindowEvent::MouseInput {
+                state,
+                button,
+                ..
+            } if *button == self.pan_button => {
+                self.panning = *state == ElementState::Pressed;
+            }
+            WindowEvent::MouseWheel {
+                delta, ..
+            } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(p) => p.y as f32 / PIXELS_PER_SCROLL_LINE,
+                };
+                if lines != 0.0 {
+                    self.zoom(camera, self.zoom_speed.powf(-lines));
+                    return true;
+                }
+            }
+            _ => (),
+        }
+        false
+    }
+
+    /// Pan with touch drags and zoom by pinching, from
+    /// [`GlassContext::input`](crate::GlassContext::input). Call once per frame in update. Returns
+    /// whether the camera changed.
+    pub fn handle_touch(&self, camera: &mut Camera2D, input: &Input) -> bool {
+        let [dx, dy] = input.pan_delta();
+        let pinch = input.pinch_scale();
+        if dx == 0.0 && dy == 0.0 && pinch == 1.0 {
+            return false;
+        }
+        camera.pan([dx, dy]);
+        if let Some(center) = input.pinch_center() {
+            self.zoom_at(camera, 1.0 / pinch, center);
+        }
+        self.clamp(camera);
+        true
+    }
+
+    /// Multiply camera scale, keeping the world point under the cursor fixed
+    pub fn zoom(&self, camera: &mut Camera2D, factor: f32) {
+        let center = [camera.size[0] / 2.0, camera.size[1] / 2.0];
+        self.zoom_at(camera, factor, self.cursor.unwrap_or(center));
+    }
+
+    /// Multiply camera scale, keeping the world point at screen position `anchor` fixed
+    pub fn zoom_at(&self, camera: &mut Camera2D, factor: f32, anchor: [f32; 2]) {
+        // Snapping would make the anchor jitter
+        let mut unsnapped = Camera2D {
+            pixel_snap: false,
+            ..*camera
+        };
+        let before = unsnapped.screen_to_world(anchor);
+        unsnapped.scale = (camera.scale * factor).clamp(self.min_scale, self.max_scale);
+        let after = unsnapped.screen_to_world(anchor);
+        camera.scale = unsnapped.scale;
+        camera.pos[0] += before[0] - after[0];
+        camera.pos[1] += before[1] - after[1];
+        self.clamp(camera);
+    }
+
+    fn clamp(&self, camera: &mut Camera2D) {
+        if let Some([min, max]) = self.bounds {
+            camera.pos[0] = camera.pos[0].clamp(min[0], max[0]);
+            camera.pos[1] = camera.pos[1].clamp(min[1], max[1]);
+        }
+    }
+}
+
+/// Perspective 3D camera looking from `pos` at `target`. Right handed, y up.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Camera3D {
+    pub pos: [f32; 3],
+    pub target: [f32; 3],
+    pub up: [f32; 3],
+    /// Vertical field of view in radians
+    pub fov_y: f32,
+    /// Width divided by height of the screen
+    pub aspect: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Camera3D {
+    pub fn new(pos: [f32; 3], target: [f32; 3], size: [f32; 2]) -> Camera3D {
+        Camera3D {
+            pos,
+            target,
+            up: [0.0, 1.0, 0.0],
+            fov_y: 45f32.to_radians(),
+            aspect: size[0] / size[1].max(1.0),
+            near: 0.1,
+            far: 1000.0,
+        }
+    }
+
+    /// Column major view matrix
+    pub fn view(&self) -> [[f32; 4]; 4] {
+        let f = normalize3(sub3(self.target, self.pos));
+        let s = normalize3(cross3(f, self.up));
+        let u = cross3(s, f);
+        [
+            [s[0], u[0], -f[0], 0.0],
+            [s[1], u[1], -f[1], 0.0],
+            [s[2], u[2], -f[2], 0.0],
+            [
+                -dot3(s, self.pos),
+                -dot3(u, self.pos),
+                dot3(f, self.pos),
+                1.0,
+            ],
+        ]
+    }
+
+    /// Column major projection matrix with wgpu's 0..1 depth range
+    pub fn proj(&self) -> [[f32; 4]; 4] {
+        let f = 1.0 / (self.fov_y * 0.5).tan();
+        let range = self.near - self.far;
+        [
+            [f / self.aspect, 0.0, 0.0, 0.0],
+            [0.0, f, 0.0, 0.0],
+            [0.0, 0.0, self.far / range, -1.0],
+            [0.0, 0.0, self.near * self.far / range, 0.0],
+        ]
+    }
+
+    pub fn view_proj(&self) -> [[f32; 4]; 4] {
+        mul(self.proj(), self.view())
+    }
+}
+
+impl Resizable for Camera3D {
+    fn resize(&mut self, _device: &Device, size: [u32; 2]) {
+        self.aspect = size[0] as f32 / size[1].max(1) as f32;
+    }
+}
+
+/// Rotate counter-clockwise by `angle` radians
+fn rotate2(v: [f32; 2], angle: f32) -> [f32; 2] {
+    let (sin, cos) = angle.sin_cos();
+    [cos * v[0] - sin * v[1], sin * v[0] + cos * v[1]]
+}
+
+fn sub3(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot3(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross3(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize3(a: [f32; 3]) -> [f32; 3] {
+    let len = dot3(a, a).sqrt();
+    if len > 0.0 {
+        [a[0] / len, a[1] / len, a[2] / len]
+    } else {
+        a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        camera::{Camera2D, Camera3D},
+        transform::transform_point,
+    };
+
+    fn assert_near(a: &[f32], b: &[f32]) {
+        assert!(
+            a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-3),
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    /// Column major matrix times `[x, y, z, 1]`, divided by w
+    fn project(m: [[f32; 4]; 4], p: [f32; 3]) -> [f32; 3] {
+        let v = [p[0], p[1], p[2], 1.0];
+        let row = |r: usize| (0..4).map(|c| m[c][r] * v[c]).sum::<f32>();
+        let w = row(3);
+        [row(0) / w, row(1) / w, row(2) / w]
+    }
+
+    fn camera_2d() -> Camera2D {
+        Camera2D {
+            pos: [120.0, -40.0],
+            scale: 2.0,
+            rotation: 0.6,
+            ..Camera2D::new([800.0, 600.0])
+        }
+    }
+
+    #[test]
+    fn test_screen_to_world_round_trip() {
+        let camera = camera_2d();
+        for screen in [[0.0, 0.0], [400.0, 300.0], [799.0, 13.0], [250.5, 599.0]] {
+            let world = camera.screen_to_world(screen);
+            assert_near(&camera.world_to_screen(world), &screen);
+        }
+        assert_near(&camera.screen_to_world([400.0, 300.0]), &camera.pos);
+    }
+
+    #[test]
+    fn test_view_proj_matches_world_to_screen() {
+        let camera = camera_2d();
+        for world in [[0.0, 0.0], [120.0, -40.0], [-300.0, 500.0]] {
+            let [x, y] = camera.world_to_screen(world);
+            let ndc = [x / 400.0 - 1.0, 1.0 - y / 300.0];
+            assert_near(&transform_point(&camera.view_proj(), world), &ndc);
+        }
+        // Depth 0 is in the middle of the range
+        assert_near(&project(camera.view_proj(), [0.0, 0.0, 0.0])[2..], &[0.5]);
+    }
+
+    #[test]
+    fn test_projection_is_pixel_sized() {
+        let view_proj = Camera2D::projection([800.0, 600.0]);
+        assert_near(&transform_point(&view_proj, [400.0, 300.0]), &[1.0, 1.0]);
+        assert_near(&transform_point(&view_proj, [-400.0, -300.0]), &[
+            -1.0, -1.0,
+        ]);
+    }
+
+    #[test]
+    fn test_camera_3d_view_proj() {
+        let camera = Camera3D::new([0.0, 0.0, 5.0], [0.0; 3], [800.0, 600.0]);
+        // The target is in the center, near and far map to wgpu's 0..1 depth
+        assert_near(&project(camera.view_proj(), [0.0; 3])[..2], &[0.0, 0.0]);
+        assert_near(&project(camera.view_proj(), [0.0, 0.0, 4.9]), &[
+            0.0, 0.0, 0.0,
+        ]);
+        assert_near(&project(camera.view_proj(), [0.0, 0.0, -995.0]), &[
+            0.0, 0.0, 1.0,
+        ]);
+        // Right handed, y up: +x is right and +y is up on screen
+        let right = project(camera.view_proj(), [1.0, 0.0, 0.0]);
+        let up = project(camera.view_proj(), [0.0, 1.0, 0.0]);
+        assert!(right[0] > 0.0 && up[1] > 0.0);
+        // The vertical field of view spans ndc -1..1
+        let edge = 5.0 * (camera.fov_y * 0.5).tan();
+        assert_near(&project(camera.view_proj(), [0.0, edge, 0.0])[1..2], &[1.0]);
+    }
+}
@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use image::RgbaImage;
+use log::{error, warn};
+use wgpu::{
+    Buffer, BufferDescriptor, BufferUsages, CommandEncoder, Device, ImageCopyBuffer,
+    ImageCopyTexture, ImageDataLayout, MapMode, Origin3d, Texture, TextureAspect, TextureFormat,
+};
+
+use crate::utils::padded_bytes_per_row;
+
+/// Called with the captured frame once its readback has completed
+pub type CaptureCallback = Box<dyn FnOnce(RgbaImage) + Send>;
+
+/// A frame copy that has been encoded but not yet read back
+pub(crate) struct PendingCapture {
+    buffer: Arc<Buffer>,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+    format: TextureFormat,
+}
+
+impl PendingCapture {
+    /// Encode a copy of `texture` (e.g. a surface frame) into a readback buffer. The texture
+    /// needs `COPY_SRC` usage. `reuse` is used as the readback buffer if its size matches.
+    /// Returns `None` if the format can't be converted to rgba8.
+    pub(crate) fn copy(
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        texture: &Texture,
+        reuse: Option<Arc<Buffer>>,
+    ) -> Option<PendingCapture> {
+        let format = texture.format();
+        if !is_capture_format_supported(format) {
+            warn!("Capturing frames of format {:?} is not supported", format);
+            return None;
+        }
+        let size = texture.size();
+        let bytes_per_pixel = format.block_copy_size(None)?;
+        let padded_bytes_per_row = padded_bytes_per_row(size.width * bytes_per_pixel);
+        let buffer_size = (padded_bytes_per_row * size.height) as u64;
+        let buffer = reuse
+            .filter(|buffer| buffer.size() == buffer_size)
+            .unwrap_or_else(|| {
+                Arc::new(device.create_buffer(&BufferDescriptor {
+                    label: Some("frame_capture_buffer"),
+                    size: buffer_size,
+                    usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }))
+            });
+        encoder.copy_texture_to_buffer(
+            ImageCopyTexture {
+                texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            ImageCopyBuffer {
+                buffer: &buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            size,
+        );
+        Some(PendingCapture {
+            buffer,
+            width: size.width,
+            height: size.height,
+            padded_bytes_per_row,
+            format,
+        })
+    }
+
+    /// Map the buffer after the copy has been submitted. `callbacks` are run from device polling
+    /// once the data is available.
+    pub(crate) fn read(self, callbacks: Vec<CaptureCallback>) {
+        self.read_then(|image, _buffer| {
+            if let Some(image) = image {
+                for callback in callbacks {
+                    callback(image.clone());
+                }
+            }
+        });
+    }
+
+    /// Map the buffer after the copy has been submitted. `on_read` is run from device polling
+    /// with the image (`None` if mapping failed) and the unmapped buffer for reuse.
+    pub(crate) fn read_then(
+        self,
+        on_read: impl FnOnce(Option<RgbaImage>, Arc<Buffer>) + Send + 'static,
+    ) {
+        let buffer = self.buffer.clone();
+        buffer.slice(..).map_async(MapMode::Read, move |result| {
+            let buffer = self.buffer;
+            if let Err(e) = result {
+                error!("Failed to map frame capture: {}", e);
+                on_read(None, buffer);
+                return;
+            }
+            let image = {
+                let padded = buffer.slice(..).get_mapped_range();
+                let bytes_per_row =
+                    (self.width * self.format.block_copy_size(None).unwrap()) as usize;
+                let mut data = Vec::with_capacity(bytes_per_row * self.height as usize);
+                for row in padded.chunks(self.padded_bytes_per_row as usize) {
+                    data.extend_from_slice(&row[..bytes_per_row]);
+                }
+                to_rgba_image(&data, self.width, self.height, self.format)
+            };
+            buffer.unmap();
+            on_read(image, buffer);
+        });
+    }
+}
+
+/// Whether frames of `format` can be converted by [`to_rgba_image`]
+pub fn is_capture_format_supported(format: TextureFormat) -> bool {
+    matches!(
+        format,
+        TextureFormat::Rgba8Unorm
+            | TextureFormat::Rgba8UnormSrgb
+            | TextureFormat::Bgra8Unorm
+            | TextureFormat::Bgra8UnormSrgb
+            | TextureFormat::Rgb10a2Unorm
+            | TextureFormat::Rgba16Float
+            | TextureFormat::Rgba32Float
+    )
+}
+
+/// Convert tightly packed texel data to an rgba8 image. Values are not color converted, so e.g.
+/// linear float data is only clamped to [0, 1].
+pub fn to_rgba_image(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+) -> Option<RgbaImage> {
+    let rgba = match format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => data.to_vec(),
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => data
+            .chunks_exact(4)
+            .flat_map(|bgra| [bgra[2], bgra[1], bgra[0], bgra[3]])
+            .collect(),
+        TextureFormat::Rgb10a2Unorm => data
+            .chunks_exact(4)
+            .flat_map(|texel| {
+                let v = u32::from_le_bytes([texel[0], texel[1], texel[2], texel[3]]);
+                let channel = |shift: u32| ((v >> shift) & 0x3ff) as f32 / 1023.0;
+                [
+                    to_u8(channel(0)),
+                    to_u8(channel(10)),
+                    to_u8(channel(20)),
+                    to_u8(((v >> 30) & 0x3) as f32 / 3.0),
+                ]
+            })
+            .collect(),
+        TextureFormat::Rgba16Float => data
+            .chunks_exact(2)
+            .map(|half| to_u8(f16_to_f32(u16::from_le_bytes([half[0], half[1]]))))
+            .collect(),
+        TextureFormat::Rgba32Float => data
+            .chunks_exact(4)
+            .map(|v| to_u8(f32::from_le_bytes([v[0], v[1], v[2], v[3]])))
+            .collect(),
+        _ => return None,
+    };
+    RgbaImage::from_raw(width, height, rgba)
+}
+
+fn to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((half >> 10) & 0x1f) as i32;
+    let mantissa = (half & 0x3ff) as f32;
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
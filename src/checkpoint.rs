@@ -0,0 +1,222 @@
+use std::{
+    cmp::Reverse,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use log::{info, warn};
+use wgpu::{Buffer, Device, Queue, Texture};
+
+use crate::{
+    locale::{format_message, message, Message},
+    utils::{read_buffer, read_texture, write_texture},
+    GlassError,
+};
+
+const META_FILE: &str = "step";
+
+#[derive(Debug, Clone)]
+pub struct CheckpointConfig {
+    /// Directory where checkpoints are written
+    pub dir: PathBuf,
+    /// Save a checkpoint every `interval` steps
+    pub interval: u64,
+    /// Number of checkpoints kept on disk. The oldest is overwritten first.
+    pub ring_size: usize,
+}
+
+impl Default for CheckpointConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("checkpoints"),
+            interval: 1000,
+            ring_size: 3,
+        }
+    }
+}
+
+enum CheckpointResource {
+    Buffer(Arc<Buffer>),
+    Texture(Arc<Texture>),
+}
+
+/// Periodically streams registered buffers and textures to disk, so long running simulations can
+/// be resumed after a crash. Checkpoints are written into a ring of `ring_size` slots, each slot
+/// being a directory with one file per resource.
+///
+/// Buffers need `COPY_SRC | COPY_DST` and textures `COPY_SRC | COPY_DST` usage. Textures must
+/// have an uncompressed color format, only mip level 0 is saved.
+pub struct Checkpointer {
+    config: CheckpointConfig,
+    resources: Vec<(String, CheckpointResource)>,
+    next_slot: usize,
+}
+
+impl Checkpointer {
+    pub fn new(config: CheckpointConfig) -> Result<Checkpointer, GlassError> {
+        if config.ring_size == 0 {
+            return Err(GlassError::ArgumentError(
+                message(Message::CheckpointRingSizeZero).into_owned(),
+            ));
+        }
+        fs::create_dir_all(&config.dir).map_err(GlassError::IoError)?;
+        let mut checkpointer = Checkpointer {
+            config,
+            resources: vec![],
+            next_slot: 0,
+        };
+        // Continue the ring after the latest existing checkpoint
+        if let Some((slot, _step)) = checkpointer.latest_slot() {
+            checkpointer.next_slot = (slot + 1) % checkpointer.config.ring_size;
+        }
+        Ok(checkpointer)
+    }
+
+    pub fn register_buffer(&mut self, name: &str, buffer: Arc<Buffer>) {
+        self.resources
+            .push((name.to_string(), CheckpointResource::Buffer(buffer)));
+    }
+
+    pub fn register_texture(&mut self, name: &str, texture: Arc<Texture>) {
+        self.resources
+            .push((name.to_string(), CheckpointResource::Texture(texture)));
+    }
+
+    pub fn config(&self) -> &CheckpointConfig {
+        &self.config
+    }
+
+    pub fn should_save(&self, step: u64) -> bool {
+        self.config.interval > 0 && step > 0 && step.is_multiple_of(self.config.interval)
+    }
+
+    /// Read back all registered resources and write them to the next slot of the ring. Blocks
+    /// until the readback has completed.
+    pub fn save(&mut self, device: &Device, queue: &Queue, step: u64) -> Result<(), GlassError> {
+        let slot_dir = self.slot_dir(self.next_slot);
+        fs::create_dir_all(&slot_dir).map_err(GlassError::IoError)?;
+        // Invalidate the slot first so a crash mid-write never leaves a valid looking checkpoint
+        let meta = slot_dir.join(META_FILE);
+        if meta.exists() {
+            fs::remove_file(&meta).map_err(GlassError::IoError)?;
+        }
+        for (name, resource) in self.resources.iter() {
+            let data = match resource {
+                CheckpointResource::Buffer(buffer) => read_buffer(device, queue, buffer),
+                CheckpointResource::Texture(texture) => read_texture(device, queue, texture),
+            };
+            fs::write(slot_dir.join(format!("{}.bin", name)), data).map_err(GlassError::IoError)?;
+        }
+        fs::write(&meta, step.to_string()).map_err(GlassError::IoError)?;
+        info!(
+            "Saved checkpoint of step {} to {}",
+            step,
+            slot_dir.display()
+        );
+        self.next_slot = (self.next_slot + 1) % self.config.ring_size;
+        Ok(())
+    }
+
+    /// Step of the latest complete checkpoint on disk
+    pub fn latest_step(&self) -> Option<u64> {
+        self.latest_slot().map(|(_, step)| step)
+    }
+
+    /// Write the latest valid checkpoint back to the registered resources. Slots are tried from
+    /// the newest step down, so a truncated or mismatching newest slot falls back to an older one.
+    /// Returns the step of the restored checkpoint, or `None` if there was nothing to restore. If
+    /// no slot validates, the error of the newest one is returned.
+    pub fn restore_latest(&self, queue: &Queue) -> Result<Option<u64>, GlassError> {
+        let mut newest_error = None;
+        for (slot, step) in self.slots_newest_first() {
+            let slot_dir = self.slot_dir(slot);
+            let contents = match self.read_slot(&slot_dir) {
+                Ok(contents) => contents,
+                Err(e) => {
+                    warn!("Skipping checkpoint {}: {}", slot_dir.display(), e);
+                    newest_error.get_or_insert(e);
+                    continue;
+                }
+            };
+            for ((_, resource), data) in self.resources.iter().zip(contents) {
+                match resource {
+                    CheckpointResource::Buffer(buffer) => queue.write_buffer(buffer, 0, &data),
+                    CheckpointResource::Texture(texture) => write_texture(queue, texture, &data),
+                }
+            }
+            info!(
+                "Restored checkpoint of step {} from {}",
+                step,
+                slot_dir.display()
+            );
+            return Ok(Some(step));
+        }
+        match newest_error {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+
+    /// Read & validate every resource of a slot, so a stale checkpoint doesn't restore partially
+    fn read_slot(&self, slot_dir: &Path) -> Result<Vec<Vec<u8>>, GlassError> {
+        let mut contents = Vec::with_capacity(self.resources.len());
+        for (name, resource) in self.resources.iter() {
+            let path = slot_dir.join(format!("{}.bin", name));
+            let data = fs::read(&path).map_err(GlassError::IoError)?;
+            let expected = resource_size(resource);
+            if data.len() as u64 != expected {
+                return Err(GlassError::IoError(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format_message(Message::CheckpointSizeMismatch, &[
+                        ("path", &path.display()),
+                        ("size", &data.len()),
+                        ("expected", &expected),
+                    ]),
+                )));
+            }
+            contents.push(data);
+        }
+        Ok(contents)
+    }
+
+    fn latest_slot(&self) -> Option<(usize, u64)> {
+        self.slots_newest_first().into_iter().next()
+    }
+
+    /// Slots with a complete step file, sorted by descending step
+    fn slots_newest_first(&self) -> Vec<(usize, u64)> {
+        let mut slots: Vec<(usize, u64)> = (0..self.config.ring_size)
+            .filter_map(|slot| read_step(&self.slot_dir(slot)).map(|step| (slot, step)))
+            .collect();
+        slots.sort_by_key(|(_, step)| Reverse(*step));
+        slots
+    }
+
+    fn slot_dir(&self, slot: usize) -> PathBuf {
+        self.config.dir.join(format!("checkpoint_{}", slot))
+    }
+}
+
+/// Size of the data saved for a resource, the whole buffer or mip 0 of all texture layers
+fn resource_size(resource: &CheckpointResource) -> u64 {
+    match resource {
+        CheckpointResource::Buffer(buffer) => buffer.size(),
+        CheckpointResource::Texture(texture) => {
+            let bytes_per_texel = texture
+                .format()
+                .block_copy_size(None)
+                .expect("Checkpointed textures need an uncompressed color format");
+            texture.width() as u64
+                * texture.height() as u64
+                * texture.depth_or_array_layers() as u64
+                * bytes_per_texel as u64
+        }
+    }
+}
+
+fn read_step(slot_dir: &Path) -> Option<u64> {
+    fs::read_to_string(slot_dir.join(META_FILE))
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+}
@@ -0,0 +1,37 @@
+use crate::GlassError;
+
+/// Text access to the system clipboard, requires the `clipboard` feature. Accessible through
+/// [`GlassContext::clipboard`](crate::GlassContext::clipboard). The platform clipboard is
+/// opened on first use.
+#[derive(Default)]
+pub struct Clipboard {
+    inner: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    pub fn new() -> Clipboard {
+        Clipboard::default()
+    }
+
+    fn inner(&mut self) -> Result<&mut arboard::Clipboard, GlassError> {
+        if self.inner.is_none() {
+            self.inner = Some(
+                arboard::Clipboard::new().map_err(|e| GlassError::ClipboardError(e.to_string()))?,
+            );
+        }
+        Ok(self.inner.as_mut().unwrap())
+    }
+
+    /// Text on the clipboard. Errors if the clipboard is empty or holds something else.
+    pub fn get_text(&mut self) -> Result<String, GlassError> {
+        self.inner()?
+            .get_text()
+            .map_err(|e| GlassError::ClipboardError(e.to_string()))
+    }
+
+    pub fn set_text(&mut self, text: impl Into<String>) -> Result<(), GlassError> {
+        self.inner()?
+            .set_text(text.into())
+            .map_err(|e| GlassError::ClipboardError(e.to_string()))
+    }
+}
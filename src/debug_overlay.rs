@@ -0,0 +1,407 @@
+use winit::keyboard::KeyCode;
+
+/// Default key toggling the debug overlay
+pub const DEFAULT_TOGGLE_KEY: KeyCode = KeyCode::F3;
+
+/// Configuration of the runner's [`DebugOverlay`], see
+/// [`GlassConfig::debug_overlay`](crate::GlassConfig::debug_overlay).
+#[derive(Debug, Copy, Clone)]
+pub struct DebugOverlayConfig {
+    /// A ttf/otf font the overlay's text is drawn with
+    pub font: &'static [u8],
+    /// Key toggling the overlay, `None` to only toggle it from code
+    pub toggle_key: Option<KeyCode>,
+    /// Whether the overlay is shown from the start
+    pub visible: bool,
+}
+
+impl DebugOverlayConfig {
+    /// Hidden overlay toggled with [`DEFAULT_TOGGLE_KEY`]
+    pub fn new(font: &'static [u8]) -> DebugOverlayConfig {
+        DebugOverlayConfig {
+            font,
+            toggle_key: Some(DEFAULT_TOGGLE_KEY),
+            visible: false,
+        }
+    }
+}
+
+#[cfg(feature = "text")]
+pub use overlay::DebugOverlay;
+
+#[cfg(feature = "text")]
+mod overlay {
+    use std::{
+        collections::{HashMap, VecDeque},
+        fmt::Display,
+        sync::Mutex,
+    };
+
+    use wgpu::{
+        AdapterInfo, ColorTargetState, CommandEncoder, Device, Queue, RenderPass, SurfaceTexture,
+        TextureFormat,
+    };
+    use winit::{keyboard::KeyCode, window::WindowId};
+
+    use super::DebugOverlayConfig;
+    use crate::{
+        device_context::DeviceContext,
+        locale::{format_message, Message},
+        pipelines::{
+            require_push_constants, Font, GlyphInstance, Shape, ShapePipeline, ShapeStyle,
+            TextPipeline,
+        },
+        time::FrameTime,
+        utils::{GpuTiming, GrowableBuffer},
+        window::GlassWindow,
+        GlassError,
+    };
+
+    /// Push constant size of the shape pipeline, the largest used
+    const PUSH_CONSTANT_SIZE: u32 = 128;
+    const HISTORY_LEN: usize = 120;
+    const FONT_SIZE: f32 = 14.0;
+    const MARGIN: f32 = 8.0;
+    const PADDING: f32 = 6.0;
+    const BAR_WIDTH: f32 = 2.0;
+    const GRAPH_HEIGHT: f32 = 48.0;
+    /// Frame time at the top of the graph
+    const GRAPH_MAX_MS: f32 = 1000.0 / 30.0;
+    const TARGET_MS: f32 = 1000.0 / 60.0;
+    const BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.7];
+    const TEXT_COLOR: [f32; 4] = [1.0; 4];
+    const TARGET_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.3];
+    const GPU_COLOR: [f32; 4] = [0.3, 0.6, 1.0, 1.0];
+
+    /// Frame statistics drawn over every window by the runner: FPS, a graph of recent CPU (frame
+    /// delta) and GPU frame times, GPU scope timings, surface info and stats reported by the app
+    /// with [`DebugOverlay::set_stat`]. GPU times are shown if
+    /// [`GlassConfig::gpu_profiler_scopes`](crate::GlassConfig::gpu_profiler_scopes) is set.
+    ///
+    /// The overlay is drawn after frame captures and recordings, so they don't include it.
+    /// Drawing needs `Features::PUSH_CONSTANTS` and a `max_push_constant_size` limit of 128.
+    ///
+    /// ```ignore
+    /// // wgpu doesn't count draw calls, so report them from update
+    /// if let Some(overlay) = context.debug_overlay_mut() {
+    ///     overlay.set_stat("Sprites", self.sprites.len());
+    /// }
+    /// ```
+    pub struct DebugOverlay {
+        font: Font,
+        pipelines: HashMap<TextureFormat, (TextPipeline, ShapePipeline)>,
+        /// Glyphs of each window, reused every frame
+        glyphs: Mutex<HashMap<WindowId, GrowableBuffer<GlyphInstance>>>,
+        toggle_key: Option<KeyCode>,
+        visible: bool,
+        adapter: String,
+        cpu_history: VecDeque<f32>,
+        gpu_history: VecDeque<f32>,
+        stats: Vec<(String, String)>,
+        text: String,
+    }
+
+    impl DebugOverlay {
+        pub fn new(
+            device: &Device,
+            queue: &Queue,
+            adapter_info: &AdapterInfo,
+            config: &DebugOverlayConfig,
+        ) -> Result<DebugOverlay, GlassError> {
+            require_push_constants(device, "DebugOverlay", PUSH_CONSTANT_SIZE as usize)?;
+            Ok(DebugOverlay {
+                font: Font::new(device, queue, config.font, FONT_SIZE)?,
+                pipelines: HashMap::new(),
+                glyphs: Mutex::new(HashMap::new()),
+                toggle_key: config.toggle_key,
+                visible: config.visible,
+                adapter: format!("{} ({:?})", adapter_info.name, adapter_info.backend),
+                cpu_history: VecDeque::with_capacity(HISTORY_LEN),
+                gpu_history: VecDeque::with_capacity(HISTORY_LEN),
+                stats: vec![],
+                text: String::new(),
+            })
+        }
+
+        /// Whether the device can draw the overlay
+        pub fn is_supported(device: &Device) -> bool {
+            require_push_constants(device, "DebugOverlay", PUSH_CONSTANT_SIZE as usize).is_ok()
+        }
+
+        pub fn is_visible(&self) -> bool {
+            self.visible
+        }
+
+        pub fn set_visible(&mut self, visible: bool) {
+            self.visible = visible;
+        }
+
+        pub fn toggle(&mut self) {
+            self.visible = !self.visible;
+        }
+
+        /// Show a line `label: value` until it's set again or removed, e.g. draw call counts
+        /// of the app's own renderers
+        pub fn set_stat(&mut self, label: &str, value: impl Display) {
+            let value = value.to_string();
+            match self.stats.iter_mut().find(|(l, _)| l == label) {
+                Some(stat) => stat.1 = value,
+                None => self.stats.push((label.to_string(), value)),
+            }
+        }
+
+        pub fn remove_stat(&mut self, label: &str) {
+            self.stats.retain(|(l, _)| l != label);
+        }
+
+        pub fn clear_stats(&mut self) {
+            self.stats.clear();
+        }
+
+        /// Toggle visibility if `key` is the toggle key. Returns whether the key was handled.
+        pub(crate) fn handle_hotkey(&mut self, key: KeyCode) -> bool {
+            if self.toggle_key != Some(key) {
+                return false;
+            }
+            self.toggle();
+            true
+        }
+
+        /// Record this frame's timings and create pipelines for new surface formats. Called by
+        /// the runner before render.
+        pub(crate) fn prepare(
+            &mut self,
+            device_context: &DeviceContext,
+            frame_time: &FrameTime,
+            gpu_timings: &[GpuTiming],
+            surface_formats: impl Iterator<Item = TextureFormat>,
+        ) {
+            let gpu_ms = gpu_timings
+                .iter()
+                .map(|timing| timing.duration.as_secs_f32() * 1000.0)
+                .sum::<f32>();
+            push_history(
+                &mut self.cpu_history,
+                frame_time.delta().as_secs_f32() * 1000.0,
+            );
+            push_history(&mut self.gpu_history, gpu_ms);
+            if !self.visible {
+                return;
+            }
+            for format in surface_formats {
+                self.pipelines.entry(format).or_insert_with(|| {
+                    let color_target_state = ColorTargetState {
+                        format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    };
+                    // Push constants were checked in new
+                    (
+                        TextPipeline::new(device_context, color_target_state.clone(), 1)
+                            .expect("Failed to create overlay text pipeline"),
+                        ShapePipeline::new(device_context, color_target_state, 1)
+                            .expect("Failed to create overlay shape pipeline"),
+                    )
+                });
+            }
+
+            let mut text = format!(
+                "{}\n{}",
+                format_message(Message::DebugOverlayFrameTime, &[
+                    ("fps", &format!("{:.0}", frame_time.fps())),
+                    ("ms", &format!("{:.2}", frame_time.avg_ms())),
+                ]),
+                self.adapter
+            );
+            if !gpu_timings.is_empty() {
+                text.push('\n');
+                text.push_str(&format_message(Message::DebugOverlayGpuTime, &[(
+                    "ms",
+                    &format!("{:.2}", gpu_ms),
+                )]));
+                for timing in gpu_timings {
+                    text.push_str(&format!(
+                        "\n  {}: {:.2} ms",
+                        timing.label,
+                        timing.duration.as_secs_f32() * 1000.0
+                    ));
+                }
+            }
+            for (label, value) in self.stats.iter() {
+                text.push_str(&format!("\n{}: {}", label, value));
+            }
+            self.text = text;
+        }
+
+        /// Draw the overlay into the frame of a window. Called by the runner after the app's
+        /// render.
+        pub(crate) fn draw(
+            &self,
+            device: &Device,
+            queue: &Queue,
+            encoder: &mut CommandEncoder,
+            window: &GlassWindow,
+            frame: &SurfaceTexture,
+        ) {
+            if !self.visible {
+                return;
+            }
+            let Some((text_pipeline, shape_pipeline)) =
+                self.pipelines.get(&window.surface_format())
+            else {
+                return;
+            };
+            let text = format!(
+                "{}\n{}",
+                self.text,
+                format_message(Message::DebugOverlaySurface, &[
+                    ("width", &window.surface_size()[0]),
+                    ("height", &window.surface_size()[1]),
+                    ("format", &format!("{:?}", window.surface_format())),
+                    ("present_mode", &format!("{:?}", window.present_mode())),
+                    ("samples", &window.msaa_samples()),
+                ])
+            );
+            let [width, height] = window.surface_size().map(|s| s.max(1) as f32);
+            // Pixels with origin at bottom left
+            let view_proj = [
+                [2.0 / width, 0.0, 0.0, 0.0],
+                [0.0, 2.0 / height, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [-1.0, -1.0, 0.0, 1.0],
+            ];
+            let graph_width = HISTORY_LEN as f32 * BAR_WIDTH;
+            let text_size = self.font.measure(&text, FONT_SIZE);
+            let panel_size = [
+                text_size[0].max(graph_width) + 2.0 * PADDING,
+                text_size[1] + GRAPH_HEIGHT + 3.0 * PADDING,
+            ];
+            let top = height - MARGIN;
+            let graph_origin = [MARGIN + PADDING, top - panel_size[1] + PADDING];
+
+            let view = frame.texture.create_view(&Default::default());
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("glass::debug_overlay"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            draw_rect(
+                &mut rpass,
+                shape_pipeline,
+                view_proj,
+                [width, height],
+                [MARGIN, top - panel_size[1]],
+                panel_size,
+                BACKGROUND,
+            );
+            for (i, (cpu_ms, gpu_ms)) in self
+                .cpu_history
+                .iter()
+                .zip(self.gpu_history.iter())
+                .enumerate()
+            {
+                let x = graph_origin[0] + i as f32 * BAR_WIDTH;
+                let bar_height = |ms: f32| (ms / GRAPH_MAX_MS).min(1.0) * GRAPH_HEIGHT;
+                draw_rect(
+                    &mut rpass,
+                    shape_pipeline,
+                    view_proj,
+                    [width, height],
+                    [x, graph_origin[1]],
+                    [BAR_WIDTH, bar_height(*cpu_ms)],
+                    frame_time_color(*cpu_ms),
+                );
+                if *gpu_ms > 0.0 {
+                    draw_rect(
+                        &mut rpass,
+                        shape_pipeline,
+                        view_proj,
+                        [width, height],
+                        [x, graph_origin[1]],
+                        [BAR_WIDTH / 2.0, bar_height(*gpu_ms)],
+                        GPU_COLOR,
+                    );
+                }
+            }
+            draw_rect(
+                &mut rpass,
+                shape_pipeline,
+                view_proj,
+                [width, height],
+                [
+                    graph_origin[0],
+                    graph_origin[1] + TARGET_MS / GRAPH_MAX_MS * GRAPH_HEIGHT,
+                ],
+                [graph_width, 1.0],
+                TARGET_COLOR,
+            );
+            let mut glyphs = self.glyphs.lock().unwrap();
+            let glyphs = glyphs
+                .entry(window.window().id())
+                .or_insert_with(|| TextPipeline::create_instance_buffer(device));
+            text_pipeline.draw_text(
+                device,
+                queue,
+                &mut rpass,
+                glyphs,
+                &self.font,
+                &text,
+                [MARGIN + PADDING, top - PADDING - FONT_SIZE],
+                FONT_SIZE,
+                TEXT_COLOR,
+                view_proj,
+            );
+        }
+    }
+
+    /// Axis aligned rect with its bottom left corner at `pos`
+    fn draw_rect<'r>(
+        rpass: &mut RenderPass<'r>,
+        pipeline: &'r ShapePipeline,
+        view_proj: [[f32; 4]; 4],
+        viewport: [f32; 2],
+        pos: [f32; 2],
+        size: [f32; 2],
+        color: [f32; 4],
+    ) {
+        pipeline.draw(
+            rpass,
+            view_proj,
+            Shape::RoundedRect {
+                size,
+                corner_radius: 0.0,
+            },
+            [pos[0] + size[0] / 2.0, pos[1] + size[1] / 2.0],
+            0.0,
+            ShapeStyle::fill(color),
+            viewport,
+        );
+    }
+
+    fn push_history(history: &mut VecDeque<f32>, value: f32) {
+        if history.len() == HISTORY_LEN {
+            history.pop_front();
+        }
+        history.push_back(value);
+    }
+
+    /// Green at 60 FPS or faster, yellow at 30 FPS or faster, red otherwise
+    fn frame_time_color(ms: f32) -> [f32; 4] {
+        if ms <= TARGET_MS {
+            [0.3, 0.9, 0.3, 1.0]
+        } else if ms <= GRAPH_MAX_MS {
+            [0.9, 0.8, 0.2, 1.0]
+        } else {
+            [0.9, 0.3, 0.3, 1.0]
+        }
+    }
+}
@@ -1,22 +1,44 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
 use wgpu::{
-    Adapter, AddressMode, Backends, Device, DeviceDescriptor, FilterMode, Instance,
-    InstanceDescriptor, InstanceFlags, Limits, MemoryHints, PowerPreference, Queue,
-    RequestAdapterOptions, Sampler, SamplerDescriptor, Surface,
+    Adapter, AddressMode, Backends, Device, DeviceDescriptor, DownlevelFlags, FilterMode, Instance,
+    InstanceDescriptor, InstanceFlags, Limits, MemoryHints, PipelineCache, PipelineCacheDescriptor,
+    PowerPreference, Queue, RequestAdapterOptions, Sampler, SamplerDescriptor, Surface,
 };
 
-use crate::{utils::wait_async, GlassError};
+use crate::{
+    assets::AssetLoader,
+    pipelines::DrawParamsRings,
+    texture::TextureLoader,
+    utils::{wait_async, SamplerKey},
+    GlassError,
+};
 
 #[derive(Debug, Clone)]
 pub struct DeviceConfig {
     pub power_preference: PowerPreference,
     pub memory_hints: MemoryHints,
     pub features: wgpu::Features,
+    /// Features enabled in addition to `features` when the adapter supports them
+    pub optional_features: wgpu::Features,
     pub limits: Limits,
     pub backends: Backends,
     pub instance_flags: InstanceFlags,
+    /// Record a wgpu API trace of the device into this directory, requires wgpu's `trace`
+    /// feature. For single frames, see
+    /// [`GlassContext::capture_next_frame`](crate::GlassContext::capture_next_frame).
     pub trace_path: Option<PathBuf>,
+    /// Directory to load and save a pipeline cache in, used when the backend supports
+    /// `Features::PIPELINE_CACHE` (currently Vulkan). Bundled pipelines compile from the cache,
+    /// see [`DeviceContext::save_pipeline_cache`].
+    pub pipeline_cache_path: Option<PathBuf>,
+    /// Maximum anisotropy of the built-in linear samplers, clamped to 1..=16. Ignored when the
+    /// adapter doesn't support anisotropic filtering.
+    pub default_sampler_anisotropy: u16,
 }
 
 impl DeviceConfig {
@@ -25,10 +47,13 @@ impl DeviceConfig {
             power_preference: PowerPreference::HighPerformance,
             memory_hints: MemoryHints::Performance,
             features: wgpu::Features::empty(),
+            optional_features: wgpu::Features::empty(),
             limits: Limits::default(),
             backends: Backends::all(),
             instance_flags: InstanceFlags::from_build_config(),
             trace_path: None,
+            pipeline_cache_path: None,
+            default_sampler_anisotropy: 1,
         }
     }
 }
@@ -39,10 +64,13 @@ impl Default for DeviceConfig {
             power_preference: PowerPreference::default(),
             memory_hints: MemoryHints::Performance,
             features: wgpu::Features::empty(),
+            optional_features: wgpu::Features::empty(),
             limits: Limits::default(),
             backends: Backends::all(),
             instance_flags: InstanceFlags::from_build_config(),
             trace_path: None,
+            pipeline_cache_path: None,
+            default_sampler_anisotropy: 1,
         }
     }
 }
@@ -54,16 +82,15 @@ pub struct DeviceContext {
     adapter: Adapter,
     device: Arc<Device>,
     queue: Arc<Queue>,
+    pipeline_cache: Option<PipelineCache>,
     sampler_nearest_repeat: Arc<Sampler>,
     sampler_linear_repeat: Arc<Sampler>,
     sampler_nearest_clamp_to_edge: Arc<Sampler>,
     sampler_linear_clamp_to_edge: Arc<Sampler>,
+    samplers: Mutex<HashMap<SamplerKey, Arc<Sampler>>>,
+    draw_params_rings: DrawParamsRings,
 }
 
-unsafe impl Send for DeviceContext {}
-
-unsafe impl Sync for DeviceContext {}
-
 impl DeviceContext {
     pub fn new(config: &DeviceConfig) -> Result<DeviceContext, GlassError> {
         let instance = Instance::new(InstanceDescriptor {
@@ -73,6 +100,13 @@ impl DeviceContext {
         });
         let (adapter, device, queue) =
             Self::create_adapter_device_and_queue(config, &instance, None)?;
+        let device = Arc::new(device);
+        let pipeline_cache = Self::create_pipeline_cache(config, &adapter, &device);
+        let anisotropy = if supports_anisotropy(&adapter) {
+            config.default_sampler_anisotropy.clamp(1, 16)
+        } else {
+            1
+        };
         let sampler_nearest_repeat = Arc::new(device.create_sampler(&SamplerDescriptor {
             label: None,
             address_mode_u: AddressMode::Repeat,
@@ -89,6 +123,7 @@ impl DeviceContext {
             mag_filter: FilterMode::Linear,
             min_filter: FilterMode::Linear,
             mipmap_filter: FilterMode::Linear,
+            anisotropy_clamp: anisotropy,
             ..Default::default()
         }));
         let sampler_nearest_clamp_to_edge = Arc::new(device.create_sampler(&SamplerDescriptor {
@@ -107,6 +142,7 @@ impl DeviceContext {
             mag_filter: FilterMode::Linear,
             min_filter: FilterMode::Linear,
             mipmap_filter: FilterMode::Linear,
+            anisotropy_clamp: anisotropy,
             ..Default::default()
         }));
 
@@ -114,12 +150,15 @@ impl DeviceContext {
             config: config.clone(),
             instance,
             adapter,
-            device: Arc::new(device),
+            device,
             queue: Arc::new(queue),
+            pipeline_cache,
             sampler_nearest_repeat,
             sampler_linear_repeat,
             sampler_nearest_clamp_to_edge,
             sampler_linear_clamp_to_edge,
+            samplers: Mutex::new(HashMap::new()),
+            draw_params_rings: DrawParamsRings::default(),
         })
     }
 
@@ -128,12 +167,66 @@ impl DeviceContext {
     pub fn reconfigure_with_surface(&mut self, surface: &Surface) -> Result<(), GlassError> {
         let (adapter, device, queue) =
             Self::create_adapter_device_and_queue(&self.config, &self.instance, Some(surface))?;
+        let device = Arc::new(device);
+        self.pipeline_cache = Self::create_pipeline_cache(&self.config, &adapter, &device);
         self.adapter = adapter;
-        self.device = Arc::new(device);
+        self.device = device;
         self.queue = Arc::new(queue);
+        self.samplers.lock().unwrap().clear();
+        self.draw_params_rings.clear();
         Ok(())
     }
 
+    /// Load the cache from [`DeviceConfig::pipeline_cache_path`], or start an empty one
+    fn create_pipeline_cache(
+        config: &DeviceConfig,
+        adapter: &Adapter,
+        device: &Device,
+    ) -> Option<PipelineCache> {
+        if !device.features().contains(wgpu::Features::PIPELINE_CACHE) {
+            return None;
+        }
+        let path = Self::pipeline_cache_file(config, adapter)?;
+        let data = std::fs::read(&path).ok();
+        // Safety: the file name is keyed by the adapter, so the data was produced by a cache
+        // of the same adapter. Invalid or outdated data is discarded with `fallback`.
+        let cache = unsafe {
+            device.create_pipeline_cache(&PipelineCacheDescriptor {
+                label: Some("glass_pipeline_cache"),
+                data: data.as_deref(),
+                fallback: true,
+            })
+        };
+        Some(cache)
+    }
+
+    fn pipeline_cache_file(config: &DeviceConfig, adapter: &Adapter) -> Option<PathBuf> {
+        let dir = config.pipeline_cache_path.as_deref()?;
+        let key = wgpu::util::pipeline_cache_key(&adapter.get_info())?;
+        Some(dir.join(key))
+    }
+
+    /// The device's pipeline cache, if [`DeviceConfig::pipeline_cache_path`] is set and the
+    /// backend supports one. Pass it as `cache` in pipeline descriptors.
+    pub fn pipeline_cache(&self) -> Option<&PipelineCache> {
+        self.pipeline_cache.as_ref()
+    }
+
+    /// Write the pipeline cache to [`DeviceConfig::pipeline_cache_path`]. The runner does this
+    /// when the app exits. Does nothing without a cache.
+    pub fn save_pipeline_cache(&self) -> Result<(), GlassError> {
+        let (Some(cache), Some(path)) = (
+            &self.pipeline_cache,
+            Self::pipeline_cache_file(&self.config, &self.adapter),
+        ) else {
+            return Ok(());
+        };
+        let Some(data) = cache.get_data() else {
+            return Ok(());
+        };
+        write_atomically(&path, &data).map_err(GlassError::IoError)
+    }
+
     fn create_adapter_device_and_queue(
         config: &DeviceConfig,
         instance: &Instance,
@@ -150,11 +243,24 @@ impl DeviceContext {
 
         let path = config.trace_path.as_deref();
         // Create the logical device and command queue
+        // The pipeline cache feature is only requested when a cache path is configured
+        let cache_feature = if config.pipeline_cache_path.is_some() {
+            wgpu::Features::PIPELINE_CACHE
+        } else {
+            wgpu::Features::empty()
+        };
+        let required_features =
+            config.features | ((config.optional_features | cache_feature) & adapter.features());
+        let mut required_limits = config.limits.clone();
+        // Push constants may be optional, e.g. with the uniform fallback of bundled pipelines
+        if !required_features.contains(wgpu::Features::PUSH_CONSTANTS) {
+            required_limits.max_push_constant_size = 0;
+        }
         let (device, queue) = match wait_async(adapter.request_device(
             &DeviceDescriptor {
                 label: None,
-                required_features: config.features,
-                required_limits: config.limits.clone(),
+                required_features,
+                required_limits,
                 memory_hints: config.memory_hints.clone(),
             },
             path,
@@ -182,6 +288,23 @@ impl DeviceContext {
         &self.sampler_linear_clamp_to_edge
     }
 
+    /// A sampler shared by everyone asking for the same key, for samplers beyond the built-in
+    /// ones such as anisotropic or comparison samplers. Anisotropy is ignored when the adapter
+    /// doesn't support anisotropic filtering.
+    pub fn get_or_create_sampler(&self, key: SamplerKey) -> Arc<Sampler> {
+        let key = if supports_anisotropy(&self.adapter) {
+            key
+        } else {
+            key.anisotropy(1)
+        };
+        self.samplers
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_insert_with(|| Arc::new(self.device.create_sampler(&key.descriptor(None))))
+            .clone()
+    }
+
     pub fn instance(&self) -> &Instance {
         &self.instance
     }
@@ -205,4 +328,79 @@ impl DeviceContext {
     pub fn queue_arc(&self) -> Arc<Queue> {
         self.queue.clone()
     }
+
+    pub(crate) fn draw_params_rings(&self) -> &DrawParamsRings {
+        &self.draw_params_rings
+    }
+
+    /// Upload the parameters staged by bundled pipelines created with this context when push
+    /// constants aren't available. The runner calls this before submitting each frame, so draws
+    /// recorded in [`GlassApp::render`](crate::GlassApp::render) need no manual upload.
+    pub fn upload_draw_params(&self) {
+        self.draw_params_rings.upload(&self.queue);
+    }
+
+    /// Return a [`SharedGpu`] handle that can be sent to other threads.
+    pub fn shared(&self) -> SharedGpu {
+        SharedGpu {
+            device: self.device.clone(),
+            queue: self.queue.clone(),
+            sampler_nearest_repeat: self.sampler_nearest_repeat.clone(),
+            sampler_linear_repeat: self.sampler_linear_repeat.clone(),
+            sampler_nearest_clamp_to_edge: self.sampler_nearest_clamp_to_edge.clone(),
+            sampler_linear_clamp_to_edge: self.sampler_linear_clamp_to_edge.clone(),
+        }
+    }
+}
+
+/// Write through a temporary file so a crash never leaves a truncated cache behind
+pub(crate) fn write_atomically(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let temp = path.with_extension("tmp");
+    std::fs::write(&temp, data)?;
+    std::fs::rename(&temp, path)
+}
+
+fn supports_anisotropy(adapter: &Adapter) -> bool {
+    adapter
+        .get_downlevel_capabilities()
+        .flags
+        .contains(DownlevelFlags::ANISOTROPIC_FILTERING)
+}
+
+/// Cheaply cloneable handle to the device, queue and common samplers, intended for use from
+/// other threads. Unlike [`DeviceContext`], it does not own the instance or adapter.
+///
+/// The handle refers to the device it was created with. If the device context is reconfigured
+/// (e.g. when the first window is created for a windowless context), create a new handle.
+#[derive(Debug, Clone)]
+pub struct SharedGpu {
+    pub device: Arc<Device>,
+    pub queue: Arc<Queue>,
+    pub sampler_nearest_repeat: Arc<Sampler>,
+    pub sampler_linear_repeat: Arc<Sampler>,
+    pub sampler_nearest_clamp_to_edge: Arc<Sampler>,
+    pub sampler_linear_clamp_to_edge: Arc<Sampler>,
 }
+
+impl SharedGpu {
+    pub fn texture_loader(&self) -> TextureLoader {
+        TextureLoader::new(self.device.clone(), self.queue.clone())
+    }
+
+    /// An [`AssetLoader`] decoding on `threads` background threads
+    pub fn asset_loader(&self, threads: usize) -> AssetLoader {
+        AssetLoader::new(self.device.clone(), self.queue.clone(), threads)
+    }
+}
+
+// Types meant to cross threads must stay Send + Sync without unsafe impls
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<DeviceContext>();
+    assert_send_sync::<SharedGpu>();
+    assert_send_sync::<TextureLoader>();
+    assert_send_sync::<AssetLoader>();
+};
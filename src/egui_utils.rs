@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+
+use egui::{FullOutput, ViewportId};
+use egui_wgpu::ScreenDescriptor;
+use egui_winit::EventResponse;
+use wgpu::{CommandBuffer, CommandEncoder, StoreOp, TextureView};
+use winit::{event::WindowEvent, event_loop::ActiveEventLoop, window::WindowId};
+
+use crate::{gui::GuiBackend, window::GlassWindow, GlassContext};
+
+/// egui state of a single window
+pub struct EguiWindow {
+    ctx: egui::Context,
+    state: egui_winit::State,
+    renderer: egui_wgpu::Renderer,
+    repaint: bool,
+    /// A pass was started by [`GuiBackend::update`] and not yet rendered
+    in_pass: bool,
+}
+
+impl EguiWindow {
+    pub fn new(
+        event_loop: &ActiveEventLoop,
+        context: &GlassContext,
+        window: &GlassWindow,
+    ) -> EguiWindow {
+        let ctx = egui::Context::default();
+        let pixels_per_point = window.window().scale_factor() as f32;
+        let state = egui_winit::State::new(
+            ctx.clone(),
+            ViewportId::ROOT,
+            event_loop,
+            Some(pixels_per_point),
+            None,
+            Some(context.device().limits().max_texture_dimension_2d as usize),
+        );
+        let renderer =
+            egui_wgpu::Renderer::new(context.device(), window.surface_format(), None, 1, true);
+        EguiWindow {
+            ctx,
+            state,
+            renderer,
+            repaint: false,
+            in_pass: false,
+        }
+    }
+
+    /// The egui context of this window, e.g. to set its style or, when driven by the runner as a
+    /// [`GuiBackend`], to build UI during update
+    pub fn context(&self) -> &egui::Context {
+        &self.ctx
+    }
+
+    /// Whether the last event asked for a repaint
+    pub fn needs_repaint(&self) -> bool {
+        self.repaint
+    }
+
+    /// Upload and draw the output of an egui pass over `view`
+    fn paint(
+        &mut self,
+        context: &GlassContext,
+        encoder: &mut CommandEncoder,
+        window: &GlassWindow,
+        view: &TextureView,
+        output: FullOutput,
+    ) -> Vec<CommandBuffer> {
+        let EguiWindow {
+            ctx,
+            state,
+            renderer,
+            ..
+        } = self;
+        let FullOutput {
+            platform_output,
+            shapes,
+            textures_delta,
+            pixels_per_point,
+            ..
+        } = output;
+        state.handle_platform_output(window.window(), platform_output);
+        let clipped_primitives = ctx.tessellate(shapes, pixels_per_point);
+        let screen_descriptor = ScreenDescriptor {
+            size_in_pixels: window.surface_size(),
+            pixels_per_point,
+        };
+
+        for (id, image_delta) in &textures_delta.set {
+            renderer.update_texture(context.device(), context.queue(), *id, image_delta);
+        }
+        let user_cmd_bufs = renderer.update_buffers(
+            context.device(),
+            context.queue(),
+            encoder,
+            &clipped_primitives,
+            &screen_descriptor,
+        );
+        {
+            let render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("egui"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: context
+                    .gpu_profiler()
+                    .and_then(|profiler| profiler.pass_timestamp_writes("glass::egui")),
+                occlusion_query_set: None,
+            });
+            renderer.render(
+                &mut render_pass.forget_lifetime(),
+                &clipped_primitives,
+                &screen_descriptor,
+            );
+        }
+        for id in &textures_delta.free {
+            renderer.free_texture(id);
+        }
+        user_cmd_bufs
+    }
+}
+
+/// egui for any number of windows, requires the `egui_gui` feature. Each window has its own egui
+/// context, input state and renderer, so e.g. an inspector window can show different UI than the
+/// main window.
+///
+/// ```ignore
+/// // In start, for each window with UI
+/// self.gui.add_window(event_loop, context, window_id);
+/// // In window_input
+/// if self.gui.handle_window_event(context, window_id, event) {
+///     return;
+/// }
+/// // In render, after rendering the scene into `view`
+/// let buffers = self.gui.render(context, encoder, window, &view, |ctx| {
+///     egui::Window::new("Inspector").show(ctx, |ui| ui.label("Hello"));
+/// });
+/// ```
+///
+/// Alternatively return it from [`GlassApp::gui`](crate::GlassApp::gui) and let the runner pass
+/// events and render it, see [`GuiBackend`]. UI is then built in update:
+///
+/// ```ignore
+/// let ctx = self.gui.window(window_id).unwrap().context();
+/// egui::Window::new("Inspector").show(ctx, |ui| ui.label("Hello"));
+/// ```
+#[derive(Default)]
+pub struct EguiGui {
+    windows: HashMap<WindowId, EguiWindow>,
+}
+
+impl EguiGui {
+    pub fn new() -> EguiGui {
+        EguiGui::default()
+    }
+
+    /// Show UI in a window. Returns false if the window doesn't exist.
+    pub fn add_window(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        context: &GlassContext,
+        window_id: WindowId,
+    ) -> bool {
+        let Some(window) = context.render_window(window_id) else {
+            return false;
+        };
+        self.windows
+            .insert(window_id, EguiWindow::new(event_loop, context, window));
+        true
+    }
+
+    pub fn remove_window(&mut self, window_id: WindowId) {
+        self.windows.remove(&window_id);
+    }
+
+    pub fn window(&self, window_id: WindowId) -> Option<&EguiWindow> {
+        self.windows.get(&window_id)
+    }
+
+    /// Pass a window event to the window's egui. Returns whether egui consumed the event, in
+    /// which case the app should ignore it. Destroyed windows are removed.
+    pub fn handle_window_event(
+        &mut self,
+        context: &GlassContext,
+        window_id: WindowId,
+        event: &WindowEvent,
+    ) -> bool {
+        if let WindowEvent::Destroyed = event {
+            self.remove_window(window_id);
+            return false;
+        }
+        let (Some(gui), Some(window)) = (
+            self.windows.get_mut(&window_id),
+            context.render_window(window_id),
+        ) else {
+            return false;
+        };
+        let EventResponse {
+            consumed,
+            repaint,
+        } = gui.state.on_window_event(window.window(), event);
+        gui.repaint = repaint;
+        consumed
+    }
+
+    /// Run `ui` for the window and render it over `view`. Returns command buffers to submit
+    /// before the encoder's, none if the window has no UI.
+    pub fn render(
+        &mut self,
+        context: &GlassContext,
+        encoder: &mut CommandEncoder,
+        window: &GlassWindow,
+        view: &TextureView,
+        ui: impl FnMut(&egui::Context),
+    ) -> Vec<CommandBuffer> {
+        let Some(gui) = self.windows.get_mut(&window.window().id()) else {
+            return vec![];
+        };
+        let raw_input = gui.state.take_egui_input(window.window());
+        let output = gui.ctx.run(raw_input, ui);
+        gui.paint(context, encoder, window, view, output)
+    }
+}
+
+impl GuiBackend for EguiGui {
+    fn handle_window_event(
+        &mut self,
+        context: &GlassContext,
+        window_id: WindowId,
+        event: &WindowEvent,
+    ) -> bool {
+        EguiGui::handle_window_event(self, context, window_id, event)
+    }
+
+    fn update(&mut self, context: &GlassContext) {
+        for (window_id, gui) in self.windows.iter_mut() {
+            let Some(window) = context.render_window(*window_id) else {
+                continue;
+            };
+            // The window wasn't rendered since the last update, e.g. while resizing
+            if gui.in_pass {
+                gui.ctx.end_pass();
+            }
+            let raw_input = gui.state.take_egui_input(window.window());
+            gui.ctx.begin_pass(raw_input);
+            gui.in_pass = true;
+        }
+    }
+
+    fn render(
+        &mut self,
+        context: &GlassContext,
+        encoder: &mut CommandEncoder,
+        window: &GlassWindow,
+        view: &TextureView,
+    ) -> Vec<CommandBuffer> {
+        let Some(gui) = self.windows.get_mut(&window.window().id()) else {
+            return vec![];
+        };
+        if !gui.in_pass {
+            return vec![];
+        }
+        gui.in_pass = false;
+        let output = gui.ctx.end_pass();
+        gui.paint(context, encoder, window, view, output)
+    }
+}
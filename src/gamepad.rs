@@ -0,0 +1,226 @@
+use std::collections::{HashMap, HashSet};
+
+pub use gilrs::{Axis, Button, GamepadId};
+use gilrs::{EventType, Gilrs};
+use log::{error, info};
+
+use crate::input::Input;
+
+/// Default dead zone of sticks and triggers
+pub const DEFAULT_DEAD_ZONE: f32 = 0.15;
+
+/// Analog stick of a gamepad
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Stick {
+    Left,
+    Right,
+}
+
+/// Button and axis state of a connected gamepad
+#[derive(Debug, Clone)]
+pub struct GamepadState {
+    name: String,
+    pressed: HashSet<Button>,
+    just_pressed: HashSet<Button>,
+    just_released: HashSet<Button>,
+    axes: HashMap<Axis, f32>,
+    dead_zone: f32,
+}
+
+impl GamepadState {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn pressed(&self, button: Button) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    /// Pressed during this frame
+    pub fn just_pressed(&self, button: Button) -> bool {
+        self.just_pressed.contains(&button)
+    }
+
+    /// Released during this frame
+    pub fn just_released(&self, button: Button) -> bool {
+        self.just_released.contains(&button)
+    }
+
+    /// Axis value in `-1.0..=1.0` with the dead zone removed and the rest rescaled, so small
+    /// values start from zero
+    pub fn axis(&self, axis: Axis) -> f32 {
+        let value = self.axes.get(&axis).copied().unwrap_or(0.0);
+        if value.abs() <= self.dead_zone {
+            0.0
+        } else {
+            value.signum() * (value.abs() - self.dead_zone) / (1.0 - self.dead_zone)
+        }
+    }
+
+    /// Stick position with a radial dead zone, so diagonals aren't snapped to axes. Y points up.
+    pub fn stick(&self, stick: Stick) -> [f32; 2] {
+        let (x_axis, y_axis) = match stick {
+            Stick::Left => (Axis::LeftStickX, Axis::LeftStickY),
+            Stick::Right => (Axis::RightStickX, Axis::RightStickY),
+        };
+        let x = self.axes.get(&x_axis).copied().unwrap_or(0.0);
+        let y = self.axes.get(&y_axis).copied().unwrap_or(0.0);
+        let length = (x * x + y * y).sqrt();
+        if length <= self.dead_zone {
+            return [0.0; 2];
+        }
+        let scale = ((length - self.dead_zone) / (1.0 - self.dead_zone)).min(1.0) / length;
+        [x * scale, y * scale]
+    }
+}
+
+/// Gamepads polled by the runner with gilrs, requires the `gamepad` feature. Accessible through
+/// [`GlassContext::gamepads`](crate::GlassContext::gamepads). Buttons of all pads are also
+/// fed to [`Input`], so they can be bound to actions with
+/// [`InputMap`](crate::input::InputMap).
+pub struct Gamepads {
+    gilrs: Option<Gilrs>,
+    pads: HashMap<GamepadId, GamepadState>,
+    dead_zone: f32,
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Gamepads {
+    /// Start listening to gamepads. If gilrs can't be initialized, the error is logged and no
+    /// gamepads are reported.
+    pub fn new() -> Gamepads {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                error!("Failed to initialize gamepads: {}", e);
+                None
+            }
+        };
+        let mut gamepads = Gamepads {
+            gilrs,
+            pads: HashMap::new(),
+            dead_zone: DEFAULT_DEAD_ZONE,
+        };
+        let connected = gamepads.gilrs.as_ref().map_or(vec![], |gilrs| {
+            gilrs
+                .gamepads()
+                .map(|(id, pad)| (id, pad.name().to_string()))
+                .collect()
+        });
+        for (id, name) in connected {
+            gamepads.connect(id, name);
+        }
+        gamepads
+    }
+
+    /// Ids of connected gamepads
+    pub fn connected(&self) -> impl Iterator<Item = GamepadId> + '_ {
+        self.pads.keys().copied()
+    }
+
+    pub fn pad(&self, id: GamepadId) -> Option<&GamepadState> {
+        self.pads.get(&id)
+    }
+
+    /// Any connected gamepad, for single player games
+    pub fn first(&self) -> Option<&GamepadState> {
+        self.pads.values().next()
+    }
+
+    pub fn dead_zone(&self) -> f32 {
+        self.dead_zone
+    }
+
+    /// Set the dead zone of sticks and triggers, `0.0..1.0`
+    pub fn set_dead_zone(&mut self, dead_zone: f32) {
+        self.dead_zone = dead_zone.clamp(0.0, 0.99);
+        for pad in self.pads.values_mut() {
+            pad.dead_zone = self.dead_zone;
+        }
+    }
+
+    fn connect(&mut self, id: GamepadId, name: String) {
+        info!("Gamepad connected: {}", name);
+        self.pads.insert(id, GamepadState {
+            name,
+            pressed: HashSet::new(),
+            just_pressed: HashSet::new(),
+            just_released: HashSet::new(),
+            axes: HashMap::new(),
+            dead_zone: self.dead_zone,
+        });
+    }
+
+    /// Process gamepad events since the last poll. Called by the runner before update.
+    pub fn poll(&mut self, input: &mut Input) {
+        let Some(gilrs) = self.gilrs.as_mut() else {
+            return;
+        };
+        let mut events = vec![];
+        while let Some(event) = gilrs.next_event() {
+            let name = match event.event {
+                EventType::Connected => Some(gilrs.gamepad(event.id).name().to_string()),
+                _ => None,
+            };
+            events.push((event.id, event.event, name));
+        }
+        for (id, event, name) in events {
+            if let Some(name) = name {
+                self.connect(id, name);
+                continue;
+            }
+            if event == EventType::Disconnected {
+                if let Some(pad) = self.pads.remove(&id) {
+                    info!("Gamepad disconnected: {}", pad.name);
+                    for button in pad.pressed {
+                        if !self.any_pressed(button) {
+                            input.handle_gamepad_button(button, false);
+                        }
+                    }
+                }
+                continue;
+            }
+            let Some(pad) = self.pads.get_mut(&id) else {
+                continue;
+            };
+            match event {
+                EventType::ButtonPressed(button, _) => {
+                    if pad.pressed.insert(button) {
+                        pad.just_pressed.insert(button);
+                    }
+                    input.handle_gamepad_button(button, true);
+                }
+                EventType::ButtonReleased(button, _) => {
+                    if pad.pressed.remove(&button) {
+                        pad.just_released.insert(button);
+                    }
+                    // Held on another pad
+                    if !self.any_pressed(button) {
+                        input.handle_gamepad_button(button, false);
+                    }
+                }
+                EventType::AxisChanged(axis, value, _) => {
+                    pad.axes.insert(axis, value);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn any_pressed(&self, button: Button) -> bool {
+        self.pads.values().any(|pad| pad.pressed(button))
+    }
+
+    /// Clear per frame state. Called by the runner at the end of each frame.
+    pub fn end_frame(&mut self) {
+        for pad in self.pads.values_mut() {
+            pad.just_pressed.clear();
+            pad.just_released.clear();
+        }
+    }
+}
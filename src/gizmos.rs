@@ -0,0 +1,202 @@
+use wgpu::{BufferUsages, ColorTargetState, Device, RenderPass, TextureFormat};
+
+use crate::{
+    device_context::DeviceContext,
+    pipelines::{ColoredVertex, Curve, LinePipeline, LineWidth, DEFAULT_CURVE_TOLERANCE},
+    utils::GrowableBuffer,
+};
+
+/// Immediate mode debug lines. Shapes added during update are uploaded by the runner before
+/// render and drawn into any render pass with [`Gizmos::draw`]. Access it with
+/// [`GlassContext::gizmos_mut`](crate::GlassContext::gizmos_mut), the shapes are cleared each
+/// frame.
+///
+/// With push constants, drawing needs a `max_push_constant_size` limit of 128.
+pub struct Gizmos {
+    vertices: GrowableBuffer<ColoredVertex>,
+    pipeline: Option<LinePipeline>,
+    width: LineWidth,
+}
+
+impl Gizmos {
+    pub fn new(device: &Device) -> Gizmos {
+        Gizmos {
+            vertices: GrowableBuffer::new(
+                device,
+                Some("Gizmo Vertices"),
+                BufferUsages::VERTEX,
+                1024,
+            ),
+            pipeline: None,
+            width: LineWidth::Pixels(1.0),
+        }
+    }
+
+    /// Create the pipeline for a render target. If not set, the pipeline is created for the
+    /// primary window's surface on first use.
+    pub fn set_target(
+        &mut self,
+        device_context: &DeviceContext,
+        color_target_state: ColorTargetState,
+        sample_count: u32,
+    ) {
+        self.pipeline = Some(LinePipeline::new(
+            device_context,
+            color_target_state,
+            sample_count,
+        ));
+    }
+
+    pub fn set_width(&mut self, width: LineWidth) {
+        self.width = width;
+    }
+
+    pub fn width(&self) -> LineWidth {
+        self.width
+    }
+
+    /// Whether no shapes were added this frame
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    pub fn line(&mut self, a: [f32; 2], b: [f32; 2], color: [f32; 4]) {
+        self.vertices.extend([
+            ColoredVertex::new_2d(a, color),
+            ColoredVertex::new_2d(b, color),
+        ]);
+    }
+
+    /// Lines through all points
+    pub fn line_strip(&mut self, points: &[[f32; 2]], color: [f32; 4]) {
+        for w in points.windows(2) {
+            self.line(w[0], w[1], color);
+        }
+    }
+
+    pub fn circle(&mut self, center: [f32; 2], radius: f32, color: [f32; 4]) {
+        self.curve(
+            Curve::Arc {
+                center,
+                radius,
+                start_angle: 0.0,
+                end_angle: std::f32::consts::TAU,
+            },
+            color,
+        );
+    }
+
+    /// Rect outline centered at `center`
+    pub fn rect(&mut self, center: [f32; 2], size: [f32; 2], color: [f32; 4]) {
+        let [hx, hy] = [size[0] * 0.5, size[1] * 0.5];
+        let [x, y] = center;
+        self.line_strip(
+            &[
+                [x - hx, y - hy],
+                [x + hx, y - hy],
+                [x + hx, y + hy],
+                [x - hx, y + hy],
+                [x - hx, y - hy],
+            ],
+            color,
+        );
+    }
+
+    /// Line from `from` to `to` with a head at `to` sized relative to the length
+    pub fn arrow(&mut self, from: [f32; 2], to: [f32; 2], color: [f32; 4]) {
+        self.line(from, to, color);
+        let d = [to[0] - from[0], to[1] - from[1]];
+        let head = 0.2;
+        // Head sides are the reversed direction rotated by +-30 degrees
+        let (sin, cos) = std::f32::consts::FRAC_PI_6.sin_cos();
+        for sin in [sin, -sin] {
+            let side = [
+                -(d[0] * cos - d[1] * sin) * head,
+                -(d[0] * sin + d[1] * cos) * head,
+            ];
+            self.line(to, [to[0] + side[0], to[1] + side[1]], color);
+        }
+    }
+
+    /// Grid of `cells` centered at `center`
+    pub fn grid(
+        &mut self,
+        center: [f32; 2],
+        cell_size: [f32; 2],
+        cells: [u32; 2],
+        color: [f32; 4],
+    ) {
+        let size = [
+            cell_size[0] * cells[0] as f32,
+            cell_size[1] * cells[1] as f32,
+        ];
+        let min = [center[0] - size[0] * 0.5, center[1] - size[1] * 0.5];
+        for i in 0..=cells[0] {
+            let x = min[0] + i as f32 * cell_size[0];
+            self.line([x, min[1]], [x, min[1] + size[1]], color);
+        }
+        for j in 0..=cells[1] {
+            let y = min[1] + j as f32 * cell_size[1];
+            self.line([min[0], y], [min[0] + size[0], y], color);
+        }
+    }
+
+    pub fn curve(&mut self, curve: Curve, color: [f32; 4]) {
+        let tolerance = match curve {
+            Curve::Arc {
+                radius, ..
+            } => radius * 0.002,
+            _ => DEFAULT_CURVE_TOLERANCE,
+        };
+        self.vertices.extend(curve.line_list(color, tolerance));
+    }
+
+    /// Upload this frame's shapes and start collecting the next frame's. `target` is the format
+    /// and sample count used if no pipeline was created yet.
+    pub(crate) fn flush(
+        &mut self,
+        device_context: &DeviceContext,
+        target: Option<(TextureFormat, u32)>,
+    ) {
+        if self.pipeline.is_none() && !self.vertices.is_empty() {
+            if let Some((format, sample_count)) = target {
+                self.set_target(
+                    device_context,
+                    ColorTargetState {
+                        format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    },
+                    sample_count,
+                );
+            }
+        }
+        self.vertices
+            .upload(device_context.device(), device_context.queue());
+        self.vertices.clear();
+    }
+
+    /// Draw the shapes added during this frame's update. `viewport` is the size of the render
+    /// target in pixels.
+    pub fn draw<'r>(
+        &'r self,
+        rpass: &mut RenderPass<'r>,
+        view_proj: [[f32; 4]; 4],
+        viewport: [f32; 2],
+    ) {
+        let Some(pipeline) = &self.pipeline else {
+            return;
+        };
+        if self.vertices.uploaded_len() == 0 {
+            return;
+        }
+        pipeline.draw_thick_line_buffer(
+            rpass,
+            view_proj,
+            self.vertices.buffer(),
+            0..self.vertices.uploaded_len() / 2,
+            self.width,
+            viewport,
+        );
+    }
+}
@@ -1,7 +1,14 @@
-use std::{fmt::Formatter, sync::Arc};
+use std::{
+    fmt::Formatter,
+    fs,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use image::ImageError;
 use indexmap::IndexMap;
+use log::{error, info, warn};
 use wgpu::{
     Adapter, CreateSurfaceError, Device, Instance, PowerPreference, Queue, RequestDeviceError,
     Sampler, SurfaceConfiguration,
@@ -9,17 +16,38 @@ use wgpu::{
 use winit::{
     application::ApplicationHandler,
     error::{EventLoopError, OsError},
-    event::{DeviceEvent, DeviceId, ElementState, StartCause, WindowEvent},
+    event::{DeviceEvent, DeviceId, ElementState, Ime, StartCause, WindowEvent},
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
-    keyboard::{Key, NamedKey},
+    keyboard::{Key, NamedKey, PhysicalKey},
     window::{Fullscreen, Window, WindowId},
 };
 
+#[cfg(feature = "clipboard")]
+use crate::clipboard::Clipboard;
+#[cfg(feature = "text")]
+use crate::debug_overlay::DebugOverlay;
+#[cfg(feature = "gamepad")]
+use crate::gamepad::Gamepads;
 use crate::{
-    device_context::{DeviceConfig, DeviceContext},
+    capture::{CaptureCallback, PendingCapture},
+    debug_overlay::DebugOverlayConfig,
+    device_context::{DeviceConfig, DeviceContext, SharedGpu},
+    gizmos::Gizmos,
+    gpu_error::GpuErrors,
+    hot_reload::{HotReloaded, ShaderHotReload},
+    input::{Input, TextEvent},
+    locale::{message, Message},
+    recorder::{FrameRecorder, RecorderConfig},
+    texture::TextureLoader,
+    time::{FrameTime, Time},
+    utils::{
+        BindGroupCache, GpuProfiler, GpuTiming, ShaderModule, UploadBelt, WatchedShaderModule,
+    },
+    watchdog::{FrameStage, Watchdog, WatchdogConfig},
     window::{
-        get_best_videomode, get_centered_window_position, get_fitting_videomode, GlassWindow,
-        WindowConfig, WindowPos,
+        get_best_videomode, get_centered_window_position, get_fitting_videomode,
+        get_window_position_on_monitor, window_title, GlassWindow, MonitorInfo, WindowConfig,
+        WindowPos,
     },
     GlassApp, RenderData,
 };
@@ -116,21 +144,48 @@ impl ApplicationHandler for Glass {
             runner_state,
             ..
         } = self;
-        app.window_input(context, event_loop, window_id, &event);
+        context.input.handle_window_event(window_id, &event);
+        let consumed = app
+            .gui()
+            .is_some_and(|gui| gui.handle_window_event(context, window_id, &event));
+        if !consumed {
+            app.window_input(context, event_loop, window_id, &event);
+        }
 
         let mut is_extra_update = false;
 
+        let drag_and_drop = context
+            .windows
+            .get(&window_id)
+            .map(|w| w.drag_and_drop())
+            .unwrap_or(false);
+        if drag_and_drop {
+            match &event {
+                WindowEvent::DroppedFile(path) => {
+                    app.file_dropped(context, window_id, path.clone());
+                }
+                WindowEvent::HoveredFile(path) => {
+                    app.file_hovered(context, window_id, path.clone());
+                }
+                WindowEvent::HoveredFileCancelled => {
+                    app.file_hover_cancelled(context, window_id);
+                }
+                _ => (),
+            }
+        }
+
+        let mut text_event = None;
         if let Some(window) = context.windows.get_mut(&window_id) {
             match event {
-                WindowEvent::Resized(physical_size) => {
-                    // On windows, minimized app can have 0,0 size
-                    if physical_size.width > 0 && physical_size.height > 0 {
-                        window.configure_surface_with_size(
-                            context.device_context.device(),
-                            physical_size,
-                        );
-                        is_extra_update = true;
-                    }
+                // On windows, minimized app can have 0,0 size
+                WindowEvent::Resized(physical_size)
+                    if physical_size.width > 0 && physical_size.height > 0 =>
+                {
+                    window.configure_surface_with_size(
+                        context.device_context.device(),
+                        physical_size,
+                    );
+                    is_extra_update = true;
                 }
                 WindowEvent::ScaleFactorChanged {
                     ..
@@ -153,9 +208,54 @@ impl ApplicationHandler for Glass {
                         runner_state.request_window_close = true;
                         runner_state.remove_windows.push(window_id);
                     }
+                    if let PhysicalKey::Code(key) = event.physical_key {
+                        let pressed =
+                            !is_synthetic && !event.repeat && event.state == ElementState::Pressed;
+                        if pressed && context.time_hotkeys {
+                            context.time.handle_hotkey(key);
+                        }
+                        #[cfg(feature = "text")]
+                        if pressed {
+                            if let Some(overlay) = &mut context.debug_overlay {
+                                overlay.handle_hotkey(key);
+                            }
+                        }
+                    }
+                    // While composing, keys go to the IME and text arrives as a commit
+                    if let Some(text) = event.text.filter(|text| {
+                        event.state == ElementState::Pressed
+                            && !window.is_ime_composing()
+                            && !text.chars().any(char::is_control)
+                    }) {
+                        text_event = Some(TextEvent::Commit(text.to_string()));
+                    }
+                }
+                WindowEvent::Ime(ime) => {
+                    text_event = Some(match ime {
+                        Ime::Enabled => TextEvent::ImeEnabled,
+                        Ime::Preedit(text, cursor) => {
+                            window.set_ime_composing(!text.is_empty());
+                            TextEvent::Preedit {
+                                text,
+                                cursor,
+                            }
+                        }
+                        Ime::Commit(text) => {
+                            window.set_ime_composing(false);
+                            TextEvent::Commit(text)
+                        }
+                        Ime::Disabled => {
+                            window.set_ime_composing(false);
+                            TextEvent::ImeDisabled
+                        }
+                    });
                 }
                 WindowEvent::Focused(has_focus) => {
                     window.set_focus(has_focus);
+                    // Some platforms release the grab when focus is lost
+                    if has_focus && window.is_relative_mouse() {
+                        window.set_relative_mouse(true);
+                    }
                 }
                 WindowEvent::CloseRequested => {
                     runner_state.request_window_close = true;
@@ -164,6 +264,9 @@ impl ApplicationHandler for Glass {
                 _ => (),
             }
         }
+        if let Some(text_event) = text_event {
+            app.text_input(context, window_id, text_event);
+        }
         // Update immediately, because about_to_wait isn't triggered during resize. If it did,
         // this would not be needed.
 
@@ -190,6 +293,7 @@ impl ApplicationHandler for Glass {
             context,
             ..
         } = self;
+        context.input.handle_device_event(&event);
         app.device_input(context, event_loop, device_id, &event);
     }
 
@@ -210,6 +314,10 @@ impl ApplicationHandler for Glass {
             ..
         } = self;
         app.end(context);
+        context.stop_recording();
+        if let Err(e) = context.device_context.save_pipeline_cache() {
+            error!("Failed to save pipeline cache: {}", e);
+        }
     }
 }
 
@@ -236,15 +344,140 @@ fn run_update(
             return;
         }
     }
+    let hangs = context
+        .watchdog
+        .as_ref()
+        .map(|watchdog| watchdog.take_hangs())
+        .unwrap_or_default();
+    for hang in hangs {
+        app.on_hang(context, &hang);
+    }
+    if let Some(watchdog) = &context.watchdog {
+        watchdog.begin_frame();
+    }
+    for request in std::mem::take(&mut context.state_requests) {
+        handle_state_request(app, request);
+    }
+    context.frame_time.tick();
+    context.gpu_errors.begin_frame(
+        context.device_context.device(),
+        context.frame_time.frame_index(),
+    );
+    let debugger_capture = std::mem::take(&mut context.debugger_capture_requested);
+    if debugger_capture {
+        info!("Capturing frame {}", context.frame_time.frame_index());
+        context.device_context.device().start_capture();
+    }
+    context.time.tick();
+    #[cfg(feature = "gamepad")]
+    context.gamepads.poll(&mut context.input);
+    context
+        .shader_hot_reload
+        .update(context.device_context.device());
+    for _ in 0..context.time.fixed_steps() {
+        app.fixed_update(context);
+    }
+    if let Some(gui) = app.gui() {
+        gui.update(context);
+    }
     app.update(context);
 
+    let gizmo_target = context
+        .primary_render_window_maybe()
+        .map(|window| (window.surface_format(), window.msaa_samples()));
+    context.gizmos.flush(&context.device_context, gizmo_target);
+    if let Some(uploads) = context.upload_belt.finish() {
+        context.device_context.queue().submit(Some(uploads));
+        context.track_submission();
+    }
+    #[cfg(feature = "text")]
+    if context.debug_overlay.is_some() {
+        let gpu_timings = context.gpu_timings();
+        if let Some(overlay) = &mut context.debug_overlay {
+            overlay.prepare(
+                &context.device_context,
+                &context.frame_time,
+                &gpu_timings,
+                context
+                    .windows
+                    .values()
+                    .map(|window| window.surface_format()),
+            );
+        }
+    }
+    context.set_frame_stage(FrameStage::Render);
     render(app, context);
+    context.upload_belt.recall();
+    context.bind_group_cache.end_frame();
+    if let Some(profiler) = &context.gpu_profiler {
+        profiler.end_frame(
+            context.device_context.device(),
+            context.device_context.queue(),
+        );
+    }
+    if debugger_capture {
+        context.device_context.device().stop_capture();
+    }
+    let gpu_errors = context
+        .gpu_errors
+        .end_frame(context.device_context.device());
+    for error in gpu_errors {
+        app.gpu_error(context, &error);
+    }
 
     app.end_of_frame(context);
+    context.input.end_frame();
+    #[cfg(feature = "gamepad")]
+    context.gamepads.end_frame();
+    if let Some(watchdog) = &context.watchdog {
+        watchdog.end_frame();
+    }
+
+    limit_frame_rate(context);
+}
+
+fn handle_state_request(app: &mut Box<dyn GlassApp>, request: StateRequest) {
+    match request {
+        StateRequest::Save(path) => {
+            let state = app.save_state();
+            if state.is_empty() {
+                warn!("App returned no state to save");
+                return;
+            }
+            match fs::write(&path, state) {
+                Ok(()) => info!("Saved state to {}", path.display()),
+                Err(e) => error!("Failed to save state to {}: {}", path.display(), e),
+            }
+        }
+        StateRequest::Load(path) => match fs::read(&path) {
+            Ok(state) => {
+                app.load_state(&state);
+                info!("Loaded state from {}", path.display());
+            }
+            Err(e) => error!("Failed to load state from {}: {}", path.display(), e),
+        },
+    }
+}
+
+fn limit_frame_rate(context: &mut GlassContext) {
+    if let Some(target) = context.target_frame_time() {
+        let elapsed = context.last_frame_end.elapsed();
+        if elapsed < target {
+            std::thread::sleep(target - elapsed);
+        }
+    }
+    context.last_frame_end = Instant::now();
 }
 
 fn render(app: &mut Box<dyn GlassApp>, context: &mut GlassContext) {
-    for (_, window) in context.windows.iter() {
+    if context.synchronized_present && context.windows.len() > 1 {
+        render_synchronized(app, context);
+        return;
+    }
+    let mut captures = std::mem::take(&mut context.frame_captures);
+    let had_captures = !captures.is_empty();
+    let mut recorder = context.frame_recorder.take();
+    for (window_id, window) in context.windows.iter() {
         match window.surface().get_current_texture() {
             Ok(frame) => {
                 let mut encoder = context.device_context.device().create_command_encoder(
@@ -259,12 +492,53 @@ fn render(app: &mut Box<dyn GlassApp>, context: &mut GlassContext) {
                         encoder: &mut encoder,
                         window,
                         frame: &frame,
+                        msaa_view: window.msaa_view(),
+                        depth_view: window.depth_view(),
                     })
                     .unwrap_or_default();
+                if let Some(gui) = app.gui() {
+                    let view = frame.texture.create_view(&Default::default());
+                    buffers.extend(gui.render(context, &mut encoder, window, &view));
+                }
+                let capture = copy_frame_for_captures(
+                    context.device_context.device(),
+                    &mut encoder,
+                    &mut captures,
+                    *window_id,
+                    window,
+                    &frame,
+                );
+                let recording = copy_frame_for_recording(
+                    context.device_context.device(),
+                    &mut encoder,
+                    &mut recorder,
+                    *window_id,
+                    &frame,
+                );
+                #[cfg(feature = "text")]
+                if let Some(overlay) = &context.debug_overlay {
+                    overlay.draw(
+                        context.device_context.device(),
+                        context.device_context.queue(),
+                        &mut encoder,
+                        window,
+                        &frame,
+                    );
+                }
                 buffers.push(encoder.finish());
+                context.device_context.upload_draw_params();
                 context.device_context.queue().submit(buffers);
+                context.track_submission();
+                if let Some((capture, callbacks)) = capture {
+                    capture.read(callbacks);
+                }
+                if let (Some(recorder), Some(recording)) = (&mut recorder, recording) {
+                    recorder.read(recording);
+                }
 
+                context.set_frame_stage(FrameStage::Present);
                 frame.present();
+                context.set_frame_stage(FrameStage::Render);
             }
             Err(error) => {
                 if error == wgpu::SurfaceError::OutOfMemory {
@@ -274,6 +548,170 @@ fn render(app: &mut Box<dyn GlassApp>, context: &mut GlassContext) {
         }
         window.window().request_redraw();
     }
+    context.frame_recorder = recorder;
+    finish_captures(context, captures, had_captures);
+}
+
+/// Acquires every window's frame first, submits all command buffers at once and then presents
+/// all frames in a tight loop. This keeps the outputs of e.g. video walls as close together as
+/// possible. The time between the first and the last present is stored as present skew.
+fn render_synchronized(app: &mut Box<dyn GlassApp>, context: &mut GlassContext) {
+    let mut captures = std::mem::take(&mut context.frame_captures);
+    let had_captures = !captures.is_empty();
+    let mut recorder = context.frame_recorder.take();
+    let mut frames = vec![];
+    for (window_id, window) in context.windows.iter() {
+        match window.surface().get_current_texture() {
+            Ok(frame) => frames.push((*window_id, window, frame)),
+            Err(error) => {
+                if error == wgpu::SurfaceError::OutOfMemory {
+                    panic!("Swapchain error: {error}. Rendering cannot continue.")
+                }
+            }
+        }
+    }
+
+    let mut buffers = vec![];
+    let mut pending_captures = vec![];
+    let mut pending_recording = None;
+    for (window_id, window, frame) in frames.iter() {
+        let mut encoder = context.device_context.device().create_command_encoder(
+            &wgpu::CommandEncoderDescriptor {
+                label: Some("Render Commands"),
+            },
+        );
+        // Run render
+        let mut window_buffers = app
+            .render(context, RenderData {
+                encoder: &mut encoder,
+                window,
+                frame,
+                msaa_view: window.msaa_view(),
+                depth_view: window.depth_view(),
+            })
+            .unwrap_or_default();
+        if let Some(gui) = app.gui() {
+            let view = frame.texture.create_view(&Default::default());
+            window_buffers.extend(gui.render(context, &mut encoder, window, &view));
+        }
+        pending_captures.extend(copy_frame_for_captures(
+            context.device_context.device(),
+            &mut encoder,
+            &mut captures,
+            *window_id,
+            window,
+            frame,
+        ));
+        pending_recording = pending_recording.or(copy_frame_for_recording(
+            context.device_context.device(),
+            &mut encoder,
+            &mut recorder,
+            *window_id,
+            frame,
+        ));
+        #[cfg(feature = "text")]
+        if let Some(overlay) = &context.debug_overlay {
+            overlay.draw(
+                context.device_context.device(),
+                context.device_context.queue(),
+                &mut encoder,
+                window,
+                frame,
+            );
+        }
+        window_buffers.push(encoder.finish());
+        buffers.extend(window_buffers);
+    }
+    context.device_context.upload_draw_params();
+    context.device_context.queue().submit(buffers);
+    context.track_submission();
+    for (capture, callbacks) in pending_captures {
+        capture.read(callbacks);
+    }
+    if let (Some(recorder), Some(recording)) = (&mut recorder, pending_recording) {
+        recorder.read(recording);
+    }
+
+    context.set_frame_stage(FrameStage::Present);
+    let present_start = Instant::now();
+    for (_, _, frame) in frames {
+        frame.present();
+    }
+    let skew = present_start.elapsed();
+
+    for (_, window) in context.windows.iter() {
+        window.window().request_redraw();
+    }
+    context.present_skew = Some(skew);
+    context.frame_recorder = recorder;
+    finish_captures(context, captures, had_captures);
+}
+
+/// Encode a copy of the frame if captures were requested for the window
+fn copy_frame_for_captures(
+    device: &Device,
+    encoder: &mut wgpu::CommandEncoder,
+    captures: &mut Vec<(WindowId, CaptureCallback)>,
+    window_id: WindowId,
+    window: &GlassWindow,
+    frame: &wgpu::SurfaceTexture,
+) -> Option<(PendingCapture, Vec<CaptureCallback>)> {
+    if !captures.iter().any(|(id, _)| *id == window_id) {
+        return None;
+    }
+    let callbacks = captures
+        .extract_if(.., |(id, _)| *id == window_id)
+        .map(|(_, callback)| callback)
+        .collect::<Vec<_>>();
+    if !window.supports_capture() {
+        warn!(
+            "Window surface doesn't support frame capture (format {:?})",
+            window.surface_format()
+        );
+        return None;
+    }
+    PendingCapture::copy(device, encoder, &frame.texture, None).map(|capture| (capture, callbacks))
+}
+
+fn copy_frame_for_recording(
+    device: &Device,
+    encoder: &mut wgpu::CommandEncoder,
+    recorder: &mut Option<FrameRecorder>,
+    window_id: WindowId,
+    frame: &wgpu::SurfaceTexture,
+) -> Option<PendingCapture> {
+    match recorder {
+        Some(recorder) if recorder.window_id() == window_id => {
+            recorder.copy(device, encoder, &frame.texture)
+        }
+        _ => None,
+    }
+}
+
+/// Keep captures of windows that didn't render this frame & poll finished readbacks
+fn finish_captures(
+    context: &mut GlassContext,
+    captures: Vec<(WindowId, CaptureCallback)>,
+    had_captures: bool,
+) {
+    context.frame_captures.extend(
+        captures
+            .into_iter()
+            .filter(|(id, _)| context.windows.contains_key(id)),
+    );
+    // Also runs the watchdog's submission callbacks & gpu profiler readbacks
+    if had_captures
+        || context.frame_recorder.is_some()
+        || context.watchdog.is_some()
+        || context.gpu_profiler.is_some()
+    {
+        context.device_context.device().poll(wgpu::Maintain::Poll);
+    }
+}
+
+enum StateRequest {
+    Save(PathBuf),
+    Load(PathBuf),
 }
 
 #[derive(Default)]
@@ -288,6 +726,29 @@ struct RunnerState {
 pub struct GlassConfig {
     pub device_config: DeviceConfig,
     pub window_configs: Vec<WindowConfig>,
+    /// Acquire all window surfaces, submit once and present all frames together. Useful for
+    /// multi-projector or video-wall setups where outputs should not tear against each other.
+    pub synchronized_present: bool,
+    /// Limit how often the update & render loop runs.
+    pub frame_limit: FrameLimit,
+    /// Run [`GlassApp::fixed_update`] at this interval of (scaled) simulation time.
+    pub fixed_timestep: Option<Duration>,
+    /// Enable default time control hotkeys: F9 pause, F10 step, F7 slower & F8 faster.
+    pub time_hotkeys: bool,
+    /// Monitor frames and GPU work for hangs, see [`GlassApp::on_hang`].
+    pub watchdog: Option<WatchdogConfig>,
+    /// Create a [`GpuProfiler`] measuring at most this many scopes per frame, if the adapter
+    /// supports timestamp queries. See [`GlassContext::gpu_profiler`].
+    pub gpu_profiler_scopes: Option<u32>,
+    /// Cache parsed shader modules in this directory to speed up startup with many shaders.
+    /// Requires the `shader_cache` feature.
+    pub shader_cache_dir: Option<PathBuf>,
+    /// Draw frame statistics over every window, see [`DebugOverlay`](crate::debug_overlay).
+    /// Requires the `text` feature.
+    pub debug_overlay: Option<DebugOverlayConfig>,
+    /// Wrap each frame's update and render in wgpu error scopes, so errors are reported with the
+    /// frame they happened in. Errors are passed to [`GlassApp::gpu_error`] either way.
+    pub gpu_error_scopes: bool,
 }
 
 impl GlassConfig {
@@ -295,6 +756,15 @@ impl GlassConfig {
         Self {
             device_config: DeviceConfig::default(),
             window_configs: vec![],
+            synchronized_present: false,
+            frame_limit: FrameLimit::Unlimited,
+            fixed_timestep: None,
+            time_hotkeys: false,
+            watchdog: None,
+            gpu_profiler_scopes: None,
+            shader_cache_dir: None,
+            debug_overlay: None,
+            gpu_error_scopes: false,
         }
     }
 
@@ -310,6 +780,15 @@ impl GlassConfig {
                 exit_on_esc: false,
                 ..WindowConfig::default()
             }],
+            synchronized_present: false,
+            frame_limit: FrameLimit::Unlimited,
+            fixed_timestep: None,
+            time_hotkeys: false,
+            watchdog: None,
+            gpu_profiler_scopes: None,
+            shader_cache_dir: None,
+            debug_overlay: None,
+            gpu_error_scopes: false,
         }
     }
 }
@@ -319,10 +798,31 @@ impl Default for GlassConfig {
         Self {
             device_config: DeviceConfig::default(),
             window_configs: vec![WindowConfig::default()],
+            synchronized_present: false,
+            frame_limit: FrameLimit::Unlimited,
+            fixed_timestep: None,
+            time_hotkeys: false,
+            watchdog: None,
+            gpu_profiler_scopes: None,
+            shader_cache_dir: None,
+            debug_overlay: None,
+            gpu_error_scopes: false,
         }
     }
 }
 
+/// Frame pacing of the update & render loop.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FrameLimit {
+    /// Run as fast as presentation allows.
+    Unlimited,
+    /// Target a fixed amount of frames per second.
+    Fps(f64),
+    /// Target the refresh rate of the monitor of the primary window. Useful with adaptive sync
+    /// displays, whose refresh rate may be higher than what `AutoVsync` would pace to.
+    RefreshRate,
+}
+
 #[derive(Debug)]
 pub enum GlassError {
     WindowError(OsError),
@@ -331,17 +831,39 @@ pub enum GlassError {
     DeviceError(RequestDeviceError),
     ImageError(ImageError),
     EventLoopError(EventLoopError),
+    IoError(std::io::Error),
+    ArgumentError(String),
+    CaptureNotSupported,
+    FontError(String),
+    LutError(String),
+    ClipboardError(String),
+    /// A pipeline without a uniform fallback was created on a device without enough push
+    /// constants
+    PushConstantsNotSupported(String),
 }
 
 impl std::fmt::Display for GlassError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let s = match self {
-            GlassError::WindowError(e) => format!("WindowError: {}", e),
-            GlassError::SurfaceError(e) => format!("SurfaceError: {}", e),
-            GlassError::AdapterError => "AdapterError".to_owned(),
-            GlassError::DeviceError(e) => format!("DeviceError: {}", e),
-            GlassError::ImageError(e) => format!("ImageError: {}", e),
-            GlassError::EventLoopError(e) => format!("EventLoopError: {}", e),
+            GlassError::WindowError(e) => format!("{}: {}", message(Message::WindowError), e),
+            GlassError::SurfaceError(e) => format!("{}: {}", message(Message::SurfaceError), e),
+            GlassError::AdapterError => message(Message::AdapterError).into_owned(),
+            GlassError::DeviceError(e) => format!("{}: {}", message(Message::DeviceError), e),
+            GlassError::ImageError(e) => format!("{}: {}", message(Message::ImageError), e),
+            GlassError::EventLoopError(e) => {
+                format!("{}: {}", message(Message::EventLoopError), e)
+            }
+            GlassError::IoError(e) => format!("{}: {}", message(Message::IoError), e),
+            GlassError::ArgumentError(e) => format!("{}: {}", message(Message::ArgumentError), e),
+            GlassError::CaptureNotSupported => message(Message::CaptureNotSupported).into_owned(),
+            GlassError::FontError(e) => format!("{}: {}", message(Message::FontError), e),
+            GlassError::LutError(e) => format!("{}: {}", message(Message::LutError), e),
+            GlassError::ClipboardError(e) => {
+                format!("{}: {}", message(Message::ClipboardError), e)
+            }
+            GlassError::PushConstantsNotSupported(e) => {
+                format!("{}: {}", message(Message::PushConstantsNotSupported), e)
+            }
         };
         write!(f, "{}", s)
     }
@@ -353,23 +875,105 @@ impl std::fmt::Display for GlassError {
 pub struct GlassContext {
     device_context: DeviceContext,
     windows: IndexMap<WindowId, GlassWindow>,
+    synchronized_present: bool,
+    present_skew: Option<Duration>,
+    frame_limit: FrameLimit,
+    last_frame_end: Instant,
+    frame_captures: Vec<(WindowId, CaptureCallback)>,
+    debugger_capture_requested: bool,
+    frame_recorder: Option<FrameRecorder>,
+    time: Time,
+    frame_time: FrameTime,
+    time_hotkeys: bool,
+    watchdog: Option<Watchdog>,
+    gpu_profiler_scopes: Option<u32>,
+    gpu_profiler: Option<Arc<GpuProfiler>>,
+    gpu_errors: GpuErrors,
+    #[cfg(feature = "text")]
+    debug_overlay_config: Option<DebugOverlayConfig>,
+    #[cfg(feature = "text")]
+    debug_overlay: Option<DebugOverlay>,
+    bind_group_cache: BindGroupCache,
+    upload_belt: UploadBelt,
+    gizmos: Gizmos,
+    shader_hot_reload: ShaderHotReload,
+    input: Input,
+    #[cfg(feature = "gamepad")]
+    gamepads: Gamepads,
+    #[cfg(feature = "clipboard")]
+    clipboard: Clipboard,
+    state_requests: Vec<StateRequest>,
     exit: bool,
 }
 
 impl GlassContext {
     pub fn new(mut config: GlassConfig) -> Result<Self, GlassError> {
         // Modify features & limits needed for common pipelines
-        // Add push constants feature for common pipelines
-        config.device_config.features |= wgpu::Features::PUSH_CONSTANTS
-            | wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+        // Common pipelines use push constants when available and fall back to uniform buffers
+        // otherwise, e.g. on the web. Neither feature exists on WebGPU, so both are optional.
+        config.device_config.optional_features |=
+            wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+                | wgpu::Features::PUSH_CONSTANTS;
         config.device_config.limits = wgpu::Limits {
+            // The largest push constants of common pipelines, dropped when the adapter has none
+            max_push_constant_size: config.device_config.limits.max_push_constant_size.max(128),
             ..config.device_config.limits
         };
+        if config.gpu_profiler_scopes.is_some() {
+            config.device_config.optional_features |= GpuProfiler::required_features();
+        }
         let device_context = DeviceContext::new(&config.device_config)?;
+        let watchdog = config
+            .watchdog
+            .map(|watchdog| Watchdog::new(watchdog, device_context.adapter().get_info()));
+
+        let gpu_profiler = create_gpu_profiler(&device_context, config.gpu_profiler_scopes);
+        let gpu_errors = GpuErrors::new(device_context.device(), config.gpu_error_scopes);
+        #[cfg(feature = "shader_cache")]
+        crate::shader_cache::set_shader_cache_dir(config.shader_cache_dir.clone());
+        #[cfg(not(feature = "shader_cache"))]
+        if config.shader_cache_dir.is_some() {
+            warn!("GlassConfig::shader_cache_dir requires the shader_cache feature");
+        }
+        #[cfg(feature = "text")]
+        let debug_overlay = create_debug_overlay(&device_context, config.debug_overlay.as_ref());
+        #[cfg(not(feature = "text"))]
+        if config.debug_overlay.is_some() {
+            warn!("GlassConfig::debug_overlay requires the text feature");
+        }
+        let gizmos = Gizmos::new(device_context.device());
 
         Ok(Self {
             device_context,
             windows: IndexMap::default(),
+            synchronized_present: config.synchronized_present,
+            present_skew: None,
+            frame_limit: config.frame_limit,
+            last_frame_end: Instant::now(),
+            frame_captures: vec![],
+            debugger_capture_requested: false,
+            frame_recorder: None,
+            time: Time::new(config.fixed_timestep),
+            frame_time: FrameTime::new(),
+            time_hotkeys: config.time_hotkeys,
+            watchdog,
+            gpu_profiler_scopes: config.gpu_profiler_scopes,
+            gpu_profiler,
+            gpu_errors,
+            #[cfg(feature = "text")]
+            debug_overlay_config: config.debug_overlay,
+            #[cfg(feature = "text")]
+            debug_overlay,
+            bind_group_cache: BindGroupCache::new(),
+            upload_belt: UploadBelt::default(),
+            gizmos,
+            shader_hot_reload: ShaderHotReload::new(),
+            input: Input::new(),
+            #[cfg(feature = "gamepad")]
+            gamepads: Gamepads::new(),
+            #[cfg(feature = "clipboard")]
+            clipboard: Clipboard::new(),
+            state_requests: vec![],
             exit: false,
         })
     }
@@ -407,6 +1011,11 @@ impl GlassContext {
         self.device_context.device_arc()
     }
 
+    /// Device, queue and shared samplers, e.g. for creating bundled pipelines
+    pub fn device_context(&self) -> &DeviceContext {
+        &self.device_context
+    }
+
     pub fn queue(&self) -> &Queue {
         self.device_context.queue()
     }
@@ -415,6 +1024,63 @@ impl GlassContext {
         self.device_context.queue_arc()
     }
 
+    /// Return a [`TextureLoader`] that can be sent to worker threads to create textures there.
+    pub fn texture_loader(&self) -> TextureLoader {
+        TextureLoader::new(self.device_arc(), self.queue_arc())
+    }
+
+    /// Cache for deduplicating bind group creation, see [`BindGroupCache`]
+    pub fn bind_group_cache(&self) -> &BindGroupCache {
+        &self.bind_group_cache
+    }
+
+    /// Batched buffer & texture uploads submitted by the runner each frame, see [`UploadBelt`]
+    pub fn upload_belt(&self) -> &UploadBelt {
+        &self.upload_belt
+    }
+
+    /// Build a value, e.g. a pipeline, from a watched shader and rebuild it before
+    /// [`GlassApp::update`] whenever the shader changes, see [`ShaderHotReload`]
+    pub fn register_shader<T: 'static>(
+        &mut self,
+        watched: WatchedShaderModule,
+        build: impl FnMut(&Device, &ShaderModule) -> T + 'static,
+    ) -> HotReloaded<T> {
+        self.shader_hot_reload
+            .register(self.device_context.device(), watched, build)
+    }
+
+    pub fn shader_hot_reload(&self) -> &ShaderHotReload {
+        &self.shader_hot_reload
+    }
+
+    /// Debug lines drawn with [`Gizmos::draw`] during render
+    pub fn gizmos(&self) -> &Gizmos {
+        &self.gizmos
+    }
+
+    /// Add debug lines for this frame during update
+    pub fn gizmos_mut(&mut self) -> &mut Gizmos {
+        &mut self.gizmos
+    }
+
+    /// The overlay enabled with [`GlassConfig::debug_overlay`], if it could be created
+    #[cfg(feature = "text")]
+    pub fn debug_overlay(&self) -> Option<&DebugOverlay> {
+        self.debug_overlay.as_ref()
+    }
+
+    /// Toggle the overlay or report app stats with [`DebugOverlay::set_stat`]
+    #[cfg(feature = "text")]
+    pub fn debug_overlay_mut(&mut self) -> Option<&mut DebugOverlay> {
+        self.debug_overlay.as_mut()
+    }
+
+    /// Return a [`SharedGpu`] handle that can be sent to worker threads.
+    pub fn shared_gpu(&self) -> SharedGpu {
+        self.device_context.shared()
+    }
+
     pub fn configure_surface(&mut self, window_id: &WindowId, config: &SurfaceConfiguration) {
         if let Some(window) = self.windows.get_mut(window_id) {
             window.configure_surface(self.device_context.device(), config);
@@ -443,6 +1109,18 @@ impl GlassContext {
         self.windows.get_mut(&id)
     }
 
+    /// Return all available monitors. Indices match those of [`WindowPos::OnMonitor`].
+    pub fn monitors(&self, event_loop: &ActiveEventLoop) -> Vec<MonitorInfo> {
+        event_loop
+            .available_monitors()
+            .map(MonitorInfo::new)
+            .collect()
+    }
+
+    pub fn primary_monitor(&self, event_loop: &ActiveEventLoop) -> Option<MonitorInfo> {
+        event_loop.primary_monitor().map(MonitorInfo::new)
+    }
+
     pub fn create_window(
         &mut self,
         event_loop: &ActiveEventLoop,
@@ -456,6 +1134,24 @@ impl GlassContext {
         if reconfigure_device {
             let surface = window.surface();
             self.device_context.reconfigure_with_surface(surface)?;
+            // The query set belonged to the previous device
+            self.gpu_profiler = create_gpu_profiler(&self.device_context, self.gpu_profiler_scopes);
+            self.gpu_errors.reset(self.device_context.device());
+            self.bind_group_cache.clear();
+            self.upload_belt = UploadBelt::default();
+            self.gizmos = Gizmos::new(self.device_context.device());
+            #[cfg(feature = "text")]
+            {
+                let visible = self
+                    .debug_overlay
+                    .as_ref()
+                    .map(|overlay| overlay.is_visible());
+                self.debug_overlay =
+                    create_debug_overlay(&self.device_context, self.debug_overlay_config.as_ref());
+                if let (Some(overlay), Some(visible)) = (&mut self.debug_overlay, visible) {
+                    overlay.set_visible(visible);
+                }
+            }
         }
         // Configure surface with size
         window.configure_surface_with_size(
@@ -471,6 +1167,7 @@ impl GlassContext {
         window: Arc<Window>,
     ) -> Result<WindowId, GlassError> {
         let id = window.id();
+        window.set_ime_allowed(config.ime_allowed);
         let render_window = match GlassWindow::new(&self.device_context, config, window) {
             Ok(window) => window,
             Err(e) => return Err(GlassError::SurfaceError(e)),
@@ -485,7 +1182,13 @@ impl GlassContext {
     ) -> Result<Arc<Window>, GlassError> {
         let mut window_attributes = Window::default_attributes()
             .with_inner_size(winit::dpi::LogicalSize::new(config.width, config.height))
-            .with_title(config.title);
+            .with_title(window_title(config.title));
+
+        #[cfg(target_os = "windows")]
+        {
+            use winit::platform::windows::WindowAttributesExtWindows;
+            window_attributes = window_attributes.with_drag_and_drop(config.drag_and_drop);
+        }
 
         // Min size
         if let Some(inner_size) = config.min_size {
@@ -518,6 +1221,13 @@ impl GlassContext {
             }
             WindowPos::FullScreenBorderless => window_attributes
                 .with_fullscreen(Some(Fullscreen::Borderless(event_loop.primary_monitor()))),
+            WindowPos::FullScreenBorderlessOnMonitor(index) => {
+                let monitor = event_loop
+                    .available_monitors()
+                    .nth(*index)
+                    .or_else(|| event_loop.primary_monitor());
+                window_attributes.with_fullscreen(Some(Fullscreen::Borderless(monitor)))
+            }
             WindowPos::Pos(pos) => window_attributes.with_position(*pos),
             WindowPos::Centered => {
                 if let Some(monitor) = event_loop.primary_monitor() {
@@ -530,6 +1240,21 @@ impl GlassContext {
                     window_attributes
                 }
             }
+            WindowPos::OnMonitor {
+                index,
+                pos,
+            } => {
+                if let Some(monitor) = event_loop.available_monitors().nth(*index) {
+                    window_attributes.with_position(get_window_position_on_monitor(
+                        &monitor,
+                        *pos,
+                        config.width,
+                        config.height,
+                    ))
+                } else {
+                    window_attributes
+                }
+            }
         };
 
         match event_loop.create_window(window_attributes) {
@@ -538,7 +1263,221 @@ impl GlassContext {
         }
     }
 
+    /// Enable or disable synchronized presentation of all windows at runtime.
+    pub fn set_synchronized_present(&mut self, synchronized_present: bool) {
+        self.synchronized_present = synchronized_present;
+        if !synchronized_present {
+            self.present_skew = None;
+        }
+    }
+
+    pub fn is_synchronized_present(&self) -> bool {
+        self.synchronized_present
+    }
+
+    /// Time between the first and the last window present of the previous frame. Only measured
+    /// when synchronized presentation is enabled and there are multiple windows.
+    pub fn present_skew(&self) -> Option<Duration> {
+        self.present_skew
+    }
+
+    pub fn set_frame_limit(&mut self, frame_limit: FrameLimit) {
+        self.frame_limit = frame_limit;
+    }
+
+    pub fn frame_limit(&self) -> FrameLimit {
+        self.frame_limit
+    }
+
+    /// Frame duration the loop is paced to, `None` if the frame rate is unlimited or the refresh
+    /// rate can't be determined.
+    pub fn target_frame_time(&self) -> Option<Duration> {
+        let fps = match self.frame_limit {
+            FrameLimit::Unlimited => None,
+            FrameLimit::Fps(fps) => Some(fps),
+            FrameLimit::RefreshRate => self
+                .primary_render_window_maybe()
+                .and_then(|w| w.refresh_rate_hz()),
+        };
+        fps.filter(|fps| *fps > 0.0)
+            .map(|fps| Duration::from_secs_f64(1.0 / fps))
+    }
+
+    /// Capture the next rendered frame of a window. `callback` is run with the image once the
+    /// frame has been read back, usually a frame or two later. The window's surface needs to
+    /// support copying, see [`GlassWindow::supports_capture`].
+    pub fn capture_frame(
+        &mut self,
+        window_id: WindowId,
+        callback: impl FnOnce(image::RgbaImage) + Send + 'static,
+    ) {
+        self.frame_captures.push((window_id, Box::new(callback)));
+    }
+
+    /// Capture all GPU work of the next frame, from update to submitting its render, in an
+    /// attached graphics debugger: RenderDoc on Vulkan, DX12 and OpenGL, Xcode on Metal. Start
+    /// the app from the debugger, no special config or restart is needed. Does nothing without
+    /// a debugger.
+    ///
+    /// For a wgpu API trace, set [`DeviceConfig::trace_path`] and enable wgpu's `trace` feature.
+    /// Traces cover the device's whole lifetime, they can't be started at runtime.
+    pub fn capture_next_frame(&mut self) {
+        self.debugger_capture_requested = true;
+    }
+
+    /// Start recording every presented frame of a window, see [`FrameRecorder`]. Stops a
+    /// previous recording.
+    pub fn start_recording(
+        &mut self,
+        window_id: WindowId,
+        config: RecorderConfig,
+    ) -> Result<(), GlassError> {
+        self.stop_recording();
+        match self.windows.get(&window_id) {
+            Some(window) if window.supports_capture() => {}
+            _ => return Err(GlassError::CaptureNotSupported),
+        }
+        self.frame_recorder = Some(FrameRecorder::new(window_id, config)?);
+        Ok(())
+    }
+
+    /// Stop recording and wait for recorded frames to be written
+    pub fn stop_recording(&mut self) {
+        if let Some(recorder) = self.frame_recorder.take() {
+            recorder.finish(self.device_context.device());
+        }
+    }
+
+    pub fn frame_recorder(&self) -> Option<&FrameRecorder> {
+        self.frame_recorder.as_ref()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.frame_recorder.is_some()
+    }
+
+    /// Keyboard and mouse state, see [`InputMap`](crate::input::InputMap) for action bindings
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    /// Connected gamepads, requires the `gamepad` feature
+    #[cfg(feature = "gamepad")]
+    pub fn gamepads(&self) -> &Gamepads {
+        &self.gamepads
+    }
+
+    /// E.g. to set the dead zone
+    #[cfg(feature = "gamepad")]
+    pub fn gamepads_mut(&mut self) -> &mut Gamepads {
+        &mut self.gamepads
+    }
+
+    /// System clipboard, requires the `clipboard` feature
+    #[cfg(feature = "clipboard")]
+    pub fn clipboard(&mut self) -> &mut Clipboard {
+        &mut self.clipboard
+    }
+
+    /// Simulation time of the update loop
+    pub fn time(&self) -> &Time {
+        &self.time
+    }
+
+    /// Pause, step or scale simulation time
+    pub fn time_mut(&mut self) -> &mut Time {
+        &mut self.time
+    }
+
+    /// Wall clock delta, smoothed fps, frame index and elapsed time of the frame loop
+    pub fn frame_time(&self) -> &FrameTime {
+        &self.frame_time
+    }
+
+    pub fn set_time_hotkeys(&mut self, time_hotkeys: bool) {
+        self.time_hotkeys = time_hotkeys;
+    }
+
+    /// The profiler measuring GPU time of scopes, if enabled with
+    /// [`GlassConfig::gpu_profiler_scopes`] and supported by the adapter
+    pub fn gpu_profiler(&self) -> Option<&GpuProfiler> {
+        self.gpu_profiler.as_deref()
+    }
+
+    /// Shared handle of the profiler, e.g. for [`crate::pipelines::BloomPipeline::set_profiler`]
+    /// so crate pipelines time their passes
+    pub fn gpu_profiler_arc(&self) -> Option<Arc<GpuProfiler>> {
+        self.gpu_profiler.clone()
+    }
+
+    /// GPU times of the profiled scopes of the latest resolved frame
+    pub fn gpu_timings(&self) -> Vec<GpuTiming> {
+        self.gpu_profiler
+            .as_ref()
+            .map(|profiler| profiler.timings())
+            .unwrap_or_default()
+    }
+
+    /// Write [`GlassApp::save_state`] to a file before the next update
+    pub fn save_state(&mut self, path: impl Into<PathBuf>) {
+        self.state_requests.push(StateRequest::Save(path.into()));
+    }
+
+    /// Read a file and pass it to [`GlassApp::load_state`] before the next update
+    pub fn load_state(&mut self, path: impl Into<PathBuf>) {
+        self.state_requests.push(StateRequest::Load(path.into()));
+    }
+
+    /// Whether frames and GPU work are monitored for hangs
+    pub fn has_watchdog(&self) -> bool {
+        self.watchdog.is_some()
+    }
+
+    fn set_frame_stage(&self, stage: FrameStage) {
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.set_stage(stage);
+        }
+    }
+
+    fn track_submission(&self) {
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.track_submission(self.device_context.queue());
+        }
+    }
+
     pub fn exit(&mut self) {
         self.exit = true;
     }
 }
+
+fn create_gpu_profiler(
+    device_context: &DeviceContext,
+    max_scopes: Option<u32>,
+) -> Option<Arc<GpuProfiler>> {
+    let max_scopes = max_scopes?;
+    let profiler = GpuProfiler::new(device_context.device(), device_context.queue(), max_scopes);
+    if profiler.is_none() {
+        warn!("Gpu profiling is not supported by the adapter");
+    }
+    profiler.map(Arc::new)
+}
+
+#[cfg(feature = "text")]
+fn create_debug_overlay(
+    device_context: &DeviceContext,
+    config: Option<&DebugOverlayConfig>,
+) -> Option<DebugOverlay> {
+    let config = config?;
+    match DebugOverlay::new(
+        device_context.device(),
+        device_context.queue(),
+        &device_context.adapter().get_info(),
+        config,
+    ) {
+        Ok(overlay) => Some(overlay),
+        Err(e) => {
+            warn!("Failed to create debug overlay: {}", e);
+            None
+        }
+    }
+}
@@ -1,11 +1,17 @@
-use wgpu::{CommandBuffer, CommandEncoder, StoreOp, SurfaceTexture};
+use std::path::PathBuf;
+
+use log::error;
+use wgpu::{CommandBuffer, CommandEncoder, StoreOp, SurfaceTexture, TextureView};
 use winit::{
     event::{DeviceEvent, DeviceId, WindowEvent},
     event_loop::ActiveEventLoop,
     window::WindowId,
 };
 
-use crate::{window::GlassWindow, GlassContext};
+use crate::{
+    gpu_error::GpuError, gui::GuiBackend, input::TextEvent, watchdog::HangInfo,
+    window::GlassWindow, GlassContext,
+};
 
 /// All necessary data required to render with wgpu. This data only lives for the duration of
 /// rendering.
@@ -14,6 +20,12 @@ pub struct RenderData<'a> {
     pub encoder: &'a mut CommandEncoder,
     pub window: &'a GlassWindow,
     pub frame: &'a SurfaceTexture,
+    /// Multisampled color view of the window when MSAA is enabled. Render into this with the
+    /// frame view as resolve target, see [`GlassWindow::color_attachment`].
+    pub msaa_view: Option<&'a TextureView>,
+    /// Depth view of the window when a depth format is configured. It is resized with the
+    /// surface and has the window's MSAA sample count.
+    pub depth_view: Option<&'a TextureView>,
 }
 
 /// A trait to define all stages of your Glass app. Each function here is run at a specific stage
@@ -42,6 +54,24 @@ pub trait GlassApp {
         _event: &WindowEvent,
     ) {
     }
+    /// Run on text typed into a window, merged from keyboard input and IME events. Enable IME
+    /// with [`WindowConfig::ime_allowed`](crate::window::WindowConfig::ime_allowed).
+    fn text_input(&mut self, _context: &mut GlassContext, _window_id: WindowId, _event: TextEvent) {
+    }
+    /// UI driven by the runner, see [`GuiBackend`]. Called whenever the runner passes events to
+    /// the UI, updates or renders it.
+    fn gui(&mut self) -> Option<&mut dyn GuiBackend> {
+        None
+    }
+    /// Run when a file is dropped on a window with drag and drop enabled
+    fn file_dropped(&mut self, _context: &mut GlassContext, _window_id: WindowId, _path: PathBuf) {}
+    /// Run when a file is dragged over a window with drag and drop enabled
+    fn file_hovered(&mut self, _context: &mut GlassContext, _window_id: WindowId, _path: PathBuf) {}
+    /// Run when hovered files leave the window without being dropped
+    fn file_hover_cancelled(&mut self, _context: &mut GlassContext, _window_id: WindowId) {}
+    /// Run zero or more times before update at the interval of
+    /// [`crate::GlassConfig::fixed_timestep`]
+    fn fixed_update(&mut self, _context: &mut GlassContext) {}
     /// Run each frame, called within winit's `about_to_wait`.
     fn update(&mut self, _context: &mut GlassContext) {}
     /// Run each frame for each window after update
@@ -53,6 +83,7 @@ pub trait GlassApp {
         let RenderData {
             encoder,
             frame,
+            window,
             ..
         } = _render_data;
         let view = frame
@@ -61,14 +92,10 @@ pub trait GlassApp {
         {
             let _r = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: None,
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                        store: StoreOp::Store,
-                    },
-                })],
+                color_attachments: &[Some(window.color_attachment(&view, wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: StoreOp::Store,
+                }))],
                 depth_stencil_attachment: None,
                 timestamp_writes: None,
                 occlusion_query_set: None,
@@ -77,6 +104,22 @@ pub trait GlassApp {
 
         None
     }
+    /// Run when the watchdog (see [`crate::GlassConfig::watchdog`]) detected a long frame or GPU
+    /// work that didn't complete. Called once the runner regains control after the hang.
+    fn on_hang(&mut self, _context: &mut GlassContext, _hang: &HangInfo) {}
+    /// Run for each wgpu error, e.g. a validation error of a bad bind group, before
+    /// [`GlassApp::end_of_frame`]. Errors are logged by default instead of panicking.
+    fn gpu_error(&mut self, _context: &mut GlassContext, error: &GpuError) {
+        error!("{}", error);
+    }
+    /// Serialize app state for [`GlassContext::save_state`], which is the only caller. An empty
+    /// state is not written.
+    fn save_state(&self) -> Vec<u8> {
+        vec![]
+    }
+    /// Restore state produced by [`GlassApp::save_state`], called by
+    /// [`GlassContext::load_state`]
+    fn load_state(&mut self, _state: &[u8]) {}
     /// Run each frame last
     fn end_of_frame(&mut self, _context: &mut GlassContext) {}
     /// Run at exit
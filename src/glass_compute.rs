@@ -0,0 +1,177 @@
+use std::sync::Arc;
+
+use log::error;
+use wgpu::{Adapter, Device, Instance, Maintain, Queue, Sampler};
+
+use crate::{
+    checkpoint::Checkpointer,
+    device_context::{DeviceConfig, DeviceContext, SharedGpu},
+    texture::TextureLoader,
+    GlassError,
+};
+
+/// [`GlassCompute`] runs a [`GlassComputeApp`] without winit or any windows. Use it for batch
+/// GPU jobs like image processing or simulations that write their results to disk.
+pub struct GlassCompute;
+
+impl GlassCompute {
+    pub fn run(
+        config: DeviceConfig,
+        app_create_fn: impl FnOnce(&mut GlassComputeContext) -> Box<dyn GlassComputeApp>,
+    ) -> Result<(), GlassError> {
+        let mut context = GlassComputeContext::new(config)?;
+        let mut app = app_create_fn(&mut context);
+        app.start(&mut context);
+        while !context.exit {
+            app.update(&mut context);
+            context.step += 1;
+            if let Err(e) = context.save_checkpoint_if_due() {
+                error!("Failed to save checkpoint: {}", e);
+            }
+        }
+        app.end(&mut context);
+        Ok(())
+    }
+}
+
+/// A trait to define all stages of your windowless Glass app. [`GlassComputeApp::update`] is run
+/// in a loop until [`GlassComputeContext::exit`] is called.
+pub trait GlassComputeApp {
+    /// Run at start
+    fn start(&mut self, _context: &mut GlassComputeContext) {}
+    /// Run in a loop until exit is requested
+    fn update(&mut self, context: &mut GlassComputeContext);
+    /// Run at exit
+    fn end(&mut self, _context: &mut GlassComputeContext) {}
+}
+
+/// The runtime context accessible through [`GlassComputeApp`].
+pub struct GlassComputeContext {
+    device_context: DeviceContext,
+    checkpointer: Option<Checkpointer>,
+    step: u64,
+    exit: bool,
+}
+
+impl GlassComputeContext {
+    pub fn new(mut config: DeviceConfig) -> Result<Self, GlassError> {
+        // Common pipelines use push constants when available and fall back to uniform buffers
+        // otherwise, so neither feature is required
+        config.optional_features |= wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+            | wgpu::Features::PUSH_CONSTANTS;
+        config.limits = wgpu::Limits {
+            // The largest push constants of common pipelines, dropped when the adapter has none
+            max_push_constant_size: config.limits.max_push_constant_size.max(128),
+            ..config.limits
+        };
+        let device_context = DeviceContext::new(&config)?;
+        Ok(Self {
+            device_context,
+            checkpointer: None,
+            step: 0,
+            exit: false,
+        })
+    }
+
+    /// Number of completed update steps
+    pub fn step(&self) -> u64 {
+        self.step
+    }
+
+    /// Set a [`Checkpointer`] whose registered resources are saved periodically after updates.
+    pub fn set_checkpointer(&mut self, checkpointer: Checkpointer) {
+        self.checkpointer = Some(checkpointer);
+    }
+
+    pub fn checkpointer_mut(&mut self) -> Option<&mut Checkpointer> {
+        self.checkpointer.as_mut()
+    }
+
+    /// Restore registered resources from the latest checkpoint and continue counting steps from
+    /// it. Call this in [`GlassComputeApp::start`] after registering resources. Returns the
+    /// restored step.
+    pub fn resume_from_checkpoint(&mut self) -> Result<Option<u64>, GlassError> {
+        let Some(checkpointer) = &self.checkpointer else {
+            return Ok(None);
+        };
+        let step = checkpointer.restore_latest(self.device_context.queue())?;
+        if let Some(step) = step {
+            self.step = step;
+        }
+        Ok(step)
+    }
+
+    fn save_checkpoint_if_due(&mut self) -> Result<(), GlassError> {
+        if let Some(checkpointer) = &mut self.checkpointer {
+            if checkpointer.should_save(self.step) {
+                checkpointer.save(
+                    self.device_context.device(),
+                    self.device_context.queue(),
+                    self.step,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Block until all submitted work has completed
+    pub fn wait_idle(&self) {
+        self.device_context.device().poll(Maintain::Wait);
+    }
+
+    pub fn sampler_nearest_repeat(&self) -> &Arc<Sampler> {
+        self.device_context.sampler_nearest_repeat()
+    }
+
+    pub fn sampler_linear_repeat(&self) -> &Arc<Sampler> {
+        self.device_context.sampler_linear_repeat()
+    }
+
+    pub fn sampler_nearest_clamp_to_edge(&self) -> &Arc<Sampler> {
+        self.device_context.sampler_nearest_clamp_to_edge()
+    }
+
+    pub fn sampler_linear_clamp_to_edge(&self) -> &Arc<Sampler> {
+        self.device_context.sampler_linear_clamp_to_edge()
+    }
+
+    pub fn instance(&self) -> &Instance {
+        self.device_context.instance()
+    }
+
+    pub fn adapter(&self) -> &Adapter {
+        self.device_context.adapter()
+    }
+
+    pub fn device(&self) -> &Device {
+        self.device_context.device()
+    }
+
+    pub fn device_arc(&self) -> Arc<Device> {
+        self.device_context.device_arc()
+    }
+
+    pub fn queue(&self) -> &Queue {
+        self.device_context.queue()
+    }
+
+    pub fn queue_arc(&self) -> Arc<Queue> {
+        self.device_context.queue_arc()
+    }
+
+    pub fn device_context(&self) -> &DeviceContext {
+        &self.device_context
+    }
+
+    pub fn texture_loader(&self) -> TextureLoader {
+        TextureLoader::new(self.device_arc(), self.queue_arc())
+    }
+
+    pub fn shared_gpu(&self) -> SharedGpu {
+        self.device_context.shared()
+    }
+
+    pub fn exit(&mut self) {
+        self.exit = true;
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    fmt::Formatter,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use flume::{unbounded, Receiver, Sender};
+use wgpu::{Device, ErrorFilter};
+
+use crate::utils::wait_async;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GpuErrorKind {
+    Validation,
+    OutOfMemory,
+    Internal,
+}
+
+/// A wgpu error passed to [`GlassApp::gpu_error`](crate::GlassApp::gpu_error)
+#[derive(Debug, Clone)]
+pub struct GpuError {
+    pub kind: GpuErrorKind,
+    /// The full error, for validation errors including the labels of the objects involved
+    pub message: String,
+    /// Frame index during which the error was caught, see
+    /// [`FrameTime::frame_index`](crate::time::FrameTime::frame_index)
+    pub frame: u64,
+    /// Whether the error was caught by the runner's error scopes, see
+    /// [`GlassConfig::gpu_error_scopes`](crate::GlassConfig::gpu_error_scopes). Other errors
+    /// are reported by wgpu's uncaptured error handler.
+    pub scoped: bool,
+}
+
+impl GpuError {
+    fn new(error: wgpu::Error, frame: u64, scoped: bool) -> GpuError {
+        let kind = match &error {
+            wgpu::Error::Validation {
+                ..
+            } => GpuErrorKind::Validation,
+            wgpu::Error::OutOfMemory {
+                ..
+            } => GpuErrorKind::OutOfMemory,
+            wgpu::Error::Internal {
+                ..
+            } => GpuErrorKind::Internal,
+        };
+        GpuError {
+            kind,
+            message: error.to_string(),
+            frame,
+            scoped,
+        }
+    }
+}
+
+impl std::fmt::Display for GpuError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "GPU {:?} error in frame {}: {}",
+            self.kind, self.frame, self.message
+        )
+    }
+}
+
+/// Collects wgpu errors for the runner. Uncaptured errors are sent from wgpu's handler, which
+/// would otherwise panic, and with scopes enabled each frame's update and render are wrapped in
+/// error scopes.
+pub(crate) struct GpuErrors {
+    sender: Sender<GpuError>,
+    receiver: Receiver<GpuError>,
+    frame: Arc<AtomicU64>,
+    scopes: bool,
+    in_scope: bool,
+}
+
+impl GpuErrors {
+    pub(crate) fn new(device: &Device, scopes: bool) -> GpuErrors {
+        let (sender, receiver) = unbounded();
+        let errors = GpuErrors {
+            sender,
+            receiver,
+            frame: Arc::new(AtomicU64::new(0)),
+            scopes,
+            in_scope: false,
+        };
+        errors.install(device);
+        errors
+    }
+
+    /// Route errors of a new device to the runner. Scopes pushed on the previous device are
+    /// dropped.
+    pub(crate) fn reset(&mut self, device: &Device) {
+        self.in_scope = false;
+        self.install(device);
+    }
+
+    fn install(&self, device: &Device) {
+        let sender = self.sender.clone();
+        let frame = self.frame.clone();
+        device.on_uncaptured_error(Box::new(move |error| {
+            let _ = sender.send(GpuError::new(error, frame.load(Ordering::Relaxed), false));
+        }));
+    }
+
+    pub(crate) fn begin_frame(&mut self, device: &Device, frame: u64) {
+        self.frame.store(frame, Ordering::Relaxed);
+        if self.scopes {
+            device.push_error_scope(ErrorFilter::OutOfMemory);
+            device.push_error_scope(ErrorFilter::Validation);
+            self.in_scope = true;
+        }
+    }
+
+    /// Pop the frame's scopes and return the errors since the last call
+    pub(crate) fn end_frame(&mut self, device: &Device) -> Vec<GpuError> {
+        let mut errors = vec![];
+        if std::mem::take(&mut self.in_scope) {
+            let frame = self.frame.load(Ordering::Relaxed);
+            for _ in 0..2 {
+                if let Some(error) = wait_async(device.pop_error_scope()) {
+                    errors.push(GpuError::new(error, frame, true));
+                }
+            }
+        }
+        errors.extend(self.receiver.try_iter());
+        errors
+    }
+}
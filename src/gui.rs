@@ -0,0 +1,36 @@
+use wgpu::{CommandBuffer, CommandEncoder, TextureView};
+use winit::{event::WindowEvent, window::WindowId};
+
+use crate::{window::GlassWindow, GlassContext};
+
+/// A UI library driven by the runner. Return it from [`GlassApp::gui`](crate::GlassApp::gui) and
+/// the runner passes it window events, starts its frame before
+/// [`GlassApp::update`](crate::GlassApp::update) and renders it over each window after
+/// [`GlassApp::render`](crate::GlassApp::render). UI is built during update, so switching the UI
+/// library doesn't change the app's structure.
+///
+/// Implemented by [`EguiGui`](crate::egui_utils::EguiGui) with the `egui_gui` feature.
+pub trait GuiBackend {
+    /// Pass a window event to the UI. Returns whether the UI consumed the event, in which case
+    /// [`GlassApp::window_input`](crate::GlassApp::window_input) isn't called for it.
+    fn handle_window_event(
+        &mut self,
+        context: &GlassContext,
+        window_id: WindowId,
+        event: &WindowEvent,
+    ) -> bool;
+
+    /// Start a UI frame for each window, called before
+    /// [`GlassApp::update`](crate::GlassApp::update)
+    fn update(&mut self, context: &GlassContext);
+
+    /// Render the window's UI over `view`, which is the window's frame. Returns command buffers
+    /// to submit before `encoder`'s.
+    fn render(
+        &mut self,
+        context: &GlassContext,
+        encoder: &mut CommandEncoder,
+        window: &GlassWindow,
+        view: &TextureView,
+    ) -> Vec<CommandBuffer>;
+}
@@ -0,0 +1,267 @@
+use std::sync::{Arc, RwLock};
+
+use log::{error, info};
+use wgpu::{Device, ErrorFilter};
+
+use crate::utils::{
+    wait_async, ShaderCompileError, ShaderError, ShaderModule, WatchedShaderModule,
+};
+
+/// A value rebuilt by [`ShaderHotReload`] when its shader changes, e.g. a render pipeline.
+/// Cheap to clone.
+pub struct HotReloaded<T> {
+    value: Arc<RwLock<(Arc<T>, u64)>>,
+}
+
+impl<T> Clone for HotReloaded<T> {
+    fn clone(&self) -> Self {
+        HotReloaded {
+            value: self.value.clone(),
+        }
+    }
+}
+
+impl<T> HotReloaded<T> {
+    /// The latest successfully built value
+    pub fn get(&self) -> Arc<T> {
+        self.value.read().unwrap().0.clone()
+    }
+
+    /// Incremented on each rebuild
+    pub fn version(&self) -> u64 {
+        self.value.read().unwrap().1
+    }
+}
+
+/// A shader whose reload failed
+#[derive(Debug, Clone)]
+pub struct ShaderReloadError {
+    pub path: String,
+    pub message: String,
+    /// File, line and snippet of a parse error, `None` for other errors, e.g. validation
+    pub compile_error: Option<ShaderCompileError>,
+}
+
+impl ShaderReloadError {
+    fn new(path: &str, error: &ShaderError) -> ShaderReloadError {
+        ShaderReloadError {
+            path: path.to_string(),
+            message: error.to_string(),
+            compile_error: match error {
+                ShaderError::WgslParseError(e) => Some(e.clone()),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Builds from the module and stores the result if it passed validation
+type Rebuild = Box<dyn FnMut(&Device, &ShaderModule) -> Result<(), String>>;
+
+struct Entry {
+    watched: WatchedShaderModule,
+    rebuild: Rebuild,
+    error: Option<ShaderReloadError>,
+}
+
+/// Hot reloads registered shaders. The runner checks for changes once per frame before
+/// [`GlassApp::update`](crate::GlassApp::update) and rebuilds the values depending on changed
+/// shaders. Parse and validation errors are logged and kept in [`ShaderHotReload::errors`], and
+/// the previous value stays in use, so a typo never stops the app.
+///
+/// ```ignore
+/// let watched = WatchedShaderModule::new(Path::new("shaders/sprite.wgsl"))?;
+/// self.pipeline = Some(context.register_shader(watched, |device, shader| {
+///     create_sprite_pipeline(device, &shader.create_shader_module(device, None))
+/// }));
+/// // In render
+/// rpass.set_pipeline(&self.pipeline.as_ref().unwrap().get());
+/// ```
+#[derive(Default)]
+pub struct ShaderHotReload {
+    entries: Vec<Entry>,
+}
+
+impl ShaderHotReload {
+    pub fn new() -> ShaderHotReload {
+        ShaderHotReload::default()
+    }
+
+    /// Build a value from the shader now and rebuild it whenever the shader or its includes
+    /// change. If the shader doesn't compile initially, the value is built from
+    /// [`ShaderModule::fallback`].
+    pub fn register<T: 'static>(
+        &mut self,
+        device: &Device,
+        watched: WatchedShaderModule,
+        mut build: impl FnMut(&Device, &ShaderModule) -> T + 'static,
+    ) -> HotReloaded<T> {
+        let mut error = None;
+        let module = match watched.module() {
+            Ok(module) => module,
+            Err(e) => {
+                error!("{}", e);
+                error = Some(ShaderReloadError::new(watched.path(), &e));
+                ShaderModule::fallback()
+            }
+        };
+        let handle = HotReloaded {
+            value: Arc::new(RwLock::new((Arc::new(build(device, &module)), 0))),
+        };
+        let value = handle.value.clone();
+        let rebuild = move |device: &Device, module: &ShaderModule| {
+            device.push_error_scope(ErrorFilter::Validation);
+            let built = build(device, module);
+            if let Some(e) = wait_async(device.pop_error_scope()) {
+                return Err(e.to_string());
+            }
+            let mut value = value.write().unwrap();
+            *value = (Arc::new(built), value.1 + 1);
+            Ok(())
+        };
+        self.entries.push(Entry {
+            watched,
+            rebuild: Box::new(rebuild),
+            error,
+        });
+        handle
+    }
+
+    /// Reload changed shaders and rebuild their values. Returns whether anything was rebuilt.
+    pub fn update(&mut self, device: &Device) -> bool {
+        let mut rebuilt = false;
+        for entry in self.entries.iter_mut() {
+            if entry.watched.changed_paths().is_empty() {
+                continue;
+            }
+            let path = entry.watched.path().to_string();
+            let result = match entry.watched.reload().and_then(|_| entry.watched.module()) {
+                Ok(module) => {
+                    (entry.rebuild)(device, &module).map_err(|message| ShaderReloadError {
+                        path: path.clone(),
+                        message,
+                        compile_error: None,
+                    })
+                }
+                Err(e) => Err(ShaderReloadError::new(&path, &e)),
+            };
+            match result {
+                Ok(()) => {
+                    info!("Reloaded shader {}", path);
+                    entry.error = None;
+                    rebuilt = true;
+                }
+                Err(e) => {
+                    error!("Failed to reload shader {}: {}", path, e.message);
+                    entry.error = Some(e);
+                }
+            }
+        }
+        rebuilt
+    }
+
+    /// Errors of shaders whose latest reload failed
+    pub fn errors(&self) -> Vec<ShaderReloadError> {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.error.clone())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Draws [`ShaderHotReload::errors`] on top of the frame, so a failed reload is visible without
+/// looking at the log. Requires the `text` feature.
+///
+/// ```ignore
+/// // In render, after drawing the scene
+/// let errors = context.shader_hot_reload().errors();
+/// overlay.draw(context.device(), context.queue(), &mut rpass, &errors, window.surface_size());
+/// ```
+#[cfg(feature = "text")]
+pub struct ShaderErrorOverlay {
+    pipeline: crate::pipelines::TextPipeline,
+    font: crate::pipelines::Font,
+    instances: crate::utils::GrowableBuffer<crate::pipelines::GlyphInstance>,
+}
+
+#[cfg(feature = "text")]
+impl ShaderErrorOverlay {
+    const COLOR: [f32; 4] = [1.0, 0.3, 0.3, 1.0];
+    const FONT_SIZE: f32 = 16.0;
+    const MARGIN: f32 = 8.0;
+
+    /// Create an overlay drawing with a ttf/otf font
+    pub fn new(
+        device_context: &crate::device_context::DeviceContext,
+        font_bytes: &[u8],
+        color_target_state: wgpu::ColorTargetState,
+        sample_count: u32,
+    ) -> Result<ShaderErrorOverlay, crate::GlassError> {
+        let device = device_context.device();
+        Ok(ShaderErrorOverlay {
+            pipeline: crate::pipelines::TextPipeline::new(
+                device_context,
+                color_target_state,
+                sample_count,
+            )?,
+            font: crate::pipelines::Font::new(
+                device,
+                device_context.queue(),
+                font_bytes,
+                Self::FONT_SIZE,
+            )?,
+            instances: crate::pipelines::TextPipeline::create_instance_buffer(device),
+        })
+    }
+
+    /// Draw errors from the top left corner of a target of `size` pixels. Draws nothing if
+    /// `errors` is empty.
+    pub fn draw(
+        &mut self,
+        device: &Device,
+        queue: &wgpu::Queue,
+        rpass: &mut wgpu::RenderPass,
+        errors: &[ShaderReloadError],
+        size: [u32; 2],
+    ) {
+        if errors.is_empty() {
+            return;
+        }
+        let text = errors
+            .iter()
+            .map(|e| match &e.compile_error {
+                Some(compile_error) => compile_error.to_string(),
+                None => format!("{}: {}", e.path, e.message),
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let [width, height] = [size[0].max(1) as f32, size[1].max(1) as f32];
+        // Pixels with origin at bottom left
+        let view_proj = [
+            [2.0 / width, 0.0, 0.0, 0.0],
+            [0.0, 2.0 / height, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [-1.0, -1.0, 0.0, 1.0],
+        ];
+        self.pipeline.draw_text(
+            device,
+            queue,
+            rpass,
+            &mut self.instances,
+            &self.font,
+            &text,
+            [Self::MARGIN, height - Self::MARGIN - Self::FONT_SIZE],
+            Self::FONT_SIZE,
+            Self::COLOR,
+            view_proj,
+        );
+    }
+}
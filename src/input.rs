@@ -0,0 +1,634 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use winit::{
+    event::{DeviceEvent, ElementState, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+    window::WindowId,
+};
+
+/// A key or button that can be bound to an action
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "input_serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InputButton {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// A button of any gamepad, requires the `gamepad` feature
+    #[cfg(feature = "gamepad")]
+    Gamepad(gilrs::Button),
+}
+
+impl From<KeyCode> for InputButton {
+    fn from(key: KeyCode) -> Self {
+        InputButton::Key(key)
+    }
+}
+
+impl From<MouseButton> for InputButton {
+    fn from(button: MouseButton) -> Self {
+        InputButton::Mouse(button)
+    }
+}
+
+#[cfg(feature = "gamepad")]
+impl From<gilrs::Button> for InputButton {
+    fn from(button: gilrs::Button) -> Self {
+        InputButton::Gamepad(button)
+    }
+}
+
+/// Buttons that trigger an action when all are held, e.g. `Ctrl + S`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "input_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Binding {
+    pub buttons: Vec<InputButton>,
+}
+
+impl Binding {
+    pub fn chord(buttons: impl IntoIterator<Item = impl Into<InputButton>>) -> Binding {
+        Binding {
+            buttons: buttons.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<T: Into<InputButton>> From<T> for Binding {
+    fn from(button: T) -> Self {
+        Binding {
+            buttons: vec![button.into()],
+        }
+    }
+}
+
+/// Bindings of app defined actions. An action is triggered by any of its bindings. Bindings can be
+/// changed at runtime, e.g. from a settings menu, and with the `input_serde` feature the map can be
+/// saved to and loaded from a config file.
+///
+/// ```ignore
+/// #[derive(Copy, Clone, PartialEq, Eq, Hash)]
+/// enum Action { Jump, Save }
+///
+/// let mut map = InputMap::new();
+/// map.bind(Action::Jump, KeyCode::Space);
+/// map.bind(Action::Jump, MouseButton::Left);
+/// map.bind(Action::Save, Binding::chord([KeyCode::ControlLeft, KeyCode::KeyS]));
+/// // In update
+/// if context.input().action_just_pressed(&map, Action::Jump) {
+///     self.player.jump();
+/// }
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "input_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InputMap<A: Eq + Hash> {
+    bindings: HashMap<A, Vec<Binding>>,
+}
+
+impl<A: Eq + Hash> Default for InputMap<A> {
+    fn default() -> Self {
+        InputMap {
+            bindings: HashMap::default(),
+        }
+    }
+}
+
+impl<A: Copy + Eq + Hash> InputMap<A> {
+    pub fn new() -> InputMap<A> {
+        InputMap::default()
+    }
+
+    /// Add a binding for `action`, keeping its existing bindings
+    pub fn bind(&mut self, action: A, binding: impl Into<Binding>) -> &mut Self {
+        let binding = binding.into();
+        let bindings = self.bindings.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+        self
+    }
+
+    /// Replace the bindings of `action` with `binding`
+    pub fn rebind(&mut self, action: A, binding: impl Into<Binding>) -> &mut Self {
+        self.bindings.insert(action, vec![binding.into()]);
+        self
+    }
+
+    /// Remove all bindings of `action`
+    pub fn unbind(&mut self, action: A) {
+        self.bindings.remove(&action);
+    }
+
+    pub fn bindings(&self, action: A) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Actions and their bindings
+    pub fn iter(&self) -> impl Iterator<Item = (A, &[Binding])> {
+        self.bindings
+            .iter()
+            .map(|(action, bindings)| (*action, bindings.as_slice()))
+    }
+}
+
+/// Text typed into a window, see [`GlassApp::text_input`](crate::GlassApp::text_input)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextEvent {
+    /// Text to insert at the cursor, typed characters or text committed by the IME
+    Commit(String),
+    /// Text being composed by the IME, to be shown at the cursor until committed. Replaces the
+    /// previous preedit, empty when composition is cleared. `cursor` is a byte range in `text`.
+    Preedit {
+        text: String,
+        cursor: Option<(usize, usize)>,
+    },
+    ImeEnabled,
+    ImeDisabled,
+}
+
+/// A finger on a touch screen, positions in physical pixels from the top left of `window`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TouchPoint {
+    pub id: u64,
+    pub window: WindowId,
+    pub position: [f32; 2],
+    /// Position when the touch started
+    pub start_position: [f32; 2],
+    /// Position at the start of this frame
+    pub previous_position: [f32; 2],
+}
+
+impl TouchPoint {
+    /// Movement during this frame
+    pub fn delta(&self) -> [f32; 2] {
+        [
+            self.position[0] - self.previous_position[0],
+            self.position[1] - self.previous_position[1],
+        ]
+    }
+}
+
+/// Keyboard, mouse, touch and gamepad button state, updated by the runner from window events.
+/// Accessible through [`GlassContext::input`](crate::GlassContext::input). "Just" pressed and
+/// released states last for one frame, so query them in
+/// [`GlassApp::update`](crate::GlassApp::update).
+#[derive(Debug, Default, Clone)]
+pub struct Input {
+    pressed: HashSet<InputButton>,
+    just_pressed: HashSet<InputButton>,
+    just_released: HashSet<InputButton>,
+    cursor_position: Option<[f32; 2]>,
+    cursor_window: Option<WindowId>,
+    scroll_delta: [f32; 2],
+    mouse_delta: [f32; 2],
+    /// In the order the touches started
+    touches: Vec<TouchPoint>,
+    touches_started: Vec<u64>,
+    touches_ended: Vec<TouchPoint>,
+}
+
+/// Pixels scrolled per line of a line based scroll delta
+pub(crate) const PIXELS_PER_SCROLL_LINE: f32 = 20.0;
+
+impl Input {
+    pub fn new() -> Input {
+        Input::default()
+    }
+
+    pub fn pressed(&self, button: impl Into<InputButton>) -> bool {
+        self.pressed.contains(&button.into())
+    }
+
+    /// Pressed during this frame
+    pub fn just_pressed(&self, button: impl Into<InputButton>) -> bool {
+        self.just_pressed.contains(&button.into())
+    }
+
+    /// Released during this frame
+    pub fn just_released(&self, button: impl Into<InputButton>) -> bool {
+        self.just_released.contains(&button.into())
+    }
+
+    /// Buttons pressed during this frame, e.g. to capture a new binding
+    pub fn just_pressed_buttons(&self) -> impl Iterator<Item = InputButton> + '_ {
+        self.just_pressed.iter().copied()
+    }
+
+    /// Whether all buttons of a binding are held
+    pub fn binding_pressed(&self, binding: &Binding) -> bool {
+        !binding.buttons.is_empty() && binding.buttons.iter().all(|b| self.pressed.contains(b))
+    }
+
+    /// Whether a binding became held during this frame
+    pub fn binding_just_pressed(&self, binding: &Binding) -> bool {
+        self.binding_pressed(binding)
+            && binding
+                .buttons
+                .iter()
+                .any(|b| self.just_pressed.contains(b))
+    }
+
+    /// Whether a held binding was released during this frame
+    pub fn binding_just_released(&self, binding: &Binding) -> bool {
+        let was_held = binding
+            .buttons
+            .iter()
+            .all(|b| self.pressed.contains(b) || self.just_released.contains(b));
+        !binding.buttons.is_empty()
+            && was_held
+            && binding
+                .buttons
+                .iter()
+                .any(|b| self.just_released.contains(b))
+    }
+
+    pub fn action_pressed<A: Copy + Eq + Hash>(&self, map: &InputMap<A>, action: A) -> bool {
+        map.bindings(action)
+            .iter()
+            .any(|binding| self.binding_pressed(binding))
+    }
+
+    pub fn action_just_pressed<A: Copy + Eq + Hash>(&self, map: &InputMap<A>, action: A) -> bool {
+        let bindings = map.bindings(action);
+        // An action already held through another binding isn't pressed again
+        let held_before = bindings
+            .iter()
+            .any(|b| self.binding_pressed(b) && !self.binding_just_pressed(b));
+        !held_before && bindings.iter().any(|b| self.binding_just_pressed(b))
+    }
+
+    pub fn action_just_released<A: Copy + Eq + Hash>(&self, map: &InputMap<A>, action: A) -> bool {
+        !self.action_pressed(map, action)
+            && map
+                .bindings(action)
+                .iter()
+                .any(|binding| self.binding_just_released(binding))
+    }
+
+    /// Cursor position in physical pixels from the top left of [`Input::cursor_window`]
+    pub fn cursor_position(&self) -> Option<[f32; 2]> {
+        self.cursor_position
+    }
+
+    /// The window the cursor is over
+    pub fn cursor_window(&self) -> Option<WindowId> {
+        self.cursor_window
+    }
+
+    /// Scroll during this frame in pixels, line based scrolling is converted to pixels
+    pub fn scroll_delta(&self) -> [f32; 2] {
+        self.scroll_delta
+    }
+
+    /// Raw mouse movement during this frame, unaffected by cursor acceleration, screen edges or
+    /// grabbing. Units are device specific, usually close to pixels. Use with
+    /// [`GlassWindow::set_relative_mouse`](crate::window::GlassWindow::set_relative_mouse) for
+    /// first person cameras.
+    pub fn mouse_delta(&self) -> [f32; 2] {
+        self.mouse_delta
+    }
+
+    /// Active touches in the order they started
+    pub fn touches(&self) -> &[TouchPoint] {
+        &self.touches
+    }
+
+    pub fn touch(&self, id: u64) -> Option<&TouchPoint> {
+        self.touches.iter().find(|touch| touch.id == id)
+    }
+
+    /// Touches started during this frame
+    pub fn just_started_touches(&self) -> impl Iterator<Item = &TouchPoint> {
+        self.touches
+            .iter()
+            .filter(|touch| self.touches_started.contains(&touch.id))
+    }
+
+    /// Touches ended or cancelled during this frame, e.g. to detect taps
+    pub fn just_ended_touches(&self) -> &[TouchPoint] {
+        &self.touches_ended
+    }
+
+    /// Average movement of active touches during this frame, for panning with any number of
+    /// fingers
+    pub fn pan_delta(&self) -> [f32; 2] {
+        if self.touches.is_empty() {
+            return [0.0; 2];
+        }
+        let count = self.touches.len() as f32;
+        let [x, y] = self.touches.iter().fold([0.0; 2], |[x, y], touch| {
+            let [dx, dy] = touch.delta();
+            [x + dx, y + dy]
+        });
+        [x / count, y / count]
+    }
+
+    /// How much the distance between the first two touches changed during this frame, larger
+    /// than 1 when the fingers spread. 1 with less than two touches.
+    pub fn pinch_scale(&self) -> f32 {
+        let [a, b] = match self.touches.as_slice() {
+            [a, b, ..] => [a, b],
+            _ => return 1.0,
+        };
+        let distance =
+            |p: [f32; 2], q: [f32; 2]| ((p[0] - q[0]).powi(2) + (p[1] - q[1]).powi(2)).sqrt();
+        let previous = distance(a.previous_position, b.previous_position);
+        if previous <= f32::EPSILON {
+            return 1.0;
+        }
+        distance(a.position, b.position) / previous
+    }
+
+    /// Midpoint of the first two touches, the point to zoom towards when pinching
+    pub fn pinch_center(&self) -> Option<[f32; 2]> {
+        match self.touches.as_slice() {
+            [a, b, ..] => Some([
+                (a.position[0] + b.position[0]) / 2.0,
+                (a.position[1] + b.position[1]) / 2.0,
+            ]),
+            _ => None,
+        }
+    }
+
+    /// Update the state from a window event. Called by the runner.
+    pub fn handle_window_event(&mut self, window_id: WindowId, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput {
+                event, ..
+            } => {
+                if let PhysicalKey::Code(key) = event.physical_key {
+                    if !event.repeat {
+                        self.set_button(key.into(), event.state);
+                    }
+                }
+            }
+            WindowEvent::MouseInput {
+                state,
+                button,
+                ..
+            } => {
+                self.set_button((*button).into(), *state);
+            }
+            WindowEvent::CursorMoved {
+                position, ..
+            } => {
+                self.cursor_position = Some([position.x as f32, position.y as f32]);
+                self.cursor_window = Some(window_id);
+            }
+            WindowEvent::CursorLeft {
+                ..
+            } if self.cursor_window == Some(window_id) => {
+                self.cursor_position = None;
+                self.cursor_window = None;
+            }
+            WindowEvent::MouseWheel {
+                delta, ..
+            } => {
+                let [x, y] = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => {
+                        [x * PIXELS_PER_SCROLL_LINE, y * PIXELS_PER_SCROLL_LINE]
+                    }
+                    MouseScrollDelta::PixelDelta(delta) => [delta.x as f32, delta.y as f32],
+                };
+                self.scroll_delta[0] += x;
+                self.scroll_delta[1] += y;
+            }
+            WindowEvent::Touch(touch) => {
+                let position = [touch.location.x as f32, touch.location.y as f32];
+                match touch.phase {
+                    TouchPhase::Started => {
+                        self.touches.push(TouchPoint {
+                            id: touch.id,
+                            window: window_id,
+                            position,
+                            start_position: position,
+                            previous_position: position,
+                        });
+                        self.touches_started.push(touch.id);
+                    }
+                    TouchPhase::Moved => {
+                        if let Some(point) = self.touches.iter_mut().find(|t| t.id == touch.id) {
+                            point.position = position;
+                        }
+                    }
+                    TouchPhase::Ended | TouchPhase::Cancelled => {
+                        if let Some(i) = self.touches.iter().position(|t| t.id == touch.id) {
+                            let mut point = self.touches.remove(i);
+                            point.position = position;
+                            self.touches_ended.push(point);
+                        }
+                    }
+                }
+            }
+            WindowEvent::Focused(false) => {
+                // Releases while unfocused aren't received
+                let pressed = std::mem::take(&mut self.pressed);
+                self.just_released.extend(pressed);
+            }
+            _ => (),
+        }
+    }
+
+    /// Update the state from a device event. Called by the runner.
+    pub fn handle_device_event(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion {
+            delta,
+        } = event
+        {
+            self.mouse_delta[0] += delta.0 as f32;
+            self.mouse_delta[1] += delta.1 as f32;
+        }
+    }
+
+    /// Update a gamepad button held on any pad. Called by [`Gamepads`](crate::gamepad::Gamepads).
+    #[cfg(feature = "gamepad")]
+    pub fn handle_gamepad_button(&mut self, button: gilrs::Button, pressed: bool) {
+        let state = if pressed {
+            ElementState::Pressed
+        } else {
+            ElementState::Released
+        };
+        self.set_button(button.into(), state);
+    }
+
+    fn set_button(&mut self, button: InputButton, state: ElementState) {
+        match state {
+            ElementState::Pressed => {
+                if self.pressed.insert(button) {
+                    self.just_pressed.insert(button);
+                }
+            }
+            ElementState::Released => {
+                if self.pressed.remove(&button) {
+                    self.just_released.insert(button);
+                }
+            }
+        }
+    }
+
+    /// Clear per frame state. Called by the runner at the end of each frame.
+    pub fn end_frame(&mut self) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+        self.scroll_delta = [0.0; 2];
+        self.mouse_delta = [0.0; 2];
+        for touch in self.touches.iter_mut() {
+            touch.previous_position = touch.position;
+        }
+        self.touches_started.clear();
+        self.touches_ended.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::{
+        event::{DeviceId, ElementState, MouseButton, WindowEvent},
+        keyboard::KeyCode,
+        window::WindowId,
+    };
+
+    use crate::input::{Binding, Input, InputMap};
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    enum Action {
+        Jump,
+        Save,
+    }
+
+    /// Key events can't be constructed outside winit, so keys are set directly
+    fn key(input: &mut Input, key: KeyCode, state: ElementState) {
+        input.set_button(key.into(), state);
+    }
+
+    fn mouse(input: &mut Input, button: MouseButton, state: ElementState) {
+        input.handle_window_event(WindowId::dummy(), &WindowEvent::MouseInput {
+            device_id: DeviceId::dummy(),
+            state,
+            button,
+        });
+    }
+
+    #[test]
+    fn test_button_just_pressed_lasts_one_frame() {
+        let mut input = Input::new();
+        key(&mut input, KeyCode::Space, ElementState::Pressed);
+        assert!(input.pressed(KeyCode::Space));
+        assert!(input.just_pressed(KeyCode::Space));
+        input.end_frame();
+        assert!(input.pressed(KeyCode::Space));
+        assert!(!input.just_pressed(KeyCode::Space));
+        key(&mut input, KeyCode::Space, ElementState::Released);
+        assert!(!input.pressed(KeyCode::Space));
+        assert!(input.just_released(KeyCode::Space));
+        input.end_frame();
+        assert!(!input.just_released(KeyCode::Space));
+    }
+
+    #[test]
+    fn test_mouse_button_events() {
+        let mut input = Input::new();
+        mouse(&mut input, MouseButton::Left, ElementState::Pressed);
+        assert!(input.just_pressed(MouseButton::Left));
+        assert!(!input.pressed(MouseButton::Right));
+        mouse(&mut input, MouseButton::Left, ElementState::Released);
+        assert!(input.just_released(MouseButton::Left));
+    }
+
+    #[test]
+    fn test_chord_needs_all_buttons() {
+        let mut input = Input::new();
+        let save = Binding::chord([KeyCode::ControlLeft, KeyCode::KeyS]);
+        key(&mut input, KeyCode::KeyS, ElementState::Pressed);
+        assert!(!input.binding_pressed(&save));
+        assert!(!input.binding_just_pressed(&save));
+        input.end_frame();
+        // Completing the chord in a later frame still counts as just pressed
+        key(&mut input, KeyCode::ControlLeft, ElementState::Pressed);
+        assert!(input.binding_pressed(&save));
+        assert!(input.binding_just_pressed(&save));
+        input.end_frame();
+        assert!(input.binding_pressed(&save));
+        assert!(!input.binding_just_pressed(&save));
+        key(&mut input, KeyCode::KeyS, ElementState::Released);
+        assert!(!input.binding_pressed(&save));
+        assert!(input.binding_just_released(&save));
+    }
+
+    #[test]
+    fn test_empty_binding_is_never_pressed() {
+        let input = Input::new();
+        let empty = Binding::chord(Vec::<KeyCode>::new());
+        assert!(!input.binding_pressed(&empty));
+        assert!(!input.binding_just_released(&empty));
+    }
+
+    #[test]
+    fn test_action_triggered_by_any_binding() {
+        let mut map = InputMap::new();
+        map.bind(Action::Jump, KeyCode::Space)
+            .bind(Action::Jump, MouseButton::Left)
+            .bind(
+                Action::Save,
+                Binding::chord([KeyCode::ControlLeft, KeyCode::KeyS]),
+            );
+        let mut input = Input::new();
+        mouse(&mut input, MouseButton::Left, ElementState::Pressed);
+        assert!(input.action_pressed(&map, Action::Jump));
+        assert!(input.action_just_pressed(&map, Action::Jump));
+        assert!(!input.action_pressed(&map, Action::Save));
+        input.end_frame();
+        mouse(&mut input, MouseButton::Left, ElementState::Released);
+        assert!(!input.action_pressed(&map, Action::Jump));
+        assert!(input.action_just_released(&map, Action::Jump));
+    }
+
+    #[test]
+    fn test_action_held_through_another_binding() {
+        let mut map = InputMap::new();
+        map.bind(Action::Jump, KeyCode::Space)
+            .bind(Action::Jump, MouseButton::Left);
+        let mut input = Input::new();
+        key(&mut input, KeyCode::Space, ElementState::Pressed);
+        input.end_frame();
+        // Already held with space, so the mouse doesn't press it again
+        mouse(&mut input, MouseButton::Left, ElementState::Pressed);
+        assert!(!input.action_just_pressed(&map, Action::Jump));
+        input.end_frame();
+        // Still held with the mouse
+        key(&mut input, KeyCode::Space, ElementState::Released);
+        assert!(input.action_pressed(&map, Action::Jump));
+        assert!(!input.action_just_released(&map, Action::Jump));
+        input.end_frame();
+        mouse(&mut input, MouseButton::Left, ElementState::Released);
+        assert!(input.action_just_released(&map, Action::Jump));
+    }
+
+    #[test]
+    fn test_rebind_and_unbind() {
+        let mut map = InputMap::new();
+        map.bind(Action::Jump, KeyCode::Space)
+            .bind(Action::Jump, KeyCode::Space);
+        assert_eq!(map.bindings(Action::Jump), &[Binding::from(KeyCode::Space)]);
+        map.rebind(Action::Jump, KeyCode::KeyW);
+        let mut input = Input::new();
+        key(&mut input, KeyCode::Space, ElementState::Pressed);
+        assert!(!input.action_pressed(&map, Action::Jump));
+        key(&mut input, KeyCode::KeyW, ElementState::Pressed);
+        assert!(input.action_pressed(&map, Action::Jump));
+        map.unbind(Action::Jump);
+        assert!(map.bindings(Action::Jump).is_empty());
+        assert!(!input.action_pressed(&map, Action::Jump));
+    }
+
+    #[test]
+    fn test_focus_loss_releases_buttons() {
+        let mut input = Input::new();
+        key(&mut input, KeyCode::KeyA, ElementState::Pressed);
+        mouse(&mut input, MouseButton::Left, ElementState::Pressed);
+        input.handle_window_event(WindowId::dummy(), &WindowEvent::Focused(false));
+        assert!(!input.pressed(KeyCode::KeyA));
+        assert!(input.just_released(KeyCode::KeyA));
+        assert!(input.just_released(MouseButton::Left));
+    }
+}
@@ -1,10 +1,38 @@
+mod args;
 pub mod device_context;
 mod glass;
 mod glass_app;
+mod glass_compute;
 
+pub mod assets;
+pub mod camera;
+pub mod capture;
+pub mod checkpoint;
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+pub mod debug_overlay;
+#[cfg(feature = "egui_gui")]
+pub mod egui_utils;
+#[cfg(feature = "gamepad")]
+pub mod gamepad;
+pub mod gizmos;
+pub mod gpu_error;
+pub mod gui;
+pub mod hot_reload;
+pub mod input;
+pub mod locale;
+pub mod pass;
 pub mod pipelines;
+pub mod recorder;
+pub mod render_scale;
+pub mod resize;
+#[cfg(feature = "shader_cache")]
+pub mod shader_cache;
 pub mod texture;
+pub mod time;
+pub mod transform;
 pub mod utils;
+pub mod watchdog;
 pub mod window;
 
 // For convenience, export egui libs when that feature is enabled
@@ -20,9 +48,10 @@ pub use egui_plot;
 pub use egui_wgpu;
 #[cfg(feature = "egui_gui")]
 pub use egui_winit;
+pub use glass_macros::include_shader;
 // --
 pub use image;
 pub use wgpu;
 pub use winit;
 
-pub use crate::{glass::*, glass_app::*};
+pub use crate::{glass::*, glass_app::*, glass_compute::*};
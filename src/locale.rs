@@ -0,0 +1,136 @@
+use std::{
+    borrow::Cow,
+    fmt::Display,
+    sync::{Arc, RwLock},
+};
+
+/// Keys of all user-visible strings glass produces. Templates may contain placeholders like
+/// `{flag}`, which are filled in by [`format_message`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Message {
+    /// Title of windows that keep the default [`WindowConfig`](crate::window::WindowConfig) title
+    DefaultWindowTitle,
+    WindowError,
+    SurfaceError,
+    AdapterError,
+    DeviceError,
+    ImageError,
+    EventLoopError,
+    IoError,
+    ArgumentError,
+    CaptureNotSupported,
+    FontError,
+    LutError,
+    ClipboardError,
+    PushConstantsNotSupported,
+    ShaderFileReadError,
+    ShaderInvalidExtension,
+    ShaderAlreadyIncluded,
+    ShaderParseError,
+    ShaderPreprocessError,
+    ShaderGlslParseError,
+    ShaderSpirvParseError,
+    /// `{flag}`
+    ArgumentRequiresValue,
+    /// `{value}`, `{flag}`
+    ArgumentInvalidValue,
+    /// `{value}`
+    ArgumentUnknownBackend,
+    ArgumentUsage,
+    /// `{fps}`, `{ms}`
+    DebugOverlayFrameTime,
+    /// `{ms}`
+    DebugOverlayGpuTime,
+    /// `{width}`, `{height}`, `{format}`, `{present_mode}`, `{samples}`
+    DebugOverlaySurface,
+    CheckpointRingSizeZero,
+    /// `{path}`, `{size}`, `{expected}`
+    CheckpointSizeMismatch,
+}
+
+impl Message {
+    /// The built-in english text of the message
+    pub fn default_text(self) -> &'static str {
+        match self {
+            Message::DefaultWindowTitle => "App",
+            Message::WindowError => "WindowError",
+            Message::SurfaceError => "SurfaceError",
+            Message::AdapterError => "AdapterError",
+            Message::DeviceError => "DeviceError",
+            Message::ImageError => "ImageError",
+            Message::EventLoopError => "EventLoopError",
+            Message::IoError => "IoError",
+            Message::ArgumentError => "ArgumentError",
+            Message::CaptureNotSupported => "CaptureNotSupported",
+            Message::FontError => "FontError",
+            Message::LutError => "LutError",
+            Message::ClipboardError => "ClipboardError",
+            Message::PushConstantsNotSupported => "PushConstantsNotSupported",
+            Message::ShaderFileReadError => "ShaderError::FileReadError",
+            Message::ShaderInvalidExtension => "ShaderError::InvalidExtension",
+            Message::ShaderAlreadyIncluded => "ShaderError::AlreadyIncluded",
+            Message::ShaderParseError => "ShaderError::WgslParseError",
+            Message::ShaderPreprocessError => "ShaderError::PreprocessError",
+            Message::ShaderGlslParseError => "ShaderError::GlslParseError",
+            Message::ShaderSpirvParseError => "ShaderError::SpirvParseError",
+            Message::ArgumentRequiresValue => "{flag} requires a value",
+            Message::ArgumentInvalidValue => "Invalid value {value} for {flag}",
+            Message::ArgumentUnknownBackend => "Unknown backend {value}",
+            Message::ArgumentUsage => {
+                "Glass options:
+  --windowed           Run in a window
+  --fullscreen         Run in borderless fullscreen
+  --width <px>         Window width
+  --height <px>        Window height
+  --backend <name>     vulkan, metal, dx12, gl, webgpu, primary or all
+  --vsync [on|off]     Enable or disable vsync
+  --no-vsync           Disable vsync
+  --monitor <index>    Open the window on the monitor at index"
+            }
+            Message::DebugOverlayFrameTime => "{fps} FPS  {ms} ms",
+            Message::DebugOverlayGpuTime => "GPU {ms} ms",
+            Message::DebugOverlaySurface => {
+                "{width}x{height} {format} {present_mode} MSAA {samples}"
+            }
+            Message::CheckpointRingSizeZero => "Checkpoint ring size must be > 0",
+            Message::CheckpointSizeMismatch => {
+                "Checkpoint {path} has {size} bytes, expected {expected}"
+            }
+        }
+    }
+}
+
+/// Translates glass messages. Return `None` to fall back to [`Message::default_text`].
+pub trait LocaleProvider: Send + Sync {
+    fn message(&self, message: Message) -> Option<Cow<'static, str>>;
+}
+
+static LOCALE_PROVIDER: RwLock<Option<Arc<dyn LocaleProvider>>> = RwLock::new(None);
+
+/// Set the provider used for all glass-originated text. Set it before creating windows so the
+/// default window title is translated too.
+pub fn set_locale_provider(provider: impl LocaleProvider + 'static) {
+    *LOCALE_PROVIDER.write().unwrap() = Some(Arc::new(provider));
+}
+
+/// Restore the built-in english messages
+pub fn clear_locale_provider() {
+    *LOCALE_PROVIDER.write().unwrap() = None;
+}
+
+/// Text of a message from the current provider
+pub fn message(message: Message) -> Cow<'static, str> {
+    let provider = LOCALE_PROVIDER.read().unwrap().clone();
+    provider
+        .and_then(|provider| provider.message(message))
+        .unwrap_or(Cow::Borrowed(message.default_text()))
+}
+
+/// Text of a message with `{name}` placeholders replaced by given arguments
+pub fn format_message(msg: Message, args: &[(&str, &dyn Display)]) -> String {
+    let mut text = message(msg).into_owned();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    text
+}
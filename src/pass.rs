@@ -0,0 +1,324 @@
+use log::warn;
+use wgpu::{
+    Color, CommandEncoder, LoadOp, Operations, RenderPass, RenderPassColorAttachment,
+    RenderPassDepthStencilAttachment, RenderPassDescriptor, RenderPassTimestampWrites, StoreOp,
+    TextureView,
+};
+
+use crate::texture::Texture;
+
+enum ColorTarget<'a> {
+    Texture(&'a Texture, usize),
+    View(&'a TextureView),
+}
+
+struct ColorDesc<'a> {
+    target: ColorTarget<'a>,
+    resolve_target: Option<&'a TextureView>,
+    load: Option<LoadOp<Color>>,
+    store: StoreOp,
+}
+
+struct DepthDesc<'a> {
+    view: &'a TextureView,
+    load: LoadOp<f32>,
+    store: StoreOp,
+}
+
+/// A declarative description of a render pass. Load ops are picked automatically unless set:
+/// [`Texture`] target mip levels are loaded if they have been written to before and cleared
+/// otherwise, plain view targets are cleared. Explicitly loading a mip level that has never been
+/// written to logs a warning, since it would read uninitialized contents.
+///
+/// ```ignore
+/// let mut rpass = PassDesc::new("draw")
+///     .color(&target)
+///     .clear(Color::BLACK)
+///     .depth(&depth_view)
+///     .begin(encoder);
+/// ```
+pub struct PassDesc<'a> {
+    label: Option<&'a str>,
+    colors: Vec<ColorDesc<'a>>,
+    depth: Option<DepthDesc<'a>>,
+    timestamp_writes: Option<RenderPassTimestampWrites<'a>>,
+}
+
+impl<'a> PassDesc<'a> {
+    pub fn new(label: &'a str) -> PassDesc<'a> {
+        PassDesc {
+            label: Some(label),
+            colors: vec![],
+            depth: None,
+            timestamp_writes: None,
+        }
+    }
+
+    /// Add mip level 0 of a texture as the next color attachment
+    pub fn color(self, texture: &'a Texture) -> Self {
+        self.color_mip(texture, 0)
+    }
+
+    /// Add given mip level of a texture as the next color attachment
+    pub fn color_mip(mut self, texture: &'a Texture, mip_level: usize) -> Self {
+        self.colors.push(ColorDesc {
+            target: ColorTarget::Texture(texture, mip_level),
+            resolve_target: None,
+            load: None,
+            store: StoreOp::Store,
+        });
+        self
+    }
+
+    /// Add a view (e.g. a swapchain frame view) as the next color attachment
+    pub fn color_view(mut self, view: &'a TextureView) -> Self {
+        self.colors.push(ColorDesc {
+            target: ColorTarget::View(view),
+            resolve_target: None,
+            load: None,
+            store: StoreOp::Store,
+        });
+        self
+    }
+
+    /// Clear the last added color attachment
+    pub fn clear(mut self, color: Color) -> Self {
+        self.last_color().load = Some(LoadOp::Clear(color));
+        self
+    }
+
+    /// Load the previous contents of the last added color attachment
+    pub fn load(mut self) -> Self {
+        self.last_color().load = Some(LoadOp::Load);
+        self
+    }
+
+    /// Discard the rendered contents of the last added color attachment, e.g. after resolving
+    pub fn discard(mut self) -> Self {
+        self.last_color().store = StoreOp::Discard;
+        self
+    }
+
+    /// Resolve the last added (multisampled) color attachment into `view`
+    pub fn resolve(mut self, view: &'a TextureView) -> Self {
+        self.last_color().resolve_target = Some(view);
+        self
+    }
+
+    /// Set the depth attachment. Depth is cleared to 1.0 unless [`PassDesc::load_depth`] is used.
+    pub fn depth(mut self, view: &'a TextureView) -> Self {
+        self.depth = Some(DepthDesc {
+            view,
+            load: LoadOp::Clear(1.0),
+            store: StoreOp::Store,
+        });
+        self
+    }
+
+    pub fn clear_depth(mut self, value: f32) -> Self {
+        self.last_depth().load = LoadOp::Clear(value);
+        self
+    }
+
+    pub fn load_depth(mut self) -> Self {
+        self.last_depth().load = LoadOp::Load;
+        self
+    }
+
+    /// Write timestamps at the beginning and end of the pass, see
+    /// [`GpuProfiler::pass_timestamp_writes`](crate::utils::GpuProfiler::pass_timestamp_writes)
+    pub fn timestamp_writes(mut self, writes: Option<RenderPassTimestampWrites<'a>>) -> Self {
+        self.timestamp_writes = writes;
+        self
+    }
+
+    /// Begin the described render pass. Texture target mip levels are marked as initialized.
+    pub fn begin<'e>(self, encoder: &'e mut CommandEncoder) -> RenderPass<'e> {
+        let label = self.label.unwrap_or("unnamed");
+        let color_attachments = self
+            .colors
+            .iter()
+            .map(|color| {
+                let (view, load) = match color.target {
+                    ColorTarget::Texture(texture, mip_level) => {
+                        let initialized = texture.is_mip_initialized(mip_level as u32);
+                        let load = match color.load {
+                            Some(LoadOp::Load) if !initialized => {
+                                warn!(
+                                    "Pass {} loads a texture that has never been written to",
+                                    label
+                                );
+                                LoadOp::Load
+                            }
+                            Some(load) => load,
+                            None if initialized => LoadOp::Load,
+                            None => LoadOp::Clear(Color::TRANSPARENT),
+                        };
+                        texture.mark_mip_initialized(mip_level as u32);
+                        (&texture.views[mip_level], load)
+                    }
+                    ColorTarget::View(view) => {
+                        (view, color.load.unwrap_or(LoadOp::Clear(Color::BLACK)))
+                    }
+                };
+                Some(RenderPassColorAttachment {
+                    view,
+                    resolve_target: color.resolve_target,
+                    ops: Operations {
+                        load,
+                        store: color.store,
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+        let depth_stencil_attachment =
+            self.depth
+                .as_ref()
+                .map(|depth| RenderPassDepthStencilAttachment {
+                    view: depth.view,
+                    depth_ops: Some(Operations {
+                        load: depth.load,
+                        store: depth.store,
+                    }),
+                    stencil_ops: None,
+                });
+        encoder.begin_render_pass(&RenderPassDescriptor {
+            label: self.label,
+            color_attachments: &color_attachments,
+            depth_stencil_attachment,
+            timestamp_writes: self.timestamp_writes,
+            occlusion_query_set: None,
+        })
+    }
+
+    fn last_color(&mut self) -> &mut ColorDesc<'a> {
+        self.colors
+            .last_mut()
+            .expect("Add a color attachment before setting its ops")
+    }
+
+    fn last_depth(&mut self) -> &mut DepthDesc<'a> {
+        self.depth
+            .as_mut()
+            .expect("Add a depth attachment before setting its ops")
+    }
+}
+
+/// Nested scissor rects for UI-like clipping. Each pushed rect is intersected with the current
+/// one, so children never draw outside their parents. Rects are `[x, y, width, height]` in
+/// pixels of the render target.
+///
+/// ```ignore
+/// let mut clip = ClipStack::new(target_size);
+/// clip.push(&mut rpass, [10, 10, 200, 100]);
+/// quad_pipeline.draw(&mut rpass, ...);
+/// clip.pop(&mut rpass);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClipStack {
+    target_size: [u32; 2],
+    rects: Vec<[u32; 4]>,
+}
+
+impl ClipStack {
+    pub fn new(target_size: [u32; 2]) -> ClipStack {
+        ClipStack {
+            target_size,
+            rects: vec![],
+        }
+    }
+
+    /// The active clip rect, the whole target when the stack is empty
+    pub fn current(&self) -> [u32; 4] {
+        self.rects
+            .last()
+            .copied()
+            .unwrap_or([0, 0, self.target_size[0], self.target_size[1]])
+    }
+
+    /// Number of pushed rects
+    pub fn depth(&self) -> usize {
+        self.rects.len()
+    }
+
+    /// Clip to `rect` within the current clip rect and set it as the pass' scissor rect. Returns
+    /// the resulting rect, which may be empty.
+    pub fn push(&mut self, rpass: &mut RenderPass, rect: [u32; 4]) -> [u32; 4] {
+        let rect = intersect_rects(self.current(), rect);
+        self.rects.push(rect);
+        rpass.set_scissor_rect(rect[0], rect[1], rect[2], rect[3]);
+        rect
+    }
+
+    /// Restore the previous clip rect. Returns the popped rect.
+    pub fn pop(&mut self, rpass: &mut RenderPass) -> Option<[u32; 4]> {
+        let rect = self.rects.pop();
+        let [x, y, width, height] = self.current();
+        rpass.set_scissor_rect(x, y, width, height);
+        rect
+    }
+
+    /// Empty the stack for a new pass, e.g. after the target has been resized
+    pub fn reset(&mut self, target_size: [u32; 2]) {
+        self.target_size = target_size;
+        self.rects.clear();
+    }
+}
+
+/// Intersection of `b` with `a`. The result always lies within `a`, an empty intersection is
+/// placed on `a`'s edge, so scissor rects never extend past the target.
+pub(crate) fn intersect_rects(a: [u32; 4], b: [u32; 4]) -> [u32; 4] {
+    let a_right = a[0] + a[2];
+    let a_bottom = a[1] + a[3];
+    let x = a[0].max(b[0]).min(a_right);
+    let y = a[1].max(b[1]).min(a_bottom);
+    let right = a_right.min(b[0].saturating_add(b[2]));
+    let bottom = a_bottom.min(b[1].saturating_add(b[3]));
+    [x, y, right.saturating_sub(x), bottom.saturating_sub(y)]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pass::intersect_rects;
+
+    #[test]
+    fn test_intersect_rects_overlap() {
+        assert_eq!(intersect_rects([0, 0, 800, 600], [100, 50, 200, 100]), [
+            100, 50, 200, 100
+        ]);
+        assert_eq!(
+            intersect_rects([100, 100, 200, 200], [50, 150, 100, 300]),
+            [100, 150, 50, 150]
+        );
+    }
+
+    #[test]
+    fn test_intersect_rects_past_edge() {
+        assert_eq!(intersect_rects([0, 0, 800, 600], [700, 500, 200, 200]), [
+            700, 500, 100, 100
+        ]);
+        assert_eq!(
+            intersect_rects([0, 0, 800, 600], [10, 10, u32::MAX, u32::MAX]),
+            [10, 10, 790, 590]
+        );
+    }
+
+    #[test]
+    fn test_intersect_rects_outside() {
+        // Starts past the right edge, the rect must stay within the target
+        let rect = intersect_rects([0, 0, 800, 600], [1000, 700, 50, 50]);
+        assert_eq!(rect, [800, 600, 0, 0]);
+        // Ends before the parent starts
+        let rect = intersect_rects([100, 100, 200, 200], [0, 0, 50, 50]);
+        assert_eq!(rect, [100, 100, 0, 0]);
+    }
+
+    #[test]
+    fn test_intersect_rects_nested() {
+        let parent = intersect_rects([0, 0, 800, 600], [100, 100, 400, 300]);
+        let child = intersect_rects(parent, [450, 350, 100, 100]);
+        assert_eq!(child, [450, 350, 50, 50]);
+        let outside = intersect_rects(child, [0, 0, 10, 10]);
+        assert_eq!(outside, [450, 350, 0, 0]);
+    }
+}
@@ -0,0 +1,180 @@
+// Compute variant of bloom.wgsl. Writes into storage textures instead of render targets, so
+// upsampling blends with the downsampled chain manually instead of with blend state.
+
+@group(0) @binding(0)
+var input_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var s: sampler;
+@group(0) @binding(2)
+var output_texture: texture_storage_2d<rgba16float, write>;
+// Downsampled mip the upsampled result is blended over
+@group(0) @binding(3)
+var base_texture: texture_2d<f32>;
+
+struct PushConstants {
+    threshold_precomputations: vec4<f32>,
+    viewport: vec4<f32>,
+    aspect: f32,
+    use_treshold: u32,
+    blend: f32,
+    additive: u32,
+}
+var<push_constant> pc: PushConstants;
+
+// https://catlikecoding.com/unity/tutorials/advanced-rendering/bloom/#3.4
+fn soft_threshold(color: vec3<f32>) -> vec3<f32> {
+    let brightness = max(color.r, max(color.g, color.b));
+    var softness = brightness - pc.threshold_precomputations.y;
+    softness = clamp(softness, 0.0, pc.threshold_precomputations.z);
+    softness = softness * softness * pc.threshold_precomputations.w;
+    var contribution = max(brightness - pc.threshold_precomputations.x, softness);
+    contribution /= max(brightness, 0.00001); // Prevent division by 0
+    return color * contribution;
+}
+
+// luminance coefficients from Rec. 709.
+// https://en.wikipedia.org/wiki/Rec._709
+fn tonemapping_luminance(v: vec3<f32>) -> f32 {
+    return dot(v, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+fn rgb_to_srgb_simple(color: vec3<f32>) -> vec3<f32> {
+    return pow(color, vec3<f32>(1.0 / 2.2));
+}
+
+// http://graphicrants.blogspot.com/2013/12/tone-mapping.html
+fn karis_average(color: vec3<f32>) -> f32 {
+    let luma = tonemapping_luminance(rgb_to_srgb_simple(color)) / 4.0;
+    return 1.0 / (1.0 + luma);
+}
+
+fn tap(uv: vec2<f32>, offset: vec2<f32>) -> vec3<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(input_texture));
+    return textureSampleLevel(input_texture, s, uv + offset * texel, 0.0).rgb;
+}
+
+fn sample_input_13_tap_first(uv: vec2<f32>) -> vec3<f32> {
+    let a = tap(uv, vec2<f32>(-2.0, 2.0));
+    let b = tap(uv, vec2<f32>(0.0, 2.0));
+    let c = tap(uv, vec2<f32>(2.0, 2.0));
+    let d = tap(uv, vec2<f32>(-2.0, 0.0));
+    let e = tap(uv, vec2<f32>(0.0, 0.0));
+    let f = tap(uv, vec2<f32>(2.0, 0.0));
+    let g = tap(uv, vec2<f32>(-2.0, -2.0));
+    let h = tap(uv, vec2<f32>(0.0, -2.0));
+    let i = tap(uv, vec2<f32>(2.0, -2.0));
+    let j = tap(uv, vec2<f32>(-1.0, 1.0));
+    let k = tap(uv, vec2<f32>(1.0, 1.0));
+    let l = tap(uv, vec2<f32>(-1.0, -1.0));
+    let m = tap(uv, vec2<f32>(1.0, -1.0));
+
+    // Firefly reduction, see bloom.wgsl
+    var group0 = (a + b + d + e) * (0.125f / 4.0f);
+    var group1 = (b + c + e + f) * (0.125f / 4.0f);
+    var group2 = (d + e + g + h) * (0.125f / 4.0f);
+    var group3 = (e + f + h + i) * (0.125f / 4.0f);
+    var group4 = (j + k + l + m) * (0.5f / 4.0f);
+    group0 *= karis_average(group0);
+    group1 *= karis_average(group1);
+    group2 *= karis_average(group2);
+    group3 *= karis_average(group3);
+    group4 *= karis_average(group4);
+    return group0 + group1 + group2 + group3 + group4;
+}
+
+fn sample_input_13_tap(uv: vec2<f32>) -> vec3<f32> {
+    let a = tap(uv, vec2<f32>(-2.0, 2.0));
+    let b = tap(uv, vec2<f32>(0.0, 2.0));
+    let c = tap(uv, vec2<f32>(2.0, 2.0));
+    let d = tap(uv, vec2<f32>(-2.0, 0.0));
+    let e = tap(uv, vec2<f32>(0.0, 0.0));
+    let f = tap(uv, vec2<f32>(2.0, 0.0));
+    let g = tap(uv, vec2<f32>(-2.0, -2.0));
+    let h = tap(uv, vec2<f32>(0.0, -2.0));
+    let i = tap(uv, vec2<f32>(2.0, -2.0));
+    let j = tap(uv, vec2<f32>(-1.0, 1.0));
+    let k = tap(uv, vec2<f32>(1.0, 1.0));
+    let l = tap(uv, vec2<f32>(-1.0, -1.0));
+    let m = tap(uv, vec2<f32>(1.0, -1.0));
+
+    var sampl = (a + c + g + i) * 0.03125;
+    sampl += (b + d + f + h) * 0.0625;
+    sampl += (e + j + k + l + m) * 0.125;
+    return sampl;
+}
+
+fn sample_input_3x3_tent(uv: vec2<f32>) -> vec3<f32> {
+    let x = 0.004 / pc.aspect;
+    let y = 0.004;
+
+    let a = textureSampleLevel(input_texture, s, vec2<f32>(uv.x - x, uv.y + y), 0.0).rgb;
+    let b = textureSampleLevel(input_texture, s, vec2<f32>(uv.x, uv.y + y), 0.0).rgb;
+    let c = textureSampleLevel(input_texture, s, vec2<f32>(uv.x + x, uv.y + y), 0.0).rgb;
+
+    let d = textureSampleLevel(input_texture, s, vec2<f32>(uv.x - x, uv.y), 0.0).rgb;
+    let e = textureSampleLevel(input_texture, s, vec2<f32>(uv.x, uv.y), 0.0).rgb;
+    let f = textureSampleLevel(input_texture, s, vec2<f32>(uv.x + x, uv.y), 0.0).rgb;
+
+    let g = textureSampleLevel(input_texture, s, vec2<f32>(uv.x - x, uv.y - y), 0.0).rgb;
+    let h = textureSampleLevel(input_texture, s, vec2<f32>(uv.x, uv.y - y), 0.0).rgb;
+    let i = textureSampleLevel(input_texture, s, vec2<f32>(uv.x + x, uv.y - y), 0.0).rgb;
+
+    var sampl = e * 0.25;
+    sampl += (b + d + f + h) * 0.125;
+    sampl += (a + c + g + i) * 0.0625;
+
+    return sampl;
+}
+
+// Uv of the invocation's output texel, or None (x < 0) if outside the output
+// Set from the pipeline so dispatch sizes match
+override WORKGROUP_SIZE: u32 = 8u;
+
+fn output_uv(id: vec3<u32>) -> vec2<f32> {
+    let size = textureDimensions(output_texture);
+    if (any(id.xy >= size)) {
+        return vec2<f32>(-1.0);
+    }
+    return (vec2<f32>(id.xy) + 0.5) / vec2<f32>(size);
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, 1)
+fn downsample_first(@builtin(global_invocation_id) id: vec3<u32>) {
+    let uv = output_uv(id);
+    if (uv.x < 0.0) {
+        return;
+    }
+    let sample_uv = pc.viewport.xy + uv * pc.viewport.zw;
+    var sampl = sample_input_13_tap_first(sample_uv);
+    // Lower bound avoids black boxes, upper bound NaNs, see bloom.wgsl
+    sampl = clamp(sampl, vec3<f32>(0.0001), vec3<f32>(3.40282347E+38));
+    if (pc.use_treshold == u32(1)) {
+        sampl = soft_threshold(sampl);
+    }
+    textureStore(output_texture, id.xy, vec4<f32>(sampl, 1.0));
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, 1)
+fn downsample(@builtin(global_invocation_id) id: vec3<u32>) {
+    let uv = output_uv(id);
+    if (uv.x < 0.0) {
+        return;
+    }
+    textureStore(output_texture, id.xy, vec4<f32>(sample_input_13_tap(uv), 1.0));
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, 1)
+fn upsample(@builtin(global_invocation_id) id: vec3<u32>) {
+    let uv = output_uv(id);
+    if (uv.x < 0.0) {
+        return;
+    }
+    let upsampled = sample_input_3x3_tent(uv) * pc.blend;
+    let base = textureLoad(base_texture, id.xy, 0).rgb;
+    // Same as the render variant's constant blend states
+    var color = upsampled + base * (1.0 - pc.blend);
+    if (pc.additive == u32(1)) {
+        color = upsampled + base;
+    }
+    textureStore(output_texture, id.xy, vec4<f32>(color, 1.0));
+}
@@ -0,0 +1,379 @@
+use std::{borrow::Cow, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, CommandEncoder, ComputePassDescriptor,
+    ComputePipeline, Device, Extent3d, PipelineCache, PushConstantRange, Sampler,
+    SamplerBindingType, ShaderStages, StorageTextureAccess, TextureFormat, TextureSampleType,
+    TextureUsages, TextureView, TextureViewDimension,
+};
+
+use super::pipeline::{BloomCompositeMode, BloomPushConstants, BloomSettings, ScopeLabels};
+use crate::{
+    texture::Texture,
+    utils::{ComputePipelineBuilder, GpuProfiler},
+};
+
+/// Format of the mip chains in compute mode, must match the storage format in
+/// bloom_compute.wgsl
+pub(crate) const COMPUTE_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Downsampling & upsampling of [`BloomPipeline`](super::BloomPipeline) in compute passes.
+/// Downsamples into the pipeline's bloom texture, upsamples into a separate chain as storage
+/// textures can't be blended into. The final composite stays a render pass.
+pub(crate) struct BloomCompute {
+    downsample_first_pipeline: ComputePipeline,
+    downsample_pipeline: ComputePipeline,
+    upsample_pipeline: ComputePipeline,
+    bind_group_layout: BindGroupLayout,
+    upsample_texture: Texture,
+    downsampling_bind_groups: Vec<BindGroup>,
+    upsampling_bind_groups: Vec<BindGroup>,
+    /// Reads the upsampled mip 0, for the final render pass
+    final_bind_group: BindGroup,
+    scope_labels: ScopeLabels,
+}
+
+impl BloomCompute {
+    pub fn new(
+        device: &Device,
+        cache: Option<&PipelineCache>,
+        final_bind_group_layout: &BindGroupLayout,
+        bloom_texture: &Texture,
+        sampler: &Sampler,
+        mip_count: u32,
+    ) -> BloomCompute {
+        let sampled = |binding| BindGroupLayoutEntry {
+            binding,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float {
+                    filterable: true,
+                },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            visibility: ShaderStages::COMPUTE,
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("bloom_compute_bind_group_layout"),
+            entries: &[
+                sampled(0),
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    visibility: ShaderStages::COMPUTE,
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    ty: BindingType::StorageTexture {
+                        access: StorageTextureAccess::WriteOnly,
+                        format: COMPUTE_TEXTURE_FORMAT,
+                        view_dimension: TextureViewDimension::D2,
+                    },
+                    visibility: ShaderStages::COMPUTE,
+                    count: None,
+                },
+                sampled(3),
+            ],
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Bloom Compute Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("bloom_compute.wgsl"))),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Bloom Compute Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[PushConstantRange {
+                stages: ShaderStages::COMPUTE,
+                range: 0..std::mem::size_of::<BloomComputePushConstants>() as u32,
+            }],
+        });
+        let pipeline = |label, entry_point| {
+            ComputePipelineBuilder::new(label, &shader, entry_point)
+                .layout(&layout)
+                .constant("WORKGROUP_SIZE", WORKGROUP_SIZE as f64)
+                .cache(cache)
+                .build(device)
+        };
+        let downsample_first_pipeline = pipeline(
+            "Bloom Compute Downsample First Pipeline",
+            "downsample_first",
+        );
+        let downsample_pipeline = pipeline("Bloom Compute Downsample Pipeline", "downsample");
+        let upsample_pipeline = pipeline("Bloom Compute Upsample Pipeline", "upsample");
+
+        let upsample_texture = Self::create_upsample_texture(device, bloom_texture, mip_count);
+        let (downsampling_bind_groups, upsampling_bind_groups, final_bind_group) =
+            Self::create_bind_groups(
+                device,
+                &bind_group_layout,
+                final_bind_group_layout,
+                bloom_texture,
+                &upsample_texture,
+                sampler,
+                mip_count,
+            );
+        BloomCompute {
+            downsample_first_pipeline,
+            downsample_pipeline,
+            upsample_pipeline,
+            bind_group_layout,
+            upsample_texture,
+            downsampling_bind_groups,
+            upsampling_bind_groups,
+            final_bind_group,
+            scope_labels: ScopeLabels::new("glass::bloom_compute", mip_count),
+        }
+    }
+
+    /// Recreate size dependent resources after the bloom texture was recreated
+    pub fn recreate_textures(
+        &mut self,
+        device: &Device,
+        final_bind_group_layout: &BindGroupLayout,
+        bloom_texture: &Texture,
+        sampler: &Sampler,
+        mip_count: u32,
+    ) {
+        self.upsample_texture = Self::create_upsample_texture(device, bloom_texture, mip_count);
+        (
+            self.downsampling_bind_groups,
+            self.upsampling_bind_groups,
+            self.final_bind_group,
+        ) = Self::create_bind_groups(
+            device,
+            &self.bind_group_layout,
+            final_bind_group_layout,
+            bloom_texture,
+            &self.upsample_texture,
+            sampler,
+            mip_count,
+        );
+        self.scope_labels = ScopeLabels::new("glass::bloom_compute", mip_count);
+    }
+
+    /// Same size as the bloom texture, one mip less as the smallest mip isn't upsampled into
+    fn create_upsample_texture(
+        device: &Device,
+        bloom_texture: &Texture,
+        mip_count: u32,
+    ) -> Texture {
+        Texture::empty(
+            device,
+            "bloom_upsample_texture",
+            Extent3d {
+                width: bloom_texture.size[0] as u32,
+                height: bloom_texture.size[1] as u32,
+                depth_or_array_layers: 1,
+            },
+            mip_count - 1,
+            COMPUTE_TEXTURE_FORMAT,
+            TextureUsages::STORAGE_BINDING | TextureUsages::TEXTURE_BINDING,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_bind_groups(
+        device: &Device,
+        layout: &BindGroupLayout,
+        final_bind_group_layout: &BindGroupLayout,
+        bloom_texture: &Texture,
+        upsample_texture: &Texture,
+        sampler: &Sampler,
+        mip_count: u32,
+    ) -> (Vec<BindGroup>, Vec<BindGroup>, BindGroup) {
+        let mip_count = mip_count as usize;
+        let downsampling_bind_groups = (1..mip_count)
+            .map(|mip| {
+                Self::bind_group(
+                    device,
+                    layout,
+                    &bloom_texture.views[mip - 1],
+                    sampler,
+                    &bloom_texture.views[mip],
+                    &bloom_texture.views[mip - 1],
+                )
+            })
+            .collect();
+        // Smallest mip first, the last downsampled mip is the first source
+        let upsampling_bind_groups = (1..mip_count)
+            .rev()
+            .map(|mip| {
+                let input = if mip == mip_count - 1 {
+                    &bloom_texture.views[mip]
+                } else {
+                    &upsample_texture.views[mip]
+                };
+                Self::bind_group(
+                    device,
+                    layout,
+                    input,
+                    sampler,
+                    &upsample_texture.views[mip - 1],
+                    &bloom_texture.views[mip - 1],
+                )
+            })
+            .collect();
+        let final_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("bloom_compute_final_bind_group"),
+            layout: final_bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&upsample_texture.views[0]),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+        (
+            downsampling_bind_groups,
+            upsampling_bind_groups,
+            final_bind_group,
+        )
+    }
+
+    fn bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        input: &TextureView,
+        sampler: &Sampler,
+        output: &TextureView,
+        base: &TextureView,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("bloom_compute_bind_group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(input),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(sampler),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::TextureView(output),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::TextureView(base),
+                },
+            ],
+        })
+    }
+
+    pub fn final_bind_group(&self) -> &BindGroup {
+        &self.final_bind_group
+    }
+
+    /// Downsample `input` through the bloom texture and upsample back to mip 0 of the upsample
+    /// chain. `blend` gives the blend factor of each upsampled mip.
+    #[allow(clippy::too_many_arguments)]
+    pub fn bloom(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        bloom_texture: &Texture,
+        input: &TextureView,
+        sampler: &Sampler,
+        settings: &BloomSettings,
+        push_constants: BloomPushConstants,
+        blend: impl Fn(u32) -> f32,
+        profiler: Option<&Arc<GpuProfiler>>,
+    ) {
+        let mip_count = self.downsampling_bind_groups.len() + 1;
+        let additive = (settings.composite_mode == BloomCompositeMode::Additive) as u32;
+        let push_constants = |blend| BloomComputePushConstants {
+            threshold_precomputations: push_constants.threshold_precomputations,
+            viewport: push_constants.viewport,
+            aspect: push_constants.aspect,
+            use_treshold: push_constants.use_treshold,
+            blend,
+            additive,
+        };
+        let first_bind_group = Self::bind_group(
+            device,
+            &self.bind_group_layout,
+            input,
+            sampler,
+            &bloom_texture.views[0],
+            input,
+        );
+        let mip_size = |mip: usize| {
+            [
+                (bloom_texture.size[0] as u32 >> mip).max(1),
+                (bloom_texture.size[1] as u32 >> mip).max(1),
+            ]
+        };
+        let dispatch = |encoder: &mut CommandEncoder,
+                        label: &str,
+                        pipeline: &ComputePipeline,
+                        bind_group: &BindGroup,
+                        pc: BloomComputePushConstants,
+                        size: [u32; 2]| {
+            let mut cpass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some(label),
+                timestamp_writes: profiler
+                    .and_then(|profiler| profiler.compute_pass_timestamp_writes(label)),
+            });
+            cpass.set_pipeline(pipeline);
+            cpass.set_bind_group(0, bind_group, &[]);
+            cpass.set_push_constants(0, bytemuck::cast_slice(&[pc]));
+            cpass.dispatch_workgroups(
+                size[0].div_ceil(WORKGROUP_SIZE),
+                size[1].div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+        };
+
+        dispatch(
+            encoder,
+            &self.scope_labels.downsample[0],
+            &self.downsample_first_pipeline,
+            &first_bind_group,
+            push_constants(0.0),
+            mip_size(0),
+        );
+        for mip in 1..mip_count {
+            dispatch(
+                encoder,
+                &self.scope_labels.downsample[mip],
+                &self.downsample_pipeline,
+                &self.downsampling_bind_groups[mip - 1],
+                push_constants(0.0),
+                mip_size(mip),
+            );
+        }
+        for mip in (1..mip_count).rev() {
+            dispatch(
+                encoder,
+                &self.scope_labels.upsample[mip],
+                &self.upsample_pipeline,
+                &self.upsampling_bind_groups[mip_count - mip - 1],
+                push_constants(blend(mip as u32)),
+                mip_size(mip - 1),
+            );
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct BloomComputePushConstants {
+    pub threshold_precomputations: [f32; 4],
+    pub viewport: [f32; 4],
+    pub aspect: f32,
+    pub use_treshold: u32,
+    pub blend: f32,
+    pub additive: u32,
+}
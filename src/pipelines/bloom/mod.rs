@@ -1,3 +1,5 @@
+mod compute;
 mod pipeline;
 
-pub use pipeline::{BloomCompositeMode, BloomPipeline, BloomSettings};
+pub(crate) use pipeline::ScopeLabels;
+pub use pipeline::{BloomBackend, BloomCompositeMode, BloomPipeline, BloomSettings};
@@ -1,26 +1,88 @@
-use std::borrow::Cow;
+use std::sync::Arc;
 
 use bytemuck::{Pod, Zeroable};
+use log::warn;
 use wgpu::{
     util::DeviceExt, AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry,
     BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, BlendComponent,
     BlendFactor, BlendOperation, BlendState, Buffer, Color, ColorTargetState, ColorWrites,
-    CommandEncoder, Device, Extent3d, FilterMode, LoadOp, Operations, PushConstantRange,
-    RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, Sampler, SamplerBindingType,
-    SamplerDescriptor, ShaderStages, StoreOp, TextureFormat, TextureSampleType, TextureUsages,
-    TextureViewDimension,
+    CommandEncoder, Device, Extent3d, FilterMode, LoadOp, Operations, RenderPassColorAttachment,
+    RenderPassDescriptor, RenderPassTimestampWrites, RenderPipeline, Sampler, SamplerBindingType,
+    SamplerDescriptor, ShaderStages, StoreOp, TextureFormat, TextureFormatFeatureFlags,
+    TextureSampleType, TextureUsages, TextureViewDimension,
 };
 
+use super::compute::{BloomCompute, COMPUTE_TEXTURE_FORMAT};
 use crate::{
-    pipelines::{SimpleTexturedVertex, FULL_SCREEN_TRIANGLE_VERTICES},
+    device_context::DeviceContext,
+    pass::PassDesc,
+    pipelines::{DrawParams, DrawParamsSlot, SimpleTexturedVertex, FULL_SCREEN_TRIANGLE_VERTICES},
+    resize::Resizable,
     texture::Texture,
+    utils::GpuProfiler,
 };
 
 const BLOOM_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rg11b10Ufloat;
 const FINAL_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+const FALLBACK_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
 const MAX_MIP_DIMENSION: u32 = 512;
 
-fn create_bloom_texture(device: &Device, width: u32, height: u32, mip_count: u32) -> Texture {
+/// `format` if the device can render, blend and filter it, otherwise Rgba16Float. Rg11b10Ufloat
+/// needs [`wgpu::Features::RG11B10UFLOAT_RENDERABLE`].
+fn supported_bloom_format(device: &Device, format: TextureFormat) -> TextureFormat {
+    let features = format.guaranteed_format_features(device.features());
+    let supported = features
+        .allowed_usages
+        .contains(TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING)
+        && features
+            .flags
+            .contains(TextureFormatFeatureFlags::FILTERABLE | TextureFormatFeatureFlags::BLENDABLE);
+    if supported {
+        format
+    } else {
+        warn!(
+            "Bloom texture format {:?} is not renderable on this device, using {:?}",
+            format, FALLBACK_TEXTURE_FORMAT
+        );
+        FALLBACK_TEXTURE_FORMAT
+    }
+}
+
+/// Size of the first bloom mip and the mip count. The first mip is `max_mip_dimension` high
+/// (keeping aspect), clamped to the device's texture limits, and mips stop at 2 pixels.
+fn bloom_texture_size(
+    device: &Device,
+    settings: &BloomSettings,
+    width: u32,
+    height: u32,
+) -> ([u32; 2], u32) {
+    let max_dimension = device.limits().max_texture_dimension_2d;
+    let max_mip_dimension = settings.max_mip_dimension.max(4);
+    let ratio = max_mip_dimension as f32 / height.max(1) as f32;
+    let size = [
+        ((width.max(1) as f32 * ratio).round() as u32).clamp(4, max_dimension),
+        max_mip_dimension.min(max_dimension),
+    ];
+    let max_mip_count = size[0].min(size[1]).ilog2();
+    let mip_count = settings
+        .mip_count
+        .unwrap_or(max_mip_dimension.ilog2() - 1)
+        .clamp(2, max_mip_count);
+    (size, mip_count)
+}
+
+fn create_bloom_texture(
+    device: &Device,
+    format: TextureFormat,
+    backend: BloomBackend,
+    width: u32,
+    height: u32,
+    mip_count: u32,
+) -> Texture {
+    let usage = match backend {
+        BloomBackend::RenderPass => TextureUsages::RENDER_ATTACHMENT,
+        BloomBackend::Compute => TextureUsages::STORAGE_BINDING,
+    };
     Texture::empty(
         device,
         "bloom_texture",
@@ -30,11 +92,14 @@ fn create_bloom_texture(device: &Device, width: u32, height: u32, mip_count: u32
             depth_or_array_layers: 1,
         },
         mip_count,
-        BLOOM_TEXTURE_FORMAT,
-        TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+        format,
+        usage | TextureUsages::TEXTURE_BINDING,
     )
 }
 
+/// Bind group of the bloom parameters when push constants aren't available
+const PARAMS_GROUP: u32 = 1;
+
 pub struct BloomPipeline {
     downsample_first_pipeline: RenderPipeline,
     downsample_pipeline: RenderPipeline,
@@ -44,35 +109,72 @@ pub struct BloomPipeline {
     bloom_sampler: Sampler,
     downsampling_bind_groups: Vec<BindGroup>,
     upsampling_bind_groups: Vec<BindGroup>,
+    compute: Option<BloomCompute>,
+    params: DrawParams,
     vertices: Buffer,
+    texture_format: TextureFormat,
+    /// Backend in use, compute falls back to render passes without push constants
+    backend: BloomBackend,
     mip_count: u32,
     width: u32,
     height: u32,
     settings: BloomSettings,
+    profiler: Option<Arc<GpuProfiler>>,
+    scope_labels: ScopeLabels,
+}
+
+/// Profiler scope labels of each mip, built with the mip chain so passes don't format them
+/// every frame
+pub(crate) struct ScopeLabels {
+    pub downsample: Vec<String>,
+    pub upsample: Vec<String>,
+}
+
+impl ScopeLabels {
+    pub fn new(prefix: &str, mip_count: u32) -> ScopeLabels {
+        let labels = |pass: &str| {
+            (0..mip_count)
+                .map(|mip| format!("{}_{}_{}", prefix, pass, mip))
+                .collect()
+        };
+        ScopeLabels {
+            downsample: labels("downsample"),
+            upsample: labels("upsample"),
+        }
+    }
 }
 
 impl BloomPipeline {
     pub fn new(
-        device: &Device,
+        device_context: &DeviceContext,
         bloom_settings: BloomSettings,
         width: u32,
         height: u32,
     ) -> BloomPipeline {
+        let device = device_context.device();
         let vertices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Bloom Vertex Buffer"),
             contents: bytemuck::cast_slice(FULL_SCREEN_TRIANGLE_VERTICES),
             usage: wgpu::BufferUsages::VERTEX,
         });
 
-        let mip_count = MAX_MIP_DIMENSION.ilog2().max(2) - 1;
-        let mip_height_ratio = MAX_MIP_DIMENSION as f32 / height as f32;
-
-        let bloom_texture = create_bloom_texture(
-            device,
-            ((width as f32 * mip_height_ratio).round() as u32).max(1),
-            ((height as f32 * mip_height_ratio).round() as u32).max(1),
-            mip_count,
-        );
+        let backend = if bloom_settings.backend == BloomBackend::Compute
+            && !DrawParams::uses_push_constants(device)
+        {
+            warn!("Compute bloom requires push constants, falling back to render passes");
+            BloomBackend::RenderPass
+        } else {
+            bloom_settings.backend
+        };
+        let texture_format = match backend {
+            BloomBackend::RenderPass => {
+                supported_bloom_format(device, bloom_settings.texture_format)
+            }
+            BloomBackend::Compute => COMPUTE_TEXTURE_FORMAT,
+        };
+        let (size, mip_count) = bloom_texture_size(device, &bloom_settings, width, height);
+        let bloom_texture =
+            create_bloom_texture(device, texture_format, backend, size[0], size[1], mip_count);
 
         // Bind group layout
         let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
@@ -101,15 +203,23 @@ impl BloomPipeline {
 
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Bloom Shader"),
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("bloom.wgsl"))),
+            source: wgpu::ShaderSource::Wgsl(DrawParams::shader_source(
+                device,
+                include_str!("bloom.wgsl"),
+                PARAMS_GROUP,
+            )),
         });
+        let params_layout = DrawParams::bind_group_layout(device, ShaderStages::FRAGMENT);
+        let mut bind_group_layouts = vec![&bind_group_layout];
+        bind_group_layouts.extend(params_layout.as_ref());
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Bloom Pipeline Layout"),
-            bind_group_layouts: &[&bind_group_layout],
-            push_constant_ranges: &[PushConstantRange {
-                stages: ShaderStages::FRAGMENT,
-                range: 0..std::mem::size_of::<BloomPushConstants>() as u32,
-            }],
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges: &DrawParams::push_constant_ranges(
+                device,
+                ShaderStages::FRAGMENT,
+                std::mem::size_of::<BloomPushConstants>(),
+            ),
         });
         let downsample_first_pipeline =
             device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
@@ -126,7 +236,7 @@ impl BloomPipeline {
                     entry_point: Some("downsample_first"),
                     compilation_options: Default::default(),
                     targets: &[Some(ColorTargetState {
-                        format: BLOOM_TEXTURE_FORMAT,
+                        format: texture_format,
                         blend: None,
                         write_mask: ColorWrites::ALL,
                     })],
@@ -135,7 +245,7 @@ impl BloomPipeline {
                 depth_stencil: None,
                 multisample: wgpu::MultisampleState::default(),
                 multiview: None,
-                cache: None,
+                cache: device_context.pipeline_cache(),
             });
         let downsample_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Bloom Downsample Pipeline"),
@@ -151,7 +261,7 @@ impl BloomPipeline {
                 entry_point: Some("downsample"),
                 compilation_options: Default::default(),
                 targets: &[Some(ColorTargetState {
-                    format: BLOOM_TEXTURE_FORMAT,
+                    format: texture_format,
                     blend: None,
                     write_mask: ColorWrites::ALL,
                 })],
@@ -160,7 +270,7 @@ impl BloomPipeline {
             depth_stencil: None,
             multisample: wgpu::MultisampleState::default(),
             multiview: None,
-            cache: None,
+            cache: device_context.pipeline_cache(),
         });
 
         let color_blend = match bloom_settings.composite_mode {
@@ -190,7 +300,7 @@ impl BloomPipeline {
                 entry_point: Some("upsample"),
                 compilation_options: Default::default(),
                 targets: &[Some(ColorTargetState {
-                    format: BLOOM_TEXTURE_FORMAT,
+                    format: texture_format,
                     blend: Some(BlendState {
                         color: color_blend,
                         alpha: BlendComponent {
@@ -206,7 +316,7 @@ impl BloomPipeline {
             depth_stencil: None,
             multisample: wgpu::MultisampleState::default(),
             multiview: None,
-            cache: None,
+            cache: device_context.pipeline_cache(),
         });
 
         let final_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
@@ -223,7 +333,7 @@ impl BloomPipeline {
                 entry_point: Some("upsample"),
                 compilation_options: Default::default(),
                 targets: &[Some(ColorTargetState {
-                    format: FINAL_TEXTURE_FORMAT,
+                    format: bloom_settings.target_format,
                     blend: Some(BlendState {
                         color: color_blend,
                         alpha: BlendComponent {
@@ -239,7 +349,7 @@ impl BloomPipeline {
             depth_stencil: None,
             multisample: wgpu::MultisampleState::default(),
             multiview: None,
-            cache: None,
+            cache: device_context.pipeline_cache(),
         });
         let bloom_sampler = device.create_sampler(&SamplerDescriptor {
             min_filter: FilterMode::Linear,
@@ -257,6 +367,16 @@ impl BloomPipeline {
             &bloom_sampler,
             mip_count,
         );
+        let compute = (backend == BloomBackend::Compute).then(|| {
+            BloomCompute::new(
+                device,
+                device_context.pipeline_cache(),
+                &final_pipeline.get_bind_group_layout(0),
+                &bloom_texture,
+                &bloom_sampler,
+                mip_count,
+            )
+        });
 
         BloomPipeline {
             downsample_first_pipeline,
@@ -267,11 +387,21 @@ impl BloomPipeline {
             bloom_sampler,
             downsampling_bind_groups,
             upsampling_bind_groups,
+            compute,
+            params: DrawParams::new(
+                device_context,
+                ShaderStages::FRAGMENT,
+                std::mem::size_of::<BloomPushConstants>(),
+            ),
             vertices,
+            texture_format,
+            backend,
             mip_count,
             width,
             height,
             settings: bloom_settings,
+            profiler: None,
+            scope_labels: ScopeLabels::new("glass::bloom", mip_count),
         }
     }
 
@@ -323,19 +453,87 @@ impl BloomPipeline {
         (downsampling_bind_groups, upsampling_bind_groups)
     }
 
-    pub fn configure(&mut self, device: &Device, settings: BloomSettings, width: u32, height: u32) {
+    pub fn configure(
+        &mut self,
+        device_context: &DeviceContext,
+        settings: BloomSettings,
+        width: u32,
+        height: u32,
+    ) {
         // Changes to these requires recreation of the pipeline
         let recreate_pipeline = settings.composite_mode != self.settings.composite_mode
+            || settings.backend != self.settings.backend
+            || settings.texture_format != self.settings.texture_format
+            || settings.target_format != self.settings.target_format;
+        // Changes to these only require a new mip chain, compiled pipelines are kept
+        let recreate_textures = settings.max_mip_dimension != self.settings.max_mip_dimension
+            || settings.mip_count != self.settings.mip_count
             || width != self.width
             || height != self.height;
         if recreate_pipeline {
-            // Limit dimensions to prevent texture max width error...
-            *self = BloomPipeline::new(device, settings, width.max(256), height.max(256));
+            let profiler = self.profiler.take();
+            *self = BloomPipeline::new(device_context, settings, width, height);
+            self.profiler = profiler;
         } else {
             self.settings = settings;
+            if recreate_textures {
+                self.recreate_textures(device_context.device(), width, height);
+            }
         }
     }
 
+    fn recreate_textures(&mut self, device: &Device, width: u32, height: u32) {
+        let (size, mip_count) = bloom_texture_size(device, &self.settings, width, height);
+        self.bloom_texture = create_bloom_texture(
+            device,
+            self.texture_format,
+            self.backend,
+            size[0],
+            size[1],
+            mip_count,
+        );
+        (self.downsampling_bind_groups, self.upsampling_bind_groups) = Self::create_bind_groups(
+            device,
+            &self.downsample_pipeline,
+            &self.upsample_pipeline,
+            &self.bloom_texture,
+            &self.bloom_sampler,
+            mip_count,
+        );
+        if let Some(compute) = &mut self.compute {
+            compute.recreate_textures(
+                device,
+                &self.final_pipeline.get_bind_group_layout(0),
+                &self.bloom_texture,
+                &self.bloom_sampler,
+                mip_count,
+            );
+        }
+        self.mip_count = mip_count;
+        self.scope_labels = ScopeLabels::new("glass::bloom", mip_count);
+        self.width = width;
+        self.height = height;
+    }
+
+    pub fn settings(&self) -> BloomSettings {
+        self.settings
+    }
+
+    /// Format of the internal mip chain, differs from the one in settings if that wasn't
+    /// renderable on the device
+    pub fn texture_format(&self) -> TextureFormat {
+        self.texture_format
+    }
+
+    pub fn mip_count(&self) -> u32 {
+        self.mip_count
+    }
+
+    /// Time the pipeline's passes, see [`GpuProfiler::pass_timestamp_writes`]
+    pub fn set_profiler(&mut self, profiler: Option<Arc<GpuProfiler>>) {
+        self.profiler = profiler;
+    }
+
     pub fn bloom(
         &self,
         device: &Device,
@@ -350,6 +548,76 @@ impl BloomPipeline {
                 size[0] as u32,
                 size[1] as u32,
             ]);
+        // Every pass reads the same parameters, stage them once ahead of the passes
+        let params = &self.params.stage(&push_constants);
+        self.params.upload_with_encoder(device, encoder);
+        let final_bind_group = if let Some(compute) = &self.compute {
+            compute.bloom(
+                device,
+                encoder,
+                &self.bloom_texture,
+                &bloom_target.views[0],
+                &self.bloom_sampler,
+                &self.settings,
+                push_constants,
+                |mip| compute_blend_factor(&self.settings, mip as f32, (self.mip_count - 1) as f32),
+                self.profiler.as_ref(),
+            );
+            compute.final_bind_group()
+        } else {
+            self.render_mips(device, encoder, bloom_target, params);
+            &self.upsampling_bind_groups[(self.mip_count - 1) as usize]
+        };
+
+        // Final upsample pass
+        {
+            let mut upsampling_final_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("bloom_upsampling_final_pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &bloom_target.views[0],
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: self.timestamp_writes("glass::bloom_upsample_final"),
+                occlusion_query_set: None,
+            });
+            upsampling_final_pass.set_pipeline(&self.final_pipeline);
+            upsampling_final_pass.set_bind_group(0, final_bind_group, &[]);
+            upsampling_final_pass.set_vertex_buffer(0, self.vertices.slice(..));
+            upsampling_final_pass.set_viewport(
+                viewport_origin[0] as f32,
+                viewport_origin[1] as f32,
+                viewport_size[0] as f32,
+                viewport_size[1] as f32,
+                0.0,
+                1.0,
+            );
+            let blend = compute_blend_factor(&self.settings, 0.0, (self.mip_count - 1) as f32);
+            upsampling_final_pass.set_blend_constant(Color {
+                r: blend as f64,
+                g: blend as f64,
+                b: blend as f64,
+                a: 1.0,
+            });
+            self.params
+                .bind(&mut upsampling_final_pass, PARAMS_GROUP, params);
+            upsampling_final_pass.draw(0..3, 0..1);
+        }
+    }
+
+    /// Downsample & upsample with render passes, leaving the result in mip 0 of the bloom
+    /// texture
+    fn render_mips(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        bloom_target: &Texture,
+        params: &DrawParamsSlot,
+    ) {
         // First downsample pass (main image)
         let downsampling_first_bind_group = device.create_bind_group(&BindGroupDescriptor {
             label: Some("bloom_downsampling_first_bind_group"),
@@ -377,39 +645,26 @@ impl BloomPipeline {
                     ops: Operations::default(),
                 })],
                 depth_stencil_attachment: None,
-                timestamp_writes: None,
+                timestamp_writes: self.timestamp_writes(&self.scope_labels.downsample[0]),
                 occlusion_query_set: None,
             });
             first_downsample_pass.set_pipeline(&self.downsample_first_pipeline);
             first_downsample_pass.set_bind_group(0, &downsampling_first_bind_group, &[]);
             first_downsample_pass.set_vertex_buffer(0, self.vertices.slice(..));
-            first_downsample_pass.set_push_constants(
-                ShaderStages::FRAGMENT,
-                0,
-                bytemuck::cast_slice(&[push_constants]),
-            );
+            self.params
+                .bind(&mut first_downsample_pass, PARAMS_GROUP, params);
             first_downsample_pass.draw(0..3, 0..1);
         }
 
         // Other Downsamples
         for mip in 1..self.mip_count as usize {
-            // Write to next bloom texture, 1, 2, 3, 4...
-            let view = &self.bloom_texture.views[mip];
-            let mut downsampling_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("bloom_downsampling_pass"),
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view,
-                    resolve_target: None,
-                    ops: Operations {
-                        load: LoadOp::Load,
-                        store: StoreOp::Store,
-                    },
-                    // ops: Operations::default(),
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
+            // Write to next bloom texture, 1, 2, 3, 4... Each mip is fully overwritten, so there's
+            // nothing to load
+            let mut downsampling_pass = PassDesc::new("bloom_downsampling_pass")
+                .color_mip(&self.bloom_texture, mip)
+                .clear(Color::TRANSPARENT)
+                .timestamp_writes(self.timestamp_writes(&self.scope_labels.downsample[mip]))
+                .begin(encoder);
             downsampling_pass.set_pipeline(&self.downsample_pipeline);
             downsampling_pass.set_bind_group(
                 0,
@@ -418,11 +673,8 @@ impl BloomPipeline {
                 &[],
             );
             downsampling_pass.set_vertex_buffer(0, self.vertices.slice(..));
-            downsampling_pass.set_push_constants(
-                ShaderStages::FRAGMENT,
-                0,
-                bytemuck::cast_slice(&[push_constants]),
-            );
+            self.params
+                .bind(&mut downsampling_pass, PARAMS_GROUP, params);
             downsampling_pass.draw(0..3, 0..1);
         }
 
@@ -441,7 +693,7 @@ impl BloomPipeline {
                     },
                 })],
                 depth_stencil_attachment: None,
-                timestamp_writes: None,
+                timestamp_writes: self.timestamp_writes(&self.scope_labels.upsample[mip]),
                 occlusion_query_set: None,
             });
             upsampling_pass.set_pipeline(&self.upsample_pipeline);
@@ -460,59 +712,15 @@ impl BloomPipeline {
                 b: blend as f64,
                 a: 1.0,
             });
-            upsampling_pass.set_push_constants(
-                ShaderStages::FRAGMENT,
-                0,
-                bytemuck::cast_slice(&[push_constants]),
-            );
+            self.params.bind(&mut upsampling_pass, PARAMS_GROUP, params);
             upsampling_pass.draw(0..3, 0..1);
         }
+    }
 
-        // Final upsample pass
-        {
-            let mut upsampling_final_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("bloom_upsampling_final_pass"),
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &bloom_target.views[0],
-                    resolve_target: None,
-                    ops: Operations {
-                        load: LoadOp::Load,
-                        store: StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-            upsampling_final_pass.set_pipeline(&self.final_pipeline);
-            upsampling_final_pass.set_bind_group(
-                0,
-                &self.upsampling_bind_groups[(self.mip_count - 1) as usize],
-                &[],
-            );
-            upsampling_final_pass.set_vertex_buffer(0, self.vertices.slice(..));
-            upsampling_final_pass.set_viewport(
-                viewport_origin[0] as f32,
-                viewport_origin[1] as f32,
-                viewport_size[0] as f32,
-                viewport_size[1] as f32,
-                0.0,
-                1.0,
-            );
-            let blend = compute_blend_factor(&self.settings, 0.0, (self.mip_count - 1) as f32);
-            upsampling_final_pass.set_blend_constant(Color {
-                r: blend as f64,
-                g: blend as f64,
-                b: blend as f64,
-                a: 1.0,
-            });
-            upsampling_final_pass.set_push_constants(
-                ShaderStages::FRAGMENT,
-                0,
-                bytemuck::cast_slice(&[push_constants]),
-            );
-            upsampling_final_pass.draw(0..3, 0..1);
-        }
+    fn timestamp_writes(&self, label: &str) -> Option<RenderPassTimestampWrites<'_>> {
+        self.profiler
+            .as_ref()
+            .and_then(|profiler| profiler.pass_timestamp_writes(label))
     }
 }
 
@@ -554,6 +762,15 @@ impl BloomPushConstants {
     }
 }
 
+impl Resizable for BloomPipeline {
+    /// Only the mip chain depends on the size, the pipelines are kept
+    fn resize(&mut self, device: &Device, size: [u32; 2]) {
+        if size != [self.width, self.height] {
+            self.recreate_textures(device, size[0], size[1]);
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct BloomSettings {
     pub intensity: f32,
@@ -562,6 +779,28 @@ pub struct BloomSettings {
     pub high_pass_frequency: f32,
     pub prefilter_settings: BloomPrefilterSettings,
     pub composite_mode: BloomCompositeMode,
+    /// Height of the first mip of the bloom chain, larger values give finer bloom
+    pub max_mip_dimension: u32,
+    /// Number of mips in the chain, `None` derives it from `max_mip_dimension`. Clamped so the
+    /// smallest mip is at least 2 pixels.
+    pub mip_count: Option<u32>,
+    /// Format of the mip chain, falls back to Rgba16Float if not renderable on the device.
+    /// Ignored by [`BloomBackend::Compute`].
+    pub texture_format: TextureFormat,
+    /// Format of the bloom target passed to [`BloomPipeline::bloom`]
+    pub target_format: TextureFormat,
+    pub backend: BloomBackend,
+}
+
+/// How [`BloomPipeline`] builds its mip chain
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum BloomBackend {
+    /// A render pass per mip, blending upsampled mips with blend state
+    #[default]
+    RenderPass,
+    /// A compute pass per mip writing storage textures. Always uses an Rgba16Float chain, which
+    /// avoids many small render passes and doesn't need a renderable `texture_format`.
+    Compute,
 }
 
 impl BloomSettings {
@@ -576,6 +815,11 @@ impl BloomSettings {
             threshold_softness: 0.0,
         },
         composite_mode: BloomCompositeMode::EnergyConserving,
+        max_mip_dimension: MAX_MIP_DIMENSION,
+        mip_count: None,
+        texture_format: BLOOM_TEXTURE_FORMAT,
+        target_format: FINAL_TEXTURE_FORMAT,
+        backend: BloomBackend::RenderPass,
     };
     /// A preset that's similiar to how older games did bloom.
     pub const OLD_SCHOOL: Self = Self {
@@ -588,6 +832,11 @@ impl BloomSettings {
             threshold_softness: 0.2,
         },
         composite_mode: BloomCompositeMode::Additive,
+        max_mip_dimension: MAX_MIP_DIMENSION,
+        mip_count: None,
+        texture_format: BLOOM_TEXTURE_FORMAT,
+        target_format: FINAL_TEXTURE_FORMAT,
+        backend: BloomBackend::RenderPass,
     };
     /// A preset that applies a very strong bloom, and blurs the whole screen.
     pub const SCREEN_BLUR: Self = Self {
@@ -600,6 +849,11 @@ impl BloomSettings {
             threshold_softness: 0.0,
         },
         composite_mode: BloomCompositeMode::EnergyConserving,
+        max_mip_dimension: MAX_MIP_DIMENSION,
+        mip_count: None,
+        texture_format: BLOOM_TEXTURE_FORMAT,
+        target_format: FINAL_TEXTURE_FORMAT,
+        backend: BloomBackend::RenderPass,
     };
 }
 
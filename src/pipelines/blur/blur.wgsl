@@ -0,0 +1,58 @@
+struct VertexOutput {
+    @builtin(position)
+    position: vec4<f32>,
+    @location(0)
+    uv: vec2<f32>,
+};
+
+// https://github.com/bevyengine/bevy/blob/09df19bcadb52d2f4dbbc899aef74cafa9091538/crates/bevy_core_pipeline/src/fullscreen_vertex_shader/fullscreen.wgsl
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32(vertex_index >> 1u), f32(vertex_index & 1u)) * 2.0;
+    let clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    return VertexOutput(clip_position, uv);
+}
+
+@group(0) @binding(0)
+var source_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+struct PushConstants {
+    radius: f32,
+}
+var<push_constant> pc: PushConstants;
+
+fn sample(uv: vec2<f32>) -> vec4<f32> {
+    return textureSample(source_texture, source_sampler, uv);
+}
+
+fn half_pixel() -> vec2<f32> {
+    return 0.5 / vec2<f32>(textureDimensions(source_texture)) * pc.radius;
+}
+
+// Dual Kawase filter, https://community.arm.com/cfs-file/__key/communityserver-blogs-components-weblogfiles/00-00-00-20-66/siggraph2015_2D00_mmg_2D00_marius_2D00_notes.pdf
+@fragment
+fn downsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    let hp = half_pixel();
+    var sum = sample(in.uv) * 4.0;
+    sum += sample(in.uv - hp);
+    sum += sample(in.uv + hp);
+    sum += sample(in.uv + vec2<f32>(hp.x, -hp.y));
+    sum += sample(in.uv - vec2<f32>(hp.x, -hp.y));
+    return sum / 8.0;
+}
+
+@fragment
+fn upsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    let hp = half_pixel();
+    var sum = sample(in.uv + vec2<f32>(-hp.x * 2.0, 0.0));
+    sum += sample(in.uv + vec2<f32>(-hp.x, hp.y)) * 2.0;
+    sum += sample(in.uv + vec2<f32>(0.0, hp.y * 2.0));
+    sum += sample(in.uv + vec2<f32>(hp.x, hp.y)) * 2.0;
+    sum += sample(in.uv + vec2<f32>(hp.x * 2.0, 0.0));
+    sum += sample(in.uv + vec2<f32>(hp.x, -hp.y)) * 2.0;
+    sum += sample(in.uv + vec2<f32>(0.0, -hp.y * 2.0));
+    sum += sample(in.uv + vec2<f32>(-hp.x, -hp.y)) * 2.0;
+    return sum / 12.0;
+}
@@ -0,0 +1,366 @@
+use std::{borrow::Cow, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    AddressMode, BindGroup, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry,
+    BindingType, ColorTargetState, ColorWrites, CommandEncoder, Device, Extent3d, FilterMode,
+    Operations, PipelineCache, PushConstantRange, RenderPassColorAttachment, RenderPassDescriptor,
+    RenderPipeline, Sampler, SamplerBindingType, SamplerDescriptor, ShaderModule, ShaderStages,
+    TextureFormat, TextureSampleType, TextureUsages, TextureView, TextureViewDimension,
+};
+
+use crate::{
+    device_context::DeviceContext,
+    pipelines::{bloom::ScopeLabels, require_push_constants},
+    resize::Resizable,
+    texture::Texture,
+    utils::GpuProfiler,
+    GlassError,
+};
+
+const BLUR_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BlurSettings {
+    /// Number of halvings of resolution, each roughly doubles the blur size
+    pub iterations: u32,
+    /// Sample offset in texels, larger values blur more with fewer iterations at the cost of
+    /// artifacts
+    pub radius: f32,
+}
+
+impl Default for BlurSettings {
+    fn default() -> Self {
+        Self {
+            iterations: 4,
+            radius: 1.0,
+        }
+    }
+}
+
+/// A standalone dual Kawase blur. The input is downsampled through a chain of mips like in
+/// [`BloomPipeline`](super::BloomPipeline) and upsampled back into the output.
+pub struct BlurPipeline {
+    downsample_pipeline: RenderPipeline,
+    upsample_pipeline: RenderPipeline,
+    output_pipeline: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+    sampler: Sampler,
+    blur_texture: Texture,
+    mip_bind_groups: Vec<BindGroup>,
+    output_format: TextureFormat,
+    settings: BlurSettings,
+    width: u32,
+    height: u32,
+    profiler: Option<Arc<GpuProfiler>>,
+    scope_labels: ScopeLabels,
+}
+
+impl BlurPipeline {
+    /// `width` and `height` are the size of the input and output
+    pub fn new(
+        device_context: &DeviceContext,
+        output_format: TextureFormat,
+        settings: BlurSettings,
+        width: u32,
+        height: u32,
+    ) -> Result<BlurPipeline, GlassError> {
+        let device = device_context.device();
+        require_push_constants(
+            device,
+            "BlurPipeline",
+            std::mem::size_of::<BlurPushConstants>(),
+        )?;
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("blur_bind_group_layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float {
+                            filterable: true,
+                        },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    visibility: ShaderStages::FRAGMENT,
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    visibility: ShaderStages::FRAGMENT,
+                    count: None,
+                },
+            ],
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Blur Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("blur.wgsl"))),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Blur Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[PushConstantRange {
+                stages: ShaderStages::FRAGMENT,
+                range: 0..std::mem::size_of::<BlurPushConstants>() as u32,
+            }],
+        });
+        let create_pipeline = |entry_point: &str, format: TextureFormat| {
+            Self::create_pipeline(
+                device,
+                device_context.pipeline_cache(),
+                &layout,
+                &shader,
+                entry_point,
+                format,
+            )
+        };
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("blur_sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+        let (blur_texture, mip_bind_groups) = Self::create_blur_texture(
+            device,
+            &bind_group_layout,
+            &sampler,
+            settings,
+            width,
+            height,
+        );
+        let scope_labels = ScopeLabels::new("glass::blur", blur_texture.views.len() as u32);
+
+        Ok(BlurPipeline {
+            downsample_pipeline: create_pipeline("downsample", BLUR_TEXTURE_FORMAT),
+            upsample_pipeline: create_pipeline("upsample", BLUR_TEXTURE_FORMAT),
+            output_pipeline: create_pipeline("upsample", output_format),
+            bind_group_layout,
+            sampler,
+            blur_texture,
+            mip_bind_groups,
+            output_format,
+            settings,
+            width,
+            height,
+            profiler: None,
+            scope_labels,
+        })
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        cache: Option<&PipelineCache>,
+        layout: &wgpu::PipelineLayout,
+        shader: &ShaderModule,
+        entry_point: &str,
+        format: TextureFormat,
+    ) -> RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Blur Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache,
+        })
+    }
+
+    /// Half resolution texture with a mip per iteration, and bind groups reading each mip
+    fn create_blur_texture(
+        device: &Device,
+        bind_group_layout: &BindGroupLayout,
+        sampler: &Sampler,
+        settings: BlurSettings,
+        width: u32,
+        height: u32,
+    ) -> (Texture, Vec<BindGroup>) {
+        let width = (width / 2).max(1);
+        let height = (height / 2).max(1);
+        let max_mips = 32 - width.max(height).leading_zeros();
+        let blur_texture = Texture::empty(
+            device,
+            "blur_texture",
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            settings.iterations.clamp(1, max_mips),
+            BLUR_TEXTURE_FORMAT,
+            TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+        );
+        let bind_groups = blur_texture
+            .views
+            .iter()
+            .map(|view| Self::bind_group(device, bind_group_layout, view, sampler))
+            .collect();
+        (blur_texture, bind_groups)
+    }
+
+    fn bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        view: &TextureView,
+        sampler: &Sampler,
+    ) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+            label: Some("blur_bind_group"),
+        })
+    }
+
+    pub fn configure(&mut self, device: &Device, settings: BlurSettings, width: u32, height: u32) {
+        let recreate_texture = settings.iterations != self.settings.iterations
+            || width != self.width
+            || height != self.height;
+        self.settings = settings;
+        if recreate_texture {
+            (self.blur_texture, self.mip_bind_groups) = Self::create_blur_texture(
+                device,
+                &self.bind_group_layout,
+                &self.sampler,
+                settings,
+                width,
+                height,
+            );
+            self.scope_labels =
+                ScopeLabels::new("glass::blur", self.blur_texture.views.len() as u32);
+            self.width = width;
+            self.height = height;
+        }
+    }
+
+    pub fn settings(&self) -> BlurSettings {
+        self.settings
+    }
+
+    pub fn output_format(&self) -> TextureFormat {
+        self.output_format
+    }
+
+    /// Time the pipeline's passes, see [`GpuProfiler::pass_timestamp_writes`]
+    pub fn set_profiler(&mut self, profiler: Option<Arc<GpuProfiler>>) {
+        self.profiler = profiler;
+    }
+
+    /// Write a blurred input into output. Input must be filterable and output have the format
+    /// the pipeline was created with.
+    pub fn blur(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        input: &TextureView,
+        output: &TextureView,
+    ) {
+        let input_bind_group =
+            Self::bind_group(device, &self.bind_group_layout, input, &self.sampler);
+        let mip_count = self.blur_texture.views.len();
+        for mip in 0..mip_count {
+            let source = if mip == 0 {
+                &input_bind_group
+            } else {
+                &self.mip_bind_groups[mip - 1]
+            };
+            self.pass(
+                encoder,
+                &self.downsample_pipeline,
+                source,
+                &self.blur_texture.views[mip],
+                &self.scope_labels.downsample[mip],
+            );
+        }
+        for mip in (0..mip_count - 1).rev() {
+            self.pass(
+                encoder,
+                &self.upsample_pipeline,
+                &self.mip_bind_groups[mip + 1],
+                &self.blur_texture.views[mip],
+                &self.scope_labels.upsample[mip],
+            );
+        }
+        self.pass(
+            encoder,
+            &self.output_pipeline,
+            &self.mip_bind_groups[0],
+            output,
+            "glass::blur_output",
+        );
+    }
+
+    fn pass(
+        &self,
+        encoder: &mut CommandEncoder,
+        pipeline: &RenderPipeline,
+        source: &BindGroup,
+        target: &TextureView,
+        label: &str,
+    ) {
+        let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: self
+                .profiler
+                .as_ref()
+                .and_then(|profiler| profiler.pass_timestamp_writes(label)),
+            occlusion_query_set: None,
+        });
+        r_pass.set_pipeline(pipeline);
+        r_pass.set_bind_group(0, source, &[]);
+        r_pass.set_push_constants(
+            ShaderStages::FRAGMENT,
+            0,
+            bytemuck::cast_slice(&[BlurPushConstants {
+                radius: self.settings.radius,
+            }]),
+        );
+        r_pass.draw(0..3, 0..1);
+    }
+}
+
+impl Resizable for BlurPipeline {
+    fn resize(&mut self, device: &Device, size: [u32; 2]) {
+        self.configure(device, self.settings, size[0], size[1]);
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct BlurPushConstants {
+    pub radius: f32,
+}
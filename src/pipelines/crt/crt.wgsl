@@ -0,0 +1,57 @@
+struct VertexOutput {
+    @builtin(position)
+    position: vec4<f32>,
+    @location(0)
+    uv: vec2<f32>,
+};
+
+// https://github.com/bevyengine/bevy/blob/09df19bcadb52d2f4dbbc899aef74cafa9091538/crates/bevy_core_pipeline/src/fullscreen_vertex_shader/fullscreen.wgsl
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32(vertex_index >> 1u), f32(vertex_index & 1u)) * 2.0;
+    let clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    return VertexOutput(clip_position, uv);
+}
+
+@group(0) @binding(0)
+var source_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+struct PushConstants {
+    // x: curvature, y: scanlines, z: mask, w: vignette
+    settings: vec4<f32>,
+}
+var<push_constant> pc: PushConstants;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Barrel distortion
+    var centered = in.uv * 2.0 - 1.0;
+    centered *= 1.0 + pc.settings.x * centered.yx * centered.yx;
+    let uv = centered * 0.5 + 0.5;
+    let outside = any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0));
+
+    // Pixel art anti aliasing: sharp texels with a linearly filtered one pixel wide seam
+    let size = vec2<f32>(textureDimensions(source_texture));
+    let texel = uv * size;
+    let seam = floor(texel + 0.5);
+    let texel_per_pixel = max(fwidth(texel), vec2<f32>(1e-5));
+    let aa_texel = seam + clamp((texel - seam) / texel_per_pixel, vec2<f32>(-0.5), vec2<f32>(0.5));
+    var color = textureSample(source_texture, source_sampler, aa_texel / size);
+
+    // Darken between source rows
+    let scanline = sin(fract(texel.y) * 3.14159265);
+    color = vec4<f32>(color.rgb * mix(1.0, scanline, pc.settings.y), color.a);
+
+    // Aperture grille, one color channel per output pixel column
+    let column = u32(in.position.x) % 3u;
+    var mask = vec3<f32>(1.0 - pc.settings.z);
+    mask[column] = 1.0;
+    color = vec4<f32>(color.rgb * mask, color.a);
+
+    let edge = uv * (1.0 - uv.yx);
+    let vignette = pow(saturate(edge.x * edge.y * 16.0), 0.25);
+    color = vec4<f32>(color.rgb * mix(1.0, vignette, pc.settings.w), color.a);
+    return select(color, vec4<f32>(0.0, 0.0, 0.0, 1.0), outside);
+}
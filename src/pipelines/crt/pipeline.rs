@@ -0,0 +1,230 @@
+use std::{borrow::Cow, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroup, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, Color,
+    ColorTargetState, ColorWrites, CommandEncoder, Device, LoadOp, Operations, PushConstantRange,
+    RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, Sampler, SamplerBindingType,
+    ShaderStages, StoreOp, TextureFormat, TextureSampleType, TextureView, TextureViewDimension,
+};
+
+use crate::{
+    device_context::DeviceContext, pipelines::require_push_constants, utils::GpuProfiler,
+    window::Viewport, GlassError,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CrtSettings {
+    /// Barrel distortion, 0.0 is flat
+    pub curvature: f32,
+    /// Darkening between source pixel rows, 0.0 to 1.0
+    pub scanlines: f32,
+    /// Strength of an RGB aperture grille, 0.0 to 1.0
+    pub mask: f32,
+    /// Darkening towards the edges, 0.0 to 1.0
+    pub vignette: f32,
+}
+
+impl CrtSettings {
+    /// No CRT effects, only anti aliased upscaling
+    pub fn pixel_art() -> CrtSettings {
+        CrtSettings {
+            curvature: 0.0,
+            scanlines: 0.0,
+            mask: 0.0,
+            vignette: 0.0,
+        }
+    }
+}
+
+impl Default for CrtSettings {
+    fn default() -> Self {
+        Self {
+            curvature: 0.03,
+            scanlines: 0.5,
+            mask: 0.2,
+            vignette: 0.3,
+        }
+    }
+}
+
+/// Upscales a low resolution render target to the window with crisp but anti aliased pixels at
+/// any scale, optionally emulating a CRT. Bind the source with a linear sampler, the seams
+/// between source pixels are filtered over one output pixel.
+pub struct CrtPipeline {
+    crt_pipeline: RenderPipeline,
+    settings: CrtSettings,
+    profiler: Option<Arc<GpuProfiler>>,
+}
+
+impl CrtPipeline {
+    pub fn new(
+        device_context: &DeviceContext,
+        target_texture_format: TextureFormat,
+        settings: CrtSettings,
+    ) -> Result<CrtPipeline, GlassError> {
+        let device = device_context.device();
+        require_push_constants(
+            device,
+            "CrtPipeline",
+            std::mem::size_of::<CrtPushConstants>(),
+        )?;
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("crt_bind_group_layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float {
+                            filterable: true,
+                        },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    visibility: ShaderStages::FRAGMENT,
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    visibility: ShaderStages::FRAGMENT,
+                    count: None,
+                },
+            ],
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Crt Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("crt.wgsl"))),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Crt Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[PushConstantRange {
+                stages: ShaderStages::FRAGMENT,
+                range: 0..std::mem::size_of::<CrtPushConstants>() as u32,
+            }],
+        });
+        let crt_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Crt Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fragment"),
+                compilation_options: Default::default(),
+                targets: &[Some(ColorTargetState {
+                    format: target_texture_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: device_context.pipeline_cache(),
+        });
+
+        Ok(CrtPipeline {
+            crt_pipeline,
+            settings,
+            profiler: None,
+        })
+    }
+
+    pub fn create_bind_group(
+        &self,
+        device: &Device,
+        image: &TextureView,
+        sampler: &Sampler,
+    ) -> BindGroup {
+        let bind_group_layout = self.crt_pipeline.get_bind_group_layout(0);
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(image),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+            label: Some("crt_bind_group"),
+        })
+    }
+
+    pub fn settings(&self) -> CrtSettings {
+        self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: CrtSettings) {
+        self.settings = settings;
+    }
+
+    /// Time the pipeline's passes, see [`GpuProfiler::pass_timestamp_writes`]
+    pub fn set_profiler(&mut self, profiler: Option<Arc<GpuProfiler>>) {
+        self.profiler = profiler;
+    }
+
+    /// Draw the source into `viewport` of output, e.g. from
+    /// [`GlassWindow::viewport`](crate::window::GlassWindow::viewport), or the whole output if
+    /// `None`. The rest of the output is cleared to black.
+    pub fn upscale(
+        &self,
+        encoder: &mut CommandEncoder,
+        source_bind_group: &BindGroup,
+        output: &TextureView,
+        viewport: Option<Viewport>,
+    ) {
+        let settings = self.settings;
+        let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("crt_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::BLACK),
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: self
+                .profiler
+                .as_ref()
+                .and_then(|profiler| profiler.pass_timestamp_writes("glass::crt")),
+            occlusion_query_set: None,
+        });
+        if let Some(viewport) = viewport {
+            viewport.apply(&mut r_pass);
+        }
+        r_pass.set_pipeline(&self.crt_pipeline);
+        r_pass.set_bind_group(0, source_bind_group, &[]);
+        r_pass.set_push_constants(
+            ShaderStages::FRAGMENT,
+            0,
+            bytemuck::cast_slice(&[CrtPushConstants {
+                settings: [
+                    settings.curvature,
+                    settings.scanlines,
+                    settings.mask,
+                    settings.vignette,
+                ],
+            }]),
+        );
+        r_pass.draw(0..3, 0..1);
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct CrtPushConstants {
+    /// Curvature, scanlines, mask & vignette
+    pub settings: [f32; 4],
+}
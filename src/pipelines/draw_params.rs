@@ -0,0 +1,404 @@
+use std::{
+    borrow::Cow,
+    num::NonZeroU64,
+    ops::Range,
+    sync::{Arc, Mutex, OnceLock, Weak},
+};
+
+use bytemuck::Pod;
+use log::warn;
+use wgpu::{
+    util::DeviceExt, BindGroup, BindGroupLayout, Buffer, CommandEncoder, Device, Features,
+    PushConstantRange, Queue, RenderBundleEncoder, RenderPass, ShaderStages,
+};
+
+use crate::device_context::DeviceContext;
+
+/// Draws a page of the uniform fallback holds. Pages are added as draws are recorded.
+const DRAW_PARAMS_PAGE_CAPACITY: u64 = 4096;
+/// Pages a uniform ring grows to before its parameters wrap around and overwrite earlier draws
+const MAX_DRAW_PARAMS_PAGES: usize = 256;
+
+const PUSH_CONSTANT_DECLARATION: &str = "var<push_constant> pc";
+
+/// Per draw parameters of bundled pipelines. Uses push constants when the device has
+/// `Features::PUSH_CONSTANTS` and a dynamic uniform buffer ring otherwise, e.g. on WebGPU and
+/// WebGL. In the uniform fallback parameters are staged on the CPU and uploaded before the draws
+/// are submitted, by the runner with [`DeviceContext::upload_draw_params`] or by the owner with
+/// [`DrawParams::upload`] or [`DrawParams::upload_with_encoder`]. The ring grows by a page
+/// whenever more draws are recorded between uploads than it holds.
+///
+/// Shaders declare `var<push_constant> pc`, which [`DrawParams::shader_source`] turns into a
+/// uniform binding in the fallback.
+///
+/// Persistent params, see [`DrawParams::new_persistent`], keep uploaded parameters instead of
+/// reusing the ring each frame, which render bundles replaying old draws rely on.
+pub(crate) struct DrawParams {
+    stages: ShaderStages,
+    uniform: Option<Arc<UniformRing>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct UniformRing {
+    device: Arc<Device>,
+    layout: BindGroupLayout,
+    binding_size: u64,
+    stride: u64,
+    persistent: bool,
+    /// Created in order as draws are staged and never replaced, so recorded draws keep
+    /// referring to their page
+    pages: Vec<OnceLock<RingPage>>,
+    staged: Mutex<StagedParams>,
+}
+
+#[derive(Debug)]
+struct RingPage {
+    buffer: Buffer,
+    bind_group: BindGroup,
+}
+
+/// Uniform rings of the draw params created with a [`DeviceContext`], uploaded together by
+/// [`DeviceContext::upload_draw_params`]
+#[derive(Debug, Default)]
+pub(crate) struct DrawParamsRings {
+    rings: Mutex<Vec<Weak<UniformRing>>>,
+}
+
+#[derive(Debug, Default)]
+struct StagedParams {
+    /// Parameters staged since the last upload, starting at slot `base`
+    data: Vec<u8>,
+    base: u64,
+    count: u64,
+    warned: bool,
+}
+
+/// Parameters staged for one draw, see [`DrawParams::stage`]
+pub(crate) struct DrawParamsSlot<'a> {
+    bytes: &'a [u8],
+    page: usize,
+    offset: u32,
+}
+
+impl DrawParams {
+    /// `size` is the size of the largest parameter struct set with these params
+    pub fn new(device_context: &DeviceContext, stages: ShaderStages, size: usize) -> DrawParams {
+        Self::with_persistence(device_context, stages, size, false)
+    }
+
+    /// Params whose uploaded slots aren't reused, for draws recorded into render bundles
+    pub fn new_persistent(
+        device_context: &DeviceContext,
+        stages: ShaderStages,
+        size: usize,
+    ) -> DrawParams {
+        Self::with_persistence(device_context, stages, size, true)
+    }
+
+    fn with_persistence(
+        device_context: &DeviceContext,
+        stages: ShaderStages,
+        size: usize,
+        persistent: bool,
+    ) -> DrawParams {
+        let device = device_context.device();
+        let uniform = Self::bind_group_layout(device, stages).map(|layout| {
+            let ring = Arc::new(UniformRing {
+                device: device_context.device_arc(),
+                layout,
+                binding_size: binding_size(size),
+                stride: uniform_stride(device, size),
+                persistent,
+                pages: (0..MAX_DRAW_PARAMS_PAGES)
+                    .map(|_| OnceLock::new())
+                    .collect(),
+                staged: Mutex::new(StagedParams::default()),
+            });
+            device_context.draw_params_rings().register(&ring);
+            ring
+        });
+        DrawParams {
+            stages,
+            uniform,
+        }
+    }
+
+    pub fn uses_push_constants(device: &Device) -> bool {
+        device.features().contains(Features::PUSH_CONSTANTS)
+    }
+
+    /// Layout of the uniform fallback's bind group, `None` when push constants are used
+    pub fn bind_group_layout(device: &Device, stages: ShaderStages) -> Option<BindGroupLayout> {
+        if Self::uses_push_constants(device) {
+            return None;
+        }
+        Some(
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("draw_params_bind_group_layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: stages,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            }),
+        )
+    }
+
+    /// Push constant ranges of a pipeline layout, empty in the uniform fallback
+    pub fn push_constant_ranges(
+        device: &Device,
+        stages: ShaderStages,
+        size: usize,
+    ) -> Vec<PushConstantRange> {
+        if Self::uses_push_constants(device) {
+            vec![PushConstantRange {
+                stages,
+                range: 0..size as u32,
+            }]
+        } else {
+            vec![]
+        }
+    }
+
+    /// Replace the shader's push constant declaration with a uniform binding in `group` when
+    /// push constants aren't available
+    pub fn shader_source(device: &Device, source: &'static str, group: u32) -> Cow<'static, str> {
+        if Self::uses_push_constants(device) {
+            return Cow::Borrowed(source);
+        }
+        debug_assert!(source.contains(PUSH_CONSTANT_DECLARATION));
+        Cow::Owned(source.replace(
+            PUSH_CONSTANT_DECLARATION,
+            &format!("@group({}) @binding(0) var<uniform> pc", group),
+        ))
+    }
+
+    /// Stage parameters to be bound with [`DrawParams::bind`], possibly in several passes
+    pub fn stage<'a, T: Pod>(&self, params: &'a T) -> DrawParamsSlot<'a> {
+        let bytes = bytemuck::bytes_of(params);
+        let Some(ring) = &self.uniform else {
+            return DrawParamsSlot {
+                bytes,
+                page: 0,
+                offset: 0,
+            };
+        };
+        let mut staged = ring.staged.lock().unwrap();
+        let capacity = DRAW_PARAMS_PAGE_CAPACITY * MAX_DRAW_PARAMS_PAGES as u64;
+        if staged.count == capacity {
+            if !staged.warned {
+                warn!(
+                    "More than {} draws recorded without reusing draw params, earlier draws will \
+                     be overwritten",
+                    capacity
+                );
+                staged.warned = true;
+            }
+            staged.count = 0;
+            if staged.base > 0 {
+                staged.base = 0;
+                staged.data.clear();
+            }
+        }
+        let page = (staged.count / DRAW_PARAMS_PAGE_CAPACITY) as usize;
+        let offset = (staged.count % DRAW_PARAMS_PAGE_CAPACITY) * ring.stride;
+        let start = ((staged.count - staged.base) * ring.stride) as usize;
+        let end = start + ring.stride as usize;
+        if staged.data.len() < end {
+            staged.data.resize(end, 0);
+        }
+        staged.data[start..start + bytes.len()].copy_from_slice(bytes);
+        staged.count += 1;
+        DrawParamsSlot {
+            bytes,
+            page,
+            offset: offset as u32,
+        }
+    }
+
+    /// Bind staged parameters. `group` is the bind group index of the uniform fallback.
+    pub fn bind<'r>(&'r self, rpass: &mut RenderPass<'r>, group: u32, slot: &DrawParamsSlot) {
+        match &self.uniform {
+            Some(ring) => {
+                rpass.set_bind_group(group, &ring.page(slot.page).bind_group, &[slot.offset])
+            }
+            None => rpass.set_push_constants(self.stages, 0, slot.bytes),
+        }
+    }
+
+    /// [`DrawParams::bind`] for draws recorded into a render bundle
+    pub fn bind_bundle<'r>(
+        &'r self,
+        encoder: &mut RenderBundleEncoder<'r>,
+        group: u32,
+        slot: &DrawParamsSlot,
+    ) {
+        match &self.uniform {
+            Some(ring) => {
+                encoder.set_bind_group(group, &ring.page(slot.page).bind_group, &[slot.offset])
+            }
+            None => encoder.set_push_constants(self.stages, 0, slot.bytes),
+        }
+    }
+
+    /// Stage and bind parameters for the next draw
+    pub fn set<'r, T: Pod>(&'r self, rpass: &mut RenderPass<'r>, group: u32, params: &T) {
+        let slot = self.stage(params);
+        self.bind(rpass, group, &slot);
+    }
+
+    /// Stage and bind parameters for the next draw of a render bundle
+    pub fn set_bundle<'r, T: Pod>(
+        &'r self,
+        encoder: &mut RenderBundleEncoder<'r>,
+        group: u32,
+        params: &T,
+    ) {
+        let slot = self.stage(params);
+        self.bind_bundle(encoder, group, &slot);
+    }
+
+    /// Write staged parameters to the uniform buffer. Call after recording draws and before
+    /// submitting them. Does nothing with push constants.
+    pub fn upload(&self, queue: &Queue) {
+        if let Some(ring) = &self.uniform {
+            ring.upload(queue);
+        }
+    }
+
+    /// Copy staged parameters to the uniform buffer in `encoder`. Record before the passes
+    /// binding them. Does nothing with push constants.
+    pub fn upload_with_encoder(&self, device: &Device, encoder: &mut CommandEncoder) {
+        if let Some(ring) = &self.uniform {
+            let mut staged = ring.staged.lock().unwrap();
+            if !staged.data.is_empty() {
+                let staging = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("draw_params_staging"),
+                    contents: &staged.data,
+                    usage: wgpu::BufferUsages::COPY_SRC,
+                });
+                ring.for_each_page_range(&staged, |buffer, offset, range| {
+                    encoder.copy_buffer_to_buffer(
+                        &staging,
+                        range.start as u64,
+                        buffer,
+                        offset,
+                        range.len() as u64,
+                    );
+                });
+            }
+            ring.finish_upload(&mut staged);
+        }
+    }
+}
+
+impl UniformRing {
+    /// Page at `index`, created on first use
+    fn page(&self, index: usize) -> &RingPage {
+        self.pages[index].get_or_init(|| {
+            let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("draw_params_buffer"),
+                size: self.stride * DRAW_PARAMS_PAGE_CAPACITY,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("draw_params_bind_group"),
+                layout: &self.layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &buffer,
+                        offset: 0,
+                        size: NonZeroU64::new(self.binding_size),
+                    }),
+                }],
+            });
+            RingPage {
+                buffer,
+                bind_group,
+            }
+        })
+    }
+
+    /// Split staged parameters by the pages they belong to. Calls `write` with a page's buffer,
+    /// the offset in it and the range of staged bytes to write there.
+    fn for_each_page_range(
+        &self,
+        staged: &StagedParams,
+        mut write: impl FnMut(&Buffer, u64, Range<usize>),
+    ) {
+        let mut slot = staged.base;
+        let mut start = 0;
+        while start < staged.data.len() {
+            let page_slot = slot % DRAW_PARAMS_PAGE_CAPACITY;
+            let slots = (DRAW_PARAMS_PAGE_CAPACITY - page_slot)
+                .min((staged.data.len() - start) as u64 / self.stride);
+            let end = start + (slots * self.stride) as usize;
+            let page = self.page((slot / DRAW_PARAMS_PAGE_CAPACITY) as usize);
+            write(&page.buffer, page_slot * self.stride, start..end);
+            slot += slots;
+            start = end;
+        }
+    }
+
+    fn upload(&self, queue: &Queue) {
+        let mut staged = self.staged.lock().unwrap();
+        self.for_each_page_range(&staged, |buffer, offset, range| {
+            queue.write_buffer(buffer, offset, &staged.data[range]);
+        });
+        self.finish_upload(&mut staged);
+    }
+
+    fn finish_upload(&self, staged: &mut StagedParams) {
+        staged.data.clear();
+        if self.persistent {
+            staged.base = staged.count;
+        } else {
+            staged.count = 0;
+        }
+    }
+}
+
+impl DrawParamsRings {
+    fn register(&self, ring: &Arc<UniformRing>) {
+        let mut rings = self.rings.lock().unwrap();
+        rings.retain(|ring| ring.strong_count() > 0);
+        rings.push(Arc::downgrade(ring));
+    }
+
+    /// Upload the staged parameters of all live rings
+    pub fn upload(&self, queue: &Queue) {
+        let rings = self
+            .rings
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>();
+        for ring in rings {
+            ring.upload(queue);
+        }
+    }
+
+    /// Forget all rings, e.g. when their device is replaced
+    pub fn clear(&self) {
+        self.rings.lock().unwrap().clear();
+    }
+}
+
+/// Size of a uniform binding holding a struct of `size` bytes, WGSL rounds struct sizes up to
+/// 16 bytes in the uniform address space
+fn binding_size(size: usize) -> u64 {
+    (size as u64).next_multiple_of(16)
+}
+
+fn uniform_stride(device: &Device, size: usize) -> u64 {
+    let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+    binding_size(size).next_multiple_of(alignment)
+}
@@ -0,0 +1,80 @@
+use std::borrow::Cow;
+
+use wgpu::{Device, PipelineCache, RenderPass, RenderPipeline};
+
+use crate::device_context::DeviceContext;
+
+pub const ERROR_SHADER_SOURCE: &str = include_str!("error.wgsl");
+
+/// A pipeline that fills the render target with magenta. Draw this in place of a pipeline that
+/// failed to build, e.g. during shader hot reload, so the app keeps running and the error is
+/// visible.
+pub struct ErrorPipeline {
+    pipeline: RenderPipeline,
+}
+
+impl ErrorPipeline {
+    pub fn new(
+        device_context: &DeviceContext,
+        color_target_state: wgpu::ColorTargetState,
+        sample_count: u32,
+    ) -> ErrorPipeline {
+        let device = device_context.device();
+        let pipeline = Self::new_render_pipeline(
+            device,
+            device_context.pipeline_cache(),
+            color_target_state,
+            sample_count,
+        );
+        Self {
+            pipeline,
+        }
+    }
+
+    pub fn new_render_pipeline(
+        device: &Device,
+        cache: Option<&PipelineCache>,
+        color_target_state: wgpu::ColorTargetState,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Error Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(ERROR_SHADER_SOURCE)),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Error Pipeline Layout"),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Error Render Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(color_target_state)],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache,
+        })
+    }
+
+    pub fn draw<'r>(&'r self, rpass: &mut RenderPass<'r>) {
+        rpass.set_pipeline(&self.pipeline);
+        rpass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,176 @@
+use std::{borrow::Cow, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroup, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, ColorTargetState,
+    ColorWrites, CommandEncoder, Device, Operations, PushConstantRange, RenderPassColorAttachment,
+    RenderPassDescriptor, RenderPipeline, Sampler, SamplerBindingType, ShaderStages, TextureFormat,
+    TextureSampleType, TextureView, TextureViewDimension,
+};
+
+use crate::{
+    device_context::DeviceContext, pipelines::require_push_constants, utils::GpuProfiler,
+    GlassError,
+};
+
+/// Blends the two most recent simulation frames when presenting, for simulations updating slower
+/// than the display refreshes. Render each simulation step into alternating targets and blend
+/// the previous and the current one with [`Time::fixed_alpha`](crate::time::Time::fixed_alpha)
+/// as weight, so e.g. a 60 Hz simulation doesn't strobe on a 144 Hz display.
+pub struct FrameBlendPipeline {
+    blend_pipeline: RenderPipeline,
+    profiler: Option<Arc<GpuProfiler>>,
+}
+
+impl FrameBlendPipeline {
+    pub fn new(
+        device_context: &DeviceContext,
+        target_texture_format: TextureFormat,
+    ) -> Result<FrameBlendPipeline, GlassError> {
+        let device = device_context.device();
+        require_push_constants(
+            device,
+            "FrameBlendPipeline",
+            std::mem::size_of::<FrameBlendPushConstants>(),
+        )?;
+        let texture_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float {
+                    filterable: true,
+                },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            visibility: ShaderStages::FRAGMENT,
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("frame_blend_bind_group_layout"),
+            entries: &[texture_entry(0), texture_entry(1), BindGroupLayoutEntry {
+                binding: 2,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                visibility: ShaderStages::FRAGMENT,
+                count: None,
+            }],
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Frame Blend Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("frame_blend.wgsl"))),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Frame Blend Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[PushConstantRange {
+                stages: ShaderStages::FRAGMENT,
+                range: 0..std::mem::size_of::<FrameBlendPushConstants>() as u32,
+            }],
+        });
+        let blend_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Frame Blend Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fragment"),
+                compilation_options: Default::default(),
+                targets: &[Some(ColorTargetState {
+                    format: target_texture_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: device_context.pipeline_cache(),
+        });
+
+        Ok(FrameBlendPipeline {
+            blend_pipeline,
+            profiler: None,
+        })
+    }
+
+    /// Bind the previous & current frames. Create both orders when ping-ponging between two
+    /// targets.
+    pub fn create_bind_group(
+        &self,
+        device: &Device,
+        previous: &TextureView,
+        current: &TextureView,
+        sampler: &Sampler,
+    ) -> BindGroup {
+        let bind_group_layout = self.blend_pipeline.get_bind_group_layout(0);
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(previous),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(current),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+            label: Some("frame_blend_bind_group"),
+        })
+    }
+
+    /// Time the pipeline's passes, see [`GpuProfiler::pass_timestamp_writes`]
+    pub fn set_profiler(&mut self, profiler: Option<Arc<GpuProfiler>>) {
+        self.profiler = profiler;
+    }
+
+    /// Write `mix(previous, current, alpha)` into output
+    pub fn blend(
+        &self,
+        encoder: &mut CommandEncoder,
+        frames_bind_group: &BindGroup,
+        output: &TextureView,
+        alpha: f32,
+    ) {
+        let push_constants = FrameBlendPushConstants {
+            alpha: alpha.clamp(0.0, 1.0),
+        };
+        let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("frame_blend_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: self
+                .profiler
+                .as_ref()
+                .and_then(|profiler| profiler.pass_timestamp_writes("glass::frame_blend")),
+            occlusion_query_set: None,
+        });
+        r_pass.set_pipeline(&self.blend_pipeline);
+        r_pass.set_bind_group(0, frames_bind_group, &[]);
+        r_pass.set_push_constants(
+            ShaderStages::FRAGMENT,
+            0,
+            bytemuck::cast_slice(&[push_constants]),
+        );
+        r_pass.draw(0..3, 0..1);
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct FrameBlendPushConstants {
+    pub alpha: f32,
+}
@@ -0,0 +1,66 @@
+struct PushConstants {
+    view_proj: mat4x4<f32>,
+    // xy: position, z: radius, w: falloff exponent
+    light: vec4<f32>,
+    // rgb: color, a: intensity
+    color: vec4<f32>,
+    // xy: direction, z: cos of outer half angle (below -1 for point lights), w: cos of inner half angle
+    cone: vec4<f32>,
+    // x: shadow ray steps (0 disables shadows), y: occluder opacity
+    shadow: vec4<f32>,
+}
+var<push_constant> pc: PushConstants;
+
+@group(0) @binding(0)
+var occluder_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var occluder_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    let world = pc.light.xy + corners[index % 6u] * pc.light.z;
+    var out: VertexOutput;
+    out.clip_position = pc.view_proj * vec4<f32>(world, 0.0, 1.0);
+    out.world = world;
+    return out;
+}
+
+// Occluders are rendered with the same view as the lights
+fn occluder_uv(world: vec2<f32>) -> vec2<f32> {
+    let clip = pc.view_proj * vec4<f32>(world, 0.0, 1.0);
+    let ndc = clip.xy / clip.w;
+    return vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let to_frag = in.world - pc.light.xy;
+    let dist = length(to_frag);
+    var attenuation = pow(saturate(1.0 - dist / pc.light.z), pc.light.w);
+    if (pc.cone.z >= -1.0) {
+        let dir = to_frag / max(dist, 1e-5);
+        attenuation *= smoothstep(pc.cone.z, pc.cone.w, dot(dir, pc.cone.xy));
+    }
+    // March from the fragment towards the light, the fragment itself is not an occluder
+    let steps = u32(pc.shadow.x);
+    var visibility = 1.0;
+    for (var i = 1u; i < steps; i++) {
+        let p = mix(in.world, pc.light.xy, f32(i) / f32(steps));
+        let occluder = textureSampleLevel(occluder_texture, occluder_sampler, occluder_uv(p), 0.0).a;
+        visibility *= 1.0 - occluder * pc.shadow.y;
+    }
+    return vec4<f32>(pc.color.rgb * pc.color.a * attenuation * visibility, 1.0);
+}
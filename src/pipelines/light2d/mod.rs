@@ -0,0 +1,3 @@
+mod pipeline;
+
+pub use pipeline::{Light2d, Light2dPipeline, Light2dPushConstants, Light2dSettings};
@@ -0,0 +1,505 @@
+use std::{borrow::Cow, f32::consts::PI, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroup, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType,
+    BlendComponent, BlendFactor, BlendOperation, BlendState, Color, ColorTargetState, ColorWrites,
+    CommandEncoder, Device, Extent3d, LoadOp, Operations, PushConstantRange,
+    RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, Sampler, SamplerBindingType,
+    SamplerDescriptor, ShaderStages, StoreOp, TextureFormat, TextureSampleType, TextureUsages,
+    TextureView, TextureViewDimension,
+};
+
+use crate::{
+    device_context::DeviceContext, pipelines::require_push_constants, resize::Resizable,
+    texture::Texture, utils::GpuProfiler, GlassError,
+};
+
+const LIGHT_MAP_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+fn create_light_map(device: &Device, width: u32, height: u32) -> Texture {
+    Texture::empty(
+        device,
+        "light_map",
+        Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        1,
+        LIGHT_MAP_FORMAT,
+        TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+    )
+}
+
+/// A point or cone light, position and radius in world units
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Light2d {
+    pub pos: [f32; 2],
+    pub radius: f32,
+    pub color: [f32; 3],
+    pub intensity: f32,
+    /// Exponent of the falloff from the center to the radius, 1.0 is linear
+    pub falloff: f32,
+    /// Cone direction in radians, counter clockwise from +x
+    pub direction: f32,
+    /// Half angle of the cone in radians, [`PI`] or more is a point light
+    pub cone_angle: f32,
+    /// Fraction of the cone angle fading out towards its edge
+    pub cone_softness: f32,
+    /// Whether occluders block this light
+    pub casts_shadows: bool,
+}
+
+impl Light2d {
+    pub fn point(pos: [f32; 2], radius: f32, color: [f32; 3]) -> Light2d {
+        Light2d {
+            pos,
+            radius,
+            color,
+            intensity: 1.0,
+            falloff: 2.0,
+            direction: 0.0,
+            cone_angle: PI,
+            cone_softness: 0.0,
+            casts_shadows: true,
+        }
+    }
+
+    pub fn cone(
+        pos: [f32; 2],
+        radius: f32,
+        color: [f32; 3],
+        direction: f32,
+        cone_angle: f32,
+    ) -> Light2d {
+        Light2d {
+            direction,
+            cone_angle,
+            cone_softness: 0.2,
+            ..Self::point(pos, radius, color)
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Light2dSettings {
+    /// Light applied everywhere, including shadows
+    pub ambient: [f32; 3],
+    /// Occluder samples per shadow ray, 0 disables shadows
+    pub shadow_steps: u32,
+    /// How much a fully opaque occluder sample blocks light
+    pub shadow_opacity: f32,
+}
+
+impl Default for Light2dSettings {
+    fn default() -> Self {
+        Self {
+            ambient: [0.1; 3],
+            shadow_steps: 32,
+            shadow_opacity: 1.0,
+        }
+    }
+}
+
+/// 2D lighting as a post process. Lights are accumulated additively into a light map on top of
+/// the ambient light, then [`Light2dPipeline::composite`] multiplies the scene with it.
+///
+/// Shadows are raymarched from each pixel towards the light through an occluder texture, whose
+/// alpha marks blocking pixels. Render occluders with the same view projection as the lights.
+pub struct Light2dPipeline {
+    light_pipeline: RenderPipeline,
+    composite_pipeline: RenderPipeline,
+    occluder_bind_group_layout: BindGroupLayout,
+    composite_bind_group_layout: BindGroupLayout,
+    no_occluders: BindGroup,
+    light_map: Texture,
+    output_format: TextureFormat,
+    settings: Light2dSettings,
+    profiler: Option<Arc<GpuProfiler>>,
+}
+
+impl Light2dPipeline {
+    pub fn new(
+        device_context: &DeviceContext,
+        output_format: TextureFormat,
+        settings: Light2dSettings,
+        width: u32,
+        height: u32,
+    ) -> Result<Light2dPipeline, GlassError> {
+        let device = device_context.device();
+        require_push_constants(
+            device,
+            "Light2dPipeline",
+            std::mem::size_of::<Light2dPushConstants>(),
+        )?;
+        let texture_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float {
+                    filterable: true,
+                },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            visibility: ShaderStages::FRAGMENT,
+            count: None,
+        };
+        let sampler_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            ty: BindingType::Sampler(SamplerBindingType::Filtering),
+            visibility: ShaderStages::FRAGMENT,
+            count: None,
+        };
+        let occluder_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("light2d_occluder_bind_group_layout"),
+                entries: &[texture_entry(0), sampler_entry(1)],
+            });
+        let composite_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("light2d_composite_bind_group_layout"),
+                entries: &[texture_entry(0), texture_entry(1), sampler_entry(2)],
+            });
+
+        let light_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Light2d Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("light2d.wgsl"))),
+        });
+        let light_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Light2d Pipeline Layout"),
+            bind_group_layouts: &[&occluder_bind_group_layout],
+            push_constant_ranges: &[PushConstantRange {
+                stages: ShaderStages::VERTEX_FRAGMENT,
+                range: 0..std::mem::size_of::<Light2dPushConstants>() as u32,
+            }],
+        });
+        let additive = BlendComponent {
+            src_factor: BlendFactor::One,
+            dst_factor: BlendFactor::One,
+            operation: BlendOperation::Add,
+        };
+        let light_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Light2d Pipeline"),
+            layout: Some(&light_layout),
+            vertex: wgpu::VertexState {
+                module: &light_shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &light_shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(ColorTargetState {
+                    format: LIGHT_MAP_FORMAT,
+                    blend: Some(BlendState {
+                        color: additive,
+                        alpha: additive,
+                    }),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: device_context.pipeline_cache(),
+        });
+
+        let composite_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Light2d Composite Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("composite.wgsl"))),
+        });
+        let composite_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Light2d Composite Pipeline Layout"),
+            bind_group_layouts: &[&composite_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let composite_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Light2d Composite Pipeline"),
+            layout: Some(&composite_layout),
+            vertex: wgpu::VertexState {
+                module: &composite_shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &composite_shader,
+                entry_point: Some("fragment"),
+                compilation_options: Default::default(),
+                targets: &[Some(ColorTargetState {
+                    format: output_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: device_context.pipeline_cache(),
+        });
+
+        // Lazily zeroed, so nothing is occluded
+        let empty = Texture::empty(
+            device,
+            "light2d_no_occluders",
+            Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            1,
+            TextureFormat::Rgba8Unorm,
+            TextureUsages::TEXTURE_BINDING,
+        );
+        let sampler = device.create_sampler(&SamplerDescriptor::default());
+        let no_occluders = Self::occluder_bind_group(
+            device,
+            &occluder_bind_group_layout,
+            &empty.views[0],
+            &sampler,
+        );
+
+        Ok(Light2dPipeline {
+            light_pipeline,
+            composite_pipeline,
+            occluder_bind_group_layout,
+            composite_bind_group_layout,
+            no_occluders,
+            light_map: create_light_map(device, width, height),
+            output_format,
+            settings,
+            profiler: None,
+        })
+    }
+
+    /// Recreates the light map if size changed, after which scene bind groups must be recreated
+    pub fn configure(
+        &mut self,
+        device: &Device,
+        settings: Light2dSettings,
+        width: u32,
+        height: u32,
+    ) {
+        self.settings = settings;
+        let size = self.light_map.texture.size();
+        if size.width != width.max(1) || size.height != height.max(1) {
+            self.light_map = create_light_map(device, width, height);
+        }
+    }
+
+    pub fn settings(&self) -> Light2dSettings {
+        self.settings
+    }
+
+    pub fn output_format(&self) -> TextureFormat {
+        self.output_format
+    }
+
+    /// Accumulated light of the last [`Light2dPipeline::render_lights`]
+    pub fn light_map(&self) -> &Texture {
+        &self.light_map
+    }
+
+    /// Time the pipeline's passes, see [`GpuProfiler::pass_timestamp_writes`]
+    pub fn set_profiler(&mut self, profiler: Option<Arc<GpuProfiler>>) {
+        self.profiler = profiler;
+    }
+
+    fn occluder_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        occluders: &TextureView,
+        sampler: &Sampler,
+    ) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(occluders),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+            label: Some("light2d_occluder_bind_group"),
+        })
+    }
+
+    /// Bind an occluder texture for [`Light2dPipeline::render_lights`]
+    pub fn create_occluder_bind_group(
+        &self,
+        device: &Device,
+        occluders: &TextureView,
+        sampler: &Sampler,
+    ) -> BindGroup {
+        Self::occluder_bind_group(device, &self.occluder_bind_group_layout, occluders, sampler)
+    }
+
+    /// Bind the scene to light for [`Light2dPipeline::composite`] together with the light map
+    pub fn create_scene_bind_group(
+        &self,
+        device: &Device,
+        scene: &TextureView,
+        sampler: &Sampler,
+    ) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.composite_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(scene),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&self.light_map.views[0]),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+            label: Some("light2d_scene_bind_group"),
+        })
+    }
+
+    /// Clear the light map to ambient light and add lights to it
+    pub fn render_lights(
+        &self,
+        encoder: &mut CommandEncoder,
+        view_proj: [[f32; 4]; 4],
+        lights: &[Light2d],
+        occluders: Option<&BindGroup>,
+    ) {
+        let [r, g, b] = self.settings.ambient;
+        let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("light2d_lights_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: &self.light_map.views[0],
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color {
+                        r: r as f64,
+                        g: g as f64,
+                        b: b as f64,
+                        a: 1.0,
+                    }),
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: self
+                .profiler
+                .as_ref()
+                .and_then(|profiler| profiler.pass_timestamp_writes("glass::light2d_lights")),
+            occlusion_query_set: None,
+        });
+        r_pass.set_pipeline(&self.light_pipeline);
+        r_pass.set_bind_group(0, occluders.unwrap_or(&self.no_occluders), &[]);
+        for light in lights {
+            let shadow_steps = if light.casts_shadows && occluders.is_some() {
+                self.settings.shadow_steps
+            } else {
+                0
+            };
+            r_pass.set_push_constants(
+                ShaderStages::VERTEX_FRAGMENT,
+                0,
+                bytemuck::cast_slice(&[Light2dPushConstants::new(
+                    view_proj,
+                    light,
+                    shadow_steps,
+                    self.settings.shadow_opacity,
+                )]),
+            );
+            r_pass.draw(0..6, 0..1);
+        }
+    }
+
+    /// Write the scene multiplied by the light map into output
+    pub fn composite(
+        &self,
+        encoder: &mut CommandEncoder,
+        scene_bind_group: &BindGroup,
+        output: &TextureView,
+    ) {
+        let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("light2d_composite_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: self
+                .profiler
+                .as_ref()
+                .and_then(|profiler| profiler.pass_timestamp_writes("glass::light2d_composite")),
+            occlusion_query_set: None,
+        });
+        r_pass.set_pipeline(&self.composite_pipeline);
+        r_pass.set_bind_group(0, scene_bind_group, &[]);
+        r_pass.draw(0..3, 0..1);
+    }
+}
+
+impl Resizable for Light2dPipeline {
+    fn resize(&mut self, device: &Device, size: [u32; 2]) {
+        self.configure(device, self.settings, size[0], size[1]);
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct Light2dPushConstants {
+    pub view_proj: [[f32; 4]; 4],
+    /// Position, radius & falloff
+    pub light: [f32; 4],
+    /// Color & intensity
+    pub color: [f32; 4],
+    /// Direction, cos of outer & inner cone half angles
+    pub cone: [f32; 4],
+    /// Shadow steps & occluder opacity
+    pub shadow: [f32; 4],
+}
+
+impl Light2dPushConstants {
+    pub fn new(
+        view_proj: [[f32; 4]; 4],
+        light: &Light2d,
+        shadow_steps: u32,
+        shadow_opacity: f32,
+    ) -> Light2dPushConstants {
+        let cone = if light.cone_angle >= PI {
+            [1.0, 0.0, -2.0, -2.0]
+        } else {
+            let (sin, cos) = light.direction.sin_cos();
+            let inner = light.cone_angle * (1.0 - light.cone_softness.clamp(0.0, 1.0));
+            // Keep edges apart for smoothstep
+            [
+                cos,
+                sin,
+                light.cone_angle.cos(),
+                inner.cos().max(light.cone_angle.cos() + 1e-4),
+            ]
+        };
+        Light2dPushConstants {
+            view_proj,
+            light: [light.pos[0], light.pos[1], light.radius, light.falloff],
+            color: [
+                light.color[0],
+                light.color[1],
+                light.color[2],
+                light.intensity,
+            ],
+            cone,
+            shadow: [shadow_steps as f32, shadow_opacity, 0.0, 0.0],
+        }
+    }
+}
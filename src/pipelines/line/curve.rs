@@ -0,0 +1,321 @@
+use crate::pipelines::ColoredVertex;
+
+/// Default max distance in world units between a curve and its tessellated line strip
+pub const DEFAULT_CURVE_TOLERANCE: f32 = 0.25;
+/// Bezier subdivision stops here even if the tolerance isn't reached
+const MAX_SUBDIVISION_DEPTH: u32 = 16;
+/// Arcs are split into at most this many segments even if the tolerance isn't reached
+const MAX_ARC_SEGMENTS: u32 = 1 << 16;
+
+/// A 2D curve that can be tessellated into lines for [`LinePipeline`](super::LinePipeline) or
+/// [`Polyline`](crate::pipelines::Polyline). Bezier curves are subdivided adaptively, so flat
+/// parts produce few points and sharp bends many.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Curve {
+    Quadratic {
+        from: [f32; 2],
+        ctrl: [f32; 2],
+        to: [f32; 2],
+    },
+    Cubic {
+        from: [f32; 2],
+        ctrl1: [f32; 2],
+        ctrl2: [f32; 2],
+        to: [f32; 2],
+    },
+    /// Angles in radians, counter clockwise from +x. Sweeps clockwise if `end_angle` is less
+    /// than `start_angle`.
+    Arc {
+        center: [f32; 2],
+        radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+    },
+}
+
+impl Curve {
+    /// Points of a line strip staying within `tolerance` of the curve, including both ends. Empty
+    /// if the curve has non-finite coordinates, radius or angles.
+    pub fn points(&self, tolerance: f32) -> Vec<[f32; 2]> {
+        let tolerance = tolerance.max(f32::EPSILON);
+        let mut points = vec![];
+        if !self.is_finite() {
+            return points;
+        }
+        match *self {
+            Curve::Quadratic {
+                from,
+                ctrl,
+                to,
+            } => {
+                points.push(from);
+                quadratic(&mut points, [from, ctrl, to], tolerance, 0);
+            }
+            Curve::Cubic {
+                from,
+                ctrl1,
+                ctrl2,
+                to,
+            } => {
+                points.push(from);
+                cubic(&mut points, [from, ctrl1, ctrl2, to], tolerance, 0);
+            }
+            Curve::Arc {
+                center,
+                radius,
+                start_angle,
+                end_angle,
+            } => {
+                let sweep = end_angle - start_angle;
+                // Largest step whose chord stays within tolerance of the arc
+                let step = if tolerance < radius {
+                    2.0 * (1.0 - tolerance / radius).acos()
+                } else {
+                    std::f32::consts::PI
+                };
+                let steps = ((sweep.abs() / step).ceil() as u32).clamp(1, MAX_ARC_SEGMENTS);
+                points.extend((0..=steps).map(|i| {
+                    let (sin, cos) = (start_angle + sweep * i as f32 / steps as f32).sin_cos();
+                    [center[0] + cos * radius, center[1] + sin * radius]
+                }));
+            }
+        }
+        points
+    }
+
+    fn is_finite(&self) -> bool {
+        let finite = |points: &[[f32; 2]]| points.iter().flatten().all(|v| v.is_finite());
+        match *self {
+            Curve::Quadratic {
+                from,
+                ctrl,
+                to,
+            } => finite(&[from, ctrl, to]),
+            Curve::Cubic {
+                from,
+                ctrl1,
+                ctrl2,
+                to,
+            } => finite(&[from, ctrl1, ctrl2, to]),
+            Curve::Arc {
+                center,
+                radius,
+                start_angle,
+                end_angle,
+            } => {
+                finite(&[center])
+                    && [radius, start_angle, end_angle]
+                        .iter()
+                        .all(|v| v.is_finite())
+            }
+        }
+    }
+
+    /// Vertices of a line strip, e.g. for [`Polyline::add`](crate::pipelines::Polyline::add)
+    pub fn line_strip(&self, color: [f32; 4], tolerance: f32) -> Vec<ColoredVertex> {
+        self.points(tolerance)
+            .into_iter()
+            .map(|p| ColoredVertex::new_2d(p, color))
+            .collect()
+    }
+
+    /// Vertex pairs of each segment, for the line buffers of [`LinePipeline`](super::LinePipeline)
+    pub fn line_list(&self, color: [f32; 4], tolerance: f32) -> Vec<ColoredVertex> {
+        self.points(tolerance)
+            .windows(2)
+            .flat_map(|w| {
+                [
+                    ColoredVertex::new_2d(w[0], color),
+                    ColoredVertex::new_2d(w[1], color),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Push points of a quadratic bezier after its first point
+fn quadratic(out: &mut Vec<[f32; 2]>, [p0, p1, p2]: [[f32; 2]; 3], tolerance: f32, depth: u32) {
+    // Max distance between the curve and its chord
+    let deviation = length(second_difference(p0, p1, p2)) * 0.25;
+    if deviation <= tolerance || depth >= MAX_SUBDIVISION_DEPTH {
+        out.push(p2);
+        return;
+    }
+    let p01 = mid(p0, p1);
+    let p12 = mid(p1, p2);
+    let p012 = mid(p01, p12);
+    quadratic(out, [p0, p01, p012], tolerance, depth + 1);
+    quadratic(out, [p012, p12, p2], tolerance, depth + 1);
+}
+
+/// Push points of a cubic bezier after its first point
+fn cubic(out: &mut Vec<[f32; 2]>, [p0, p1, p2, p3]: [[f32; 2]; 4], tolerance: f32, depth: u32) {
+    // Upper bound of the distance between the curve and its chord
+    let deviation =
+        length(second_difference(p0, p1, p2)).max(length(second_difference(p1, p2, p3))) * 0.75;
+    if deviation <= tolerance || depth >= MAX_SUBDIVISION_DEPTH {
+        out.push(p3);
+        return;
+    }
+    let p01 = mid(p0, p1);
+    let p12 = mid(p1, p2);
+    let p23 = mid(p2, p3);
+    let p012 = mid(p01, p12);
+    let p123 = mid(p12, p23);
+    let p0123 = mid(p012, p123);
+    cubic(out, [p0, p01, p012, p0123], tolerance, depth + 1);
+    cubic(out, [p0123, p123, p23, p3], tolerance, depth + 1);
+}
+
+fn second_difference(a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> [f32; 2] {
+    [a[0] - 2.0 * b[0] + c[0], a[1] - 2.0 * b[1] + c[1]]
+}
+
+fn mid(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [(a[0] + b[0]) * 0.5, (a[1] + b[1]) * 0.5]
+}
+
+fn length(a: [f32; 2]) -> f32 {
+    (a[0] * a[0] + a[1] * a[1]).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use crate::pipelines::{
+        line::curve::{Curve, MAX_ARC_SEGMENTS},
+        ColoredVertex,
+    };
+
+    fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+        ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt()
+    }
+
+    fn quadratic_at([p0, p1, p2]: [[f32; 2]; 3], t: f32) -> [f32; 2] {
+        let s = 1.0 - t;
+        [0, 1].map(|i| s * s * p0[i] + 2.0 * s * t * p1[i] + t * t * p2[i])
+    }
+
+    /// Distance from `p` to the closest segment of the strip
+    fn distance_to_strip(points: &[[f32; 2]], p: [f32; 2]) -> f32 {
+        points
+            .windows(2)
+            .map(|w| {
+                let d = [w[1][0] - w[0][0], w[1][1] - w[0][1]];
+                let len2 = d[0] * d[0] + d[1] * d[1];
+                let t = if len2 > 0.0 {
+                    (((p[0] - w[0][0]) * d[0] + (p[1] - w[0][1]) * d[1]) / len2).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                distance(p, [w[0][0] + d[0] * t, w[0][1] + d[1] * t])
+            })
+            .fold(f32::MAX, f32::min)
+    }
+
+    #[test]
+    fn test_flat_bezier_is_a_single_segment() {
+        let quadratic = Curve::Quadratic {
+            from: [0.0, 0.0],
+            ctrl: [1.0, 0.0],
+            to: [2.0, 0.0],
+        };
+        assert_eq!(quadratic.points(0.1), vec![[0.0, 0.0], [2.0, 0.0]]);
+        let cubic = Curve::Cubic {
+            from: [0.0, 0.0],
+            ctrl1: [1.0, 0.0],
+            ctrl2: [2.0, 0.0],
+            to: [3.0, 0.0],
+        };
+        assert_eq!(cubic.points(0.1), vec![[0.0, 0.0], [3.0, 0.0]]);
+    }
+
+    #[test]
+    fn test_quadratic_within_tolerance() {
+        let control = [[0.0, 0.0], [50.0, 100.0], [100.0, 0.0]];
+        let curve = Curve::Quadratic {
+            from: control[0],
+            ctrl: control[1],
+            to: control[2],
+        };
+        let coarse = curve.points(1.0);
+        let fine = curve.points(0.01);
+        assert!(fine.len() > coarse.len());
+        assert_eq!(coarse.first(), Some(&control[0]));
+        assert_eq!(coarse.last(), Some(&control[2]));
+        for i in 0..=100 {
+            let p = quadratic_at(control, i as f32 / 100.0);
+            assert!(distance_to_strip(&coarse, p) <= 1.0 + 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_arc() {
+        let arc = Curve::Arc {
+            center: [10.0, 10.0],
+            radius: 5.0,
+            start_angle: 0.0,
+            end_angle: PI,
+        };
+        let points = arc.points(0.01);
+        assert!(points.len() > 2);
+        assert!(distance(points[0], [15.0, 10.0]) < 1e-4);
+        assert!(distance(*points.last().unwrap(), [5.0, 10.0]) < 1e-4);
+        assert!(points
+            .iter()
+            .all(|p| (distance(*p, [10.0, 10.0]) - 5.0).abs() < 1e-4));
+        // Counter clockwise, so the arc passes above the center
+        assert!(points.iter().all(|p| p[1] >= 10.0 - 1e-4));
+        // A tolerance larger than the radius still splits the half circle
+        let coarse = Curve::Arc {
+            center: [0.0, 0.0],
+            radius: 1.0,
+            start_angle: 0.0,
+            end_angle: -PI,
+        };
+        assert_eq!(coarse.points(10.0).len(), 2);
+    }
+
+    #[test]
+    fn test_arc_is_bounded() {
+        let huge = Curve::Arc {
+            center: [0.0, 0.0],
+            radius: 1.0,
+            start_angle: 0.0,
+            end_angle: 1e30,
+        };
+        assert_eq!(huge.points(1e-6).len(), MAX_ARC_SEGMENTS as usize + 1);
+        let infinite = Curve::Arc {
+            center: [0.0, 0.0],
+            radius: 1.0,
+            start_angle: 0.0,
+            end_angle: f32::INFINITY,
+        };
+        assert!(infinite.points(0.1).is_empty());
+        let nan = Curve::Quadratic {
+            from: [0.0, 0.0],
+            ctrl: [f32::NAN, 0.0],
+            to: [1.0, 0.0],
+        };
+        assert!(nan.points(0.1).is_empty());
+    }
+
+    #[test]
+    fn test_line_list_pairs_segments() {
+        let curve = Curve::Arc {
+            center: [0.0, 0.0],
+            radius: 1.0,
+            start_angle: 0.0,
+            end_angle: PI,
+        };
+        let points = curve.points(0.01);
+        let list: Vec<ColoredVertex> = curve.line_list([1.0; 4], 0.01);
+        assert_eq!(list.len(), (points.len() - 1) * 2);
+        assert_eq!(curve.line_strip([1.0; 4], 0.01).len(), points.len());
+        for (i, pair) in list.chunks(2).enumerate() {
+            assert_eq!(pair[0].position[..2], points[i]);
+            assert_eq!(pair[1].position[..2], points[i + 1]);
+        }
+    }
+}
@@ -1,3 +1,5 @@
+mod curve;
 mod pipeline;
 
-pub use pipeline::{Line, LinePipeline};
+pub use curve::{Curve, DEFAULT_CURVE_TOLERANCE};
+pub use pipeline::{Line, LinePipeline, LineWidth};
@@ -1,46 +1,144 @@
-use std::{borrow::Cow, ops::Range};
+use std::ops::Range;
 
 use bytemuck::{Pod, Zeroable};
 use wgpu::{
-    util::DeviceExt, Buffer, Device, PushConstantRange, RenderPass, RenderPipeline, ShaderStages,
+    util::{DeviceExt, DrawIndirectArgs},
+    Buffer, BufferAddress, CompareFunction, DepthStencilState, Device, Features, PipelineCache,
+    Queue, RenderPass, RenderPipeline, ShaderStages, TextureFormat,
 };
 
-use crate::pipelines::ColoredVertex;
+use crate::{
+    device_context::DeviceContext,
+    pipelines::{ColoredVertex, DrawParams},
+};
+
+/// Bind group of the draw parameters when push constants aren't available
+const PARAMS_GROUP: u32 = 0;
+
+/// Width of lines drawn with [`LinePipeline::draw_thick`] or
+/// [`PolylinePipeline`](crate::pipelines::PolylinePipeline)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LineWidth {
+    /// Constant on screen regardless of camera zoom
+    Pixels(f32),
+    /// Scales with the camera like the line's positions
+    World(f32),
+}
 
 pub struct LinePipeline {
     pipeline: RenderPipeline,
+    thick_pipeline: RenderPipeline,
     vertices: Buffer,
+    params: DrawParams,
+    multi_draw_indirect: bool,
 }
 
 impl LinePipeline {
-    pub fn new(device: &Device, color_target_state: wgpu::ColorTargetState) -> LinePipeline {
+    pub fn new(
+        device_context: &DeviceContext,
+        color_target_state: wgpu::ColorTargetState,
+        sample_count: u32,
+    ) -> LinePipeline {
+        Self::with_depth_stencil(device_context, color_target_state, None, sample_count)
+    }
+
+    /// Line pipeline for render passes with a depth attachment of `depth_format`. Lines write
+    /// depth and are tested against it with `compare`.
+    pub fn new_with_depth(
+        device_context: &DeviceContext,
+        color_target_state: wgpu::ColorTargetState,
+        depth_format: TextureFormat,
+        compare: CompareFunction,
+        sample_count: u32,
+    ) -> LinePipeline {
+        let depth_stencil = DepthStencilState {
+            format: depth_format,
+            depth_write_enabled: true,
+            depth_compare: compare,
+            stencil: Default::default(),
+            bias: Default::default(),
+        };
+        Self::with_depth_stencil(
+            device_context,
+            color_target_state,
+            Some(depth_stencil),
+            sample_count,
+        )
+    }
+
+    fn with_depth_stencil(
+        device_context: &DeviceContext,
+        color_target_state: wgpu::ColorTargetState,
+        depth_stencil: Option<DepthStencilState>,
+        sample_count: u32,
+    ) -> LinePipeline {
+        let device = device_context.device();
         let vertices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Vertex Buffer"),
             contents: bytemuck::cast_slice(&[ColoredVertex::new_2d([1.0, 1.0], [1.0; 4]); 2]),
             usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
         });
-        let pipeline = Self::new_render_pipeline(device, color_target_state);
+        let pipeline = Self::new_depth_render_pipeline(
+            device,
+            device_context.pipeline_cache(),
+            color_target_state.clone(),
+            depth_stencil.clone(),
+            sample_count,
+        );
+        let thick_pipeline = Self::new_depth_thick_render_pipeline(
+            device,
+            device_context.pipeline_cache(),
+            color_target_state,
+            depth_stencil,
+            sample_count,
+        );
         Self {
             pipeline,
+            thick_pipeline,
             vertices,
+            // Shared by both pipelines, sized for the larger thick line parameters
+            params: DrawParams::new(
+                device_context,
+                ShaderStages::VERTEX_FRAGMENT,
+                std::mem::size_of::<ThickLinePushConstants>(),
+            ),
+            multi_draw_indirect: device.features().contains(Features::MULTI_DRAW_INDIRECT),
         }
     }
 
     pub fn new_render_pipeline(
         device: &Device,
+        cache: Option<&PipelineCache>,
         color_target_state: wgpu::ColorTargetState,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        Self::new_depth_render_pipeline(device, cache, color_target_state, None, sample_count)
+    }
+
+    pub fn new_depth_render_pipeline(
+        device: &Device,
+        cache: Option<&PipelineCache>,
+        color_target_state: wgpu::ColorTargetState,
+        depth_stencil: Option<DepthStencilState>,
+        sample_count: u32,
     ) -> RenderPipeline {
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Shader"),
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("line.wgsl"))),
+            source: wgpu::ShaderSource::Wgsl(DrawParams::shader_source(
+                device,
+                include_str!("line.wgsl"),
+                PARAMS_GROUP,
+            )),
         });
+        let params_layout = DrawParams::bind_group_layout(device, ShaderStages::VERTEX_FRAGMENT);
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Line Pipeline Layout"),
-            bind_group_layouts: &[],
-            push_constant_ranges: &[PushConstantRange {
-                stages: ShaderStages::VERTEX_FRAGMENT,
-                range: 0..std::mem::size_of::<LinePushConstants>() as u32,
-            }],
+            bind_group_layouts: &params_layout.iter().collect::<Vec<_>>(),
+            push_constant_ranges: &DrawParams::push_constant_ranges(
+                device,
+                ShaderStages::VERTEX_FRAGMENT,
+                std::mem::size_of::<LinePushConstants>(),
+            ),
         });
         let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Line Render Pipeline"),
@@ -62,29 +160,108 @@ impl LinePipeline {
                 strip_index_format: None,
                 front_face: wgpu::FrontFace::Ccw,
                 cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Line,
+                // Ignored for line topologies, so no `Features::POLYGON_MODE_LINE` is needed
+                polygon_mode: wgpu::PolygonMode::Fill,
                 unclipped_depth: false,
                 conservative: false,
             },
-            depth_stencil: None,
+            depth_stencil,
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
             multiview: None,
-            cache: None,
+            cache,
         });
         pipeline
     }
 
+    /// Pipeline drawing each segment as a quad. Requires 128 bytes of push constants.
+    pub fn new_thick_render_pipeline(
+        device: &Device,
+        cache: Option<&PipelineCache>,
+        color_target_state: wgpu::ColorTargetState,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        Self::new_depth_thick_render_pipeline(device, cache, color_target_state, None, sample_count)
+    }
+
+    /// [`LinePipeline::new_thick_render_pipeline`] with an optional depth stencil state
+    pub fn new_depth_thick_render_pipeline(
+        device: &Device,
+        cache: Option<&PipelineCache>,
+        color_target_state: wgpu::ColorTargetState,
+        depth_stencil: Option<DepthStencilState>,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Thick Line Shader"),
+            source: wgpu::ShaderSource::Wgsl(DrawParams::shader_source(
+                device,
+                include_str!("thick_line.wgsl"),
+                PARAMS_GROUP,
+            )),
+        });
+        let params_layout = DrawParams::bind_group_layout(device, ShaderStages::VERTEX_FRAGMENT);
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Thick Line Pipeline Layout"),
+            bind_group_layouts: &params_layout.iter().collect::<Vec<_>>(),
+            push_constant_ranges: &DrawParams::push_constant_ranges(
+                device,
+                ShaderStages::VERTEX_FRAGMENT,
+                std::mem::size_of::<ThickLinePushConstants>(),
+            ),
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Thick Line Render Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[segment_desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(color_target_state)],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache,
+        })
+    }
+
+    /// Upload the parameters of recorded draws when the device lacks `Features::PUSH_CONSTANTS`,
+    /// e.g. on the web. The runner does this before submitting each frame, call it only when
+    /// submitting draws yourself. Does nothing with push constants.
+    pub fn upload_draw_params(&self, queue: &Queue) {
+        self.params.upload(queue);
+    }
+
     pub fn draw<'r>(&'r self, rpass: &mut RenderPass<'r>, view_proj: [[f32; 4]; 4], line: Line) {
         rpass.set_pipeline(&self.pipeline);
         rpass.set_vertex_buffer(0, self.vertices.slice(..));
-        rpass.set_push_constants(
-            ShaderStages::VERTEX_FRAGMENT,
-            0,
-            bytemuck::cast_slice(&[LinePushConstants::new(view_proj, line)]),
+        self.params.set(
+            rpass,
+            PARAMS_GROUP,
+            &LinePushConstants::new(view_proj, line),
         );
         rpass.draw(0..2, 0..1);
     }
@@ -99,13 +276,140 @@ impl LinePipeline {
     ) {
         rpass.set_pipeline(&self.pipeline);
         rpass.set_vertex_buffer(0, buffer.slice(..));
-        rpass.set_push_constants(
-            ShaderStages::VERTEX_FRAGMENT,
-            0,
-            bytemuck::cast_slice(&[LinePushConstants::buffer(view_proj)]),
-        );
+        self.params
+            .set(rpass, PARAMS_GROUP, &LinePushConstants::buffer(view_proj));
         rpass.draw(vertices, 0..1);
     }
+
+    /// Draw a line with width. `viewport` is the size of the render target in pixels.
+    pub fn draw_thick<'r>(
+        &'r self,
+        rpass: &mut RenderPass<'r>,
+        view_proj: [[f32; 4]; 4],
+        line: Line,
+        width: LineWidth,
+        viewport: [f32; 2],
+    ) {
+        rpass.set_pipeline(&self.thick_pipeline);
+        rpass.set_vertex_buffer(0, self.vertices.slice(..));
+        self.params.set(
+            rpass,
+            PARAMS_GROUP,
+            &ThickLinePushConstants::new(LinePushConstants::new(view_proj, line), width, viewport),
+        );
+        rpass.draw(0..6, 0..1);
+    }
+
+    /// Draw [`ColoredVertex`] pairs of a buffer with width. `segments` indexes vertex pairs, so
+    /// the segment `n` is drawn from vertices `2n` and `2n + 1`.
+    pub fn draw_thick_line_buffer<'r>(
+        &'r self,
+        rpass: &mut RenderPass<'r>,
+        view_proj: [[f32; 4]; 4],
+        buffer: &'r Buffer,
+        segments: Range<u32>,
+        width: LineWidth,
+        viewport: [f32; 2],
+    ) {
+        rpass.set_pipeline(&self.thick_pipeline);
+        rpass.set_vertex_buffer(0, buffer.slice(..));
+        self.params.set(
+            rpass,
+            PARAMS_GROUP,
+            &ThickLinePushConstants::new(LinePushConstants::buffer(view_proj), width, viewport),
+        );
+        rpass.draw(0..6, segments);
+    }
+
+    /// [`LinePipeline::draw_thick_line_buffer`] with the segment range read from a
+    /// [`DrawIndirectArgs`] at `indirect_offset` of `indirect_buffer`, e.g. written by a culling
+    /// compute shader. `vertex_count` must be 6 and instances index segments. A nonzero
+    /// `first_instance` requires `Features::INDIRECT_FIRST_INSTANCE`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_thick_line_buffer_indirect<'r>(
+        &'r self,
+        rpass: &mut RenderPass<'r>,
+        view_proj: [[f32; 4]; 4],
+        buffer: &'r Buffer,
+        indirect_buffer: &'r Buffer,
+        indirect_offset: BufferAddress,
+        width: LineWidth,
+        viewport: [f32; 2],
+    ) {
+        self.multi_draw_thick_line_buffer_indirect(
+            rpass,
+            view_proj,
+            buffer,
+            indirect_buffer,
+            indirect_offset,
+            1,
+            width,
+            viewport,
+        );
+    }
+
+    /// Draw `count` consecutive [`DrawIndirectArgs`] of `indirect_buffer`. Uses a single
+    /// multi draw when the device has `Features::MULTI_DRAW_INDIRECT`, one indirect draw per
+    /// args otherwise.
+    #[allow(clippy::too_many_arguments)]
+    pub fn multi_draw_thick_line_buffer_indirect<'r>(
+        &'r self,
+        rpass: &mut RenderPass<'r>,
+        view_proj: [[f32; 4]; 4],
+        buffer: &'r Buffer,
+        indirect_buffer: &'r Buffer,
+        indirect_offset: BufferAddress,
+        count: u32,
+        width: LineWidth,
+        viewport: [f32; 2],
+    ) {
+        rpass.set_pipeline(&self.thick_pipeline);
+        rpass.set_vertex_buffer(0, buffer.slice(..));
+        self.params.set(
+            rpass,
+            PARAMS_GROUP,
+            &ThickLinePushConstants::new(LinePushConstants::buffer(view_proj), width, viewport),
+        );
+        if self.multi_draw_indirect {
+            rpass.multi_draw_indirect(indirect_buffer, indirect_offset, count);
+        } else {
+            let stride = std::mem::size_of::<DrawIndirectArgs>() as BufferAddress;
+            for i in 0..count as BufferAddress {
+                rpass.draw_indirect(indirect_buffer, indirect_offset + i * stride);
+            }
+        }
+    }
+}
+
+/// A segment is a pair of [`ColoredVertex`] read per instance
+fn segment_desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+    const VEC4: wgpu::BufferAddress = std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress;
+    wgpu::VertexBufferLayout {
+        array_stride: 2 * std::mem::size_of::<ColoredVertex>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes: &[
+            wgpu::VertexAttribute {
+                offset: 0,
+                shader_location: 0,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            wgpu::VertexAttribute {
+                offset: VEC4,
+                shader_location: 1,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            wgpu::VertexAttribute {
+                offset: 2 * VEC4,
+                shader_location: 2,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            wgpu::VertexAttribute {
+                offset: 3 * VEC4,
+                shader_location: 3,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+        ],
+    }
 }
 
 #[repr(C)]
@@ -137,6 +441,37 @@ impl LinePushConstants {
     }
 }
 
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct ThickLinePushConstants {
+    pub view_proj: [[f32; 4]; 4],
+    pub start: [f32; 4],
+    pub end: [f32; 4],
+    pub color: [f32; 4],
+    /// Width, 1.0 if width is in pixels, viewport width & height
+    pub params: [f32; 4],
+}
+
+impl ThickLinePushConstants {
+    pub fn new(
+        line: LinePushConstants,
+        width: LineWidth,
+        viewport: [f32; 2],
+    ) -> ThickLinePushConstants {
+        let (width, pixels) = match width {
+            LineWidth::Pixels(width) => (width, 1.0),
+            LineWidth::World(width) => (width, 0.0),
+        };
+        ThickLinePushConstants {
+            view_proj: line.view_proj,
+            start: line.start,
+            end: line.end,
+            color: line.color,
+            params: [width, pixels, viewport[0], viewport[1]],
+        }
+    }
+}
+
 #[derive(Default, Copy, Clone, Debug)]
 pub struct Line {
     pub start: [f32; 3],
@@ -0,0 +1,76 @@
+struct PushConstants {
+    view_proj: mat4x4<f32>,
+    start: vec4<f32>,
+    end: vec4<f32>,
+    color: vec4<f32>,
+    // x: width, y: 1.0 if width is in pixels, zw: viewport size in pixels
+    params: vec4<f32>,
+}
+var<push_constant> pc: PushConstants;
+
+// One instance per segment
+struct SegmentInput {
+    @builtin(vertex_index) index: u32,
+    @location(0) start_position: vec4<f32>,
+    @location(1) start_color: vec4<f32>,
+    @location(2) end_position: vec4<f32>,
+    @location(3) end_color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+fn normal_of(delta: vec2<f32>) -> vec2<f32> {
+    let len = length(delta);
+    var dir = vec2<f32>(1.0, 0.0);
+    if (len > 0.0) {
+        dir = delta / len;
+    }
+    return vec2<f32>(-dir.y, dir.x);
+}
+
+@vertex
+fn vs_main(
+    model: SegmentInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    // (position along segment, side)
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let corner = corners[model.index % 6u];
+    // Same as line.wgsl, buffers have 1.0 in push constant start & end
+    let start = vec4<f32>(pc.start.xy * model.start_position.xy, 0.0, 1.0);
+    let end = vec4<f32>(pc.end.xy * model.end_position.xy, 0.0, 1.0);
+    let half_width = pc.params.x * 0.5;
+    if (pc.params.y > 0.5) {
+        // Offset in screen space so the width stays constant regardless of zoom
+        let viewport = pc.params.zw;
+        let clip_start = pc.view_proj * start;
+        let clip_end = pc.view_proj * end;
+        let screen_start = clip_start.xy / clip_start.w * viewport * 0.5;
+        let screen_end = clip_end.xy / clip_end.w * viewport * 0.5;
+        let normal = normal_of(screen_end - screen_start);
+        let clip = mix(clip_start, clip_end, corner.x);
+        let offset = normal * corner.y * half_width / (viewport * 0.5) * clip.w;
+        out.clip_position = vec4<f32>(clip.xy + offset, clip.zw);
+    } else {
+        let normal = normal_of(end.xy - start.xy);
+        let world = mix(start, end, corner.x) + vec4<f32>(normal * corner.y * half_width, 0.0, 0.0);
+        out.clip_position = pc.view_proj * world;
+    }
+    out.color = pc.color * mix(model.start_color, model.end_color, corner.x);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
@@ -0,0 +1,53 @@
+struct Camera {
+    view_proj: mat4x4<f32>,
+    // xyz: direction towards the light, w: ambient light
+    light: vec4<f32>,
+}
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+@group(1) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(1) @binding(1)
+var s_diffuse: sampler;
+
+struct PushConstants {
+    model: mat4x4<f32>,
+    color: vec4<f32>,
+    // x: 1.0 if textured
+    params: vec4<f32>,
+}
+var<push_constant> pc: PushConstants;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * pc.model * vec4<f32>(model.position, 1.0);
+    // Assumes uniform scale
+    out.normal = (pc.model * vec4<f32>(model.normal, 0.0)).xyz;
+    out.uv = model.uv;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = textureSample(t_diffuse, s_diffuse, in.uv);
+    let base = pc.color * select(vec4<f32>(1.0), texel, pc.params.x > 0.5);
+    let lambert = max(dot(normalize(in.normal), normalize(camera.light.xyz)), 0.0);
+    let shade = camera.light.w + (1.0 - camera.light.w) * lambert;
+    return vec4<f32>(base.rgb * shade, base.a);
+}
@@ -0,0 +1,3 @@
+mod pipeline;
+
+pub use pipeline::{Mesh, MeshCameraUniform, MeshPipeline, MeshPushConstants, MeshVertex};
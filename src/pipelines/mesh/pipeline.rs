@@ -0,0 +1,390 @@
+use std::borrow::Cow;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    util::DeviceExt, BindGroup, BindGroupLayout, Buffer, CompareFunction, DepthStencilState,
+    Device, Extent3d, IndexFormat, PipelineCache, PushConstantRange, Queue, RenderPass,
+    RenderPipeline, Sampler, SamplerDescriptor, ShaderStages, TextureFormat, TextureUsages,
+    TextureView,
+};
+
+use crate::{
+    device_context::DeviceContext, pipelines::require_push_constants, texture::Texture, GlassError,
+};
+
+/// A vertex of a [`Mesh`]
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug, Pod, Zeroable)]
+pub struct MeshVertex {
+    pub pos: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+}
+
+impl MeshVertex {
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        use std::mem;
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<MeshVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: 2 * mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+            ],
+        }
+    }
+}
+
+/// Indexed triangle list on the GPU
+pub struct Mesh {
+    vertices: Buffer,
+    indices: Buffer,
+    index_count: u32,
+}
+
+impl Mesh {
+    pub fn new(device: &Device, vertices: &[MeshVertex], indices: &[u32]) -> Mesh {
+        Mesh {
+            vertices: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Mesh Vertex Buffer"),
+                contents: bytemuck::cast_slice(vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            }),
+            indices: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Mesh Index Buffer"),
+                contents: bytemuck::cast_slice(indices),
+                usage: wgpu::BufferUsages::INDEX,
+            }),
+            index_count: indices.len() as u32,
+        }
+    }
+
+    /// Axis aligned cube centered at origin, each face mapped to the full texture
+    pub fn cube(device: &Device, size: f32) -> Mesh {
+        let h = size * 0.5;
+        // Normal, and the two axes spanning the face
+        let faces: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+            ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+            ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+            ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+            ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+            ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ([0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ];
+        let mut vertices = vec![];
+        let mut indices = vec![];
+        for (normal, u, v) in faces {
+            let start = vertices.len() as u32;
+            for (su, sv) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                let pos = [0, 1, 2].map(|i| (normal[i] + u[i] * su + v[i] * sv) * h);
+                vertices.push(MeshVertex {
+                    pos,
+                    normal,
+                    uv: [(su + 1.0) * 0.5, (1.0 - sv) * 0.5],
+                });
+            }
+            indices.extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+        }
+        Self::new(device, &vertices, &indices)
+    }
+
+    pub fn index_count(&self) -> u32 {
+        self.index_count
+    }
+}
+
+/// Renders [`Mesh`]es with lambert shading and an optional texture. Camera and light are shared
+/// by all draws through a uniform, see [`MeshPipeline::update_camera`], while model matrix and
+/// color are set per draw.
+pub struct MeshPipeline {
+    pipeline: RenderPipeline,
+    camera_buffer: Buffer,
+    camera_bind_group: BindGroup,
+    untextured_bind_group: BindGroup,
+}
+
+impl MeshPipeline {
+    /// Pass the window's [`depth_format`](crate::window::GlassWindow::depth_format) to depth test
+    /// against its depth view
+    pub fn new(
+        device_context: &DeviceContext,
+        color_target_state: wgpu::ColorTargetState,
+        depth_format: Option<TextureFormat>,
+        sample_count: u32,
+    ) -> Result<MeshPipeline, GlassError> {
+        let device = device_context.device();
+        require_push_constants(
+            device,
+            "MeshPipeline",
+            std::mem::size_of::<MeshPushConstants>(),
+        )?;
+        let pipeline = Self::new_render_pipeline(
+            device,
+            device_context.pipeline_cache(),
+            color_target_state,
+            depth_format,
+            sample_count,
+        );
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Mesh Camera Buffer"),
+            contents: bytemuck::cast_slice(&[MeshCameraUniform::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+            label: Some("mesh_camera_bind_group"),
+        });
+        // Bound when drawing without a texture, it isn't sampled
+        let empty = Texture::empty(
+            device,
+            "mesh_untextured",
+            Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            1,
+            TextureFormat::Rgba8Unorm,
+            TextureUsages::TEXTURE_BINDING,
+        );
+        let untextured_bind_group = Self::texture_bind_group(
+            device,
+            &pipeline.get_bind_group_layout(1),
+            &empty.views[0],
+            &device.create_sampler(&SamplerDescriptor::default()),
+        );
+        Ok(MeshPipeline {
+            pipeline,
+            camera_buffer,
+            camera_bind_group,
+            untextured_bind_group,
+        })
+    }
+
+    pub fn new_render_pipeline(
+        device: &Device,
+        cache: Option<&PipelineCache>,
+        color_target_state: wgpu::ColorTargetState,
+        depth_format: Option<TextureFormat>,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        let camera_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+                label: Some("mesh_camera_bind_group_layout"),
+            });
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float {
+                                filterable: true,
+                            },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+                label: Some("mesh_texture_bind_group_layout"),
+            });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Mesh Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("mesh.wgsl"))),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mesh Pipeline Layout"),
+            bind_group_layouts: &[&camera_bind_group_layout, &texture_bind_group_layout],
+            push_constant_ranges: &[PushConstantRange {
+                stages: ShaderStages::VERTEX_FRAGMENT,
+                range: 0..std::mem::size_of::<MeshPushConstants>() as u32,
+            }],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Mesh Render Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[MeshVertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(color_target_state)],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: depth_format.map(|format| DepthStencilState {
+                format,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::Less,
+                stencil: Default::default(),
+                bias: Default::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache,
+        })
+    }
+
+    fn texture_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        image: &TextureView,
+        sampler: &Sampler,
+    ) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(image),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+            label: Some("mesh_texture_bind_group"),
+        })
+    }
+
+    /// Bind a texture for [`MeshPipeline::draw`]
+    pub fn create_bind_group(
+        &self,
+        device: &Device,
+        image: &TextureView,
+        sampler: &Sampler,
+    ) -> BindGroup {
+        Self::texture_bind_group(
+            device,
+            &self.pipeline.get_bind_group_layout(1),
+            image,
+            sampler,
+        )
+    }
+
+    /// Set the camera and light for following draws. `light_dir` points towards the light and
+    /// `ambient` is the light level of surfaces facing away from it.
+    pub fn update_camera(
+        &self,
+        queue: &Queue,
+        view_proj: [[f32; 4]; 4],
+        light_dir: [f32; 3],
+        ambient: f32,
+    ) {
+        queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::cast_slice(&[MeshCameraUniform {
+                view_proj,
+                light: [light_dir[0], light_dir[1], light_dir[2], ambient],
+            }]),
+        );
+    }
+
+    pub fn draw<'r>(
+        &'r self,
+        rpass: &mut RenderPass<'r>,
+        mesh: &'r Mesh,
+        texture: Option<&'r BindGroup>,
+        model: [[f32; 4]; 4],
+        color: [f32; 4],
+    ) {
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.camera_bind_group, &[]);
+        rpass.set_bind_group(1, texture.unwrap_or(&self.untextured_bind_group), &[]);
+        rpass.set_vertex_buffer(0, mesh.vertices.slice(..));
+        rpass.set_index_buffer(mesh.indices.slice(..), IndexFormat::Uint32);
+        rpass.set_push_constants(
+            ShaderStages::VERTEX_FRAGMENT,
+            0,
+            bytemuck::cast_slice(&[MeshPushConstants {
+                model,
+                color,
+                params: [texture.is_some() as u32 as f32, 0.0, 0.0, 0.0],
+            }]),
+        );
+        rpass.draw_indexed(0..mesh.index_count, 0, 0..1);
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct MeshCameraUniform {
+    pub view_proj: [[f32; 4]; 4],
+    /// Direction towards the light & ambient light
+    pub light: [f32; 4],
+}
+
+impl Default for MeshCameraUniform {
+    fn default() -> Self {
+        Self {
+            view_proj: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+            light: [0.3, 1.0, 0.5, 0.2],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct MeshPushConstants {
+    pub model: [[f32; 4]; 4],
+    pub color: [f32; 4],
+    /// 1.0 if textured
+    pub params: [f32; 4],
+}
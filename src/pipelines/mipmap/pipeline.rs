@@ -0,0 +1,181 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use wgpu::{
+    AddressMode, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType,
+    ColorTargetState, ColorWrites, CommandEncoder, Device, FilterMode, Operations, PipelineCache,
+    RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, Sampler, SamplerBindingType,
+    SamplerDescriptor, ShaderModule, ShaderStages, TextureFormat, TextureSampleType,
+    TextureViewDescriptor, TextureViewDimension,
+};
+
+use crate::{device_context::DeviceContext, texture::Texture};
+
+/// Fills mip levels of a texture by repeatedly downsampling the previous level, starting from
+/// mip 0. The texture needs `TEXTURE_BINDING` and `RENDER_ATTACHMENT` usages and a filterable,
+/// renderable format. A pipeline is created for each format on first use.
+pub struct MipmapGenerator {
+    shader: ShaderModule,
+    bind_group_layout: BindGroupLayout,
+    sampler: Sampler,
+    pipelines: HashMap<TextureFormat, RenderPipeline>,
+}
+
+impl MipmapGenerator {
+    pub fn new(device: &Device) -> MipmapGenerator {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Mipmap Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("mipmap.wgsl"))),
+        });
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("mipmap_bind_group_layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float {
+                            filterable: true,
+                        },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    visibility: ShaderStages::FRAGMENT,
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    visibility: ShaderStages::FRAGMENT,
+                    count: None,
+                },
+            ],
+        });
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("mipmap_sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+        MipmapGenerator {
+            shader,
+            bind_group_layout,
+            sampler,
+            pipelines: HashMap::new(),
+        }
+    }
+
+    /// Number of mip levels of a full mip chain for a size
+    pub fn mip_count(width: u32, height: u32) -> u32 {
+        32 - width.max(height).max(1).leading_zeros()
+    }
+
+    fn create_pipeline(
+        &self,
+        device: &Device,
+        cache: Option<&PipelineCache>,
+        format: TextureFormat,
+    ) -> RenderPipeline {
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mipmap Pipeline Layout"),
+            bind_group_layouts: &[&self.bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Mipmap Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &self.shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &self.shader,
+                entry_point: Some("fragment"),
+                compilation_options: Default::default(),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache,
+        })
+    }
+
+    /// Record passes writing mips `1..` of every layer of a texture from its mip 0
+    pub fn generate(
+        &mut self,
+        device_context: &DeviceContext,
+        encoder: &mut CommandEncoder,
+        texture: &Texture,
+    ) {
+        let device = device_context.device();
+        let wgpu_texture = &texture.texture;
+        let mip_count = wgpu_texture.mip_level_count();
+        if mip_count <= 1 {
+            return;
+        }
+        let format = wgpu_texture.format();
+        if !self.pipelines.contains_key(&format) {
+            let pipeline = self.create_pipeline(device, device_context.pipeline_cache(), format);
+            self.pipelines.insert(format, pipeline);
+        }
+        let pipeline = &self.pipelines[&format];
+        for layer in 0..wgpu_texture.depth_or_array_layers() {
+            let mip_view = |mip| {
+                wgpu_texture.create_view(&TextureViewDescriptor {
+                    label: Some("mipmap_view"),
+                    dimension: Some(TextureViewDimension::D2),
+                    base_mip_level: mip,
+                    mip_level_count: Some(1),
+                    base_array_layer: layer,
+                    array_layer_count: Some(1),
+                    ..Default::default()
+                })
+            };
+            let mut source = mip_view(0);
+            for mip in 1..mip_count {
+                let target = mip_view(mip);
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    layout: &self.bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&source),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(&self.sampler),
+                        },
+                    ],
+                    label: Some("mipmap_bind_group"),
+                });
+                let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: Some("mipmap_pass"),
+                    color_attachments: &[Some(RenderPassColorAttachment {
+                        view: &target,
+                        resolve_target: None,
+                        ops: Operations::default(),
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+                r_pass.set_pipeline(pipeline);
+                r_pass.set_bind_group(0, &bind_group, &[]);
+                r_pass.draw(0..3, 0..1);
+                drop(r_pass);
+                source = target;
+            }
+        }
+        for mip in 1..mip_count {
+            texture.mark_mip_initialized(mip);
+        }
+    }
+}
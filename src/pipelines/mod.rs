@@ -1,13 +1,63 @@
 mod bloom;
+mod blur;
+mod crt;
+mod draw_params;
+mod error;
+mod frame_blend;
+mod light2d;
 mod line;
+mod mesh;
+mod mipmap;
+mod nine_slice;
 mod paste;
+mod polyline;
+mod post_effects;
 mod quad;
+mod shape;
+#[cfg(feature = "text")]
+mod text;
 mod tonemapping;
 mod vertex;
 
 pub use bloom::*;
+pub use blur::*;
+pub use crt::*;
+pub(crate) use draw_params::*;
+pub use error::*;
+pub use frame_blend::*;
+pub use light2d::*;
 pub use line::*;
+pub use mesh::*;
+pub use mipmap::*;
+pub use nine_slice::*;
 pub use paste::*;
+pub use polyline::*;
+pub use post_effects::*;
 pub use quad::*;
+pub use shape::*;
+#[cfg(feature = "text")]
+pub use text::*;
 pub use tonemapping::*;
 pub use vertex::*;
+use wgpu::{Device, Features};
+
+use crate::GlassError;
+
+/// Check that the device can create a pipeline using `size` bytes of push constants. Pipelines
+/// without a uniform fallback return this error instead of failing validation.
+pub(crate) fn require_push_constants(
+    device: &Device,
+    pipeline: &str,
+    size: usize,
+) -> Result<(), GlassError> {
+    if device.features().contains(Features::PUSH_CONSTANTS)
+        && device.limits().max_push_constant_size as usize >= size
+    {
+        Ok(())
+    } else {
+        Err(GlassError::PushConstantsNotSupported(format!(
+            "{} needs {} bytes of push constants",
+            pipeline, size
+        )))
+    }
+}
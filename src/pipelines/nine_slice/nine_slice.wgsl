@@ -0,0 +1,69 @@
+struct PushConstants {
+    view_proj: mat4x4<f32>,
+    // Center xy, size zw
+    rect: vec4<f32>,
+    // Left, right, top, bottom in texels
+    insets: vec4<f32>,
+    tint: vec4<f32>,
+    // x: world units per texel of the borders
+    params: vec4<f32>,
+}
+var<push_constant> pc: PushConstants;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var input_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var s: sampler;
+
+// Grid lines from the min edge to the max edge of one axis
+fn grid_lines(min_edge: f32, size: f32, inset_min: f32, inset_max: f32) -> vec4<f32> {
+    var border_min = inset_min * pc.params.x;
+    var border_max = inset_max * pc.params.x;
+    // Shrink borders when the rect is smaller than them
+    let shrink = min(1.0, size / max(border_min + border_max, 0.0001));
+    border_min *= shrink;
+    border_max *= shrink;
+    return vec4<f32>(min_edge, min_edge + border_min, min_edge + size - border_max, min_edge + size);
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    // 9 quads of 2 triangles, corners as (x, y) offsets within the quad
+    var corners = array<vec2<u32>, 6>(
+        vec2<u32>(0u, 0u),
+        vec2<u32>(1u, 0u),
+        vec2<u32>(1u, 1u),
+        vec2<u32>(0u, 0u),
+        vec2<u32>(1u, 1u),
+        vec2<u32>(0u, 1u),
+    );
+    let cell = vertex_index / 6u;
+    let corner = corners[vertex_index % 6u];
+    // Column from left, row from bottom
+    let column = cell % 3u + corner.x;
+    let row = cell / 3u + corner.y;
+
+    let tex_size = vec2<f32>(textureDimensions(input_texture));
+    let min_edge = pc.rect.xy - pc.rect.zw * 0.5;
+    var xs = grid_lines(min_edge.x, pc.rect.z, pc.insets.x, pc.insets.y);
+    // Rows go up in world space, bottom inset first
+    var ys = grid_lines(min_edge.y, pc.rect.w, pc.insets.w, pc.insets.z);
+    var us = vec4<f32>(0.0, pc.insets.x / tex_size.x, 1.0 - pc.insets.y / tex_size.x, 1.0);
+    // Texture v goes down
+    var vs = vec4<f32>(1.0, 1.0 - pc.insets.w / tex_size.y, pc.insets.z / tex_size.y, 0.0);
+
+    var out: VertexOutput;
+    out.clip_position = pc.view_proj * vec4<f32>(xs[column], ys[row], 0.0, 1.0);
+    out.tex_coords = vec2<f32>(us[column], vs[row]);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(input_texture, s, in.tex_coords) * pc.tint;
+}
@@ -0,0 +1,211 @@
+use std::borrow::Cow;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroup, Device, PipelineCache, PushConstantRange, RenderPass, RenderPipeline, Sampler,
+    ShaderStages, TextureView,
+};
+
+use crate::{device_context::DeviceContext, pipelines::require_push_constants, GlassError};
+
+/// Where and how to draw a nine-slice texture
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct NineSlice {
+    /// Center of the rect in world units
+    pub pos: [f32; 2],
+    /// Size of the rect in world units
+    pub size: [f32; 2],
+    /// Left, right, top & bottom borders of the texture in texels. These stay fixed while the
+    /// center is stretched.
+    pub insets: [f32; 4],
+    /// World units per border texel
+    pub border_scale: f32,
+    pub tint: [f32; 4],
+}
+
+impl Default for NineSlice {
+    fn default() -> Self {
+        Self {
+            pos: [0.0; 2],
+            size: [1.0; 2],
+            insets: [0.0; 4],
+            border_scale: 1.0,
+            tint: [1.0; 4],
+        }
+    }
+}
+
+/// Draws a texture as a nine-slice (9-patch), e.g. for UI panels: corners keep their size,
+/// edges stretch along one axis and the center along both. Borders shrink when the rect is
+/// smaller than them. Vertices are generated in the shader, so no buffers are bound.
+pub struct NineSlicePipeline {
+    pipeline: RenderPipeline,
+}
+
+impl NineSlicePipeline {
+    pub fn new(
+        device_context: &DeviceContext,
+        color_target_state: wgpu::ColorTargetState,
+        sample_count: u32,
+    ) -> Result<NineSlicePipeline, GlassError> {
+        let device = device_context.device();
+        require_push_constants(
+            device,
+            "NineSlicePipeline",
+            std::mem::size_of::<NineSlicePushConstants>(),
+        )?;
+        Ok(Self {
+            pipeline: Self::new_render_pipeline(
+                device,
+                device_context.pipeline_cache(),
+                color_target_state,
+                sample_count,
+            ),
+        })
+    }
+
+    pub fn new_render_pipeline(
+        device: &Device,
+        cache: Option<&PipelineCache>,
+        color_target_state: wgpu::ColorTargetState,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        // Vertex stage reads texture dimensions
+                        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float {
+                                filterable: true,
+                            },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+                label: Some("nine_slice_bind_group_layout"),
+            });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Nine Slice Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("nine_slice.wgsl"))),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Nine Slice Pipeline Layout"),
+            bind_group_layouts: &[&texture_bind_group_layout],
+            push_constant_ranges: &[PushConstantRange {
+                stages: ShaderStages::VERTEX_FRAGMENT,
+                range: 0..std::mem::size_of::<NineSlicePushConstants>() as u32,
+            }],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Nine Slice Render Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(color_target_state)],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache,
+        })
+    }
+
+    pub fn create_bind_group(
+        &self,
+        device: &Device,
+        image: &TextureView,
+        sampler: &Sampler,
+    ) -> BindGroup {
+        let bind_group_layout = self.pipeline.get_bind_group_layout(0);
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(image),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+            label: Some("nine_slice_bind_group"),
+        })
+    }
+
+    pub fn draw<'r>(
+        &'r self,
+        rpass: &mut RenderPass<'r>,
+        bind_group: &'r BindGroup,
+        view_proj: [[f32; 4]; 4],
+        nine_slice: NineSlice,
+    ) {
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, bind_group, &[]);
+        rpass.set_push_constants(
+            ShaderStages::VERTEX_FRAGMENT,
+            0,
+            bytemuck::cast_slice(&[NineSlicePushConstants::new(view_proj, nine_slice)]),
+        );
+        rpass.draw(0..54, 0..1);
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct NineSlicePushConstants {
+    pub view_proj: [[f32; 4]; 4],
+    pub rect: [f32; 4],
+    pub insets: [f32; 4],
+    pub tint: [f32; 4],
+    pub params: [f32; 4],
+}
+
+impl NineSlicePushConstants {
+    pub fn new(view_proj: [[f32; 4]; 4], nine_slice: NineSlice) -> NineSlicePushConstants {
+        NineSlicePushConstants {
+            view_proj,
+            rect: [
+                nine_slice.pos[0],
+                nine_slice.pos[1],
+                nine_slice.size[0],
+                nine_slice.size[1],
+            ],
+            insets: nine_slice.insets,
+            tint: nine_slice.tint,
+            params: [nine_slice.border_scale, 0.0, 0.0, 0.0],
+        }
+    }
+}
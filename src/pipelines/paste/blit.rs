@@ -0,0 +1,179 @@
+use std::{borrow::Cow, sync::Arc};
+
+use wgpu::{
+    AddressMode, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType,
+    Color, ColorTargetState, ColorWrites, CommandEncoder, Device, FilterMode, LoadOp, Operations,
+    RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, Sampler, SamplerBindingType,
+    SamplerDescriptor, ShaderStages, StoreOp, TextureFormat, TextureSampleType, TextureView,
+    TextureViewDimension,
+};
+
+use crate::{device_context::DeviceContext, utils::GpuProfiler, window::Viewport};
+
+/// How [`BlitPipeline::blit`] scales the source to the destination
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BlitFilter {
+    /// Stretch over the whole destination with nearest sampling
+    Nearest,
+    /// Stretch over the whole destination with linear sampling
+    Linear,
+    /// Nearest sampling into a viewport of the destination, usually from
+    /// [`Viewport::fit_pixel_perfect`]. The rest of the destination is cleared to black.
+    PixelPerfect(Viewport),
+}
+
+/// Copies a texture view into another of any size, converting to the destination format. Unlike
+/// [`PastePipeline`](super::PastePipeline), no bind groups or placement are needed from the
+/// caller. The source format must be filterable.
+pub struct BlitPipeline {
+    blit_pipeline: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+    nearest_sampler: Sampler,
+    linear_sampler: Sampler,
+    profiler: Option<Arc<GpuProfiler>>,
+}
+
+impl BlitPipeline {
+    pub fn new(
+        device_context: &DeviceContext,
+        target_texture_format: TextureFormat,
+    ) -> BlitPipeline {
+        let device = device_context.device();
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("blit_bind_group_layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float {
+                            filterable: true,
+                        },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    visibility: ShaderStages::FRAGMENT,
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    visibility: ShaderStages::FRAGMENT,
+                    count: None,
+                },
+            ],
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Blit Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("blit.wgsl"))),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Blit Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let blit_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Blit Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fragment"),
+                compilation_options: Default::default(),
+                targets: &[Some(ColorTargetState {
+                    format: target_texture_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: device_context.pipeline_cache(),
+        });
+        let sampler = |filter| {
+            device.create_sampler(&SamplerDescriptor {
+                label: Some("blit_sampler"),
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                mag_filter: filter,
+                min_filter: filter,
+                ..Default::default()
+            })
+        };
+
+        BlitPipeline {
+            blit_pipeline,
+            bind_group_layout,
+            nearest_sampler: sampler(FilterMode::Nearest),
+            linear_sampler: sampler(FilterMode::Linear),
+            profiler: None,
+        }
+    }
+
+    /// Time the pipeline's passes, see [`GpuProfiler::pass_timestamp_writes`]
+    pub fn set_profiler(&mut self, profiler: Option<Arc<GpuProfiler>>) {
+        self.profiler = profiler;
+    }
+
+    /// Write `src` into `dst`, which must have the format the pipeline was created with
+    pub fn blit(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        src: &TextureView,
+        dst: &TextureView,
+        filter: BlitFilter,
+    ) {
+        let sampler = match filter {
+            BlitFilter::Linear => &self.linear_sampler,
+            BlitFilter::Nearest | BlitFilter::PixelPerfect(_) => &self.nearest_sampler,
+        };
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(src),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+            label: Some("blit_bind_group"),
+        });
+        let load = match filter {
+            BlitFilter::PixelPerfect(_) => LoadOp::Clear(Color::BLACK),
+            _ => LoadOp::Load,
+        };
+        let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("blit_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: dst,
+                resolve_target: None,
+                ops: Operations {
+                    load,
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: self
+                .profiler
+                .as_ref()
+                .and_then(|profiler| profiler.pass_timestamp_writes("glass::blit")),
+            occlusion_query_set: None,
+        });
+        if let BlitFilter::PixelPerfect(viewport) = filter {
+            viewport.apply(&mut r_pass);
+        }
+        r_pass.set_pipeline(&self.blit_pipeline);
+        r_pass.set_bind_group(0, &bind_group, &[]);
+        r_pass.draw(0..3, 0..1);
+    }
+}
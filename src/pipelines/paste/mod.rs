@@ -1,3 +1,5 @@
+mod blit;
 mod pipeline;
 
-pub use pipeline::PastePipeline;
+pub use blit::{BlitFilter, BlitPipeline};
+pub use pipeline::{PasteOp, PasteOptions, PastePipeline};
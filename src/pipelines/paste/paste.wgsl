@@ -11,18 +11,33 @@ struct VertexOutput {
 
 struct PushConstants {
     tint: vec4<f32>,
-    scale: vec2<f32>,
+    // Uv x, y, width & height
+    source_rect: vec4<f32>,
+    // Pixels
+    size: vec2<f32>,
     offset: vec2<f32>,
+    pivot: vec2<f32>,
+    output_size: vec2<f32>,
+    // Cos & sin
+    rotation: vec2<f32>,
+    // 1.0 if flipped
+    flip: vec2<f32>,
 }
 var<push_constant> pc: PushConstants;
 
-// https://github.com/bevyengine/bevy/blob/09df19bcadb52d2f4dbbc899aef74cafa9091538/crates/bevy_core_pipeline/src/fullscreen_vertex_shader/fullscreen.wgsl
 @vertex
 fn vs_main(
    quad: VertexInput,
 ) -> VertexOutput {
-    let world_position = vec4<f32>(quad.position.xy * pc.scale + pc.offset, 0.0, 1.0);
-    return VertexOutput(world_position, quad.tex_coords);
+    // Pixel space, y down like tex coords
+    let local = (quad.tex_coords - pc.pivot) * pc.size;
+    let c = pc.rotation.x;
+    let s = pc.rotation.y;
+    let rotated = vec2<f32>(local.x * c + local.y * s, -local.x * s + local.y * c);
+    let pixel = pc.offset + rotated;
+    let ndc = vec2<f32>(pixel.x / pc.output_size.x * 2.0 - 1.0, 1.0 - pixel.y / pc.output_size.y * 2.0);
+    let uv = mix(quad.tex_coords, 1.0 - quad.tex_coords, pc.flip);
+    return VertexOutput(vec4<f32>(ndc, 0.0, 1.0), pc.source_rect.xy + uv * pc.source_rect.zw);
 }
 
 @group(0) @binding(0)
@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, sync::Arc};
 
 use bytemuck::{Pod, Zeroable};
 use wgpu::{
@@ -10,22 +10,65 @@ use wgpu::{
 };
 
 use crate::{
-    pipelines::{TexturedVertex, QUAD_INDICES, TEXTURED_QUAD_VERTICES},
+    device_context::DeviceContext,
+    pipelines::{require_push_constants, TexturedVertex, QUAD_INDICES, TEXTURED_QUAD_VERTICES},
     texture::Texture,
+    utils::GpuProfiler,
+    GlassError,
 };
 
+/// Placement of a pasted image, see [`PastePipeline::paste_with_options`]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PasteOptions {
+    pub tint: [f32; 4],
+    /// Size on the output in pixels
+    pub size: [f32; 2],
+    /// Position of the pivot on the output in pixels
+    pub offset: [f32; 2],
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// Part of the input to paste as uv x, y, width & height, e.g. a sprite sheet cell
+    pub source_rect: [f32; 4],
+    /// Counter clockwise rotation around the pivot in radians
+    pub rotation: f32,
+    /// Point of the pasted image placed at `offset` and rotated around, 0..1 from top left
+    pub pivot: [f32; 2],
+}
+
+impl Default for PasteOptions {
+    fn default() -> Self {
+        Self {
+            tint: [1.0; 4],
+            size: [1.0; 2],
+            offset: [0.0; 2],
+            flip_x: false,
+            flip_y: false,
+            source_rect: [0.0, 0.0, 1.0, 1.0],
+            rotation: 0.0,
+            pivot: [0.5; 2],
+        }
+    }
+}
+
 pub struct PastePipeline {
     paste_pipeline: RenderPipeline,
     vertices: Buffer,
     indices: Buffer,
+    profiler: Option<Arc<GpuProfiler>>,
 }
 
 impl PastePipeline {
     pub fn new(
-        device: &Device,
+        device_context: &DeviceContext,
         target_texture_format: TextureFormat,
         is_nearest: bool,
-    ) -> PastePipeline {
+    ) -> Result<PastePipeline, GlassError> {
+        let device = device_context.device();
+        require_push_constants(
+            device,
+            "PastePipeline",
+            std::mem::size_of::<PastePushConstants>(),
+        )?;
         let vertices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Paste Vertex Buffer"),
             contents: bytemuck::cast_slice(
@@ -138,14 +181,15 @@ impl PastePipeline {
             depth_stencil: None,
             multisample: wgpu::MultisampleState::default(),
             multiview: None,
-            cache: None,
+            cache: device_context.pipeline_cache(),
         });
 
-        PastePipeline {
+        Ok(PastePipeline {
             paste_pipeline,
             vertices,
             indices,
-        }
+            profiler: None,
+        })
     }
 
     pub fn create_input_bind_group(
@@ -172,6 +216,13 @@ impl PastePipeline {
         bind_group
     }
 
+    /// Time the pipeline's passes, see [`GpuProfiler::pass_timestamp_writes`]
+    pub fn set_profiler(&mut self, profiler: Option<Arc<GpuProfiler>>) {
+        self.profiler = profiler;
+    }
+
+    /// Paste the whole input centered at `offset`, see [`PastePipeline::paste_with_options`] for
+    /// source rects & rotation
     #[allow(clippy::too_many_arguments)]
     pub fn paste(
         &self,
@@ -185,48 +236,103 @@ impl PastePipeline {
         flip_x: bool,
         flip_y: bool,
     ) {
-        let image_size = [size[0] / output.size[0], size[1] / output.size[1]];
-        let push_constants: PastePushConstants = PastePushConstants {
+        self.paste_with_options(encoder, ops, input_image_bind_group, output, PasteOptions {
             tint,
-            scale: [
-                image_size[0] * if flip_x { -1.0 } else { 1.0 },
-                image_size[1] * if flip_y { -1.0 } else { 1.0 },
-            ],
-            offset: [
-                (2.0 * offset[0] - output.size[0]) / output.size[0],
-                -(2.0 * offset[1] - output.size[1]) / output.size[1],
-            ],
-        };
-        {
-            let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("paste_pass"),
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &output.views[0],
-                    resolve_target: None,
-                    ops,
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-            r_pass.set_pipeline(&self.paste_pipeline);
-            r_pass.set_bind_group(0, input_image_bind_group, &[]);
-            r_pass.set_vertex_buffer(0, self.vertices.slice(..));
-            r_pass.set_index_buffer(self.indices.slice(..), wgpu::IndexFormat::Uint16);
+            size,
+            offset,
+            flip_x,
+            flip_y,
+            ..Default::default()
+        });
+    }
+
+    pub fn paste_with_options(
+        &self,
+        encoder: &mut CommandEncoder,
+        ops: Operations<Color>,
+        input_image_bind_group: &BindGroup,
+        output: &Texture,
+        options: PasteOptions,
+    ) {
+        self.paste_many(encoder, ops, output, &[PasteOp {
+            input_image_bind_group,
+            options,
+        }]);
+    }
+
+    /// Paste many images in order within a single render pass. Cheaper than a
+    /// [`PastePipeline::paste`] per image, which begins a pass for each.
+    pub fn paste_many(
+        &self,
+        encoder: &mut CommandEncoder,
+        ops: Operations<Color>,
+        output: &Texture,
+        pastes: &[PasteOp],
+    ) {
+        let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("paste_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: &output.views[0],
+                resolve_target: None,
+                ops,
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: self
+                .profiler
+                .as_ref()
+                .and_then(|profiler| profiler.pass_timestamp_writes("glass::paste")),
+            occlusion_query_set: None,
+        });
+        r_pass.set_pipeline(&self.paste_pipeline);
+        r_pass.set_vertex_buffer(0, self.vertices.slice(..));
+        r_pass.set_index_buffer(self.indices.slice(..), wgpu::IndexFormat::Uint16);
+        for paste in pastes {
+            r_pass.set_bind_group(0, paste.input_image_bind_group, &[]);
             r_pass.set_push_constants(
                 ShaderStages::VERTEX_FRAGMENT,
                 0,
-                bytemuck::cast_slice(&[push_constants]),
+                bytemuck::cast_slice(&[PastePushConstants::new(&paste.options, output.size)]),
             );
             r_pass.draw_indexed(0..(QUAD_INDICES.len() as u32), 0, 0..1);
         }
     }
 }
 
+/// One image of [`PastePipeline::paste_many`]
+#[derive(Debug, Copy, Clone)]
+pub struct PasteOp<'a> {
+    pub input_image_bind_group: &'a BindGroup,
+    pub options: PasteOptions,
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Pod, Zeroable)]
 pub struct PastePushConstants {
     tint: [f32; 4],
-    scale: [f32; 2],
+    source_rect: [f32; 4],
+    size: [f32; 2],
     offset: [f32; 2],
+    pivot: [f32; 2],
+    output_size: [f32; 2],
+    /// Cos & sin of the rotation
+    rotation: [f32; 2],
+    flip: [f32; 2],
+}
+
+impl PastePushConstants {
+    pub fn new(options: &PasteOptions, output_size: [f32; 2]) -> PastePushConstants {
+        PastePushConstants {
+            tint: options.tint,
+            source_rect: options.source_rect,
+            size: options.size,
+            offset: options.offset,
+            pivot: options.pivot,
+            output_size,
+            rotation: [options.rotation.cos(), options.rotation.sin()],
+            flip: [
+                if options.flip_x { 1.0 } else { 0.0 },
+                if options.flip_y { 1.0 } else { 0.0 },
+            ],
+        }
+    }
 }
@@ -0,0 +1,5 @@
+mod pipeline;
+mod tessellate;
+
+pub use pipeline::{Polyline, PolylinePipeline, PolylinePushConstants};
+pub use tessellate::{LineCap, LineJoin, PolylineStyle, PolylineVertex};
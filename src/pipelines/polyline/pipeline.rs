@@ -0,0 +1,186 @@
+use std::borrow::Cow;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BufferUsages, Device, PipelineCache, PushConstantRange, Queue, RenderPass, RenderPipeline,
+    ShaderStages,
+};
+
+use super::tessellate::{tessellate, PolylineStyle, PolylineVertex};
+use crate::{
+    device_context::DeviceContext,
+    pipelines::{require_push_constants, ColoredVertex, LineWidth},
+    utils::GrowableBuffer,
+    GlassError,
+};
+
+/// Thick line geometry tessellated on the CPU from point lists. Tessellation doesn't depend on
+/// the width, so the same geometry can be drawn with any [`LineWidth`].
+pub struct Polyline {
+    vertices: GrowableBuffer<PolylineVertex>,
+    scratch: Vec<PolylineVertex>,
+}
+
+impl Polyline {
+    pub fn new(device: &Device) -> Polyline {
+        Polyline {
+            vertices: GrowableBuffer::new(
+                device,
+                Some("Polyline Vertices"),
+                BufferUsages::VERTEX,
+                1024,
+            ),
+            scratch: vec![],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    /// Add a line strip through `points`, colors are interpolated along the line
+    pub fn add(&mut self, points: &[ColoredVertex], style: PolylineStyle) {
+        tessellate(points, style, &mut self.scratch);
+        self.vertices.extend(self.scratch.drain(..));
+    }
+
+    /// Write lines added since the last clear to the GPU
+    pub fn upload(&mut self, device: &Device, queue: &Queue) {
+        self.vertices.upload(device, queue);
+    }
+
+    /// Tessellated vertices collected since the last clear
+    pub fn vertices(&self) -> &[PolylineVertex] {
+        self.vertices.data()
+    }
+}
+
+/// Draws thick lines with joins and caps
+pub struct PolylinePipeline {
+    pipeline: RenderPipeline,
+}
+
+impl PolylinePipeline {
+    pub fn new(
+        device_context: &DeviceContext,
+        color_target_state: wgpu::ColorTargetState,
+        sample_count: u32,
+    ) -> Result<PolylinePipeline, GlassError> {
+        let device = device_context.device();
+        require_push_constants(
+            device,
+            "PolylinePipeline",
+            std::mem::size_of::<PolylinePushConstants>(),
+        )?;
+        Ok(Self {
+            pipeline: Self::new_render_pipeline(
+                device,
+                device_context.pipeline_cache(),
+                color_target_state,
+                sample_count,
+            ),
+        })
+    }
+
+    pub fn new_render_pipeline(
+        device: &Device,
+        cache: Option<&PipelineCache>,
+        color_target_state: wgpu::ColorTargetState,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Polyline Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("polyline.wgsl"))),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Polyline Pipeline Layout"),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[PushConstantRange {
+                stages: ShaderStages::VERTEX_FRAGMENT,
+                range: 0..std::mem::size_of::<PolylinePushConstants>() as u32,
+            }],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Polyline Render Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[PolylineVertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(color_target_state)],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache,
+        })
+    }
+
+    /// Draw the uploaded lines of a [`Polyline`]. `viewport` is the size of the render target in
+    /// pixels.
+    pub fn draw<'r>(
+        &'r self,
+        rpass: &mut RenderPass<'r>,
+        view_proj: [[f32; 4]; 4],
+        polyline: &'r Polyline,
+        width: LineWidth,
+        viewport: [f32; 2],
+    ) {
+        let vertices = &polyline.vertices;
+        if vertices.uploaded_len() == 0 {
+            return;
+        }
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_vertex_buffer(0, vertices.slice(vertices.range()));
+        rpass.set_push_constants(
+            ShaderStages::VERTEX_FRAGMENT,
+            0,
+            bytemuck::cast_slice(&[PolylinePushConstants::new(view_proj, width, viewport)]),
+        );
+        rpass.draw(vertices.range(), 0..1);
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct PolylinePushConstants {
+    pub view_proj: [[f32; 4]; 4],
+    /// Width, 1.0 if width is in pixels, viewport width & height
+    pub params: [f32; 4],
+}
+
+impl PolylinePushConstants {
+    pub fn new(
+        view_proj: [[f32; 4]; 4],
+        width: LineWidth,
+        viewport: [f32; 2],
+    ) -> PolylinePushConstants {
+        let (width, pixels) = match width {
+            LineWidth::Pixels(width) => (width, 1.0),
+            LineWidth::World(width) => (width, 0.0),
+        };
+        PolylinePushConstants {
+            view_proj,
+            params: [width, pixels, viewport[0], viewport[1]],
+        }
+    }
+}
@@ -0,0 +1,48 @@
+struct PushConstants {
+    view_proj: mat4x4<f32>,
+    // x: width, y: 1.0 if width is in pixels, zw: viewport size in pixels
+    params: vec4<f32>,
+}
+var<push_constant> pc: PushConstants;
+
+struct VertexInput {
+    @location(0) position: vec4<f32>,
+    @location(1) color: vec4<f32>,
+    // Extrusion from the line center in units of half width
+    @location(2) offset: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    let half_width = pc.params.x * 0.5;
+    if (pc.params.y > 0.5) {
+        // Extrude in screen space so the width stays constant regardless of zoom
+        let viewport = pc.params.zw;
+        let clip = pc.view_proj * model.position;
+        let screen_dir = (pc.view_proj * vec4<f32>(model.offset, 0.0, 0.0)).xy * viewport * 0.5;
+        var offset = vec2<f32>(0.0);
+        let len = length(screen_dir);
+        if (len > 0.0) {
+            offset = screen_dir / len * length(model.offset) * half_width / (viewport * 0.5);
+        }
+        out.clip_position = vec4<f32>(clip.xy + offset * clip.w, clip.zw);
+    } else {
+        let world = model.position + vec4<f32>(model.offset * half_width, 0.0, 0.0);
+        out.clip_position = pc.view_proj * world;
+    }
+    out.color = model.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
@@ -0,0 +1,394 @@
+use std::f32::consts::PI;
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::pipelines::ColoredVertex;
+
+/// Max angle covered by one triangle of round joins and caps
+const ROUND_STEP: f32 = PI / 8.0;
+/// Consecutive points closer than this are merged
+const EPSILON: f32 = 1e-6;
+
+/// How segments of a [`Polyline`](super::Polyline) are connected
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LineJoin {
+    /// Sharp corners, falling back to bevel past [`PolylineStyle::miter_limit`]
+    #[default]
+    Miter,
+    Bevel,
+    Round,
+}
+
+/// How the ends of an open [`Polyline`](super::Polyline) are drawn
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LineCap {
+    /// Ends exactly at the end points
+    #[default]
+    Butt,
+    /// Extends past the end points by half the width
+    Square,
+    Round,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PolylineStyle {
+    pub join: LineJoin,
+    pub cap: LineCap,
+    /// Connect the last point back to the first. Caps are not drawn for closed lines.
+    pub closed: bool,
+    /// Max ratio of miter length to half width before a miter join is beveled
+    pub miter_limit: f32,
+}
+
+impl Default for PolylineStyle {
+    fn default() -> Self {
+        Self {
+            join: LineJoin::default(),
+            cap: LineCap::default(),
+            closed: false,
+            miter_limit: 4.0,
+        }
+    }
+}
+
+/// A vertex of tessellated polyline geometry. `offset` is the extrusion from the line's center
+/// in units of half width, applied in the shader.
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug, Pod, Zeroable)]
+pub struct PolylineVertex {
+    pub position: [f32; 4],
+    pub color: [f32; 4],
+    pub offset: [f32; 2],
+}
+
+impl PolylineVertex {
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        use std::mem;
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<PolylineVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: 2 * mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+            ],
+        }
+    }
+}
+
+/// Tessellate a line strip through `points` into a triangle list appended to `out`. Geometry is
+/// generated in xy, z and w of each point are kept.
+pub(crate) fn tessellate(
+    points: &[ColoredVertex],
+    style: PolylineStyle,
+    out: &mut Vec<PolylineVertex>,
+) {
+    let mut merged: Vec<ColoredVertex> = Vec::with_capacity(points.len());
+    for p in points {
+        if !merged.last().is_some_and(|last| same_position(last, p)) {
+            merged.push(*p);
+        }
+    }
+    let mut points = merged;
+    if style.closed && points.len() > 1 && same_position(&points[0], &points[points.len() - 1]) {
+        points.pop();
+    }
+    let n = points.len();
+    if n < 2 {
+        return;
+    }
+    let closed = style.closed && n > 2;
+    let dir = |i: usize| normalize(sub(xy(&points[(i + 1) % n]), xy(&points[i])));
+
+    let segments = if closed { n } else { n - 1 };
+    for i in 0..segments {
+        let (a, b) = (&points[i], &points[(i + 1) % n]);
+        let normal = perp(dir(i));
+        let neg = scale(normal, -1.0);
+        out.extend([
+            vertex(a, normal),
+            vertex(b, normal),
+            vertex(b, neg),
+            vertex(a, normal),
+            vertex(b, neg),
+            vertex(a, neg),
+        ]);
+    }
+
+    let joins = if closed { 0..n } else { 1..n - 1 };
+    for i in joins {
+        join(out, &points[i], dir((i + n - 1) % n), dir(i), style);
+    }
+
+    if !closed {
+        cap(out, &points[0], scale(dir(0), -1.0), style.cap);
+        cap(out, &points[n - 1], dir(n - 2), style.cap);
+    }
+}
+
+/// Fill the outer side of the corner between incoming direction `d0` and outgoing `d1`. The
+/// inner side is already covered by the overlapping segments.
+fn join(
+    out: &mut Vec<PolylineVertex>,
+    p: &ColoredVertex,
+    d0: [f32; 2],
+    d1: [f32; 2],
+    style: PolylineStyle,
+) {
+    let turn = cross(d0, d1);
+    if turn.abs() < EPSILON {
+        // Straight continues need nothing, a full U-turn is closed like a cap
+        if dot(d0, d1) < 0.0 && style.join == LineJoin::Round {
+            fan(out, p, perp(d0), -PI);
+        }
+        return;
+    }
+    let side = if turn > 0.0 { -1.0 } else { 1.0 };
+    let a = scale(perp(d0), side);
+    let b = scale(perp(d1), side);
+    match style.join {
+        LineJoin::Miter => {
+            let bisector = normalize(add(a, b));
+            let miter_length = 1.0 / dot(bisector, a);
+            if miter_length <= style.miter_limit {
+                let tip = scale(bisector, miter_length);
+                triangle(out, p, [[0.0; 2], a, tip]);
+                triangle(out, p, [[0.0; 2], tip, b]);
+            } else {
+                triangle(out, p, [[0.0; 2], a, b]);
+            }
+        }
+        LineJoin::Bevel => triangle(out, p, [[0.0; 2], a, b]),
+        LineJoin::Round => fan(out, p, a, cross(a, b).atan2(dot(a, b))),
+    }
+}
+
+/// Cap an end point, `d` points away from the line
+fn cap(out: &mut Vec<PolylineVertex>, p: &ColoredVertex, d: [f32; 2], cap: LineCap) {
+    let normal = perp(d);
+    let neg = scale(normal, -1.0);
+    match cap {
+        LineCap::Butt => (),
+        LineCap::Square => {
+            triangle(out, p, [normal, add(normal, d), add(neg, d)]);
+            triangle(out, p, [normal, add(neg, d), neg]);
+        }
+        LineCap::Round => fan(out, p, normal, -PI),
+    }
+}
+
+/// Triangle fan around `p` rotating `from` by `angle` radians
+fn fan(out: &mut Vec<PolylineVertex>, p: &ColoredVertex, from: [f32; 2], angle: f32) {
+    let steps = (angle.abs() / ROUND_STEP).ceil().max(1.0) as u32;
+    let step = angle / steps as f32;
+    let mut prev = from;
+    for i in 1..=steps {
+        let (sin, cos) = (step * i as f32).sin_cos();
+        let next = [from[0] * cos - from[1] * sin, from[0] * sin + from[1] * cos];
+        triangle(out, p, [[0.0; 2], prev, next]);
+        prev = next;
+    }
+}
+
+fn triangle(out: &mut Vec<PolylineVertex>, p: &ColoredVertex, offsets: [[f32; 2]; 3]) {
+    out.extend(offsets.map(|offset| vertex(p, offset)));
+}
+
+fn vertex(p: &ColoredVertex, offset: [f32; 2]) -> PolylineVertex {
+    PolylineVertex {
+        position: p.position,
+        color: p.color,
+        offset,
+    }
+}
+
+fn same_position(a: &ColoredVertex, b: &ColoredVertex) -> bool {
+    let d = sub(xy(a), xy(b));
+    dot(d, d) < EPSILON * EPSILON
+}
+
+fn xy(p: &ColoredVertex) -> [f32; 2] {
+    [p.position[0], p.position[1]]
+}
+
+fn add(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+fn sub(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn scale(a: [f32; 2], s: f32) -> [f32; 2] {
+    [a[0] * s, a[1] * s]
+}
+
+fn dot(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+fn cross(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+/// Left hand normal
+fn perp(a: [f32; 2]) -> [f32; 2] {
+    [-a[1], a[0]]
+}
+
+fn normalize(a: [f32; 2]) -> [f32; 2] {
+    let len = dot(a, a).sqrt();
+    if len > 0.0 {
+        scale(a, 1.0 / len)
+    } else {
+        [1.0, 0.0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pipelines::{
+        polyline::tessellate::{tessellate, LineCap, LineJoin, PolylineStyle, PolylineVertex},
+        ColoredVertex,
+    };
+
+    const WHITE: [f32; 4] = [1.0; 4];
+
+    fn run(points: &[[f32; 2]], style: PolylineStyle) -> Vec<PolylineVertex> {
+        let points = points
+            .iter()
+            .map(|p| ColoredVertex::new_2d(*p, WHITE))
+            .collect::<Vec<_>>();
+        let mut out = vec![];
+        tessellate(&points, style, &mut out);
+        out
+    }
+
+    fn style(join: LineJoin, cap: LineCap) -> PolylineStyle {
+        PolylineStyle {
+            join,
+            cap,
+            ..PolylineStyle::default()
+        }
+    }
+
+    fn has_offset(vertices: &[PolylineVertex], offset: [f32; 2]) -> bool {
+        vertices.iter().any(|v| {
+            (v.offset[0] - offset[0]).abs() < 1e-5 && (v.offset[1] - offset[1]).abs() < 1e-5
+        })
+    }
+
+    #[test]
+    fn test_too_few_points() {
+        assert!(run(&[], PolylineStyle::default()).is_empty());
+        assert!(run(&[[1.0, 1.0]], PolylineStyle::default()).is_empty());
+        // Duplicates are merged into a single point
+        assert!(run(&[[1.0, 1.0], [1.0, 1.0]], PolylineStyle::default()).is_empty());
+    }
+
+    #[test]
+    fn test_segment_caps() {
+        let segment = [[0.0, 0.0], [1.0, 0.0]];
+        let butt = run(&segment, style(LineJoin::Miter, LineCap::Butt));
+        assert_eq!(butt.len(), 6);
+        assert!(has_offset(&butt, [0.0, 1.0]));
+        assert!(has_offset(&butt, [0.0, -1.0]));
+        // Two triangles per end
+        let square = run(&segment, style(LineJoin::Miter, LineCap::Square));
+        assert_eq!(square.len(), 6 + 2 * 6);
+        assert!(has_offset(&square, [1.0, 1.0]));
+        assert!(has_offset(&square, [-1.0, -1.0]));
+        // Half circles of 8 triangles per end
+        let round = run(&segment, style(LineJoin::Miter, LineCap::Round));
+        assert_eq!(round.len(), 6 + 2 * 8 * 3);
+        assert!(has_offset(&round, [1.0, 0.0]));
+        assert!(has_offset(&round, [-1.0, 0.0]));
+    }
+
+    #[test]
+    fn test_joins() {
+        let corner = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]];
+        let segments = 2 * 6;
+        let miter = run(&corner, style(LineJoin::Miter, LineCap::Butt));
+        assert_eq!(miter.len(), segments + 6);
+        assert!(has_offset(&miter, [1.0, -1.0]));
+        let bevel = run(&corner, style(LineJoin::Bevel, LineCap::Butt));
+        assert_eq!(bevel.len(), segments + 3);
+        assert!(!has_offset(&bevel, [1.0, -1.0]));
+        // A quarter circle of 4 triangles
+        let round = run(&corner, style(LineJoin::Round, LineCap::Butt));
+        assert_eq!(round.len(), segments + 4 * 3);
+    }
+
+    #[test]
+    fn test_miter_limit() {
+        // A sharp turn back would have a long miter, it's beveled instead
+        let sharp = run(
+            &[[0.0, 0.0], [1.0, 0.0], [0.0, 0.1]],
+            style(LineJoin::Miter, LineCap::Butt),
+        );
+        assert_eq!(sharp.len(), 2 * 6 + 3);
+        let unlimited = run(&[[0.0, 0.0], [1.0, 0.0], [0.0, 0.1]], PolylineStyle {
+            miter_limit: f32::MAX,
+            ..PolylineStyle::default()
+        });
+        assert_eq!(unlimited.len(), 2 * 6 + 6);
+    }
+
+    #[test]
+    fn test_straight_needs_no_join() {
+        let straight = run(
+            &[[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+            style(LineJoin::Round, LineCap::Butt),
+        );
+        assert_eq!(straight.len(), 2 * 6);
+    }
+
+    #[test]
+    fn test_closed() {
+        let square = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+        let closed = PolylineStyle {
+            join: LineJoin::Bevel,
+            cap: LineCap::Round,
+            closed: true,
+            ..PolylineStyle::default()
+        };
+        // 4 segments and 4 joins, no caps
+        assert_eq!(run(&square, closed).len(), 4 * 6 + 4 * 3);
+        // A repeated first point is dropped
+        let repeated = [square.as_slice(), &[[0.0, 0.0]]].concat();
+        assert_eq!(run(&repeated, closed).len(), 4 * 6 + 4 * 3);
+    }
+
+    #[test]
+    fn test_keeps_z_w_and_color() {
+        let color = [0.1, 0.2, 0.3, 0.4];
+        let points = [
+            ColoredVertex {
+                position: [0.0, 0.0, 0.5, 2.0],
+                color,
+            },
+            ColoredVertex {
+                position: [1.0, 0.0, 0.5, 2.0],
+                color,
+            },
+        ];
+        let mut out = vec![];
+        tessellate(&points, PolylineStyle::default(), &mut out);
+        assert!(out
+            .iter()
+            .all(|v| v.position[2] == 0.5 && v.position[3] == 2.0 && v.color == color));
+    }
+}
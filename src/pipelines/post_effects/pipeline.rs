@@ -0,0 +1,249 @@
+use std::{borrow::Cow, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroup, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, ColorTargetState,
+    ColorWrites, CommandEncoder, Device, Operations, PushConstantRange, RenderPassColorAttachment,
+    RenderPassDescriptor, RenderPipeline, Sampler, SamplerBindingType, ShaderStages, TextureFormat,
+    TextureSampleType, TextureView, TextureViewDimension,
+};
+
+use crate::{
+    device_context::DeviceContext, pipelines::require_push_constants, utils::GpuProfiler,
+    GlassError,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PostEffectsSettings {
+    /// Darkening towards the corners, 0.0 to 1.0
+    pub vignette: f32,
+    /// Width of the vignette's fade, 0.0 is a hard edge
+    pub vignette_smoothness: f32,
+    /// Red and blue channel separation at the corners in pixels
+    pub chromatic_aberration: f32,
+    /// Amplitude of the film grain noise
+    pub grain: f32,
+    /// Size of grain cells in pixels
+    pub grain_size: f32,
+    /// Add screen space dither to hide banding when writing to 8 bit targets
+    pub dither: bool,
+}
+
+impl PostEffectsSettings {
+    /// Heavy vignette, fringing and coarse grain
+    pub const CINEMATIC: Self = Self {
+        vignette: 0.6,
+        vignette_smoothness: 0.7,
+        chromatic_aberration: 3.0,
+        grain: 0.06,
+        grain_size: 2.0,
+        dither: true,
+    };
+    /// All effects off, the pass is a copy
+    pub const NONE: Self = Self {
+        vignette: 0.0,
+        vignette_smoothness: 0.5,
+        chromatic_aberration: 0.0,
+        grain: 0.0,
+        grain_size: 1.0,
+        dither: false,
+    };
+    /// Subtle defaults suited for most scenes
+    pub const SUBTLE: Self = Self {
+        vignette: 0.3,
+        vignette_smoothness: 0.5,
+        chromatic_aberration: 1.0,
+        grain: 0.02,
+        grain_size: 1.0,
+        dither: true,
+    };
+}
+
+impl Default for PostEffectsSettings {
+    fn default() -> Self {
+        Self::SUBTLE
+    }
+}
+
+/// Cheap screen effects in one fullscreen pass: chromatic aberration, vignette, film grain and
+/// dithering. Typically the last pass after tonemapping, writing to the swapchain.
+pub struct PostEffectsPipeline {
+    post_effects_pipeline: RenderPipeline,
+    settings: PostEffectsSettings,
+    profiler: Option<Arc<GpuProfiler>>,
+}
+
+impl PostEffectsPipeline {
+    pub fn new(
+        device_context: &DeviceContext,
+        target_texture_format: TextureFormat,
+        settings: PostEffectsSettings,
+    ) -> Result<PostEffectsPipeline, GlassError> {
+        let device = device_context.device();
+        require_push_constants(
+            device,
+            "PostEffectsPipeline",
+            std::mem::size_of::<PostEffectsPushConstants>(),
+        )?;
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("post_effects_bind_group_layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float {
+                            filterable: true,
+                        },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    visibility: ShaderStages::FRAGMENT,
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    visibility: ShaderStages::FRAGMENT,
+                    count: None,
+                },
+            ],
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Post Effects Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("post_effects.wgsl"))),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Effects Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[PushConstantRange {
+                stages: ShaderStages::FRAGMENT,
+                range: 0..std::mem::size_of::<PostEffectsPushConstants>() as u32,
+            }],
+        });
+        let post_effects_pipeline =
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Post Effects Pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    compilation_options: Default::default(),
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some("fragment"),
+                    compilation_options: Default::default(),
+                    targets: &[Some(ColorTargetState {
+                        format: target_texture_format,
+                        blend: None,
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: device_context.pipeline_cache(),
+            });
+
+        Ok(PostEffectsPipeline {
+            post_effects_pipeline,
+            settings,
+            profiler: None,
+        })
+    }
+
+    /// Bind the source with a linear sampler for smooth chromatic aberration
+    pub fn create_bind_group(
+        &self,
+        device: &Device,
+        image: &TextureView,
+        sampler: &Sampler,
+    ) -> BindGroup {
+        let bind_group_layout = self.post_effects_pipeline.get_bind_group_layout(0);
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(image),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+            label: Some("post_effects_bind_group"),
+        })
+    }
+
+    pub fn settings(&self) -> PostEffectsSettings {
+        self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: PostEffectsSettings) {
+        self.settings = settings;
+    }
+
+    /// Time the pipeline's passes, see [`GpuProfiler::pass_timestamp_writes`]
+    pub fn set_profiler(&mut self, profiler: Option<Arc<GpuProfiler>>) {
+        self.profiler = profiler;
+    }
+
+    /// Apply the effects from source to output. `time` in seconds animates the grain, e.g.
+    /// [`Time::elapsed`](crate::time::Time::elapsed).
+    pub fn apply(
+        &self,
+        encoder: &mut CommandEncoder,
+        source_bind_group: &BindGroup,
+        output: &TextureView,
+        time: f32,
+    ) {
+        let settings = self.settings;
+        let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("post_effects_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: self
+                .profiler
+                .as_ref()
+                .and_then(|profiler| profiler.pass_timestamp_writes("glass::post_effects")),
+            occlusion_query_set: None,
+        });
+        r_pass.set_pipeline(&self.post_effects_pipeline);
+        r_pass.set_bind_group(0, source_bind_group, &[]);
+        r_pass.set_push_constants(
+            ShaderStages::FRAGMENT,
+            0,
+            bytemuck::cast_slice(&[PostEffectsPushConstants {
+                effects: [
+                    settings.vignette,
+                    settings.vignette_smoothness,
+                    settings.chromatic_aberration,
+                    settings.grain,
+                ],
+                params: [
+                    settings.grain_size,
+                    settings.dither as u32 as f32,
+                    time,
+                    0.0,
+                ],
+            }]),
+        );
+        r_pass.draw(0..3, 0..1);
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct PostEffectsPushConstants {
+    /// Vignette, vignette smoothness, chromatic aberration & grain
+    pub effects: [f32; 4],
+    /// Grain size, dither, time & padding
+    pub params: [f32; 4],
+}
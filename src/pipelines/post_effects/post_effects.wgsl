@@ -0,0 +1,71 @@
+struct VertexOutput {
+    @builtin(position)
+    position: vec4<f32>,
+    @location(0)
+    uv: vec2<f32>,
+};
+
+// https://github.com/bevyengine/bevy/blob/09df19bcadb52d2f4dbbc899aef74cafa9091538/crates/bevy_core_pipeline/src/fullscreen_vertex_shader/fullscreen.wgsl
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32(vertex_index >> 1u), f32(vertex_index & 1u)) * 2.0;
+    let clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    return VertexOutput(clip_position, uv);
+}
+
+@group(0) @binding(0)
+var source_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+struct PushConstants {
+    // x: vignette, y: vignette smoothness, z: chromatic aberration, w: grain
+    effects: vec4<f32>,
+    // x: grain size, y: 1.0 if dithering, z: time
+    params: vec4<f32>,
+}
+var<push_constant> pc: PushConstants;
+
+fn hash(p: vec3<f32>) -> f32 {
+    var p3 = fract(p * 0.1031);
+    p3 += dot(p3, p3.zyx + 31.32);
+    return fract((p3.x + p3.y) * p3.z);
+}
+
+// Source: Advanced VR Rendering, GDC 2015, Alex Vlachos, Valve, Slide 49
+// https://media.steampowered.com/apps/valve/2015/Alex_Vlachos_Advanced_VR_Rendering_GDC2015.pdf
+fn screen_space_dither(frag_coord: vec2<f32>) -> vec3<f32> {
+    var dither = vec3<f32>(dot(vec2<f32>(171.0, 231.0), frag_coord)).xxx;
+    dither = fract(dither.rgb / vec3<f32>(103.0, 71.0, 97.0));
+    return (dither - 0.5) / 255.0;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let centered = in.uv - 0.5;
+
+    // Chromatic aberration, red and blue pushed apart towards the edges
+    let size = vec2<f32>(textureDimensions(source_texture));
+    let shift = centered * length(centered) * 2.0 * pc.effects.z / size;
+    let center = textureSample(source_texture, source_sampler, in.uv);
+    let r = textureSample(source_texture, source_sampler, in.uv + shift).r;
+    let b = textureSample(source_texture, source_sampler, in.uv - shift).b;
+    var color = vec3<f32>(r, center.g, b);
+
+    // Vignette, starts at the inscribed circle and fades by smoothness
+    let dist = length(centered * vec2<f32>(size.x / size.y, 1.0)) * 2.0;
+    let falloff = 1.0 - smoothstep(1.0 - pc.effects.y, 1.0 + pc.effects.y + 1e-4, dist);
+    color *= mix(1.0, falloff, pc.effects.x);
+
+    // Film grain, stronger in the mid tones
+    let cell = floor(in.position.xy / max(pc.params.x, 1.0));
+    let noise = hash(vec3<f32>(cell, fract(pc.params.z) * 1000.0)) - 0.5;
+    let luma = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+    let mid_tones = 1.0 - abs(saturate(luma) * 2.0 - 1.0);
+    color += noise * pc.effects.w * (0.25 + 0.75 * mid_tones);
+
+    if (pc.params.y > 0.5) {
+        color += screen_space_dither(in.position.xy);
+    }
+    return vec4<f32>(max(color, vec3<f32>(0.0)), center.a);
+}
@@ -1,21 +1,122 @@
-use std::borrow::Cow;
-
 use bytemuck::{Pod, Zeroable};
 use wgpu::{
-    util::DeviceExt, BindGroup, Buffer, Device, PushConstantRange, RenderPass, RenderPipeline,
-    Sampler, ShaderStages, TextureView,
+    util::DeviceExt, BindGroup, Buffer, BufferAddress, CompareFunction, DepthStencilState, Device,
+    PipelineCache, Queue, RenderBundleEncoder, RenderPass, RenderPipeline, Sampler, ShaderStages,
+    TextureFormat, TextureView,
+};
+
+use crate::{
+    device_context::DeviceContext,
+    pass::intersect_rects,
+    pipelines::{vertex::TexturedVertex, DrawParams, QUAD_INDICES, TEXTURED_QUAD_VERTICES},
 };
 
-use crate::pipelines::{vertex::TexturedVertex, QUAD_INDICES, TEXTURED_QUAD_VERTICES};
+/// Bind group of the draw parameters when push constants aren't available
+const PARAMS_GROUP: u32 = 1;
+
+/// How a quad's texture is sampled and placed, see [`QuadPipeline::draw_with_options`]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct QuadOptions {
+    pub uv_offset: [f32; 2],
+    pub uv_scale: [f32; 2],
+    /// Radians, counter clockwise
+    pub rotation: f32,
+    /// Rotation center as an offset from the quad's center in world units
+    pub pivot: [f32; 2],
+    /// Multiplies the texture. Stored as 8 bit unorm values to fit the 128 byte push constant
+    /// limit, so components are clamped to 0..1 and can't brighten the texture.
+    pub tint: [f32; 4],
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// Texture layer, requires a pipeline created with [`QuadPipeline::new_array`]
+    pub layer: u32,
+}
+
+impl Default for QuadOptions {
+    fn default() -> Self {
+        Self {
+            uv_offset: [0.0; 2],
+            uv_scale: [1.0; 2],
+            rotation: 0.0,
+            pivot: [0.0; 2],
+            tint: [1.0; 4],
+            flip_x: false,
+            flip_y: false,
+            layer: 0,
+        }
+    }
+}
 
 pub struct QuadPipeline {
     pipeline: RenderPipeline,
+    /// Reads quads from an instance buffer, see [`QuadPipeline::draw_indirect`]
+    instanced_pipeline: RenderPipeline,
     vertices: Buffer,
     indices: Buffer,
+    params: DrawParams,
+    /// Parameters of draws recorded into render bundles, kept across frames
+    bundle_params: DrawParams,
 }
 
 impl QuadPipeline {
-    pub fn new(device: &Device, color_target_state: wgpu::ColorTargetState) -> QuadPipeline {
+    pub fn new(
+        device_context: &DeviceContext,
+        color_target_state: wgpu::ColorTargetState,
+        sample_count: u32,
+    ) -> QuadPipeline {
+        Self::with_depth_stencil(
+            device_context,
+            color_target_state,
+            None,
+            sample_count,
+            false,
+        )
+    }
+
+    /// Quad pipeline sampling a `D2Array` texture view, e.g. atlas pages or animation frames.
+    /// Bind groups must be created from array views and the layer is picked per draw with
+    /// [`QuadPipeline::draw_layer`].
+    pub fn new_array(
+        device_context: &DeviceContext,
+        color_target_state: wgpu::ColorTargetState,
+        sample_count: u32,
+    ) -> QuadPipeline {
+        Self::with_depth_stencil(device_context, color_target_state, None, sample_count, true)
+    }
+
+    /// Quad pipeline for render passes with a depth attachment of `depth_format`. Quads write
+    /// depth and are tested against it with `compare`.
+    pub fn new_with_depth(
+        device_context: &DeviceContext,
+        color_target_state: wgpu::ColorTargetState,
+        depth_format: TextureFormat,
+        compare: CompareFunction,
+        sample_count: u32,
+    ) -> QuadPipeline {
+        let depth_stencil = DepthStencilState {
+            format: depth_format,
+            depth_write_enabled: true,
+            depth_compare: compare,
+            stencil: Default::default(),
+            bias: Default::default(),
+        };
+        Self::with_depth_stencil(
+            device_context,
+            color_target_state,
+            Some(depth_stencil),
+            sample_count,
+            false,
+        )
+    }
+
+    fn with_depth_stencil(
+        device_context: &DeviceContext,
+        color_target_state: wgpu::ColorTargetState,
+        depth_stencil: Option<DepthStencilState>,
+        sample_count: u32,
+        array: bool,
+    ) -> QuadPipeline {
+        let device = device_context.device();
         let vertices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Vertex Buffer"),
             contents: bytemuck::cast_slice(TEXTURED_QUAD_VERTICES),
@@ -26,18 +127,105 @@ impl QuadPipeline {
             contents: bytemuck::cast_slice(QUAD_INDICES),
             usage: wgpu::BufferUsages::INDEX,
         });
-        let pipeline = Self::new_render_pipeline(device, color_target_state);
+        let pipeline = Self::create_render_pipeline(
+            device,
+            device_context.pipeline_cache(),
+            color_target_state.clone(),
+            depth_stencil.clone(),
+            sample_count,
+            array,
+            false,
+        );
+        let instanced_pipeline = Self::create_render_pipeline(
+            device,
+            device_context.pipeline_cache(),
+            color_target_state,
+            depth_stencil,
+            sample_count,
+            array,
+            true,
+        );
         Self {
             pipeline,
+            instanced_pipeline,
             vertices,
             indices,
+            params: DrawParams::new(
+                device_context,
+                ShaderStages::VERTEX_FRAGMENT,
+                std::mem::size_of::<QuadPushConstants>(),
+            ),
+            bundle_params: DrawParams::new_persistent(
+                device_context,
+                ShaderStages::VERTEX_FRAGMENT,
+                std::mem::size_of::<QuadPushConstants>(),
+            ),
         }
     }
 
     pub fn new_render_pipeline(
         device: &Device,
+        cache: Option<&PipelineCache>,
+        color_target_state: wgpu::ColorTargetState,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        Self::new_depth_render_pipeline(device, cache, color_target_state, None, sample_count)
+    }
+
+    pub fn new_depth_render_pipeline(
+        device: &Device,
+        cache: Option<&PipelineCache>,
         color_target_state: wgpu::ColorTargetState,
+        depth_stencil: Option<DepthStencilState>,
+        sample_count: u32,
     ) -> RenderPipeline {
+        Self::create_render_pipeline(
+            device,
+            cache,
+            color_target_state,
+            depth_stencil,
+            sample_count,
+            false,
+            false,
+        )
+    }
+
+    /// Render pipeline of [`QuadPipeline::new_array`]
+    pub fn new_array_render_pipeline(
+        device: &Device,
+        cache: Option<&PipelineCache>,
+        color_target_state: wgpu::ColorTargetState,
+        depth_stencil: Option<DepthStencilState>,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        Self::create_render_pipeline(
+            device,
+            cache,
+            color_target_state,
+            depth_stencil,
+            sample_count,
+            true,
+            false,
+        )
+    }
+
+    fn create_render_pipeline(
+        device: &Device,
+        cache: Option<&PipelineCache>,
+        color_target_state: wgpu::ColorTargetState,
+        depth_stencil: Option<DepthStencilState>,
+        sample_count: u32,
+        array: bool,
+        instanced: bool,
+    ) -> RenderPipeline {
+        let (view_dimension, source) = if array {
+            (
+                wgpu::TextureViewDimension::D2Array,
+                include_str!("quad_array.wgsl"),
+            )
+        } else {
+            (wgpu::TextureViewDimension::D2, include_str!("quad.wgsl"))
+        };
         let texture_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 entries: &[
@@ -46,7 +234,7 @@ impl QuadPipeline {
                         visibility: wgpu::ShaderStages::FRAGMENT,
                         ty: wgpu::BindingType::Texture {
                             multisampled: false,
-                            view_dimension: wgpu::TextureViewDimension::D2,
+                            view_dimension,
                             sample_type: wgpu::TextureSampleType::Float {
                                 filterable: true,
                             },
@@ -64,24 +252,40 @@ impl QuadPipeline {
             });
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Shader"),
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("quad.wgsl"))),
+            source: wgpu::ShaderSource::Wgsl(DrawParams::shader_source(
+                device,
+                source,
+                PARAMS_GROUP,
+            )),
         });
+        let params_layout = DrawParams::bind_group_layout(device, ShaderStages::VERTEX_FRAGMENT);
+        let mut bind_group_layouts = vec![&texture_bind_group_layout];
+        bind_group_layouts.extend(params_layout.as_ref());
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Quad Pipeline Layout"),
-            bind_group_layouts: &[&texture_bind_group_layout],
-            push_constant_ranges: &[PushConstantRange {
-                stages: ShaderStages::VERTEX_FRAGMENT,
-                range: 0..std::mem::size_of::<QuadPushConstants>() as u32,
-            }],
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges: &DrawParams::push_constant_ranges(
+                device,
+                ShaderStages::VERTEX_FRAGMENT,
+                std::mem::size_of::<QuadPushConstants>(),
+            ),
         });
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        let (entry_point, buffers) = if instanced {
+            ("vs_instanced", vec![
+                TexturedVertex::desc(),
+                QuadInstance::desc(),
+            ])
+        } else {
+            ("vs_main", vec![TexturedVertex::desc()])
+        };
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Quad Render Pipeline"),
             layout: Some(&layout),
             vertex: wgpu::VertexState {
                 module: &shader,
-                entry_point: Some("vs_main"),
+                entry_point: Some(entry_point),
                 compilation_options: Default::default(),
-                buffers: &[TexturedVertex::desc()],
+                buffers: &buffers,
             },
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
@@ -99,16 +303,23 @@ impl QuadPipeline {
                 unclipped_depth: false,
                 conservative: false,
             },
-            depth_stencil: None,
+            depth_stencil,
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
             multiview: None,
-            cache: None,
-        });
-        pipeline
+            cache,
+        })
+    }
+
+    /// Upload the parameters of recorded draws and bundles when the device lacks
+    /// `Features::PUSH_CONSTANTS`, e.g. on the web. The runner does this before submitting each
+    /// frame, call it only when submitting draws yourself. Does nothing with push constants.
+    pub fn upload_draw_params(&self, queue: &Queue) {
+        self.params.upload(queue);
+        self.bundle_params.upload(queue);
     }
 
     pub fn create_bind_group(
@@ -144,14 +355,190 @@ impl QuadPipeline {
         quad_size: [f32; 2],
         aa_strength: f32,
     ) {
-        self.draw_inner(
+        self.draw_with_options(
+            rpass,
+            bind_group,
+            quad_pos,
+            view_proj,
+            quad_size,
+            &QuadOptions::default(),
+            aa_strength,
+        );
+    }
+
+    /// Record a quad into a render bundle, see
+    /// [`RenderBundleBuilder`](crate::utils::RenderBundleBuilder)
+    pub fn draw_bundle<'r>(
+        &'r self,
+        encoder: &mut RenderBundleEncoder<'r>,
+        bind_group: &'r BindGroup,
+        quad_pos: [f32; 4],
+        view_proj: [[f32; 4]; 4],
+        quad_size: [f32; 2],
+        aa_strength: f32,
+    ) {
+        encoder.set_pipeline(&self.pipeline);
+        encoder.set_bind_group(0, bind_group, &[]);
+        encoder.set_vertex_buffer(0, self.vertices.slice(..));
+        encoder.set_index_buffer(self.indices.slice(..), wgpu::IndexFormat::Uint16);
+        self.bundle_params.set_bundle(
+            encoder,
+            PARAMS_GROUP,
+            &QuadPipeline::push_constants(
+                quad_pos,
+                view_proj,
+                quad_size,
+                &QuadOptions::default(),
+                aa_strength,
+            ),
+        );
+        encoder.draw_indexed(0..(QUAD_INDICES.len() as u32), 0, 0..1);
+    }
+
+    /// Draw the [`QuadInstance`]s of `instances` with the draw call read from a
+    /// [`DrawIndexedIndirectArgs`](wgpu::util::DrawIndexedIndirectArgs) at `indirect_offset` of
+    /// `indirect_buffer`. A GPU culling pass can compact the visible quads into `instances` and
+    /// write their count as `instance_count`. `index_count` must be 6 and a nonzero
+    /// `first_instance` requires `Features::INDIRECT_FIRST_INSTANCE`. `instances` needs
+    /// `BufferUsages::VERTEX`. All instances share `options`, except for the rotation and tint
+    /// of each [`QuadInstance`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_indirect<'r>(
+        &'r self,
+        rpass: &mut RenderPass<'r>,
+        bind_group: &'r BindGroup,
+        view_proj: [[f32; 4]; 4],
+        options: &QuadOptions,
+        aa_strength: f32,
+        instances: &'r Buffer,
+        indirect_buffer: &'r Buffer,
+        indirect_offset: BufferAddress,
+    ) {
+        rpass.set_pipeline(&self.instanced_pipeline);
+        rpass.set_bind_group(0, bind_group, &[]);
+        rpass.set_vertex_buffer(0, self.vertices.slice(..));
+        rpass.set_vertex_buffer(1, instances.slice(..));
+        rpass.set_index_buffer(self.indices.slice(..), wgpu::IndexFormat::Uint16);
+        self.params.set(
+            rpass,
+            PARAMS_GROUP,
+            &QuadPipeline::push_constants([0.0; 4], view_proj, [0.0; 2], options, aa_strength),
+        );
+        rpass.draw_indexed_indirect(indirect_buffer, indirect_offset);
+    }
+
+    /// Draw a quad only within `clip_rect`, `[x, y, width, height]` in pixels of the render
+    /// target of `target_size`. The rect is clipped to the target. The scissor rect stays set on
+    /// the pass for following draws, use [`ClipStack`](crate::pass::ClipStack) to manage nested
+    /// clipping.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_clipped<'r>(
+        &'r self,
+        rpass: &mut RenderPass<'r>,
+        bind_group: &'r BindGroup,
+        quad_pos: [f32; 4],
+        view_proj: [[f32; 4]; 4],
+        quad_size: [f32; 2],
+        aa_strength: f32,
+        clip_rect: [u32; 4],
+        target_size: [u32; 2],
+    ) {
+        let [x, y, width, height] =
+            intersect_rects([0, 0, target_size[0], target_size[1]], clip_rect);
+        rpass.set_scissor_rect(x, y, width, height);
+        self.draw(
             rpass,
             bind_group,
             quad_pos,
             view_proj,
             quad_size,
-            [0.0; 2],
-            [1.0, 1.0],
+            aa_strength,
+        );
+    }
+
+    /// Draw a quad with its texture multiplied by `tint` and mirrored along x and / or y, see
+    /// [`QuadOptions::tint`] for its range
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_tinted<'r>(
+        &'r self,
+        rpass: &mut RenderPass<'r>,
+        bind_group: &'r BindGroup,
+        quad_pos: [f32; 4],
+        view_proj: [[f32; 4]; 4],
+        quad_size: [f32; 2],
+        tint: [f32; 4],
+        flip_x: bool,
+        flip_y: bool,
+        aa_strength: f32,
+    ) {
+        self.draw_with_options(
+            rpass,
+            bind_group,
+            quad_pos,
+            view_proj,
+            quad_size,
+            &QuadOptions {
+                tint,
+                flip_x,
+                flip_y,
+                ..Default::default()
+            },
+            aa_strength,
+        );
+    }
+
+    /// Draw layer `layer` of the array texture bound by `bind_group`, requires a pipeline
+    /// created with [`QuadPipeline::new_array`]
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_layer<'r>(
+        &'r self,
+        rpass: &mut RenderPass<'r>,
+        bind_group: &'r BindGroup,
+        quad_pos: [f32; 4],
+        view_proj: [[f32; 4]; 4],
+        quad_size: [f32; 2],
+        layer: u32,
+        aa_strength: f32,
+    ) {
+        self.draw_with_options(
+            rpass,
+            bind_group,
+            quad_pos,
+            view_proj,
+            quad_size,
+            &QuadOptions {
+                layer,
+                ..Default::default()
+            },
+            aa_strength,
+        );
+    }
+
+    /// Draw a quad rotated by `rotation` radians (counter clockwise) around `pivot`, an offset
+    /// from the quad's center in world units
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_rotated<'r>(
+        &'r self,
+        rpass: &mut RenderPass<'r>,
+        bind_group: &'r BindGroup,
+        quad_pos: [f32; 4],
+        view_proj: [[f32; 4]; 4],
+        quad_size: [f32; 2],
+        rotation: f32,
+        pivot: [f32; 2],
+        aa_strength: f32,
+    ) {
+        self.draw_with_options(
+            rpass,
+            bind_group,
+            quad_pos,
+            view_proj,
+            quad_size,
+            &QuadOptions {
+                rotation,
+                pivot,
+                ..Default::default()
+            },
             aa_strength,
         );
     }
@@ -168,45 +555,41 @@ impl QuadPipeline {
         uv_scale: [f32; 2],
         aa_strength: f32,
     ) {
-        self.draw_inner(
+        self.draw_with_options(
             rpass,
             bind_group,
             quad_pos,
             view_proj,
             quad_size,
-            uv_offset,
-            uv_scale,
+            &QuadOptions {
+                uv_offset,
+                uv_scale,
+                ..Default::default()
+            },
             aa_strength,
         );
     }
 
+    /// Draw a quad with any combination of uv rect, rotation, tint, flipping and texture layer
     #[allow(clippy::too_many_arguments)]
-    fn draw_inner<'r>(
+    pub fn draw_with_options<'r>(
         &'r self,
         rpass: &mut RenderPass<'r>,
         bind_group: &'r BindGroup,
         quad_pos: [f32; 4],
         view_proj: [[f32; 4]; 4],
         quad_size: [f32; 2],
-        uv_offset: [f32; 2],
-        uv_scale: [f32; 2],
+        options: &QuadOptions,
         aa_strength: f32,
     ) {
         rpass.set_pipeline(&self.pipeline);
         rpass.set_bind_group(0, bind_group, &[]);
         rpass.set_vertex_buffer(0, self.vertices.slice(..));
         rpass.set_index_buffer(self.indices.slice(..), wgpu::IndexFormat::Uint16);
-        rpass.set_push_constants(
-            ShaderStages::VERTEX_FRAGMENT,
-            0,
-            bytemuck::cast_slice(&[QuadPipeline::push_constants(
-                quad_pos,
-                view_proj,
-                quad_size,
-                uv_offset,
-                uv_scale,
-                aa_strength,
-            )]),
+        self.params.set(
+            rpass,
+            PARAMS_GROUP,
+            &QuadPipeline::push_constants(quad_pos, view_proj, quad_size, options, aa_strength),
         );
         rpass.draw_indexed(0..(QUAD_INDICES.len() as u32), 0, 0..1);
     }
@@ -215,17 +598,20 @@ impl QuadPipeline {
         quad_pos: [f32; 4],
         view_proj: [[f32; 4]; 4],
         quad_size: [f32; 2],
-        uv_offset: [f32; 2],
-        uv_scale: [f32; 2],
+        options: &QuadOptions,
         aa_strength: f32,
     ) -> QuadPushConstants {
         QuadPushConstants {
             quad_pos,
             view_proj,
             dims: quad_size,
-            uv_offset,
-            uv_scale,
+            uv_offset: options.uv_offset,
+            uv_scale: options.uv_scale,
             aa_strength,
+            rotation: options.rotation,
+            pivot: options.pivot,
+            tint: pack_unorm4x8(options.tint),
+            flags: options.flip_x as u32 | ((options.flip_y as u32) << 1) | (options.layer << 2),
         }
     }
 }
@@ -240,4 +626,97 @@ pub struct QuadPushConstants {
     pub uv_offset: [f32; 2],
     pub uv_scale: [f32; 2],
     pub aa_strength: f32,
+    /// Radians, counter clockwise
+    pub rotation: f32,
+    /// Rotation center as an offset from the quad's center
+    pub pivot: [f32; 2],
+    /// Rgba tint packed as 8 bit unorm values, red in the lowest byte. Packed to fit the 128
+    /// byte push constant limit, so components are clamped to 0..1.
+    pub tint: u32,
+    /// Bit 0 flips x, bit 1 flips y, the remaining bits are the texture layer of array pipelines
+    pub flags: u32,
+}
+
+/// A quad of [`QuadPipeline::draw_indirect`]
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct QuadInstance {
+    pub quad_pos: [f32; 4],
+    pub dims: [f32; 2],
+    /// Radians, counter clockwise
+    pub rotation: f32,
+    /// Rgba tint packed like [`QuadPushConstants::tint`]
+    pub tint: u32,
+}
+
+impl QuadInstance {
+    pub fn new(quad_pos: [f32; 4], dims: [f32; 2], rotation: f32, tint: [f32; 4]) -> QuadInstance {
+        QuadInstance {
+            quad_pos,
+            dims,
+            rotation,
+            tint: pack_unorm4x8(tint),
+        }
+    }
+
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<QuadInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            // Follows the locations of [`TexturedVertex`]
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 7]>() as wgpu::BufferAddress,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Uint32,
+                },
+            ],
+        }
+    }
+}
+
+fn pack_unorm4x8(color: [f32; 4]) -> u32 {
+    color.iter().enumerate().fold(0, |packed, (i, c)| {
+        packed | (((c.clamp(0.0, 1.0) * 255.0).round() as u32) << (i * 8))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pipelines::quad::pipeline::{QuadOptions, QuadPipeline};
+
+    #[test]
+    fn test_options_combine_in_push_constants() {
+        let options = QuadOptions {
+            uv_offset: [0.5, 0.25],
+            uv_scale: [2.0, 4.0],
+            rotation: 1.0,
+            tint: [1.0, 0.0, 2.0, -1.0],
+            flip_y: true,
+            layer: 3,
+            ..Default::default()
+        };
+        let pc = QuadPipeline::push_constants([0.0; 4], [[0.0; 4]; 4], [1.0; 2], &options, 1.0);
+        assert_eq!(pc.uv_offset, [0.5, 0.25]);
+        assert_eq!(pc.uv_scale, [2.0, 4.0]);
+        assert_eq!(pc.rotation, 1.0);
+        // Out of range tint components are clamped
+        assert_eq!(pc.tint, 0x00ff00ff);
+        assert_eq!(pc.flags, 0b1110);
+    }
 }
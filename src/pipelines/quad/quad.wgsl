@@ -5,6 +5,10 @@ struct PushConstants {
     uv_offset: vec2<f32>,
     uv_scale: vec2<f32>,
     aa_strength: f32,
+    rotation: f32,
+    pivot: vec2<f32>,
+    tint: u32,
+    flags: u32,
 }
 var<push_constant> pc: PushConstants;
 
@@ -14,28 +18,60 @@ struct VertexInput {
     @location(2) tex_coords: vec2<f32>,
 }
 
+// A quad of indirect draws, the remaining parameters come from the push constants
+struct InstanceInput {
+    @location(3) quad_pos: vec4<f32>,
+    @location(4) dims: vec2<f32>,
+    @location(5) rotation: f32,
+    @location(6) tint: u32,
+}
+
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
     @location(1) tex_coords: vec2<f32>,
 }
 
-@vertex
-fn vs_main(
+fn quad_vertex(
     model: VertexInput,
+    quad_pos: vec4<f32>,
+    dims: vec2<f32>,
+    rotation: f32,
+    tint: u32,
 ) -> VertexOutput {
     var out: VertexOutput;
-    out.tex_coords = (model.tex_coords + pc.uv_offset) / pc.uv_scale;
-    let world_position = vec4<f32>(pc.dims, 0.0, 1.0) *
-        // Scale vertices
-        model.position +
-        // Offset by pos
-        pc.quad_pos;
+    let flip = vec2<f32>(f32(pc.flags & 1u), f32((pc.flags >> 1u) & 1u));
+    let tex_coords = mix(model.tex_coords, 1.0 - model.tex_coords, flip);
+    out.tex_coords = (tex_coords + pc.uv_offset) / pc.uv_scale;
+    // Scale vertices
+    let scaled = vec4<f32>(dims, 0.0, 1.0) * model.position;
+    // Rotate around pivot
+    let cos_r = cos(rotation);
+    let sin_r = sin(rotation);
+    let local = scaled.xy - pc.pivot;
+    let rotated = vec2<f32>(local.x * cos_r - local.y * sin_r, local.x * sin_r + local.y * cos_r) + pc.pivot;
+    // Offset by pos
+    let world_position = vec4<f32>(rotated, scaled.zw) + quad_pos;
     out.clip_position = pc.view_proj * world_position;
-    out.color = model.color;
+    out.color = model.color * unpack4x8unorm(tint);
     return out;
 }
 
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    return quad_vertex(model, pc.quad_pos, pc.dims, pc.rotation, pc.tint);
+}
+
+@vertex
+fn vs_instanced(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    return quad_vertex(model, instance.quad_pos, instance.dims, instance.rotation, instance.tint);
+}
+
 @group(0) @binding(0)
 var input_texture: texture_2d<f32>;
 @group(0)@binding(1)
@@ -0,0 +1,95 @@
+struct PushConstants {
+    quad_pos: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    dims: vec2<f32>,
+    uv_offset: vec2<f32>,
+    uv_scale: vec2<f32>,
+    aa_strength: f32,
+    rotation: f32,
+    pivot: vec2<f32>,
+    tint: u32,
+    flags: u32,
+}
+var<push_constant> pc: PushConstants;
+
+struct VertexInput {
+    @location(0) position: vec4<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) tex_coords: vec2<f32>,
+}
+
+// A quad of indirect draws, the remaining parameters come from the push constants
+struct InstanceInput {
+    @location(3) quad_pos: vec4<f32>,
+    @location(4) dims: vec2<f32>,
+    @location(5) rotation: f32,
+    @location(6) tint: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) @interpolate(flat) layer: u32,
+}
+
+fn quad_vertex(
+    model: VertexInput,
+    quad_pos: vec4<f32>,
+    dims: vec2<f32>,
+    rotation: f32,
+    tint: u32,
+) -> VertexOutput {
+    var out: VertexOutput;
+    let flip = vec2<f32>(f32(pc.flags & 1u), f32((pc.flags >> 1u) & 1u));
+    let tex_coords = mix(model.tex_coords, 1.0 - model.tex_coords, flip);
+    out.tex_coords = (tex_coords + pc.uv_offset) / pc.uv_scale;
+    // Scale vertices
+    let scaled = vec4<f32>(dims, 0.0, 1.0) * model.position;
+    // Rotate around pivot
+    let cos_r = cos(rotation);
+    let sin_r = sin(rotation);
+    let local = scaled.xy - pc.pivot;
+    let rotated = vec2<f32>(local.x * cos_r - local.y * sin_r, local.x * sin_r + local.y * cos_r) + pc.pivot;
+    // Offset by pos
+    let world_position = vec4<f32>(rotated, scaled.zw) + quad_pos;
+    out.clip_position = pc.view_proj * world_position;
+    out.color = model.color * unpack4x8unorm(tint);
+    out.layer = pc.flags >> 2u;
+    return out;
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    return quad_vertex(model, pc.quad_pos, pc.dims, pc.rotation, pc.tint);
+}
+
+@vertex
+fn vs_instanced(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    return quad_vertex(model, instance.quad_pos, instance.dims, instance.rotation, instance.tint);
+}
+
+@group(0) @binding(0)
+var input_texture: texture_2d_array<f32>;
+@group(0)@binding(1)
+var s: sampler;
+
+// https://www.shadertoy.com/view/MllBWf
+fn get_coords_aa(uv: vec2<f32>) -> vec2<f32> {
+    let fl = floor(uv + 0.5);
+    var fr = fract(uv + 0.5);
+    let aa = fwidth(uv) * pc.aa_strength * 0.5;
+    fr = smoothstep(0.5 - aa, 0.5 + aa, fr);
+    return fl + fr - 0.5;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(input_texture));
+    return in.color * textureSample(input_texture, s, get_coords_aa(in.tex_coords * size) / size, in.layer);
+}
\ No newline at end of file
@@ -0,0 +1,501 @@
+use std::borrow::Cow;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    Buffer, BufferAddress, CompareFunction, DepthStencilState, Device, PipelineCache,
+    PushConstantRange, RenderBundleEncoder, RenderPass, RenderPipeline, ShaderStages,
+    TextureFormat,
+};
+
+use crate::{device_context::DeviceContext, pipelines::require_push_constants, GlassError};
+
+/// Shape kinds, must match shape.wgsl
+const KIND_RECT: f32 = 0.0;
+const KIND_CIRCLE: f32 = 1.0;
+const KIND_CAPSULE: f32 = 2.0;
+
+/// Pixels added around a shape's bounds to fit its anti-aliased edge
+const EDGE_MARGIN_PIXELS: f32 = 1.0;
+
+/// A shape drawn by [`ShapePipeline`], sizes in world units
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Shape {
+    Circle {
+        radius: f32,
+    },
+    /// A circle outline, overrides [`ShapeStyle::stroke_width`]
+    Ring {
+        radius: f32,
+        thickness: f32,
+    },
+    RoundedRect {
+        size: [f32; 2],
+        corner_radius: f32,
+    },
+    /// A horizontal (before rotation) capsule of total `length` including its caps
+    Capsule {
+        length: f32,
+        radius: f32,
+    },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShapeStyle {
+    pub color: [f32; 4],
+    /// Draw an outline of this width inside the shape's edge. 0 fills the shape.
+    pub stroke_width: f32,
+    /// Width of the anti-aliased edge in world units. 0 smooths over a pixel.
+    pub smoothing: f32,
+}
+
+impl Default for ShapeStyle {
+    fn default() -> Self {
+        Self {
+            color: [1.0; 4],
+            stroke_width: 0.0,
+            smoothing: 0.0,
+        }
+    }
+}
+
+impl ShapeStyle {
+    pub fn fill(color: [f32; 4]) -> ShapeStyle {
+        ShapeStyle {
+            color,
+            ..Default::default()
+        }
+    }
+
+    pub fn stroke(color: [f32; 4], stroke_width: f32) -> ShapeStyle {
+        ShapeStyle {
+            color,
+            stroke_width,
+            ..Default::default()
+        }
+    }
+}
+
+/// Draws circles, rings, rounded rects and capsules with signed distance fields, one quad per
+/// shape. Vertices are generated in the shader, indirect draws read their shapes from a buffer of
+/// [`ShapeInstance`]s.
+pub struct ShapePipeline {
+    pipeline: RenderPipeline,
+    /// Reads shapes from an instance buffer, see [`ShapePipeline::draw_indirect`]
+    instanced_pipeline: RenderPipeline,
+}
+
+impl ShapePipeline {
+    pub fn new(
+        device_context: &DeviceContext,
+        color_target_state: wgpu::ColorTargetState,
+        sample_count: u32,
+    ) -> Result<ShapePipeline, GlassError> {
+        let device = device_context.device();
+        require_push_constants(
+            device,
+            "ShapePipeline",
+            std::mem::size_of::<ShapePushConstants>(),
+        )?;
+        Ok(Self {
+            pipeline: Self::new_render_pipeline(
+                device,
+                device_context.pipeline_cache(),
+                color_target_state.clone(),
+                sample_count,
+            ),
+            instanced_pipeline: Self::create_render_pipeline(
+                device,
+                device_context.pipeline_cache(),
+                color_target_state,
+                None,
+                sample_count,
+                true,
+            ),
+        })
+    }
+
+    /// Shape pipeline for render passes with a depth attachment of `depth_format`. Shapes write
+    /// depth and are tested against it with `compare`.
+    pub fn new_with_depth(
+        device_context: &DeviceContext,
+        color_target_state: wgpu::ColorTargetState,
+        depth_format: TextureFormat,
+        compare: CompareFunction,
+        sample_count: u32,
+    ) -> Result<ShapePipeline, GlassError> {
+        let device = device_context.device();
+        require_push_constants(
+            device,
+            "ShapePipeline",
+            std::mem::size_of::<ShapePushConstants>(),
+        )?;
+        let depth_stencil = DepthStencilState {
+            format: depth_format,
+            depth_write_enabled: true,
+            depth_compare: compare,
+            stencil: Default::default(),
+            bias: Default::default(),
+        };
+        Ok(Self {
+            pipeline: Self::new_depth_render_pipeline(
+                device,
+                device_context.pipeline_cache(),
+                color_target_state.clone(),
+                Some(depth_stencil.clone()),
+                sample_count,
+            ),
+            instanced_pipeline: Self::create_render_pipeline(
+                device,
+                device_context.pipeline_cache(),
+                color_target_state,
+                Some(depth_stencil),
+                sample_count,
+                true,
+            ),
+        })
+    }
+
+    pub fn new_render_pipeline(
+        device: &Device,
+        cache: Option<&PipelineCache>,
+        color_target_state: wgpu::ColorTargetState,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        Self::new_depth_render_pipeline(device, cache, color_target_state, None, sample_count)
+    }
+
+    pub fn new_depth_render_pipeline(
+        device: &Device,
+        cache: Option<&PipelineCache>,
+        color_target_state: wgpu::ColorTargetState,
+        depth_stencil: Option<DepthStencilState>,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        Self::create_render_pipeline(
+            device,
+            cache,
+            color_target_state,
+            depth_stencil,
+            sample_count,
+            false,
+        )
+    }
+
+    fn create_render_pipeline(
+        device: &Device,
+        cache: Option<&PipelineCache>,
+        color_target_state: wgpu::ColorTargetState,
+        depth_stencil: Option<DepthStencilState>,
+        sample_count: u32,
+        instanced: bool,
+    ) -> RenderPipeline {
+        let (entry_point, buffers) = if instanced {
+            ("vs_instanced", vec![ShapeInstance::desc()])
+        } else {
+            ("vs_main", vec![])
+        };
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shape Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("shape.wgsl"))),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shape Pipeline Layout"),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[PushConstantRange {
+                stages: ShaderStages::VERTEX_FRAGMENT,
+                range: 0..std::mem::size_of::<ShapePushConstants>() as u32,
+            }],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shape Render Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                buffers: &buffers,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(color_target_state)],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache,
+        })
+    }
+
+    /// Draw a shape centered at `pos`, rotated by `rotation` radians. `viewport` is the size of
+    /// the render target in pixels.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw<'r>(
+        &'r self,
+        rpass: &mut RenderPass<'r>,
+        view_proj: [[f32; 4]; 4],
+        shape: Shape,
+        pos: [f32; 2],
+        rotation: f32,
+        style: ShapeStyle,
+        viewport: [f32; 2],
+    ) {
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_push_constants(
+            ShaderStages::VERTEX_FRAGMENT,
+            0,
+            bytemuck::cast_slice(&[ShapePushConstants::new(
+                view_proj, shape, pos, rotation, style, viewport,
+            )]),
+        );
+        rpass.draw(0..6, 0..1);
+    }
+
+    /// Draw the [`ShapeInstance`]s of `instances` with the draw call read from a
+    /// [`DrawIndirectArgs`](wgpu::util::DrawIndirectArgs) at `indirect_offset` of
+    /// `indirect_buffer`. A GPU culling pass can compact the visible shapes into `instances` and
+    /// write their count as `instance_count`. `vertex_count` must be 6 and a nonzero
+    /// `first_instance` requires `Features::INDIRECT_FIRST_INSTANCE`. `instances` needs
+    /// `BufferUsages::VERTEX`.
+    pub fn draw_indirect<'r>(
+        &'r self,
+        rpass: &mut RenderPass<'r>,
+        view_proj: [[f32; 4]; 4],
+        instances: &'r Buffer,
+        indirect_buffer: &'r Buffer,
+        indirect_offset: BufferAddress,
+    ) {
+        rpass.set_pipeline(&self.instanced_pipeline);
+        rpass.set_vertex_buffer(0, instances.slice(..));
+        rpass.set_push_constants(
+            ShaderStages::VERTEX_FRAGMENT,
+            0,
+            bytemuck::cast_slice(&[ShapePushConstants {
+                view_proj,
+                ..Zeroable::zeroed()
+            }]),
+        );
+        rpass.draw_indirect(indirect_buffer, indirect_offset);
+    }
+
+    /// Record a shape into a render bundle, see
+    /// [`RenderBundleBuilder`](crate::utils::RenderBundleBuilder)
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_bundle<'r>(
+        &'r self,
+        encoder: &mut RenderBundleEncoder<'r>,
+        view_proj: [[f32; 4]; 4],
+        shape: Shape,
+        pos: [f32; 2],
+        rotation: f32,
+        style: ShapeStyle,
+        viewport: [f32; 2],
+    ) {
+        encoder.set_pipeline(&self.pipeline);
+        encoder.set_push_constants(
+            ShaderStages::VERTEX_FRAGMENT,
+            0,
+            bytemuck::cast_slice(&[ShapePushConstants::new(
+                view_proj, shape, pos, rotation, style, viewport,
+            )]),
+        );
+        encoder.draw(0..6, 0..1);
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct ShapePushConstants {
+    pub view_proj: [[f32; 4]; 4],
+    pub transform: [f32; 4],
+    pub shape: [f32; 4],
+    pub color: [f32; 4],
+    pub style: [f32; 4],
+}
+
+impl ShapePushConstants {
+    pub fn new(
+        view_proj: [[f32; 4]; 4],
+        shape: Shape,
+        pos: [f32; 2],
+        rotation: f32,
+        style: ShapeStyle,
+        viewport: [f32; 2],
+    ) -> ShapePushConstants {
+        let instance = ShapeInstance::new(view_proj, shape, pos, rotation, style, viewport);
+        ShapePushConstants {
+            view_proj,
+            transform: instance.transform,
+            shape: instance.shape,
+            color: instance.color,
+            style: instance.style,
+        }
+    }
+}
+
+/// A shape of [`ShapePipeline::draw_indirect`], laid out like the per shape part of
+/// [`ShapePushConstants`]
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct ShapeInstance {
+    /// Center xy, rotation z, shape kind w
+    pub transform: [f32; 4],
+    pub shape: [f32; 4],
+    pub color: [f32; 4],
+    /// Stroke width x, smoothing y, bounds half size zw
+    pub style: [f32; 4],
+}
+
+impl ShapeInstance {
+    /// `view_proj` and `viewport` size the anti-aliased edge, see [`ShapePipeline::draw`]
+    pub fn new(
+        view_proj: [[f32; 4]; 4],
+        shape: Shape,
+        pos: [f32; 2],
+        rotation: f32,
+        style: ShapeStyle,
+        viewport: [f32; 2],
+    ) -> ShapeInstance {
+        let mut stroke_width = style.stroke_width;
+        let (kind, params, half_size) = match shape {
+            Shape::Circle {
+                radius,
+            } => (KIND_CIRCLE, [radius, 0.0, 0.0, 0.0], [radius; 2]),
+            Shape::Ring {
+                radius,
+                thickness,
+            } => {
+                stroke_width = thickness;
+                (KIND_CIRCLE, [radius, 0.0, 0.0, 0.0], [radius; 2])
+            }
+            Shape::RoundedRect {
+                size,
+                corner_radius,
+            } => {
+                let half_size = [size[0] * 0.5, size[1] * 0.5];
+                (
+                    KIND_RECT,
+                    [half_size[0], half_size[1], corner_radius, 0.0],
+                    half_size,
+                )
+            }
+            Shape::Capsule {
+                length,
+                radius,
+            } => {
+                let half_length = (length * 0.5 - radius).max(0.0);
+                (KIND_CAPSULE, [half_length, radius, 0.0, 0.0], [
+                    half_length + radius,
+                    radius,
+                ])
+            }
+        };
+        // Leave room for the outer half of the smoothed edge, plus a pixel for automatic
+        // smoothing and rasterization
+        let margin =
+            style.smoothing * 0.5 + EDGE_MARGIN_PIXELS * world_units_per_pixel(view_proj, viewport);
+        ShapeInstance {
+            transform: [pos[0], pos[1], rotation, kind],
+            shape: params,
+            color: style.color,
+            style: [
+                stroke_width,
+                style.smoothing,
+                half_size[0] + margin,
+                half_size[1] + margin,
+            ],
+        }
+    }
+
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        const VEC4: wgpu::BufferAddress = std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress;
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<ShapeInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: VEC4,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: 2 * VEC4,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: 3 * VEC4,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// Size of a pixel in world units, the larger of both axes. Assumes an orthographic `view_proj`.
+fn world_units_per_pixel(view_proj: [[f32; 4]; 4], viewport: [f32; 2]) -> f32 {
+    // Pixels a world unit along x and y spans on screen
+    let pixels =
+        |column: [f32; 4]| (column[0] * viewport[0] * 0.5).hypot(column[1] * viewport[1] * 0.5);
+    let pixels_per_unit = pixels(view_proj[0]).min(pixels(view_proj[1]));
+    if pixels_per_unit > 0.0 {
+        1.0 / pixels_per_unit
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pipelines::shape::pipeline::{Shape, ShapeInstance, ShapeStyle};
+
+    /// Orthographic projection showing `width` x `height` world units
+    fn ortho(width: f32, height: f32) -> [[f32; 4]; 4] {
+        [
+            [2.0 / width, 0.0, 0.0, 0.0],
+            [0.0, 2.0 / height, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]
+    }
+
+    #[test]
+    fn test_margin_follows_zoom() {
+        let circle = Shape::Circle {
+            radius: 10.0,
+        };
+        let bounds = |view_proj, style| {
+            ShapeInstance::new(view_proj, circle, [0.0; 2], 0.0, style, [800.0, 600.0]).style[2]
+        };
+        // A world unit per pixel
+        assert_eq!(bounds(ortho(800.0, 600.0), ShapeStyle::default()), 11.0);
+        // Zoomed out 4x, a pixel covers 4 world units
+        assert_eq!(bounds(ortho(3200.0, 2400.0), ShapeStyle::default()), 14.0);
+        // Explicit smoothing extends half its width outside the edge
+        let smooth = ShapeStyle {
+            smoothing: 2.0,
+            ..Default::default()
+        };
+        assert_eq!(bounds(ortho(800.0, 600.0), smooth), 12.0);
+    }
+}
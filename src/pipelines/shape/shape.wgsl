@@ -0,0 +1,107 @@
+struct PushConstants {
+    view_proj: mat4x4<f32>,
+    // Center xy, rotation z, shape kind w
+    transform: vec4<f32>,
+    // Rect: half size xy, corner radius z. Circle: radius x. Capsule: half length x, radius y
+    shape: vec4<f32>,
+    color: vec4<f32>,
+    // Stroke width x (0 fills), smoothing y (0 smooths by a pixel), bounds half size zw
+    style: vec4<f32>,
+}
+var<push_constant> pc: PushConstants;
+
+const KIND_RECT: u32 = 0u;
+const KIND_CIRCLE: u32 = 1u;
+const KIND_CAPSULE: u32 = 2u;
+
+// A shape of indirect draws, the per shape part of the push constants
+struct InstanceInput {
+    @location(0) transform: vec4<f32>,
+    @location(1) shape: vec4<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) style: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) local: vec2<f32>,
+    @location(1) @interpolate(flat) kind: u32,
+    @location(2) @interpolate(flat) shape: vec4<f32>,
+    @location(3) @interpolate(flat) color: vec4<f32>,
+    // Stroke width x, smoothing y
+    @location(4) @interpolate(flat) style: vec2<f32>,
+}
+
+fn shape_vertex(
+    vertex_index: u32,
+    transform: vec4<f32>,
+    shape: vec4<f32>,
+    color: vec4<f32>,
+    style: vec4<f32>,
+) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    let local = corners[vertex_index % 6u] * style.zw;
+    let c = cos(transform.z);
+    let s = sin(transform.z);
+    let world = vec2<f32>(local.x * c - local.y * s, local.x * s + local.y * c) + transform.xy;
+    var out: VertexOutput;
+    out.clip_position = pc.view_proj * vec4<f32>(world, 0.0, 1.0);
+    out.local = local;
+    out.kind = u32(transform.w);
+    out.shape = shape;
+    out.color = color;
+    out.style = style.xy;
+    return out;
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    return shape_vertex(vertex_index, pc.transform, pc.shape, pc.color, pc.style);
+}
+
+@vertex
+fn vs_instanced(
+    @builtin(vertex_index) vertex_index: u32,
+    instance: InstanceInput,
+) -> VertexOutput {
+    return shape_vertex(vertex_index, instance.transform, instance.shape, instance.color, instance.style);
+}
+
+fn sd_rounded_rect(p: vec2<f32>, half_size: vec2<f32>, radius: f32) -> f32 {
+    let r = min(radius, min(half_size.x, half_size.y));
+    let q = abs(p) - half_size + r;
+    return length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - r;
+}
+
+fn sd_capsule(p: vec2<f32>, half_length: f32, radius: f32) -> f32 {
+    let q = vec2<f32>(p.x - clamp(p.x, -half_length, half_length), p.y);
+    return length(q) - radius;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var d = 0.0;
+    if (in.kind == KIND_RECT) {
+        d = sd_rounded_rect(in.local, in.shape.xy, in.shape.z);
+    } else if (in.kind == KIND_CIRCLE) {
+        d = length(in.local) - in.shape.x;
+    } else {
+        d = sd_capsule(in.local, in.shape.x, in.shape.y);
+    }
+    let stroke = in.style.x;
+    if (stroke > 0.0) {
+        // Stroke inside the shape's edge
+        d = abs(d + stroke * 0.5) - stroke * 0.5;
+    }
+    let pixel = fwidth(d);
+    let smoothing = select(in.style.y, pixel, in.style.y <= 0.0);
+    let coverage = 1.0 - smoothstep(-smoothing * 0.5, smoothing * 0.5, d);
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
@@ -0,0 +1,423 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use bytemuck::{Pod, Zeroable};
+use fontdue::FontSettings;
+use wgpu::{
+    AddressMode, BindGroup, BindGroupLayout, BufferUsages, Device, Extent3d, FilterMode,
+    ImageCopyTexture, ImageDataLayout, Origin3d, PipelineCache, PushConstantRange, Queue,
+    RenderPass, RenderPipeline, SamplerDescriptor, ShaderStages, TextureAspect, TextureFormat,
+    TextureUsages,
+};
+
+use crate::{
+    device_context::DeviceContext, pipelines::require_push_constants, texture::Texture,
+    utils::GrowableBuffer, GlassError,
+};
+
+/// Width of the glyph atlas, rows are added until all glyphs fit. Widened for glyphs that don't
+/// fit in a row.
+const ATLAS_WIDTH: u32 = 512;
+/// Empty texels around each glyph to avoid bleeding when sampling linearly
+const GLYPH_PADDING: u32 = 1;
+/// Glyphs the instance buffer of [`TextPipeline::create_instance_buffer`] starts with
+const INITIAL_GLYPH_CAPACITY: u64 = 256;
+
+#[derive(Debug, Copy, Clone)]
+struct Glyph {
+    /// Top left & bottom right in atlas uv
+    uv: [f32; 4],
+    /// Offset from pen position to the glyph's bottom left, in pixels of the rasterized size
+    offset: [f32; 2],
+    size: [f32; 2],
+    advance: f32,
+}
+
+/// A font rasterized into a glyph atlas at a fixed pixel size. Text can be drawn at any size,
+/// but looks best close to the rasterized size. Characters not given at creation are skipped
+/// when drawing.
+pub struct Font {
+    atlas: Texture,
+    bind_group: BindGroup,
+    glyphs: HashMap<char, Glyph>,
+    px: f32,
+    line_height: f32,
+}
+
+impl Font {
+    /// Rasterize printable ASCII of a ttf/otf font at `px` pixels
+    pub fn new(device: &Device, queue: &Queue, bytes: &[u8], px: f32) -> Result<Font, GlassError> {
+        Self::new_with_chars(device, queue, bytes, px, (' '..='~').collect::<Vec<_>>())
+    }
+
+    /// Rasterize given characters of a ttf/otf font at `px` pixels. Fails if the glyphs don't fit
+    /// in a texture of the device's `max_texture_dimension_2d`.
+    pub fn new_with_chars(
+        device: &Device,
+        queue: &Queue,
+        bytes: &[u8],
+        px: f32,
+        chars: impl IntoIterator<Item = char>,
+    ) -> Result<Font, GlassError> {
+        let font = fontdue::Font::from_bytes(bytes, FontSettings::default())
+            .map_err(|e| GlassError::FontError(e.to_string()))?;
+        let line_height = font
+            .horizontal_line_metrics(px)
+            .map(|metrics| metrics.new_line_size)
+            .unwrap_or(px * 1.2);
+
+        let rasterized = chars
+            .into_iter()
+            .map(|c| {
+                let (metrics, bitmap) = font.rasterize(c, px);
+                (c, metrics, bitmap)
+            })
+            .collect::<Vec<_>>();
+        let max_dimension = device.limits().max_texture_dimension_2d;
+        let atlas_width = rasterized
+            .iter()
+            .map(|(_, metrics, _)| metrics.width as u32 + 2 * GLYPH_PADDING)
+            .fold(ATLAS_WIDTH, u32::max);
+        // Shelf pack glyphs row by row
+        let mut packed = vec![];
+        let (mut x, mut y, mut row_height) = (GLYPH_PADDING, GLYPH_PADDING, 0);
+        for (c, metrics, bitmap) in rasterized {
+            let (width, height) = (metrics.width as u32, metrics.height as u32);
+            if x + width + GLYPH_PADDING > atlas_width {
+                x = GLYPH_PADDING;
+                y += row_height + GLYPH_PADDING;
+                row_height = 0;
+            }
+            packed.push((c, metrics, bitmap, [x, y]));
+            x += width + GLYPH_PADDING;
+            row_height = row_height.max(height);
+        }
+        let used_height = y + row_height + GLYPH_PADDING;
+        if atlas_width > max_dimension || used_height > max_dimension {
+            return Err(GlassError::FontError(format!(
+                "Glyph atlas of {}x{} exceeds the max texture size {}, use a smaller px or fewer \
+                 chars",
+                atlas_width, used_height, max_dimension
+            )));
+        }
+        let atlas_height = used_height.next_power_of_two().min(max_dimension);
+
+        let mut data = vec![0u8; (atlas_width * atlas_height) as usize];
+        let mut glyphs = HashMap::new();
+        for (c, metrics, bitmap, [x, y]) in packed {
+            for row in 0..metrics.height {
+                let src = &bitmap[row * metrics.width..(row + 1) * metrics.width];
+                let start = ((y as usize + row) * atlas_width as usize) + x as usize;
+                data[start..start + metrics.width].copy_from_slice(src);
+            }
+            glyphs.insert(c, Glyph {
+                uv: [
+                    x as f32 / atlas_width as f32,
+                    y as f32 / atlas_height as f32,
+                    (x as usize + metrics.width) as f32 / atlas_width as f32,
+                    (y as usize + metrics.height) as f32 / atlas_height as f32,
+                ],
+                offset: [metrics.xmin as f32, metrics.ymin as f32],
+                size: [metrics.width as f32, metrics.height as f32],
+                advance: metrics.advance_width,
+            });
+        }
+
+        let atlas = Texture::empty(
+            device,
+            "glyph_atlas",
+            Extent3d {
+                width: atlas_width,
+                height: atlas_height,
+                depth_or_array_layers: 1,
+            },
+            1,
+            TextureFormat::R8Unorm,
+            TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+        );
+        queue.write_texture(
+            ImageCopyTexture {
+                texture: &atlas.texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            &data,
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(atlas_width),
+                rows_per_image: Some(atlas_height),
+            },
+            atlas.texture.size(),
+        );
+        atlas.mark_initialized();
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &text_bind_group_layout(device),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&atlas.views[0]),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some("glyph_atlas_bind_group"),
+        });
+        Ok(Font {
+            atlas,
+            bind_group,
+            glyphs,
+            px,
+            line_height,
+        })
+    }
+
+    /// The glyph atlas, one coverage channel
+    pub fn atlas(&self) -> &Texture {
+        &self.atlas
+    }
+
+    /// Width and height of text drawn at `size`
+    pub fn measure(&self, text: &str, size: f32) -> [f32; 2] {
+        let scale = size / self.px;
+        let mut lines = 1;
+        let (mut width, mut line_width) = (0.0f32, 0.0);
+        for c in text.chars() {
+            if c == '\n' {
+                lines += 1;
+                line_width = 0.0;
+                continue;
+            }
+            if let Some(glyph) = self.glyphs.get(&c) {
+                line_width += glyph.advance * scale;
+                width = width.max(line_width);
+            }
+        }
+        [width, lines as f32 * self.line_height * scale]
+    }
+}
+
+fn text_bind_group_layout(device: &Device) -> BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float {
+                        filterable: true,
+                    },
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+        label: Some("glyph_atlas_bind_group_layout"),
+    })
+}
+
+/// Draws text with a [`Font`], one instanced quad per glyph
+pub struct TextPipeline {
+    pipeline: RenderPipeline,
+}
+
+impl TextPipeline {
+    pub fn new(
+        device_context: &DeviceContext,
+        color_target_state: wgpu::ColorTargetState,
+        sample_count: u32,
+    ) -> Result<TextPipeline, GlassError> {
+        let device = device_context.device();
+        require_push_constants(
+            device,
+            "TextPipeline",
+            std::mem::size_of::<TextPushConstants>(),
+        )?;
+        Ok(Self {
+            pipeline: Self::new_render_pipeline(
+                device,
+                device_context.pipeline_cache(),
+                color_target_state,
+                sample_count,
+            ),
+        })
+    }
+
+    pub fn new_render_pipeline(
+        device: &Device,
+        cache: Option<&PipelineCache>,
+        color_target_state: wgpu::ColorTargetState,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Text Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("text.wgsl"))),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Text Pipeline Layout"),
+            bind_group_layouts: &[&text_bind_group_layout(device)],
+            push_constant_ranges: &[PushConstantRange {
+                stages: ShaderStages::VERTEX_FRAGMENT,
+                range: 0..std::mem::size_of::<TextPushConstants>() as u32,
+            }],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Text Render Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[GlyphInstance::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(color_target_state)],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache,
+        })
+    }
+
+    /// Draw text with its first baseline starting at `pos`. `size` is the font size in world
+    /// units, lines go downwards (towards -y). All glyphs are drawn with a single instanced draw.
+    ///
+    /// Glyphs are written to `instances`, which is reused between frames and only reallocated
+    /// when the text grows. It's written with `queue.write_buffer`, so each draw within a
+    /// submission needs its own buffer, see [`TextPipeline::create_instance_buffer`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_text(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        rpass: &mut RenderPass,
+        instances: &mut GrowableBuffer<GlyphInstance>,
+        font: &Font,
+        text: &str,
+        pos: [f32; 2],
+        size: f32,
+        color: [f32; 4],
+        view_proj: [[f32; 4]; 4],
+    ) {
+        let scale = size / font.px;
+        let mut pen = pos;
+        instances.clear();
+        for c in text.chars() {
+            if c == '\n' {
+                pen = [pos[0], pen[1] - font.line_height * scale];
+                continue;
+            }
+            let Some(glyph) = font.glyphs.get(&c) else {
+                continue;
+            };
+            if glyph.size[0] > 0.0 && glyph.size[1] > 0.0 {
+                instances.push(GlyphInstance {
+                    rect: [
+                        pen[0] + glyph.offset[0] * scale,
+                        pen[1] + glyph.offset[1] * scale,
+                        glyph.size[0] * scale,
+                        glyph.size[1] * scale,
+                    ],
+                    uv: glyph.uv,
+                });
+            }
+            pen[0] += glyph.advance * scale;
+        }
+        if instances.is_empty() {
+            return;
+        }
+        instances.upload(device, queue);
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &font.bind_group, &[]);
+        rpass.set_vertex_buffer(0, instances.buffer().slice(..));
+        rpass.set_push_constants(
+            ShaderStages::VERTEX_FRAGMENT,
+            0,
+            bytemuck::cast_slice(&[TextPushConstants {
+                view_proj,
+                color,
+            }]),
+        );
+        rpass.draw(0..6, 0..instances.uploaded_len());
+    }
+
+    /// Empty glyph buffer for [`TextPipeline::draw_text`]
+    pub fn create_instance_buffer(device: &Device) -> GrowableBuffer<GlyphInstance> {
+        GrowableBuffer::new(
+            device,
+            Some("Glyph Instances"),
+            BufferUsages::VERTEX,
+            INITIAL_GLYPH_CAPACITY,
+        )
+    }
+}
+
+/// Quad of a single glyph
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct GlyphInstance {
+    /// Bottom left xy, size zw
+    rect: [f32; 4],
+    /// Top left uv xy, bottom right uv zw
+    uv: [f32; 4],
+}
+
+impl GlyphInstance {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<GlyphInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct TextPushConstants {
+    pub view_proj: [[f32; 4]; 4],
+    pub color: [f32; 4],
+}
@@ -0,0 +1,47 @@
+struct PushConstants {
+    view_proj: mat4x4<f32>,
+    color: vec4<f32>,
+}
+var<push_constant> pc: PushConstants;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct GlyphInstance {
+    // Bottom left xy, size zw
+    @location(0) rect: vec4<f32>,
+    // Top left uv xy, bottom right uv zw
+    @location(1) uv: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, glyph: GlyphInstance) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let corner = corners[vertex_index % 6u];
+    var out: VertexOutput;
+    let position = glyph.rect.xy + corner * glyph.rect.zw;
+    out.clip_position = pc.view_proj * vec4<f32>(position, 0.0, 1.0);
+    // Texture v goes down
+    out.tex_coords = vec2<f32>(mix(glyph.uv.x, glyph.uv.z, corner.x), mix(glyph.uv.w, glyph.uv.y, corner.y));
+    return out;
+}
+
+@group(0) @binding(0)
+var atlas: texture_2d<f32>;
+@group(0) @binding(1)
+var s: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(atlas, s, in.tex_coords).r;
+    return vec4<f32>(pc.color.rgb, pc.color.a * coverage);
+}
@@ -0,0 +1,244 @@
+use std::{path::Path, str::SplitWhitespace};
+
+use wgpu::{Device, Extent3d, Queue, TextureFormat, TextureUsages, TextureView};
+
+use crate::{texture::Texture, GlassError};
+
+/// Largest `LUT_3D_SIZE` accepted from a `.cube` file
+const MAX_LUT_SIZE: u32 = 256;
+
+/// A 3D color lookup table used for color grading in
+/// [`TonemappingPipeline`](super::TonemappingPipeline). Red grows along x, green along y and blue
+/// along z. Stored as `Rgba8Unorm` so it can be sampled with linear filtering everywhere.
+pub struct ColorLut {
+    texture: Texture,
+    size: u32,
+}
+
+impl ColorLut {
+    /// Lut that leaves colors unchanged, a starting point for procedural grading
+    pub fn identity(device: &Device, queue: &Queue, size: u32) -> ColorLut {
+        let size = size.clamp(2, MAX_LUT_SIZE);
+        let max = (size - 1) as f32;
+        let mut colors = Vec::with_capacity((size * size * size) as usize);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    colors.push([r as f32 / max, g as f32 / max, b as f32 / max]);
+                }
+            }
+        }
+        Self::from_colors(device, queue, size, &colors)
+    }
+
+    /// Load an Adobe / Resolve `.cube` file
+    pub fn load(
+        device: &Device,
+        queue: &Queue,
+        path: impl AsRef<Path>,
+    ) -> Result<ColorLut, GlassError> {
+        let source = std::fs::read_to_string(path).map_err(GlassError::IoError)?;
+        Self::from_cube(device, queue, &source)
+    }
+
+    /// Parse the contents of a `.cube` file. Only 3D luts are supported, `DOMAIN_MIN` and
+    /// `DOMAIN_MAX` are used to normalize the entries.
+    pub fn from_cube(device: &Device, queue: &Queue, source: &str) -> Result<ColorLut, GlassError> {
+        let (size, colors) = parse_cube(source)?;
+        Ok(Self::from_colors(device, queue, size, &colors))
+    }
+
+    /// Create a lut from `size`³ colors in 0..1, red changing fastest
+    pub fn from_colors(device: &Device, queue: &Queue, size: u32, colors: &[[f32; 3]]) -> ColorLut {
+        assert_eq!(colors.len(), (size * size * size) as usize);
+        let data = colors
+            .iter()
+            .flat_map(|c| {
+                let [r, g, b] = c.map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8);
+                [r, g, b, 255]
+            })
+            .collect::<Vec<u8>>();
+        let texture = Texture::empty_3d(
+            device,
+            "color_lut",
+            Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: size,
+            },
+            TextureFormat::Rgba8Unorm,
+            TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+        );
+        texture.write_volume(queue, &data);
+        ColorLut {
+            texture,
+            size,
+        }
+    }
+
+    /// Number of entries along each axis
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture.texture
+    }
+
+    pub fn view(&self) -> &TextureView {
+        &self.texture.views[0]
+    }
+}
+
+fn parse_cube(source: &str) -> Result<(u32, Vec<[f32; 3]>), GlassError> {
+    let error =
+        |line: usize, msg: &str| GlassError::LutError(format!("line {}: {}", line + 1, msg));
+    let mut size = None;
+    let mut domain_min = [0.0; 3];
+    let mut domain_max = [1.0; 3];
+    let mut colors = vec![];
+    for (i, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+        let floats = |words: SplitWhitespace| {
+            three_floats(words).ok_or_else(|| error(i, "expected three numbers"))
+        };
+        match keyword {
+            "TITLE" => (),
+            "LUT_1D_SIZE" => return Err(error(i, "1D luts are not supported")),
+            "LUT_3D_SIZE" => {
+                let value = words
+                    .next()
+                    .and_then(|w| w.parse::<u32>().ok())
+                    .filter(|s| (2..=MAX_LUT_SIZE).contains(s))
+                    .ok_or_else(|| error(i, "invalid LUT_3D_SIZE"))?;
+                size = Some(value);
+            }
+            "DOMAIN_MIN" => domain_min = floats(words)?,
+            "DOMAIN_MAX" => domain_max = floats(words)?,
+            _ if keyword.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.') => {
+                // Keyword was the first number, parse the whole line
+                let color = floats(line.split_whitespace())?;
+                colors.push(std::array::from_fn(|c| {
+                    (color[c] - domain_min[c]) / (domain_max[c] - domain_min[c]).max(f32::EPSILON)
+                }));
+            }
+            // Unknown keywords are skipped as the format allows
+            _ => (),
+        }
+    }
+    let size = size.ok_or_else(|| GlassError::LutError("missing LUT_3D_SIZE".to_string()))?;
+    if colors.len() != (size * size * size) as usize {
+        return Err(GlassError::LutError(format!(
+            "expected {} entries, found {}",
+            size * size * size,
+            colors.len()
+        )));
+    }
+    Ok((size, colors))
+}
+
+fn three_floats<'a>(mut words: impl Iterator<Item = &'a str>) -> Option<[f32; 3]> {
+    let mut out = [0.0; 3];
+    for value in out.iter_mut() {
+        *value = words.next()?.parse().ok()?;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        pipelines::tonemapping::lut::{parse_cube, MAX_LUT_SIZE},
+        GlassError,
+    };
+
+    /// A 2³ identity lut with the given header
+    fn cube(header: &str) -> String {
+        let mut source = header.to_string();
+        for b in 0..2 {
+            for g in 0..2 {
+                for r in 0..2 {
+                    source.push_str(&format!("{} {} {}\n", r, g, b));
+                }
+            }
+        }
+        source
+    }
+
+    fn error(source: &str) -> String {
+        match parse_cube(source) {
+            Err(GlassError::LutError(e)) => e,
+            _ => panic!("expected a lut error"),
+        }
+    }
+
+    #[test]
+    fn test_parse_identity() {
+        let source = cube("# comment\nTITLE \"identity\"\n\nLUT_3D_SIZE 2\n");
+        let (size, colors) = parse_cube(&source).unwrap();
+        assert_eq!(size, 2);
+        assert_eq!(colors.len(), 8);
+        // Red changes fastest
+        assert_eq!(colors[0], [0.0, 0.0, 0.0]);
+        assert_eq!(colors[1], [1.0, 0.0, 0.0]);
+        assert_eq!(colors[2], [0.0, 1.0, 0.0]);
+        assert_eq!(colors[4], [0.0, 0.0, 1.0]);
+        assert_eq!(colors[7], [1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_parse_domain() {
+        let mut source = "LUT_3D_SIZE 2\nDOMAIN_MIN 0 0 0\nDOMAIN_MAX 2 4 8\n".to_string();
+        for _ in 0..8 {
+            source.push_str("1 2 4\n");
+        }
+        let (_, colors) = parse_cube(&source).unwrap();
+        assert!(colors.iter().all(|c| *c == [0.5, 0.5, 0.5]));
+    }
+
+    #[test]
+    fn test_parse_skips_unknown_keywords() {
+        let source = cube("LUT_3D_SIZE 2\nLUT_3D_INPUT_RANGE 0 1\n");
+        assert!(parse_cube(&source).is_ok());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(error(&cube("")), "missing LUT_3D_SIZE");
+        assert_eq!(
+            error("LUT_1D_SIZE 4\n"),
+            "line 1: 1D luts are not supported"
+        );
+        assert_eq!(
+            error(&cube("LUT_3D_SIZE 1\n")),
+            "line 1: invalid LUT_3D_SIZE"
+        );
+        assert_eq!(
+            error(&cube(&format!("LUT_3D_SIZE {}\n", MAX_LUT_SIZE + 1))),
+            "line 1: invalid LUT_3D_SIZE"
+        );
+        assert_eq!(
+            error(&cube("LUT_3D_SIZE x\n")),
+            "line 1: invalid LUT_3D_SIZE"
+        );
+        assert_eq!(
+            error(&cube("LUT_3D_SIZE 3\n")),
+            "expected 27 entries, found 8"
+        );
+        assert_eq!(
+            error("LUT_3D_SIZE 2\n0 0\n"),
+            "line 2: expected three numbers"
+        );
+        assert_eq!(
+            error("LUT_3D_SIZE 2\nDOMAIN_MIN 0 a 0\n"),
+            "line 2: expected three numbers"
+        );
+    }
+}
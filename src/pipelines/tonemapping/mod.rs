@@ -1,3 +1,5 @@
+mod lut;
 mod pipeline;
 
-pub use pipeline::{ColorGrading, TonemappingPipeline};
+pub use lut::ColorLut;
+pub use pipeline::{ColorGrading, Tonemapper, TonemappingPipeline};
@@ -1,16 +1,24 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, sync::Arc};
 
 use bytemuck::{Pod, Zeroable};
 use wgpu::{
-    util::DeviceExt, BindGroup, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType,
-    Buffer, ColorTargetState, ColorWrites, CommandEncoder, Device, Operations, PushConstantRange,
-    RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, Sampler, SamplerBindingType,
-    ShaderStages, TextureFormat, TextureSampleType, TextureView, TextureViewDimension,
+    util::DeviceExt, AddressMode, BindGroup, BindGroupLayoutDescriptor, BindGroupLayoutEntry,
+    BindingType, Buffer, ColorTargetState, ColorWrites, CommandEncoder, Device, Extent3d,
+    FilterMode, Operations, PushConstantRange, RenderPassColorAttachment, RenderPassDescriptor,
+    RenderPipeline, Sampler, SamplerBindingType, SamplerDescriptor, ShaderStages,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
+    TextureView, TextureViewDescriptor, TextureViewDimension,
 };
 
 use crate::{
-    pipelines::{SimpleTexturedVertex, FULL_SCREEN_TRIANGLE_VERTICES},
+    device_context::DeviceContext,
+    pipelines::{
+        require_push_constants, tonemapping::ColorLut, SimpleTexturedVertex,
+        FULL_SCREEN_TRIANGLE_VERTICES,
+    },
     texture::Texture,
+    utils::GpuProfiler,
+    GlassError,
 };
 
 const TONEMAPPING_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
@@ -18,10 +26,30 @@ const TONEMAPPING_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
 pub struct TonemappingPipeline {
     tonemapping_pipeline: RenderPipeline,
     vertices: Buffer,
+    lut_sampler: Sampler,
+    lut_bind_group: BindGroup,
+    /// Size of the bound lut, 0 when no lut is set
+    lut_size: u32,
+    profiler: Option<Arc<GpuProfiler>>,
 }
 
 impl TonemappingPipeline {
-    pub fn new(device: &Device) -> TonemappingPipeline {
+    pub fn new(device_context: &DeviceContext) -> Result<TonemappingPipeline, GlassError> {
+        Self::new_with_target_format(device_context, TONEMAPPING_TEXTURE_FORMAT)
+    }
+
+    /// Create a tonemapping pipeline that writes to given format. Use the window's negotiated
+    /// surface format to tonemap directly to the swapchain, e.g. on HDR surfaces.
+    pub fn new_with_target_format(
+        device_context: &DeviceContext,
+        target_texture_format: TextureFormat,
+    ) -> Result<TonemappingPipeline, GlassError> {
+        let device = device_context.device();
+        require_push_constants(
+            device,
+            "TonemappingPipeline",
+            std::mem::size_of::<ToneMappingPushConstants>(),
+        )?;
         let vertices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Tonemapping Vertex Buffer"),
             contents: bytemuck::cast_slice(FULL_SCREEN_TRIANGLE_VERTICES),
@@ -51,13 +79,36 @@ impl TonemappingPipeline {
                 },
             ],
         });
+        let lut_bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("tonemapping_lut_bind_group_layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float {
+                            filterable: true,
+                        },
+                        view_dimension: TextureViewDimension::D3,
+                        multisampled: false,
+                    },
+                    visibility: ShaderStages::FRAGMENT,
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    visibility: ShaderStages::FRAGMENT,
+                    count: None,
+                },
+            ],
+        });
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Tonemapping Shader"),
             source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("tonemapping.wgsl"))),
         });
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Tonemapping Pipeline Layout"),
-            bind_group_layouts: &[&bind_group_layout],
+            bind_group_layouts: &[&bind_group_layout, &lut_bind_group_layout],
             push_constant_ranges: &[PushConstantRange {
                 stages: ShaderStages::FRAGMENT,
                 range: 0..std::mem::size_of::<ToneMappingPushConstants>() as u32,
@@ -77,7 +128,7 @@ impl TonemappingPipeline {
                 entry_point: Some("fragment"),
                 compilation_options: Default::default(),
                 targets: &[Some(ColorTargetState {
-                    format: TONEMAPPING_TEXTURE_FORMAT,
+                    format: target_texture_format,
                     blend: None,
                     write_mask: ColorWrites::ALL,
                 })],
@@ -86,15 +137,92 @@ impl TonemappingPipeline {
             depth_stencil: None,
             multisample: wgpu::MultisampleState::default(),
             multiview: None,
-            cache: None,
+            cache: device_context.pipeline_cache(),
+        });
+
+        let lut_sampler = device.create_sampler(&SamplerDescriptor {
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
         });
+        // Placeholder until a lut is set, never sampled
+        let empty_lut = device
+            .create_texture(&TextureDescriptor {
+                label: Some("empty_color_lut"),
+                size: Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D3,
+                format: TextureFormat::Rgba8Unorm,
+                usage: TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&TextureViewDescriptor {
+                dimension: Some(TextureViewDimension::D3),
+                ..Default::default()
+            });
+        let lut_bind_group =
+            Self::lut_bind_group(device, &tonemapping_pipeline, &empty_lut, &lut_sampler);
 
-        TonemappingPipeline {
+        Ok(TonemappingPipeline {
             tonemapping_pipeline,
             vertices,
+            lut_sampler,
+            lut_bind_group,
+            lut_size: 0,
+            profiler: None,
+        })
+    }
+
+    fn lut_bind_group(
+        device: &Device,
+        pipeline: &RenderPipeline,
+        lut: &TextureView,
+        sampler: &Sampler,
+    ) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &pipeline.get_bind_group_layout(1),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(lut),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+            label: Some("tonemap_lut_bind_group"),
+        })
+    }
+
+    /// Set the lut applied after tonemapping, blended in by [`ColorGrading::lut_strength`].
+    /// `None` disables lut grading.
+    pub fn set_lut(&mut self, device: &Device, lut: Option<&ColorLut>) {
+        if let Some(lut) = lut {
+            self.lut_bind_group = Self::lut_bind_group(
+                device,
+                &self.tonemapping_pipeline,
+                lut.view(),
+                &self.lut_sampler,
+            );
+            self.lut_size = lut.size();
+        } else {
+            self.lut_size = 0;
         }
     }
 
+    pub fn has_lut(&self) -> bool {
+        self.lut_size > 0
+    }
+
     pub fn create_bind_group(
         &self,
         device: &Device,
@@ -119,6 +247,11 @@ impl TonemappingPipeline {
         bind_group
     }
 
+    /// Time the pipeline's passes, see [`GpuProfiler::pass_timestamp_writes`]
+    pub fn set_profiler(&mut self, profiler: Option<Arc<GpuProfiler>>) {
+        self.profiler = profiler;
+    }
+
     pub fn tonemap(
         &self,
         encoder: &mut CommandEncoder,
@@ -126,21 +259,45 @@ impl TonemappingPipeline {
         output: &Texture,
         color_grading: ColorGrading,
     ) {
-        let push_constants: ToneMappingPushConstants = color_grading.into();
+        self.tonemap_to_view(
+            encoder,
+            input_image_bind_group,
+            &output.views[0],
+            color_grading,
+        );
+    }
+
+    /// Tonemap into any view with the pipeline's target format, e.g. a swapchain frame view.
+    pub fn tonemap_to_view(
+        &self,
+        encoder: &mut CommandEncoder,
+        input_image_bind_group: &BindGroup,
+        output: &TextureView,
+        color_grading: ColorGrading,
+    ) {
+        let mut push_constants: ToneMappingPushConstants = color_grading.into();
+        push_constants.lut_size = self.lut_size as f32;
+        if self.lut_size == 0 {
+            push_constants.lut_strength = 0.0;
+        }
         {
             let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
                 label: Some("tonemapping_pass"),
                 color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &output.views[0],
+                    view: output,
                     resolve_target: None,
                     ops: Operations::default(),
                 })],
                 depth_stencil_attachment: None,
-                timestamp_writes: None,
+                timestamp_writes: self
+                    .profiler
+                    .as_ref()
+                    .and_then(|profiler| profiler.pass_timestamp_writes("glass::tonemap")),
                 occlusion_query_set: None,
             });
             r_pass.set_pipeline(&self.tonemapping_pipeline);
             r_pass.set_bind_group(0, input_image_bind_group, &[]);
+            r_pass.set_bind_group(1, &self.lut_bind_group, &[]);
             r_pass.set_vertex_buffer(0, self.vertices.slice(..));
             r_pass.set_push_constants(
                 ShaderStages::FRAGMENT,
@@ -160,6 +317,10 @@ pub struct ToneMappingPushConstants {
     pub gamma: f32,
     pub pre_saturation: f32,
     pub post_saturation: f32,
+    pub tonemapper: u32,
+    pub lut_strength: f32,
+    /// Set by the pipeline from its bound lut
+    pub lut_size: f32,
 }
 
 impl From<ColorGrading> for ToneMappingPushConstants {
@@ -170,27 +331,52 @@ impl From<ColorGrading> for ToneMappingPushConstants {
             gamma: val.gamma,
             pre_saturation: val.pre_saturation,
             post_saturation: val.post_saturation,
+            tonemapper: val.tonemapper as u32,
+            lut_strength: val.lut_strength,
+            lut_size: 0.0,
         }
     }
 }
 
+/// Curve mapping hdr colors to displayable 0..1 range
+#[repr(u32)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Tonemapper {
+    /// Reinhard applied per channel, desaturates bright colors towards white
+    Reinhard = 0,
+    /// Reinhard applied to luminance, keeps hue but bright colors never reach white
+    #[default]
+    ReinhardLuminance = 1,
+    /// Narkowicz's fit of the ACES filmic curve
+    Aces = 2,
+    /// Troy Sobotka's AgX with the default look
+    AgX = 3,
+    /// Uchimura's Gran Turismo curve, linear mid section with a soft shoulder
+    Uchimura = 4,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct ColorGrading {
     pub off: bool,
+    pub tonemapper: Tonemapper,
     pub exposure: f32,
     pub gamma: f32,
     pub pre_saturation: f32,
     pub post_saturation: f32,
+    /// How much of the pipeline's lut is blended in, see [`TonemappingPipeline::set_lut`]
+    pub lut_strength: f32,
 }
 
 impl Default for ColorGrading {
     fn default() -> Self {
         Self {
             off: false,
+            tonemapper: Tonemapper::default(),
             exposure: 0.0,
             gamma: 1.0,
             pre_saturation: 1.0,
             post_saturation: 1.0,
+            lut_strength: 1.0,
         }
     }
 }
@@ -17,6 +17,10 @@ fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
 var hdr_texture: texture_2d<f32>;
 @group(0) @binding(1)
 var hdr_sampler: sampler;
+@group(1) @binding(0)
+var lut_texture: texture_3d<f32>;
+@group(1) @binding(1)
+var lut_sampler: sampler;
 
 struct PushConstants {
     off: u32,
@@ -24,6 +28,11 @@ struct PushConstants {
     gamma: f32,
     pre_saturation: f32,
     post_saturation: f32,
+    // 0: Reinhard, 1: Reinhard luminance, 2: ACES, 3: AgX, 4: Uchimura
+    tonemapper: u32,
+    lut_strength: f32,
+    // 0 when no lut is bound
+    lut_size: f32,
 }
 var<push_constant> pc: PushConstants;
 
@@ -60,6 +69,104 @@ fn tonemapping_reinhard_luminance(color: vec3<f32>) -> vec3<f32> {
     return tonemapping_change_luminance(color, l_new);
 }
 
+fn tonemapping_reinhard(color: vec3<f32>) -> vec3<f32> {
+    return color / (1.0 + color);
+}
+
+// Krzysztof Narkowicz, ACES Filmic Tone Mapping Curve
+// https://knarkowicz.wordpress.com/2016/01/06/aces-filmic-tone-mapping-curve/
+fn tonemapping_aces(color: vec3<f32>) -> vec3<f32> {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+    return saturate((color * (a * color + b)) / (color * (c * color + d) + e));
+}
+
+// Minimal AgX with the default look, polynomial fit by Benjamin Wrensch
+// https://iolite-engine.com/blog_posts/minimal_agx_implementation
+fn agx_default_contrast(x: vec3<f32>) -> vec3<f32> {
+    let x2 = x * x;
+    let x4 = x2 * x2;
+    return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2
+        + 0.1191 * x - 0.00232;
+}
+
+fn tonemapping_agx(color: vec3<f32>) -> vec3<f32> {
+    let agx_mat = mat3x3<f32>(
+        0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+        0.0784335999999992, 0.878468636469772, 0.0784336,
+        0.0792237451477643, 0.0791661274605434, 0.879142973793104,
+    );
+    let agx_mat_inv = mat3x3<f32>(
+        1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+        -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+        -0.0990297440797205, -0.0989611768448433, 1.15107367264116,
+    );
+    let min_ev = -12.47393;
+    let max_ev = 4.026069;
+    var c = agx_mat * color;
+    c = clamp(log2(max(c, vec3(1e-10))), vec3(min_ev), vec3(max_ev));
+    c = (c - min_ev) / (max_ev - min_ev);
+    c = agx_default_contrast(c);
+    c = agx_mat_inv * c;
+    // AgX outputs display encoded values, return linear
+    return powsafe(saturate(c), 2.2);
+}
+
+// Hajime Uchimura, HDR Theory and Practice, CEDEC 2017
+// https://www.desmos.com/calculator/gslcdxvipg
+fn tonemapping_uchimura(x: vec3<f32>) -> vec3<f32> {
+    // Max brightness, contrast, linear section start & length, black tightness
+    let p = 1.0;
+    let a = 1.0;
+    let m = 0.22;
+    let l = 0.4;
+    let c = 1.33;
+    let b = 0.0;
+    let l0 = ((p - m) * l) / a;
+    let s0 = m + l0;
+    let s1 = m + a * l0;
+    let c2 = (a * p) / (p - s1);
+    let cp = -c2 / p;
+    let w0 = vec3(1.0) - smoothstep(vec3(0.0), vec3(m), x);
+    let w2 = step(vec3(m + l0), x);
+    let w1 = vec3(1.0) - w0 - w2;
+    let t = m * pow(x / m, vec3(c)) + b;
+    let s = p - (p - s1) * exp(cp * (x - s0));
+    let lin = m + a * (x - m);
+    return t * w0 + lin * w1 + s * w2;
+}
+
+fn apply_tonemapper(color: vec3<f32>) -> vec3<f32> {
+    switch pc.tonemapper {
+        case 0u: {
+            return tonemapping_reinhard(color);
+        }
+        case 2u: {
+            return tonemapping_aces(color);
+        }
+        case 3u: {
+            return tonemapping_agx(color);
+        }
+        case 4u: {
+            return tonemapping_uchimura(color);
+        }
+        default: {
+            return tonemapping_reinhard_luminance(color);
+        }
+    }
+}
+
+// Luts are authored for display encoded colors, sample texel centers
+fn apply_lut(color: vec3<f32>) -> vec3<f32> {
+    let encoded = saturate(powsafe(color, 1.0 / 2.2));
+    let coord = encoded * ((pc.lut_size - 1.0) / pc.lut_size) + 0.5 / pc.lut_size;
+    let graded = textureSampleLevel(lut_texture, lut_sampler, coord, 0.0).rgb;
+    return powsafe(mix(encoded, graded, pc.lut_strength), 2.2);
+}
+
 // Source: Advanced VR Rendering, GDC 2015, Alex Vlachos, Valve, Slide 49
 // https://media.steampowered.com/apps/valve/2015/Alex_Vlachos_Advanced_VR_Rendering_GDC2015.pdf
 fn screen_space_dither(frag_coord: vec2<f32>) -> vec3<f32> {
@@ -76,7 +183,10 @@ fn tone_mapping(in: vec4<f32>) -> vec4<f32> {
     color = color * powsafe(vec3(2.0), pc.exposure);
     color = max(color, vec3(0.0));
     // tone_mapping
-    color = tonemapping_reinhard_luminance(color.rgb);
+    color = apply_tonemapper(color.rgb);
+    if (pc.lut_size > 0.0 && pc.lut_strength > 0.0) {
+        color = apply_lut(color);
+    }
     // Perceptual post tonemapping grading
     color = saturation(color, pc.post_saturation);
 
@@ -0,0 +1,213 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Write,
+    path::PathBuf,
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::Arc,
+    thread::JoinHandle,
+};
+
+use flume::{Receiver, Sender};
+use image::RgbaImage;
+use log::{error, warn};
+use wgpu::{Buffer, CommandEncoder, Device, Maintain, Texture};
+use winit::window::WindowId;
+
+use crate::{capture::PendingCapture, GlassError};
+
+/// Where recorded frames are written
+#[derive(Debug, Clone)]
+pub enum RecordOutput {
+    /// Write `frame_000000.png`, `frame_000001.png`... into a directory
+    PngSequence { dir: PathBuf },
+    /// Pipe raw rgba8 frames into the stdin of an external encoder. `{width}` and `{height}` in
+    /// args are replaced with the frame size. E.g. for ffmpeg:
+    /// `-f rawvideo -pix_fmt rgba -s {width}x{height} -r 60 -i - -pix_fmt yuv420p out.mp4`
+    Command { program: String, args: Vec<String> },
+}
+
+#[derive(Debug, Clone)]
+pub struct RecorderConfig {
+    pub output: RecordOutput,
+    /// Number of readback buffers in flight. Frames are dropped (not waited for) when all are in
+    /// use, so recording never stalls the GPU.
+    pub ring_size: usize,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            output: RecordOutput::PngSequence {
+                dir: PathBuf::from("recording"),
+            },
+            ring_size: 3,
+        }
+    }
+}
+
+/// Records each presented frame of a window. Frames are copied into a ring of readback buffers,
+/// mapped asynchronously and written on a separate thread in frame order.
+pub struct FrameRecorder {
+    window_id: WindowId,
+    ring_size: usize,
+    in_flight: usize,
+    next_frame: u64,
+    dropped_frames: u64,
+    free_buffers_sender: Sender<Arc<Buffer>>,
+    free_buffers: Receiver<Arc<Buffer>>,
+    frame_sender: Sender<(u64, Option<RgbaImage>)>,
+    writer: JoinHandle<()>,
+}
+
+impl FrameRecorder {
+    pub fn new(window_id: WindowId, config: RecorderConfig) -> Result<FrameRecorder, GlassError> {
+        if let RecordOutput::PngSequence {
+            dir,
+        } = &config.output
+        {
+            fs::create_dir_all(dir).map_err(GlassError::IoError)?;
+        }
+        let (free_buffers_sender, free_buffers) = flume::unbounded();
+        let (frame_sender, frame_receiver) = flume::unbounded();
+        let output = config.output;
+        let writer = std::thread::spawn(move || write_frames(output, frame_receiver));
+        Ok(FrameRecorder {
+            window_id,
+            ring_size: config.ring_size.max(1),
+            in_flight: 0,
+            next_frame: 0,
+            dropped_frames: 0,
+            free_buffers_sender,
+            free_buffers,
+            frame_sender,
+            writer,
+        })
+    }
+
+    pub fn window_id(&self) -> WindowId {
+        self.window_id
+    }
+
+    /// Number of frames recorded so far
+    pub fn frames(&self) -> u64 {
+        self.next_frame
+    }
+
+    /// Number of frames skipped because all readback buffers were in flight
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames
+    }
+
+    /// Encode a copy of the frame. Call [`FrameRecorder::read`] with the result after submitting.
+    pub(crate) fn copy(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        frame: &Texture,
+    ) -> Option<PendingCapture> {
+        let reuse = match self.free_buffers.try_recv() {
+            Ok(buffer) => Some(buffer),
+            Err(_) if self.in_flight < self.ring_size => {
+                self.in_flight += 1;
+                None
+            }
+            Err(_) => {
+                self.dropped_frames += 1;
+                return None;
+            }
+        };
+        let capture = PendingCapture::copy(device, encoder, frame, reuse);
+        if capture.is_none() {
+            self.in_flight -= 1;
+        }
+        capture
+    }
+
+    pub(crate) fn read(&mut self, capture: PendingCapture) {
+        let frame = self.next_frame;
+        self.next_frame += 1;
+        let frame_sender = self.frame_sender.clone();
+        let free_buffers_sender = self.free_buffers_sender.clone();
+        capture.read_then(move |image, buffer| {
+            let _ = frame_sender.send((frame, image));
+            let _ = free_buffers_sender.send(buffer);
+        });
+    }
+
+    /// Wait for frames in flight and for the writer to finish
+    pub fn finish(self, device: &Device) {
+        device.poll(Maintain::Wait);
+        drop(self.frame_sender);
+        if self.writer.join().is_err() {
+            error!("Frame recorder writer thread panicked");
+        }
+        if self.dropped_frames > 0 {
+            warn!(
+                "Frame recorder dropped {} of {} frames",
+                self.dropped_frames,
+                self.next_frame + self.dropped_frames
+            );
+        }
+    }
+}
+
+fn write_frames(output: RecordOutput, frames: Receiver<(u64, Option<RgbaImage>)>) {
+    let mut encoder: Option<(Child, ChildStdin)> = None;
+    let mut waiting = BTreeMap::new();
+    let mut next = 0;
+    while let Ok((index, image)) = frames.recv() {
+        waiting.insert(index, image);
+        // Readbacks may complete out of order, write in frame order
+        while let Some(image) = waiting.remove(&next) {
+            let index = next;
+            next += 1;
+            let Some(image) = image else {
+                continue;
+            };
+            let result = match &output {
+                RecordOutput::PngSequence {
+                    dir,
+                } => image
+                    .save(dir.join(format!("frame_{:06}.png", index)))
+                    .map_err(|e| e.to_string()),
+                RecordOutput::Command {
+                    program,
+                    args,
+                } => write_to_command(&mut encoder, program, args, &image),
+            };
+            if let Err(e) = result {
+                error!("Failed to write recorded frame: {}", e);
+            }
+        }
+    }
+    if let Some((mut child, stdin)) = encoder {
+        drop(stdin);
+        if let Err(e) = child.wait() {
+            error!("Failed to wait for encoder: {}", e);
+        }
+    }
+}
+
+fn write_to_command(
+    encoder: &mut Option<(Child, ChildStdin)>,
+    program: &str,
+    args: &[String],
+    image: &RgbaImage,
+) -> Result<(), String> {
+    if encoder.is_none() {
+        let args = args.iter().map(|arg| {
+            arg.replace("{width}", &image.width().to_string())
+                .replace("{height}", &image.height().to_string())
+        });
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        let stdin = child.stdin.take().ok_or("Encoder has no stdin")?;
+        *encoder = Some((child, stdin));
+    }
+    let (_, stdin) = encoder.as_mut().unwrap();
+    stdin.write_all(image.as_raw()).map_err(|e| e.to_string())
+}
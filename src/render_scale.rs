@@ -0,0 +1,229 @@
+use std::{sync::Arc, time::Duration};
+
+use wgpu::{CommandEncoder, Device, Extent3d, TextureFormat, TextureUsages, TextureView};
+
+use crate::{
+    device_context::DeviceContext,
+    pipelines::{BlitFilter, BlitPipeline},
+    resize::Resizable,
+    texture::Texture,
+    utils::GpuProfiler,
+};
+
+/// Smallest render scale
+pub const MIN_RENDER_SCALE: f32 = 0.25;
+/// Largest render scale, 2.0 supersamples
+pub const MAX_RENDER_SCALE: f32 = 2.0;
+
+/// Adjusts [`RenderScale`] towards a GPU frame time budget, see [`RenderScale::update_auto`]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AutoRenderScale {
+    /// GPU time budget per frame
+    pub target_frame_time: Duration,
+    /// Scale change per adjustment
+    pub step: f32,
+    pub min_scale: f32,
+    pub max_scale: f32,
+    /// Frames to wait after an adjustment so timings reflect the new resolution
+    pub cooldown_frames: u32,
+}
+
+impl Default for AutoRenderScale {
+    fn default() -> Self {
+        Self {
+            target_frame_time: Duration::from_secs_f64(1.0 / 60.0),
+            step: 0.05,
+            min_scale: 0.5,
+            max_scale: 1.0,
+            cooldown_frames: 30,
+        }
+    }
+}
+
+/// An offscreen HDR render target whose resolution is the surface size times a scale factor,
+/// upscaled to the surface at the end of the frame. Render (or dispatch) into
+/// [`RenderScale::target`] and call [`RenderScale::upscale`]. The target is recreated when the
+/// surface is resized or the scale changes. It is only usable as a storage texture if `format`
+/// supports storage access.
+pub struct RenderScale {
+    target: Texture,
+    format: TextureFormat,
+    surface_size: [u32; 2],
+    scale: f32,
+    /// `max_texture_dimension_2d` of the device
+    max_dimension: u32,
+    auto: Option<AutoRenderScale>,
+    cooldown: u32,
+    blit: BlitPipeline,
+}
+
+impl RenderScale {
+    /// `format` is the target's format, e.g. `Rgba16Float`, `output_format` that of the view
+    /// passed to [`RenderScale::upscale`]
+    pub fn new(
+        device_context: &DeviceContext,
+        format: TextureFormat,
+        output_format: TextureFormat,
+        surface_size: [u32; 2],
+        scale: f32,
+    ) -> RenderScale {
+        let device = device_context.device();
+        let scale = scale.clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE);
+        let max_dimension = device.limits().max_texture_dimension_2d;
+        RenderScale {
+            target: Self::create_target(
+                device,
+                format,
+                scaled_size(surface_size, scale, max_dimension),
+            ),
+            format,
+            surface_size,
+            scale,
+            max_dimension,
+            auto: None,
+            cooldown: 0,
+            blit: BlitPipeline::new(device_context, output_format),
+        }
+    }
+
+    fn create_target(device: &Device, format: TextureFormat, size: [u32; 2]) -> Texture {
+        // Storage access is only added for formats that support it, e.g. not for sRGB formats
+        let storage = format
+            .guaranteed_format_features(device.features())
+            .allowed_usages
+            .intersection(TextureUsages::STORAGE_BINDING);
+        Texture::empty(
+            device,
+            "render_scale_target",
+            Extent3d {
+                width: size[0],
+                height: size[1],
+                depth_or_array_layers: 1,
+            },
+            1,
+            format,
+            TextureUsages::RENDER_ATTACHMENT
+                | TextureUsages::TEXTURE_BINDING
+                | storage
+                | TextureUsages::COPY_SRC
+                | TextureUsages::COPY_DST,
+        )
+    }
+
+    /// The scaled render target
+    pub fn target(&self) -> &Texture {
+        &self.target
+    }
+
+    /// Size of the render target in pixels, at most the device's `max_texture_dimension_2d`
+    pub fn size(&self) -> [u32; 2] {
+        scaled_size(self.surface_size, self.scale, self.max_dimension)
+    }
+
+    pub fn surface_size(&self) -> [u32; 2] {
+        self.surface_size
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Set the scale, clamped to [`MIN_RENDER_SCALE`]..[`MAX_RENDER_SCALE`]. Returns whether the
+    /// target was recreated, in which case bind groups referencing it must be recreated too.
+    pub fn set_scale(&mut self, device: &Device, scale: f32) -> bool {
+        let scale = scale.clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE);
+        let old_size = self.size();
+        self.scale = scale;
+        self.recreate_if_changed(device, old_size)
+    }
+
+    pub fn auto(&self) -> Option<AutoRenderScale> {
+        self.auto
+    }
+
+    /// Enable automatic scaling with [`RenderScale::update_auto`], or disable it with `None`
+    pub fn set_auto(&mut self, auto: Option<AutoRenderScale>) {
+        self.auto = auto;
+        self.cooldown = 0;
+    }
+
+    /// Adjust the scale by the GPU time of the profiler's latest resolved frame, see
+    /// [`GlassContext::gpu_profiler`](crate::GlassContext::gpu_profiler). Call once per frame.
+    /// Does nothing unless auto scaling is enabled. Returns whether the target was recreated.
+    pub fn update_auto(&mut self, device: &Device, profiler: &GpuProfiler) -> bool {
+        let Some(auto) = self.auto else {
+            return false;
+        };
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            return false;
+        }
+        let timings = profiler.timings();
+        if timings.is_empty() {
+            return false;
+        }
+        let frame_time = timings
+            .iter()
+            .map(|t| t.duration)
+            .sum::<Duration>()
+            .as_secs_f32();
+        let target = auto.target_frame_time.as_secs_f32();
+        // Leave headroom before scaling up again to avoid oscillating
+        let scale = if frame_time > target {
+            self.scale - auto.step
+        } else if frame_time < target * 0.8 {
+            self.scale + auto.step
+        } else {
+            return false;
+        };
+        // Unlike clamp, doesn't panic if min_scale > max_scale, max_scale wins
+        let scale = scale.max(auto.min_scale).min(auto.max_scale);
+        if scale == self.scale {
+            return false;
+        }
+        self.cooldown = auto.cooldown_frames;
+        self.set_scale(device, scale)
+    }
+
+    /// Measure the GPU time of the upscale pass with a profiler, see
+    /// [`GlassContext::gpu_profiler_arc`](crate::GlassContext::gpu_profiler_arc)
+    pub fn set_profiler(&mut self, profiler: Option<Arc<GpuProfiler>>) {
+        self.blit.set_profiler(profiler);
+    }
+
+    /// Stretch the target over `output` with linear filtering
+    pub fn upscale(&self, device: &Device, encoder: &mut CommandEncoder, output: &TextureView) {
+        self.blit.blit(
+            device,
+            encoder,
+            &self.target.views[0],
+            output,
+            BlitFilter::Linear,
+        );
+    }
+
+    fn recreate_if_changed(&mut self, device: &Device, old_size: [u32; 2]) -> bool {
+        let size = self.size();
+        if size == old_size {
+            return false;
+        }
+        self.target = Self::create_target(device, self.format, size);
+        true
+    }
+}
+
+impl Resizable for RenderScale {
+    fn resize(&mut self, device: &Device, size: [u32; 2]) {
+        let old_size = self.size();
+        self.surface_size = size;
+        self.recreate_if_changed(device, old_size);
+    }
+}
+
+fn scaled_size(size: [u32; 2], scale: f32, max_dimension: u32) -> [u32; 2] {
+    size.map(|s| ((s as f32 * scale).round() as u32).clamp(1, max_dimension.max(1)))
+}
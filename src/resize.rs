@@ -0,0 +1,112 @@
+use std::time::{Duration, Instant};
+
+use wgpu::Device;
+
+use crate::window::GlassWindow;
+
+/// A resource whose size follows a surface, e.g. a render target, a bloom pipeline or a camera.
+pub trait Resizable {
+    fn resize(&mut self, device: &Device, size: [u32; 2]);
+}
+
+impl<F: FnMut(&Device, [u32; 2])> Resizable for F {
+    fn resize(&mut self, device: &Device, size: [u32; 2]) {
+        self(device, size)
+    }
+}
+
+/// Tracks surface size changes and reports a size only once it has stayed the same for
+/// `settle_time`, so resources aren't recreated on every frame of an interactive resize. Pass
+/// all size dependent resources to [`ResizeObserver::update`] each frame so none are missed.
+///
+/// ```ignore
+/// self.resize_observer.update(context.device(), window.surface_size(), &mut [
+///     &mut self.render_target,
+///     &mut self.bloom,
+/// ]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ResizeObserver {
+    settle_time: Duration,
+    size: [u32; 2],
+    pending: Option<([u32; 2], Instant)>,
+}
+
+impl ResizeObserver {
+    pub fn new(size: [u32; 2], settle_time: Duration) -> ResizeObserver {
+        ResizeObserver {
+            settle_time,
+            size,
+            pending: None,
+        }
+    }
+
+    /// Observe the surface size of a window, starting from its current size
+    pub fn for_window(window: &GlassWindow, settle_time: Duration) -> ResizeObserver {
+        Self::new(window.surface_size(), settle_time)
+    }
+
+    /// Feed the current size. Returns the new size once it has settled. Zero sizes (e.g.
+    /// minimized windows) are ignored.
+    pub fn observe(&mut self, size: [u32; 2]) -> Option<[u32; 2]> {
+        if size[0] == 0 || size[1] == 0 {
+            return None;
+        }
+        if size == self.size {
+            self.pending = None;
+            return None;
+        }
+        let since = match self.pending {
+            Some((pending, since)) if pending == size => since,
+            _ => {
+                let now = Instant::now();
+                self.pending = Some((size, now));
+                now
+            }
+        };
+        if since.elapsed() >= self.settle_time {
+            self.size = size;
+            self.pending = None;
+            Some(size)
+        } else {
+            None
+        }
+    }
+
+    /// Feed the current size and resize all `resources` once it has settled. Returns whether
+    /// resources were resized.
+    pub fn update(
+        &mut self,
+        device: &Device,
+        size: [u32; 2],
+        resources: &mut [&mut dyn Resizable],
+    ) -> bool {
+        match self.observe(size) {
+            Some(size) => {
+                for resource in resources.iter_mut() {
+                    resource.resize(device, size);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The latest settled size
+    pub fn size(&self) -> [u32; 2] {
+        self.size
+    }
+
+    /// Whether a size change is waiting to settle
+    pub fn is_settling(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    pub fn settle_time(&self) -> Duration {
+        self.settle_time
+    }
+
+    pub fn set_settle_time(&mut self, settle_time: Duration) {
+        self.settle_time = settle_time;
+    }
+}
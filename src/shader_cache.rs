@@ -0,0 +1,88 @@
+//! Disk cache of parsed shader modules, enabled with the `shader_cache` feature. Modules are keyed
+//! by a hash of their source with includes resolved, so a changed include invalidates every
+//! shader including it. Set the directory with [`GlassConfig::shader_cache_dir`].
+//!
+//! Backend output is cached separately by the pipeline cache, see
+//! [`DeviceConfig::pipeline_cache_path`](crate::device_context::DeviceConfig::pipeline_cache_path).
+//!
+//! [`GlassConfig::shader_cache_dir`]: crate::GlassConfig::shader_cache_dir
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    sync::RwLock,
+};
+
+use log::warn;
+use wgpu::naga::Module;
+
+use crate::{device_context::write_atomically, utils::ShaderSource};
+
+static CACHE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Cache shader modules in `dir`, or stop caching with `None`. The runner sets this from
+/// [`GlassConfig::shader_cache_dir`](crate::GlassConfig::shader_cache_dir).
+pub fn set_shader_cache_dir(dir: Option<PathBuf>) {
+    *CACHE_DIR.write().unwrap() = dir;
+}
+
+pub fn shader_cache_dir() -> Option<PathBuf> {
+    CACHE_DIR.read().unwrap().clone()
+}
+
+/// Remove all cached modules
+pub fn clear_shader_cache() -> std::io::Result<()> {
+    let Some(dir) = shader_cache_dir() else {
+        return Ok(());
+    };
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "naga") {
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Key of a source. The hash isn't stable across Rust or glass versions, which only causes cache
+/// misses.
+pub(crate) fn key(source: &ShaderSource) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    format!("{:?}", source.language).hash(&mut hasher);
+    source.source.hash(&mut hasher);
+    let mut defines = source.defines.iter().collect::<Vec<_>>();
+    defines.sort();
+    defines.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn file(key: u64) -> Option<PathBuf> {
+    shader_cache_dir().map(|dir| dir.join(format!("{:016x}.naga", key)))
+}
+
+/// The cached module of `key`, if any. Unreadable entries are treated as misses.
+pub(crate) fn load(key: u64) -> Option<Module> {
+    let bytes = std::fs::read(file(key)?).ok()?;
+    match bincode::deserialize(&bytes) {
+        Ok(module) => Some(module),
+        Err(e) => {
+            warn!("Discarding invalid shader cache entry {:016x}: {}", key, e);
+            None
+        }
+    }
+}
+
+/// Store a module under `key`. Failures are logged, the cache is only an optimization.
+pub(crate) fn store(key: u64, module: &Module) {
+    let Some(path) = file(key) else {
+        return;
+    };
+    let result = bincode::serialize(module)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| write_atomically(&path, &bytes).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        warn!("Failed to write shader cache {}: {}", path.display(), e);
+    }
+}
@@ -1,17 +1,41 @@
-use image::DynamicImage;
+use std::{
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use flume::Receiver;
+use image::{imageops::FilterType, DynamicImage, Rgba32FImage, RgbaImage};
+use log::{error, info};
+use notify::{Event, RecommendedWatcher};
 use wgpu::{
-    Device, Extent3d, ImageCopyTexture, ImageDataLayout, Origin3d, Queue, TextureAspect,
-    TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView,
-    TextureViewDescriptor,
+    BindGroup, Color, CommandEncoder, Device, Extent3d, ImageCopyTexture, ImageDataLayout, LoadOp,
+    Origin3d, Queue, RenderPass, TextureAspect, TextureDescriptor, TextureDimension, TextureFormat,
+    TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension,
 };
 
-use crate::GlassError;
+use crate::{
+    capture::{is_capture_format_supported, to_rgba_image},
+    pass::PassDesc,
+    pipelines::MipmapGenerator,
+    resize::Resizable,
+    utils::{read_texture, start_file_watcher},
+    GlassError,
+};
 
 /// A utility struct to ease Gpu texture creation from image data
 pub struct Texture {
     pub texture: wgpu::Texture,
+    /// A view per mip level. Array and cube textures' views cover all layers as `D2Array` or
+    /// `Cube` views.
     pub views: Vec<TextureView>,
+    /// A `D2` view of all mips per layer of array and cube textures, empty otherwise
+    pub layer_views: Vec<TextureView>,
     pub size: [f32; 2],
+    /// Whether each subresource has been written to, indexed by `mip_level * layers + layer`
+    initialized: Vec<AtomicBool>,
 }
 
 impl Texture {
@@ -22,6 +46,76 @@ impl Texture {
         mip_count: u32,
         format: TextureFormat,
         usage: TextureUsages,
+    ) -> Self {
+        Self::create(
+            device,
+            label,
+            size,
+            mip_count,
+            format,
+            usage,
+            TextureViewDimension::D2,
+        )
+    }
+
+    /// An array texture of `layers` layers, e.g. atlas pages or animation frames
+    pub fn empty_array(
+        device: &Device,
+        label: &str,
+        size: [u32; 2],
+        layers: u32,
+        mip_count: u32,
+        format: TextureFormat,
+        usage: TextureUsages,
+    ) -> Self {
+        Self::create(
+            device,
+            label,
+            Extent3d {
+                width: size[0],
+                height: size[1],
+                depth_or_array_layers: layers,
+            },
+            mip_count,
+            format,
+            usage,
+            TextureViewDimension::D2Array,
+        )
+    }
+
+    /// A cube map with square faces of `size` pixels. Layers are the faces in +x, -x, +y, -y,
+    /// +z, -z order.
+    pub fn empty_cube(
+        device: &Device,
+        label: &str,
+        size: u32,
+        mip_count: u32,
+        format: TextureFormat,
+        usage: TextureUsages,
+    ) -> Self {
+        Self::create(
+            device,
+            label,
+            Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 6,
+            },
+            mip_count,
+            format,
+            usage,
+            TextureViewDimension::Cube,
+        )
+    }
+
+    fn create(
+        device: &Device,
+        label: &str,
+        size: Extent3d,
+        mip_count: u32,
+        format: TextureFormat,
+        usage: TextureUsages,
+        view_dimension: TextureViewDimension,
     ) -> Self {
         let texture = device.create_texture(&TextureDescriptor {
             label: Some(label),
@@ -33,23 +127,199 @@ impl Texture {
             format,
             usage,
         });
+        let layered = view_dimension != TextureViewDimension::D2;
         let mut views = vec![];
         for mip_level in 0..mip_count {
             let view = texture.create_view(&TextureViewDescriptor {
+                dimension: layered.then_some(view_dimension),
                 base_mip_level: mip_level,
                 mip_level_count: Some(1),
                 ..Default::default()
             });
             views.push(view);
         }
+        let mut layer_views = vec![];
+        if layered {
+            for layer in 0..size.depth_or_array_layers {
+                layer_views.push(texture.create_view(&TextureViewDescriptor {
+                    dimension: Some(TextureViewDimension::D2),
+                    base_array_layer: layer,
+                    array_layer_count: Some(1),
+                    ..Default::default()
+                }));
+            }
+        }
 
+        let initialized = init_flags(&texture, false);
         Self {
             texture,
             views,
+            layer_views,
+            size: [size.width as f32, size.height as f32],
+            initialized,
+        }
+    }
+
+    /// An array texture with a layer per image, all mips filled like in [`Texture::from_image`].
+    /// Panics if the images differ in size.
+    pub fn from_images_array(
+        device: &Device,
+        queue: &Queue,
+        images: &[DynamicImage],
+        label: &str,
+        format: TextureFormat,
+        usage: TextureUsages,
+        mip_count: u32,
+    ) -> Self {
+        assert!(!images.is_empty(), "Array texture needs at least one image");
+        let size = [images[0].width(), images[0].height()];
+        let texture = Self::empty_array(
+            device,
+            label,
+            size,
+            images.len() as u32,
+            mip_count,
+            format,
+            usage,
+        );
+        for (layer, img) in images.iter().enumerate() {
+            assert_eq!(
+                [img.width(), img.height()],
+                size,
+                "Array texture images must have the same size"
+            );
+            write_image_mips(queue, &texture.texture, img, layer as u32, mip_count);
+        }
+        texture.mark_initialized();
+        texture
+    }
+
+    /// Number of array layers, 6 for cube maps
+    pub fn layer_count(&self) -> u32 {
+        self.texture.depth_or_array_layers()
+    }
+
+    /// A 3D texture, e.g. a color grading lut or volume data. Has a single mip whose `D3` view is
+    /// `views[0]`.
+    pub fn empty_3d(
+        device: &Device,
+        label: &str,
+        size: Extent3d,
+        format: TextureFormat,
+        usage: TextureUsages,
+    ) -> Self {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D3,
+            view_formats: &[],
+            format,
+            usage,
+        });
+        let view = texture.create_view(&TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::D3),
+            ..Default::default()
+        });
+        let initialized = init_flags(&texture, false);
+        Self {
+            texture,
+            views: vec![view],
+            layer_views: vec![],
             size: [size.width as f32, size.height as f32],
+            initialized,
         }
     }
 
+    /// A 3D texture with a depth slice per element of `slices`, see [`Texture::write_slices`]
+    pub fn from_slices(
+        device: &Device,
+        queue: &Queue,
+        label: &str,
+        size: [u32; 2],
+        format: TextureFormat,
+        usage: TextureUsages,
+        slices: &[&[u8]],
+    ) -> Self {
+        let texture = Self::empty_3d(
+            device,
+            label,
+            Extent3d {
+                width: size[0],
+                height: size[1],
+                depth_or_array_layers: slices.len() as u32,
+            },
+            format,
+            usage | TextureUsages::COPY_DST,
+        );
+        texture.write_slices(queue, 0, slices);
+        texture
+    }
+
+    /// Write tightly packed depth slices of a 3D texture starting from slice `first`. Each slice
+    /// holds a full width * height texels. Also works for array layers of 2D textures.
+    pub fn write_slices(&self, queue: &Queue, first: u32, slices: &[&[u8]]) {
+        let (width, height) = (self.texture.width(), self.texture.height());
+        let bytes_per_row = width * self.bytes_per_texel();
+        for (i, slice) in slices.iter().enumerate() {
+            assert_eq!(
+                slice.len(),
+                (bytes_per_row * height) as usize,
+                "Slice size must match the texture's width * height"
+            );
+            queue.write_texture(
+                ImageCopyTexture {
+                    aspect: TextureAspect::All,
+                    texture: &self.texture,
+                    mip_level: 0,
+                    origin: Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: first + i as u32,
+                    },
+                },
+                slice,
+                ImageDataLayout {
+                    offset: 0,
+                    rows_per_image: Some(height),
+                    bytes_per_row: Some(bytes_per_row),
+                },
+                Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        if self.texture.dimension() == TextureDimension::D3 {
+            self.mark_mip_initialized(0);
+        } else {
+            self.mark_layers_initialized(0, first..first + slices.len() as u32);
+        }
+    }
+
+    /// Write all slices of a 3D texture from one tightly packed buffer, x changing fastest and z
+    /// slowest
+    pub fn write_volume(&self, queue: &Queue, data: &[u8]) {
+        let slice_size =
+            (self.texture.width() * self.texture.height() * self.bytes_per_texel()) as usize;
+        assert_eq!(
+            data.len(),
+            slice_size * self.texture.depth_or_array_layers() as usize,
+            "Volume data size must match the texture's extent"
+        );
+        let slices = data.chunks_exact(slice_size).collect::<Vec<_>>();
+        self.write_slices(queue, 0, &slices);
+    }
+
+    fn bytes_per_texel(&self) -> u32 {
+        self.texture
+            .format()
+            .block_copy_size(Some(TextureAspect::All))
+            .expect("Slice uploads need an uncompressed color format")
+    }
+
     pub fn from_bytes(
         device: &Device,
         queue: &Queue,
@@ -67,6 +337,181 @@ impl Texture {
         ))
     }
 
+    /// Load a Radiance `.hdr` image into an `Rgba16Float` or `Rgba32Float` texture, keeping
+    /// values above 1.0 for bloom, tonemapping and environment maps
+    #[cfg(feature = "hdr")]
+    pub fn from_hdr_bytes(
+        device: &Device,
+        queue: &Queue,
+        bytes: &[u8],
+        label: &str,
+        format: TextureFormat,
+        usage: TextureUsages,
+    ) -> Result<Self, GlassError> {
+        let img = image::load_from_memory_with_format(bytes, image::ImageFormat::Hdr)
+            .map_err(GlassError::ImageError)?;
+        Self::from_float_image(device, queue, &img.to_rgba32f(), label, format, usage)
+    }
+
+    /// Load an OpenEXR image into an `Rgba16Float` or `Rgba32Float` texture
+    #[cfg(feature = "exr")]
+    pub fn from_exr_bytes(
+        device: &Device,
+        queue: &Queue,
+        bytes: &[u8],
+        label: &str,
+        format: TextureFormat,
+        usage: TextureUsages,
+    ) -> Result<Self, GlassError> {
+        let img = image::load_from_memory_with_format(bytes, image::ImageFormat::OpenExr)
+            .map_err(GlassError::ImageError)?;
+        Self::from_float_image(device, queue, &img.to_rgba32f(), label, format, usage)
+    }
+
+    /// A float texture from linear float pixels. `format` must be `Rgba16Float` or
+    /// `Rgba32Float`, as [`Texture::from_image`] would clamp the values to 8 bits. Other formats
+    /// return [`GlassError::ArgumentError`].
+    pub fn from_float_image(
+        device: &Device,
+        queue: &Queue,
+        img: &Rgba32FImage,
+        label: &str,
+        format: TextureFormat,
+        usage: TextureUsages,
+    ) -> Result<Self, GlassError> {
+        let (width, height) = img.dimensions();
+        let size = Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let data = match format {
+            TextureFormat::Rgba32Float => bytemuck::cast_slice(img.as_raw()).to_vec(),
+            TextureFormat::Rgba16Float => img
+                .as_raw()
+                .iter()
+                .flat_map(|v| f32_to_f16(*v).to_le_bytes())
+                .collect(),
+            _ => {
+                return Err(GlassError::ArgumentError(format!(
+                    "Float images need an Rgba16Float or Rgba32Float format, got {:?}",
+                    format
+                )))
+            }
+        };
+        let texture = Self::empty(
+            device,
+            label,
+            size,
+            1,
+            format,
+            usage | TextureUsages::COPY_DST,
+        );
+        queue.write_texture(
+            ImageCopyTexture {
+                aspect: TextureAspect::All,
+                texture: &texture.texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+            },
+            &data,
+            ImageDataLayout {
+                offset: 0,
+                rows_per_image: None,
+                bytes_per_row: Some(format.block_copy_size(None).unwrap() * width),
+            },
+            size,
+        );
+        texture.mark_initialized();
+        Ok(texture)
+    }
+
+    /// Write a `[width, height]` region of mip level 0 at `origin`. `data` starts at the region's
+    /// first texel and its rows are `bytes_per_row` apart, so a region of a larger image can be
+    /// written by slicing the image from the region's start with the image's row pitch.
+    pub fn write_region(
+        &self,
+        queue: &Queue,
+        origin: [u32; 2],
+        extent: [u32; 2],
+        data: &[u8],
+        bytes_per_row: u32,
+    ) {
+        if extent[0] == 0 || extent[1] == 0 {
+            return;
+        }
+        queue.write_texture(
+            ImageCopyTexture {
+                aspect: TextureAspect::All,
+                texture: &self.texture,
+                mip_level: 0,
+                origin: Origin3d {
+                    x: origin[0],
+                    y: origin[1],
+                    z: 0,
+                },
+            },
+            data,
+            ImageDataLayout {
+                offset: 0,
+                rows_per_image: None,
+                bytes_per_row: Some(bytes_per_row),
+            },
+            Extent3d {
+                width: extent[0],
+                height: extent[1],
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    /// Write the region marked in `dirty` from a tightly packed image of the texture's size and
+    /// clear it. Does nothing when nothing has been marked.
+    pub fn write_dirty(&self, queue: &Queue, dirty: &mut DirtyRect, data: &[u8]) {
+        let Some([x, y, width, height]) = dirty.take() else {
+            return;
+        };
+        let bytes_per_texel = self.bytes_per_texel();
+        let bytes_per_row = self.texture.width() * bytes_per_texel;
+        let start = (y * bytes_per_row + x * bytes_per_texel) as usize;
+        self.write_region(
+            queue,
+            [x, y],
+            [width, height],
+            &data[start..],
+            bytes_per_row,
+        );
+    }
+
+    /// Copy mip level 0 of all layers to CPU memory with tightly packed rows, see
+    /// [`read_texture`](crate::utils::read_texture). Blocks until the copy has completed. The
+    /// texture must have been created with `COPY_SRC` usage.
+    pub fn read_to_vec(&self, device: &Device, queue: &Queue) -> Vec<u8> {
+        assert!(
+            self.texture.usage().contains(TextureUsages::COPY_SRC),
+            "Texture readback requires COPY_SRC usage"
+        );
+        read_texture(device, queue, &self.texture)
+    }
+
+    /// Read mip level 0 of the first layer back to an rgba8 image. Float formats are clamped to
+    /// [0, 1], see [`to_rgba_image`] for the supported formats. Blocks until the copy has
+    /// completed. The texture must have been created with `COPY_SRC` usage.
+    pub fn read_to_image(&self, device: &Device, queue: &Queue) -> RgbaImage {
+        let format = self.texture.format();
+        assert!(
+            is_capture_format_supported(format),
+            "Reading {:?} textures to an image is not supported",
+            format
+        );
+        let (width, height) = (self.texture.width(), self.texture.height());
+        let mut data = self.read_to_vec(device, queue);
+        data.truncate((width * height * format.block_copy_size(None).unwrap()) as usize);
+        to_rgba_image(&data, width, height, format).unwrap()
+    }
+
+    /// Mips after the first are downsampled from the image on the CPU. For textures rendered
+    /// into at runtime use [`MipmapGenerator`](crate::pipelines::MipmapGenerator) instead.
     #[allow(clippy::too_many_arguments)]
     pub fn from_image(
         device: &Device,
@@ -77,8 +522,7 @@ impl Texture {
         usage: TextureUsages,
         mip_count: u32,
     ) -> Self {
-        let rgba = img.to_rgba8();
-        let dimensions = rgba.dimensions();
+        let dimensions = (img.width(), img.height());
 
         let size = Extent3d {
             width: dimensions.0,
@@ -96,28 +540,702 @@ impl Texture {
             usage,
         });
 
+        write_image_mips(queue, &texture, img, 0, mip_count);
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        let initialized = init_flags(&texture, true);
+        Self {
+            texture,
+            views: vec![view],
+            layer_views: vec![],
+            size: [dimensions.0 as f32, dimensions.1 as f32],
+            initialized,
+        }
+    }
+
+    /// [`Texture::from_image`] with a full mip chain down to 1x1
+    pub fn with_generated_mips(
+        device: &Device,
+        queue: &Queue,
+        img: &DynamicImage,
+        label: &str,
+        format: TextureFormat,
+        usage: TextureUsages,
+    ) -> Self {
+        let mip_count = MipmapGenerator::mip_count(img.width(), img.height());
+        Self::from_image(device, queue, img, label, format, usage, mip_count)
+    }
+
+    /// A magenta & black checkerboard to substitute for textures that failed to load.
+    pub fn missing(device: &Device, queue: &Queue) -> Self {
+        const SIZE: u32 = 64;
+        const CELL: u32 = 8;
+        let img = image::RgbaImage::from_fn(SIZE, SIZE, |x, y| {
+            if (x / CELL + y / CELL).is_multiple_of(2) {
+                image::Rgba([255, 0, 255, 255])
+            } else {
+                image::Rgba([0, 0, 0, 255])
+            }
+        });
+        Self::from_image(
+            device,
+            queue,
+            &DynamicImage::ImageRgba8(img),
+            "missing_texture",
+            TextureFormat::Rgba8UnormSrgb,
+            TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            1,
+        )
+    }
+
+    /// Like [`Texture::from_bytes`], but logs errors and returns [`Texture::missing`] instead.
+    pub fn from_bytes_or_missing(
+        device: &Device,
+        queue: &Queue,
+        bytes: &[u8],
+        label: &str,
+        format: TextureFormat,
+        usage: TextureUsages,
+    ) -> Self {
+        match Self::from_bytes(device, queue, bytes, label, format, usage) {
+            Ok(texture) => texture,
+            Err(e) => {
+                error!("Failed to load texture {}: {}", label, e);
+                Self::missing(device, queue)
+            }
+        }
+    }
+
+    /// Whether every mip level and layer of the texture has contents that are meaningful to load,
+    /// i.e. it was created from data or has been rendered into with a
+    /// [`PassDesc`](crate::pass::PassDesc).
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+            .iter()
+            .all(|flag| flag.load(Ordering::Relaxed))
+    }
+
+    /// Whether all layers of a mip level have been written to
+    pub fn is_mip_initialized(&self, mip_level: u32) -> bool {
+        let layers = self.tracked_layers() as usize;
+        let start = mip_level as usize * layers;
+        self.initialized[start..start + layers]
+            .iter()
+            .all(|flag| flag.load(Ordering::Relaxed))
+    }
+
+    /// Mark the whole texture as written to, e.g. after a compute shader or copy has filled it.
+    pub fn mark_initialized(&self) {
+        for flag in &self.initialized {
+            flag.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Mark all layers of a mip level as written to
+    pub fn mark_mip_initialized(&self, mip_level: u32) {
+        self.mark_layers_initialized(mip_level, 0..self.tracked_layers());
+    }
+
+    /// Mark `layers` of a mip level as written to. 3D textures are tracked per mip level only.
+    pub fn mark_layers_initialized(&self, mip_level: u32, layers: Range<u32>) {
+        let count = self.tracked_layers();
+        for layer in layers.start.min(count)..layers.end.min(count) {
+            self.initialized[(mip_level * count + layer) as usize].store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn tracked_layers(&self) -> u32 {
+        tracked_layers(&self.texture)
+    }
+}
+
+/// A texture loaded from an image file that is re-decoded and re-uploaded when the file changes,
+/// using the same file watching as [`WatchedShaderModule`](crate::utils::WatchedShaderModule).
+/// Call [`WatchedTexture::reload_if_changed`] once per frame and rebuild bind groups referencing
+/// the texture when it returns true.
+pub struct WatchedTexture {
+    path: String,
+    format: TextureFormat,
+    usage: TextureUsages,
+    mip_count: u32,
+    texture: Texture,
+    version: u64,
+    _watcher: Option<RecommendedWatcher>,
+    receiver: Receiver<notify::Result<Event>>,
+}
+
+impl WatchedTexture {
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        path: &str,
+        format: TextureFormat,
+        usage: TextureUsages,
+        mip_count: u32,
+    ) -> Result<WatchedTexture, GlassError> {
+        let texture = Self::load(device, queue, path, format, usage, mip_count)?;
+        let (receiver, watcher) = start_file_watcher(path);
+        Ok(WatchedTexture {
+            path: path.to_string(),
+            format,
+            usage,
+            mip_count,
+            texture,
+            version: 0,
+            _watcher: watcher,
+            receiver,
+        })
+    }
+
+    /// The path doubles as the texture's label
+    fn load(
+        device: &Device,
+        queue: &Queue,
+        path: &str,
+        format: TextureFormat,
+        usage: TextureUsages,
+        mip_count: u32,
+    ) -> Result<Texture, GlassError> {
+        let bytes = std::fs::read(path).map_err(GlassError::IoError)?;
+        let img = image::load_from_memory(&bytes).map_err(GlassError::ImageError)?;
+        Ok(Texture::from_image(
+            device, queue, &img, path, format, usage, mip_count,
+        ))
+    }
+
+    /// Reload the texture if its file has changed since the last call. Returns whether the
+    /// texture was replaced. Failed reloads (e.g. a partially written file) are logged and the
+    /// previous texture is kept.
+    pub fn reload_if_changed(&mut self, device: &Device, queue: &Queue) -> bool {
+        let changed = self
+            .receiver
+            .try_iter()
+            .flatten()
+            .any(|event| event.kind.is_modify() || event.kind.is_create());
+        if !changed {
+            return false;
+        }
+        match Self::load(
+            device,
+            queue,
+            &self.path,
+            self.format,
+            self.usage,
+            self.mip_count,
+        ) {
+            Ok(texture) => {
+                info!("Reloaded texture {}", self.path);
+                self.texture = texture;
+                self.version += 1;
+                true
+            }
+            Err(e) => {
+                error!("Failed to reload texture {}: {}", self.path, e);
+                false
+            }
+        }
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Incremented on each successful reload
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+}
+
+/// An offscreen color target with an optional depth attachment, recreated at a new size by
+/// [`RenderTarget::resize`]. Implements [`Resizable`] so it can follow window resizes through a
+/// [`ResizeObserver`](crate::resize::ResizeObserver). Bind groups referencing its views must be
+/// recreated after resizing.
+pub struct RenderTarget {
+    label: String,
+    size: [u32; 2],
+    format: TextureFormat,
+    usage: TextureUsages,
+    depth_format: Option<TextureFormat>,
+    color: Texture,
+    depth: Option<Texture>,
+}
+
+impl RenderTarget {
+    /// `usage` always includes `RENDER_ATTACHMENT`
+    pub fn new(
+        device: &Device,
+        label: &str,
+        size: [u32; 2],
+        format: TextureFormat,
+        usage: TextureUsages,
+        depth_format: Option<TextureFormat>,
+    ) -> RenderTarget {
+        let usage = usage | TextureUsages::RENDER_ATTACHMENT;
+        let (color, depth) =
+            Self::create_textures(device, label, size, format, usage, depth_format);
+        RenderTarget {
+            label: label.to_string(),
+            size,
+            format,
+            usage,
+            depth_format,
+            color,
+            depth,
+        }
+    }
+
+    fn create_textures(
+        device: &Device,
+        label: &str,
+        size: [u32; 2],
+        format: TextureFormat,
+        usage: TextureUsages,
+        depth_format: Option<TextureFormat>,
+    ) -> (Texture, Option<Texture>) {
+        let extent = Extent3d {
+            width: size[0].max(1),
+            height: size[1].max(1),
+            depth_or_array_layers: 1,
+        };
+        let color = Texture::empty(device, label, extent, 1, format, usage);
+        let depth = depth_format.map(|depth_format| {
+            Texture::empty(
+                device,
+                &format!("{}_depth", label),
+                extent,
+                1,
+                depth_format,
+                TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            )
+        });
+        (color, depth)
+    }
+
+    /// Recreate the textures if `size` differs from the current size. Returns whether they were
+    /// recreated.
+    pub fn resize(&mut self, device: &Device, size: [u32; 2]) -> bool {
+        if size == self.size {
+            return false;
+        }
+        let (color, depth) = Self::create_textures(
+            device,
+            &self.label,
+            size,
+            self.format,
+            self.usage,
+            self.depth_format,
+        );
+        self.size = size;
+        self.color = color;
+        self.depth = depth;
+        true
+    }
+
+    /// Begin a pass rendering into the target. Depth is cleared to 1.0.
+    pub fn begin_pass<'e>(
+        &self,
+        encoder: &'e mut CommandEncoder,
+        load_op: LoadOp<Color>,
+    ) -> RenderPass<'e> {
+        let mut pass = PassDesc::new(&self.label).color(&self.color);
+        pass = match load_op {
+            LoadOp::Clear(color) => pass.clear(color),
+            LoadOp::Load => pass.load(),
+        };
+        if let Some(depth) = &self.depth {
+            pass = pass.depth(&depth.views[0]);
+        }
+        pass.begin(encoder)
+    }
+
+    pub fn color(&self) -> &Texture {
+        &self.color
+    }
+
+    pub fn view(&self) -> &TextureView {
+        &self.color.views[0]
+    }
+
+    pub fn depth(&self) -> Option<&Texture> {
+        self.depth.as_ref()
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        self.size
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    pub fn depth_format(&self) -> Option<TextureFormat> {
+        self.depth_format
+    }
+}
+
+impl Resizable for RenderTarget {
+    fn resize(&mut self, device: &Device, size: [u32; 2]) {
+        RenderTarget::resize(self, device, size);
+    }
+}
+
+/// Bounding rect of texels changed since the last upload, for writing only the changed part of
+/// a CPU image with [`Texture::write_dirty`]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct DirtyRect {
+    /// `[x, y, width, height]`
+    rect: Option<[u32; 4]>,
+}
+
+impl DirtyRect {
+    pub fn new() -> DirtyRect {
+        DirtyRect::default()
+    }
+
+    pub fn mark(&mut self, x: u32, y: u32) {
+        self.mark_rect([x, y, 1, 1]);
+    }
+
+    /// Grow the dirty rect to cover `[x, y, width, height]`
+    pub fn mark_rect(&mut self, rect: [u32; 4]) {
+        if rect[2] == 0 || rect[3] == 0 {
+            return;
+        }
+        self.rect = Some(match self.rect {
+            None => rect,
+            Some([x, y, width, height]) => {
+                let min = [x.min(rect[0]), y.min(rect[1])];
+                let max = [
+                    (x + width).max(rect[0] + rect[2]),
+                    (y + height).max(rect[1] + rect[3]),
+                ];
+                [min[0], min[1], max[0] - min[0], max[1] - min[1]]
+            }
+        });
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.rect.is_some()
+    }
+
+    /// The dirty `[x, y, width, height]` rect, if any
+    pub fn rect(&self) -> Option<[u32; 4]> {
+        self.rect
+    }
+
+    /// Return and clear the dirty rect
+    pub fn take(&mut self) -> Option<[u32; 4]> {
+        self.rect.take()
+    }
+}
+
+/// Convert to a half float, rounding to nearest and flushing values beyond half range to
+/// infinity
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        // Infinity or NaN
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if exponent <= 0 {
+        // Subnormal half or zero
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        let round = ((mantissa >> (shift - 1)) & 1) as u16;
+        return sign | ((mantissa >> shift) as u16 + round);
+    }
+    let round = ((mantissa >> 12) & 1) as u16;
+    // A rounding carry into the exponent is still the correct result
+    (sign | ((exponent as u16) << 10) | (mantissa >> 13) as u16) + round
+}
+
+/// Array layers tracked for initialization, 3D textures' depth slices aren't tracked separately
+fn tracked_layers(texture: &wgpu::Texture) -> u32 {
+    if texture.dimension() == TextureDimension::D3 {
+        1
+    } else {
+        texture.depth_or_array_layers()
+    }
+}
+
+fn init_flags(texture: &wgpu::Texture, initialized: bool) -> Vec<AtomicBool> {
+    (0..texture.mip_level_count() * tracked_layers(texture))
+        .map(|_| AtomicBool::new(initialized))
+        .collect()
+}
+
+/// Write an image and its downsampled mips to an array layer of a texture. Mips after the first
+/// are halved from the previous level, which filters better than resizing the full image.
+fn write_image_mips(
+    queue: &Queue,
+    texture: &wgpu::Texture,
+    img: &DynamicImage,
+    layer: u32,
+    mip_count: u32,
+) {
+    let mut level = img.to_rgba8();
+    for mip_level in 0..mip_count {
+        if mip_level > 0 {
+            let (width, height) = level.dimensions();
+            level = image::imageops::resize(
+                &level,
+                (width / 2).max(1),
+                (height / 2).max(1),
+                FilterType::Triangle,
+            );
+        }
+        let (width, height) = level.dimensions();
         queue.write_texture(
             ImageCopyTexture {
                 aspect: TextureAspect::All,
-                texture: &texture,
-                mip_level: 0,
-                origin: Origin3d::ZERO,
+                texture,
+                mip_level,
+                origin: Origin3d {
+                    x: 0,
+                    y: 0,
+                    z: layer,
+                },
             },
-            &rgba,
+            &level,
             ImageDataLayout {
                 offset: 0,
                 rows_per_image: None,
-                bytes_per_row: Some(4 * dimensions.0),
+                bytes_per_row: Some(4 * width),
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
             },
-            size,
         );
+    }
+}
 
-        let view = texture.create_view(&TextureViewDescriptor::default());
+/// Two textures of identical size & format for simulations that read last step's result and
+/// write the next one. Write into [`PingPong::dst`] while reading [`PingPong::src`], then
+/// [`PingPong::swap`] so the result becomes the source of the next step.
+pub struct PingPong {
+    textures: [Texture; 2],
+    current: usize,
+}
 
-        Self {
-            texture,
-            views: vec![view],
-            size: [dimensions.0 as f32, dimensions.1 as f32],
+impl PingPong {
+    pub fn new(
+        device: &Device,
+        label: &str,
+        size: Extent3d,
+        mip_count: u32,
+        format: TextureFormat,
+        usage: TextureUsages,
+    ) -> PingPong {
+        PingPong {
+            textures: [
+                Texture::empty(
+                    device,
+                    &format!("{}_0", label),
+                    size,
+                    mip_count,
+                    format,
+                    usage,
+                ),
+                Texture::empty(
+                    device,
+                    &format!("{}_1", label),
+                    size,
+                    mip_count,
+                    format,
+                    usage,
+                ),
+            ],
+            current: 0,
         }
     }
+
+    /// The latest result
+    pub fn src(&self) -> &Texture {
+        &self.textures[self.current]
+    }
+
+    /// The texture to write the next step into
+    pub fn dst(&self) -> &Texture {
+        &self.textures[1 - self.current]
+    }
+
+    pub fn swap(&mut self) {
+        self.current = 1 - self.current;
+    }
+
+    /// Which texture is the source, 0 or 1
+    pub fn index(&self) -> usize {
+        self.current
+    }
+
+    pub fn textures(&self) -> &[Texture; 2] {
+        &self.textures
+    }
+
+    /// Build a bind group for both directions once. `create` gets the source and destination.
+    ///
+    /// ```ignore
+    /// let bind_groups = ping_pong.create_bind_groups(|src, dst| {
+    ///     device.create_bind_group(&BindGroupDescriptor { /* src & dst views */ })
+    /// });
+    /// cpass.set_bind_group(0, bind_groups.get(&ping_pong), &[]);
+    /// ```
+    pub fn create_bind_groups(
+        &self,
+        mut create: impl FnMut(&Texture, &Texture) -> BindGroup,
+    ) -> PingPongBindGroups {
+        let [a, b] = &self.textures;
+        PingPongBindGroups([create(a, b), create(b, a)])
+    }
+}
+
+/// Bind groups for both directions of a [`PingPong`], see [`PingPong::create_bind_groups`]
+pub struct PingPongBindGroups([BindGroup; 2]);
+
+impl PingPongBindGroups {
+    /// The bind group matching the pair's current direction
+    pub fn get(&self, ping_pong: &PingPong) -> &BindGroup {
+        &self.0[ping_pong.index()]
+    }
+}
+
+/// A cloneable handle for creating [`Texture`]s outside the main thread. Create one with
+/// [`GlassContext::texture_loader`](crate::GlassContext::texture_loader) and move it into a
+/// worker thread. Uploads are written to the shared queue and become visible with the next
+/// submit.
+///
+/// The loader holds the device & queue it was created with. If the device gets recreated (e.g.
+/// when the first window is created for a windowless context), create a new loader.
+#[derive(Clone)]
+pub struct TextureLoader {
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+}
+
+impl TextureLoader {
+    pub fn new(device: Arc<Device>, queue: Arc<Queue>) -> TextureLoader {
+        TextureLoader {
+            device,
+            queue,
+        }
+    }
+
+    pub fn empty(
+        &self,
+        label: &str,
+        size: Extent3d,
+        mip_count: u32,
+        format: TextureFormat,
+        usage: TextureUsages,
+    ) -> Texture {
+        Texture::empty(&self.device, label, size, mip_count, format, usage)
+    }
+
+    pub fn from_bytes(
+        &self,
+        bytes: &[u8],
+        label: &str,
+        format: TextureFormat,
+        usage: TextureUsages,
+    ) -> Result<Texture, GlassError> {
+        Texture::from_bytes(&self.device, &self.queue, bytes, label, format, usage)
+    }
+
+    pub fn from_image(
+        &self,
+        img: &DynamicImage,
+        label: &str,
+        format: TextureFormat,
+        usage: TextureUsages,
+        mip_count: u32,
+    ) -> Texture {
+        Texture::from_image(
+            &self.device,
+            &self.queue,
+            img,
+            label,
+            format,
+            usage,
+            mip_count,
+        )
+    }
+
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    pub fn queue(&self) -> &Arc<Queue> {
+        &self.queue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::texture::f32_to_f16;
+
+    #[test]
+    fn test_f32_to_f16_exact() {
+        assert_eq!(f32_to_f16(0.0), 0x0000);
+        assert_eq!(f32_to_f16(-0.0), 0x8000);
+        assert_eq!(f32_to_f16(1.0), 0x3c00);
+        assert_eq!(f32_to_f16(0.5), 0x3800);
+        assert_eq!(f32_to_f16(-2.0), 0xc000);
+        assert_eq!(f32_to_f16(0.333_251_95), 0x3555);
+        // Largest half
+        assert_eq!(f32_to_f16(65504.0), 0x7bff);
+    }
+
+    #[test]
+    fn test_f32_to_f16_rounding() {
+        // Below and above half a unit in the last place of 1.0
+        assert_eq!(f32_to_f16(1.0 + 2f32.powi(-12)), 0x3c00);
+        assert_eq!(f32_to_f16(1.0 + 2f32.powi(-11) + 2f32.powi(-20)), 0x3c01);
+        // Rounding up a full mantissa carries into the exponent
+        assert_eq!(f32_to_f16(2.0 - 2f32.powi(-13)), 0x4000);
+        assert_eq!(f32_to_f16(65519.0), 0x7bff);
+        assert_eq!(f32_to_f16(65520.0), 0x7c00);
+    }
+
+    #[test]
+    fn test_f32_to_f16_subnormals() {
+        // Smallest normal
+        assert_eq!(f32_to_f16(2f32.powi(-14)), 0x0400);
+        assert_eq!(f32_to_f16(2f32.powi(-15)), 0x0200);
+        // Smallest subnormal
+        assert_eq!(f32_to_f16(2f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_f16(-2f32.powi(-24)), 0x8001);
+        // The largest subnormal rounds up into the smallest normal
+        assert_eq!(f32_to_f16(2f32.powi(-14) - 2f32.powi(-26)), 0x0400);
+        // Too small for a half
+        assert_eq!(f32_to_f16(2f32.powi(-26)), 0x0000);
+        assert_eq!(f32_to_f16(-1e-10), 0x8000);
+    }
+
+    #[test]
+    fn test_f32_to_f16_special() {
+        assert_eq!(f32_to_f16(f32::INFINITY), 0x7c00);
+        assert_eq!(f32_to_f16(f32::NEG_INFINITY), 0xfc00);
+        assert_eq!(f32_to_f16(1e10), 0x7c00);
+        assert_eq!(f32_to_f16(-1e10), 0xfc00);
+        let nan = f32_to_f16(f32::NAN);
+        assert_eq!(nan & 0x7c00, 0x7c00);
+        assert_ne!(nan & 0x03ff, 0);
+    }
 }
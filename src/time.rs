@@ -0,0 +1,368 @@
+use std::time::{Duration, Instant};
+
+use winit::keyboard::KeyCode;
+
+/// Fixed update steps are capped per frame so a long frame can't cause a spiral of catch-up
+/// updates.
+const MAX_FIXED_STEPS_PER_FRAME: u32 = 8;
+/// Delta used for a single step while paused when no fixed timestep is set.
+const DEFAULT_STEP_DELTA: Duration = Duration::from_nanos(16_666_667);
+
+/// Largest accepted [`Time::set_time_scale`]
+pub const MAX_TIME_SCALE: f64 = 1000.0;
+
+/// Weight of the latest frame in the smoothed frame time
+const FRAME_TIME_SMOOTHING: f64 = 0.05;
+
+/// Key to toggle pause with when time hotkeys are enabled
+pub const PAUSE_KEY: KeyCode = KeyCode::F9;
+/// Key to advance a single step while paused
+pub const STEP_KEY: KeyCode = KeyCode::F10;
+/// Key to halve the time scale
+pub const SLOWER_KEY: KeyCode = KeyCode::F7;
+/// Key to double the time scale
+pub const FASTER_KEY: KeyCode = KeyCode::F8;
+
+/// Simulation time of the update loop. Supports pausing, stepping a given amount of updates while
+/// paused and slow (or fast) motion through [`Time::set_time_scale`].
+///
+/// [`crate::GlassApp::update`] runs every frame regardless of pause, so rendering keeps working.
+/// Simulations should advance by [`Time::delta`] (zero while paused), check
+/// [`Time::is_advancing`], or use [`crate::GlassApp::fixed_update`] with a fixed timestep.
+#[derive(Debug, Clone)]
+pub struct Time {
+    last_tick: Instant,
+    real_delta: Duration,
+    delta: Duration,
+    elapsed: Duration,
+    time_scale: f64,
+    paused: bool,
+    pending_steps: u32,
+    advancing: bool,
+    fixed_timestep: Option<Duration>,
+    accumulator: Duration,
+    fixed_steps: u32,
+}
+
+impl Time {
+    pub fn new(fixed_timestep: Option<Duration>) -> Time {
+        Time {
+            last_tick: Instant::now(),
+            real_delta: Duration::ZERO,
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            time_scale: 1.0,
+            paused: false,
+            pending_steps: 0,
+            advancing: true,
+            fixed_timestep,
+            accumulator: Duration::ZERO,
+            fixed_steps: 0,
+        }
+    }
+
+    /// Scaled time since last update, zero while paused (unless stepping)
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_secs(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// Unscaled wall clock time since last update
+    pub fn real_delta(&self) -> Duration {
+        self.real_delta
+    }
+
+    /// Total scaled time advanced
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Whether the simulation should advance this update
+    pub fn is_advancing(&self) -> bool {
+        self.advancing
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.pending_steps = 0;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        if self.paused {
+            self.resume();
+        } else {
+            self.pause();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Advance `n` updates while paused. Each step advances by the fixed timestep, or 1/60 s if
+    /// none is set.
+    pub fn step(&mut self, n: u32) {
+        self.pending_steps += n;
+    }
+
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// Multiplier for delta time, e.g. 0.25 for slow motion. Clamped to 0..=[`MAX_TIME_SCALE`],
+    /// non-finite values are ignored.
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        if time_scale.is_finite() {
+            self.time_scale = time_scale.clamp(0.0, MAX_TIME_SCALE);
+        }
+    }
+
+    pub fn fixed_timestep(&self) -> Option<Duration> {
+        self.fixed_timestep
+    }
+
+    pub fn set_fixed_timestep(&mut self, fixed_timestep: Option<Duration>) {
+        self.fixed_timestep = fixed_timestep;
+        self.accumulator = Duration::ZERO;
+    }
+
+    /// Number of fixed updates run this frame
+    pub fn fixed_steps(&self) -> u32 {
+        self.fixed_steps
+    }
+
+    /// How far time has progressed towards the next fixed update, from 0 to 1. Use it to
+    /// interpolate between the two latest fixed update states when rendering, e.g. with
+    /// [`FrameBlendPipeline`](crate::pipelines::FrameBlendPipeline). 1 without a fixed timestep.
+    pub fn fixed_alpha(&self) -> f32 {
+        match self.fixed_timestep.filter(|t| !t.is_zero()) {
+            Some(fixed_timestep) => {
+                (self.accumulator.as_secs_f64() / fixed_timestep.as_secs_f64()).min(1.0) as f32
+            }
+            None => 1.0,
+        }
+    }
+
+    /// Handle the default time control hotkeys, returns whether the key was used
+    pub fn handle_hotkey(&mut self, key: KeyCode) -> bool {
+        match key {
+            PAUSE_KEY => self.toggle_pause(),
+            STEP_KEY => {
+                self.pause();
+                self.step(1);
+            }
+            SLOWER_KEY => self.set_time_scale(self.time_scale * 0.5),
+            FASTER_KEY => self.set_time_scale(self.time_scale * 2.0),
+            _ => return false,
+        }
+        true
+    }
+
+    pub(crate) fn tick(&mut self) {
+        let now = Instant::now();
+        let real_delta = now - self.last_tick;
+        self.last_tick = now;
+        self.advance(real_delta);
+    }
+
+    fn advance(&mut self, real_delta: Duration) {
+        self.real_delta = real_delta;
+
+        let stepping = self.paused && self.pending_steps > 0;
+        if stepping {
+            self.pending_steps -= 1;
+        }
+        self.advancing = !self.paused || stepping;
+        self.delta = if stepping {
+            self.fixed_timestep.unwrap_or(DEFAULT_STEP_DELTA)
+        } else if self.paused {
+            Duration::ZERO
+        } else {
+            self.real_delta.mul_f64(self.time_scale)
+        };
+        self.elapsed += self.delta;
+
+        self.fixed_steps = 0;
+        if let Some(fixed_timestep) = self.fixed_timestep.filter(|t| !t.is_zero()) {
+            self.accumulator += self.delta;
+            let steps = self.accumulator.as_nanos() / fixed_timestep.as_nanos();
+            if steps > MAX_FIXED_STEPS_PER_FRAME as u128 {
+                self.fixed_steps = MAX_FIXED_STEPS_PER_FRAME;
+                self.accumulator = Duration::ZERO;
+            } else {
+                self.fixed_steps = steps as u32;
+                self.accumulator -= fixed_timestep * self.fixed_steps;
+            }
+        }
+    }
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Time::new(None)
+    }
+}
+
+/// Wall clock timing of the frame loop, updated by the runner at the start of each frame.
+/// Unlike [`Time`], it isn't affected by pause or time scale.
+#[derive(Debug, Copy, Clone)]
+pub struct FrameTime {
+    start: Instant,
+    last_frame: Instant,
+    delta: Duration,
+    smoothed_delta: f64,
+    frame: u64,
+}
+
+impl FrameTime {
+    pub fn new() -> FrameTime {
+        let now = Instant::now();
+        FrameTime {
+            start: now,
+            last_frame: now,
+            delta: Duration::ZERO,
+            smoothed_delta: 0.0,
+            frame: 0,
+        }
+    }
+
+    /// Time since the previous frame
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_secs(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// Frames per second smoothed over recent frames
+    pub fn fps(&self) -> f64 {
+        if self.smoothed_delta > 0.0 {
+            1.0 / self.smoothed_delta
+        } else {
+            0.0
+        }
+    }
+
+    /// Smoothed frame time in milliseconds
+    pub fn avg_ms(&self) -> f64 {
+        self.smoothed_delta * 1000.0
+    }
+
+    /// Index of the current frame, starting from 1 for the first frame
+    pub fn frame_index(&self) -> u64 {
+        self.frame
+    }
+
+    /// Wall clock time since the runner started
+    pub fn elapsed(&self) -> Duration {
+        self.last_frame - self.start
+    }
+
+    pub(crate) fn tick(&mut self) {
+        let now = Instant::now();
+        self.delta = now - self.last_frame;
+        self.last_frame = now;
+        self.frame += 1;
+        let delta = self.delta.as_secs_f64();
+        self.smoothed_delta = if self.frame == 1 {
+            delta
+        } else {
+            self.smoothed_delta + (delta - self.smoothed_delta) * FRAME_TIME_SMOOTHING
+        };
+    }
+}
+
+impl Default for FrameTime {
+    fn default() -> Self {
+        FrameTime::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::time::{Time, DEFAULT_STEP_DELTA, MAX_FIXED_STEPS_PER_FRAME};
+
+    const FRAME: Duration = Duration::from_millis(10);
+
+    #[test]
+    fn test_pause_stops_time() {
+        let mut time = Time::new(Some(FRAME));
+        time.pause();
+        time.advance(FRAME * 5);
+        assert_eq!(time.delta(), Duration::ZERO);
+        assert_eq!(time.elapsed(), Duration::ZERO);
+        assert_eq!(time.fixed_steps(), 0);
+        assert!(!time.is_advancing());
+        time.resume();
+        time.advance(FRAME);
+        assert_eq!(time.delta(), FRAME);
+        assert!(time.is_advancing());
+    }
+
+    #[test]
+    fn test_single_step_while_paused() {
+        let mut time = Time::new(None);
+        time.pause();
+        time.step(1);
+        time.advance(FRAME * 5);
+        assert!(time.is_advancing());
+        assert_eq!(time.delta(), DEFAULT_STEP_DELTA);
+        time.advance(FRAME);
+        assert!(!time.is_advancing());
+        assert_eq!(time.delta(), Duration::ZERO);
+        assert_eq!(time.elapsed(), DEFAULT_STEP_DELTA);
+    }
+
+    #[test]
+    fn test_step_uses_fixed_timestep() {
+        let mut time = Time::new(Some(FRAME));
+        time.pause();
+        time.step(2);
+        time.advance(Duration::from_secs(1));
+        assert_eq!(time.delta(), FRAME);
+        assert_eq!(time.fixed_steps(), 1);
+    }
+
+    #[test]
+    fn test_time_scale() {
+        let mut time = Time::new(None);
+        time.set_time_scale(0.5);
+        time.advance(FRAME);
+        assert_eq!(time.delta(), FRAME / 2);
+        assert_eq!(time.real_delta(), FRAME);
+        time.set_time_scale(f64::NAN);
+        assert_eq!(time.time_scale(), 0.5);
+        time.set_time_scale(-1.0);
+        assert_eq!(time.time_scale(), 0.0);
+    }
+
+    #[test]
+    fn test_fixed_steps_accumulate() {
+        let mut time = Time::new(Some(FRAME));
+        time.advance(FRAME * 5 / 2);
+        assert_eq!(time.fixed_steps(), 2);
+        assert!((time.fixed_alpha() - 0.5).abs() < 1e-6);
+        time.advance(FRAME / 2);
+        assert_eq!(time.fixed_steps(), 1);
+        assert_eq!(time.fixed_alpha(), 0.0);
+    }
+
+    #[test]
+    fn test_fixed_steps_clamped() {
+        let mut time = Time::new(Some(Duration::from_nanos(1)));
+        time.set_time_scale(1000.0);
+        time.advance(Duration::from_secs(10));
+        assert_eq!(time.fixed_steps(), MAX_FIXED_STEPS_PER_FRAME);
+        assert_eq!(time.fixed_alpha(), 0.0);
+    }
+}
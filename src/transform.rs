@@ -0,0 +1,222 @@
+const IDENTITY_MATRIX: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// Translation, rotation and scale of a 2D object relative to its parent. `z` is added to the
+/// depth of the object for layering, see [`Camera2D::proj`](crate::camera::Camera2D::proj).
+///
+/// Draw with any pipeline taking a view projection by multiplying it with the object's matrix,
+/// e.g. with a [`TransformStack`], and drawing at the origin:
+///
+/// ```ignore
+/// let mut stack = TransformStack::new(camera.view_proj());
+/// stack.push(&tank);
+/// quad_pipeline.draw(&mut rpass, &tank_bind_group, [0.0; 4], stack.current(), size, 1.0);
+/// stack.push(&turret);
+/// quad_pipeline.draw(&mut rpass, &turret_bind_group, [0.0; 4], stack.current(), size, 1.0);
+/// stack.pop();
+/// stack.pop();
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform2D {
+    pub translation: [f32; 2],
+    /// Radians, counter clockwise
+    pub rotation: f32,
+    pub scale: [f32; 2],
+    pub z: f32,
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform2D {
+    pub const IDENTITY: Transform2D = Transform2D {
+        translation: [0.0; 2],
+        rotation: 0.0,
+        scale: [1.0; 2],
+        z: 0.0,
+    };
+
+    pub fn from_translation(translation: [f32; 2]) -> Transform2D {
+        Transform2D {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn with_rotation(self, rotation: f32) -> Transform2D {
+        Transform2D {
+            rotation,
+            ..self
+        }
+    }
+
+    pub fn with_scale(self, scale: [f32; 2]) -> Transform2D {
+        Transform2D {
+            scale,
+            ..self
+        }
+    }
+
+    pub fn with_z(self, z: f32) -> Transform2D {
+        Transform2D {
+            z,
+            ..self
+        }
+    }
+
+    /// Column major matrix scaling, then rotating and then translating
+    pub fn matrix(&self) -> [[f32; 4]; 4] {
+        let (sin, cos) = self.rotation.sin_cos();
+        let [sx, sy] = self.scale;
+        [
+            [cos * sx, sin * sx, 0.0, 0.0],
+            [-sin * sy, cos * sy, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [self.translation[0], self.translation[1], self.z, 1.0],
+        ]
+    }
+
+    /// Transform a point from local to parent space
+    pub fn transform_point(&self, point: [f32; 2]) -> [f32; 2] {
+        transform_point(&self.matrix(), point)
+    }
+}
+
+/// Transform a 2D point with a column major matrix
+pub fn transform_point(matrix: &[[f32; 4]; 4], point: [f32; 2]) -> [f32; 2] {
+    [
+        matrix[0][0] * point[0] + matrix[1][0] * point[1] + matrix[3][0],
+        matrix[0][1] * point[0] + matrix[1][1] * point[1] + matrix[3][1],
+    ]
+}
+
+/// Matrix stack for drawing hierarchies. Each pushed transform is relative to the previous one
+/// and [`TransformStack::current`] is the view projection to draw the innermost object with.
+#[derive(Debug, Clone)]
+pub struct TransformStack {
+    stack: Vec<[[f32; 4]; 4]>,
+}
+
+impl TransformStack {
+    /// A stack starting from `base`, usually the camera's view projection
+    pub fn new(base: [[f32; 4]; 4]) -> TransformStack {
+        TransformStack {
+            stack: vec![base],
+        }
+    }
+
+    pub fn push(&mut self, transform: &Transform2D) {
+        self.push_matrix(transform.matrix());
+    }
+
+    pub fn push_matrix(&mut self, matrix: [[f32; 4]; 4]) {
+        self.stack.push(mul(self.current(), matrix));
+    }
+
+    /// Remove the latest pushed transform. The base is never removed.
+    pub fn pop(&mut self) {
+        if self.stack.len() > 1 {
+            self.stack.pop();
+        }
+    }
+
+    /// Push `transform` for the duration of `f`
+    pub fn with(&mut self, transform: &Transform2D, f: impl FnOnce(&mut TransformStack)) {
+        self.push(transform);
+        f(self);
+        self.pop();
+    }
+
+    pub fn current(&self) -> [[f32; 4]; 4] {
+        *self.stack.last().unwrap()
+    }
+
+    /// Number of pushed transforms
+    pub fn depth(&self) -> usize {
+        self.stack.len() - 1
+    }
+}
+
+/// Id of a node in a [`TransformTree`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TransformId(usize);
+
+/// Transforms with parents, for objects that are updated and drawn separately, e.g. a turret
+/// that is attached to a moving tank. Parents are added before their children.
+///
+/// ```ignore
+/// let tank = tree.add(Transform2D::from_translation([100.0, 0.0]), None);
+/// let turret = tree.add(Transform2D::from_translation([0.0, 8.0]).with_z(1.0), Some(tank));
+/// tree.get_mut(tank).rotation += dt;
+/// let view_proj = mul(camera.view_proj(), tree.world_matrix(turret));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TransformTree {
+    nodes: Vec<(Transform2D, Option<TransformId>)>,
+}
+
+impl TransformTree {
+    pub fn new() -> TransformTree {
+        TransformTree::default()
+    }
+
+    pub fn add(&mut self, transform: Transform2D, parent: Option<TransformId>) -> TransformId {
+        self.nodes.push((transform, parent));
+        TransformId(self.nodes.len() - 1)
+    }
+
+    pub fn get(&self, id: TransformId) -> &Transform2D {
+        &self.nodes[id.0].0
+    }
+
+    pub fn get_mut(&mut self, id: TransformId) -> &mut Transform2D {
+        &mut self.nodes[id.0].0
+    }
+
+    pub fn parent(&self, id: TransformId) -> Option<TransformId> {
+        self.nodes[id.0].1
+    }
+
+    /// Matrix from the node's local space to world space
+    pub fn world_matrix(&self, id: TransformId) -> [[f32; 4]; 4] {
+        let (transform, parent) = &self.nodes[id.0];
+        match parent {
+            Some(parent) => mul(self.world_matrix(*parent), transform.matrix()),
+            None => transform.matrix(),
+        }
+    }
+
+    /// World matrices of all nodes, indexed like the ids. Cheaper than calling
+    /// [`TransformTree::world_matrix`] for each node.
+    pub fn world_matrices(&self) -> Vec<[[f32; 4]; 4]> {
+        let mut matrices: Vec<[[f32; 4]; 4]> = Vec::with_capacity(self.nodes.len());
+        for (transform, parent) in self.nodes.iter() {
+            let parent = parent.map_or(IDENTITY_MATRIX, |parent| matrices[parent.0]);
+            matrices.push(mul(parent, transform.matrix()));
+        }
+        matrices
+    }
+
+    /// World position of the node's origin
+    pub fn world_position(&self, id: TransformId) -> [f32; 2] {
+        transform_point(&self.world_matrix(id), [0.0; 2])
+    }
+}
+
+/// Multiply column major matrices, e.g. a view projection with an object's world matrix
+pub fn mul(a: [[f32; 4]; 4], b: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    let mut out = [[0.0; 4]; 4];
+    for (col, out_col) in out.iter_mut().enumerate() {
+        for (row, value) in out_col.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[k][row] * b[col][k]).sum();
+        }
+    }
+    out
+}
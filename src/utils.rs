@@ -3,14 +3,40 @@ use std::{
     collections::{HashMap, HashSet, VecDeque},
     fmt::Formatter,
     future::Future,
+    hash::Hash,
+    ops::Range,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
+use bytemuck::Pod;
 use flume::{unbounded, Receiver, Sender};
 use log::{error, info};
-use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use notify::{
+    event::{AccessKind, AccessMode},
+    Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+};
 use path_clean::PathClean;
-use wgpu::naga::Module;
+use wgpu::{
+    naga::Module, util::StagingBelt, AddressMode, BindGroup, Buffer, BufferAddress,
+    BufferDescriptor, BufferSize, BufferSlice, BufferUsages, CommandBuffer, CommandEncoder,
+    CommandEncoderDescriptor, CompareFunction, ComputePassTimestampWrites, ComputePipeline,
+    ComputePipelineDescriptor, Device, Extent3d, Features, FilterMode, ImageCopyBuffer,
+    ImageCopyTexture, ImageDataLayout, Maintain, MapMode, Origin3d, PipelineCache,
+    PipelineCompilationOptions, PipelineLayout, QuerySet, QuerySetDescriptor, QueryType, Queue,
+    RenderBundle, RenderBundleDepthStencil, RenderBundleDescriptor, RenderBundleEncoder,
+    RenderBundleEncoderDescriptor, RenderPassTimestampWrites, SamplerBorderColor,
+    SamplerDescriptor, Texture, TextureAspect, TextureFormat, QUERY_SIZE,
+};
+
+use crate::{
+    locale::{message, Message},
+    pipelines::ERROR_SHADER_SOURCE,
+};
 
 pub fn wait_async<F: Future>(fut: F) -> F::Output {
     pollster::block_on(fut)
@@ -21,23 +47,96 @@ pub enum ShaderError {
     FileReadError(String),
     InvalidExtension(String),
     AlreadyIncluded(String),
-    WgslParseError(String),
+    WgslParseError(ShaderCompileError),
+    PreprocessError(String),
+    GlslParseError(String),
+    SpirvParseError(String),
+}
+
+/// A WGSL parse error located in the file it originates from, mapped back through includes
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ShaderCompileError {
+    /// The root file or the included file containing the error
+    pub file_path: String,
+    /// 1-based line in `file_path`, 0 if the parser gave no location
+    pub line: usize,
+    /// 1-based column in `line`
+    pub column: usize,
+    pub message: String,
+    /// The offending line
+    pub snippet: String,
+    /// Full parser report against the source with includes resolved
+    pub report: String,
+}
+
+impl std::fmt::Display for ShaderCompileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.line == 0 {
+            return write!(f, "{}: {}\n{}", self.file_path, self.message, self.report);
+        }
+        writeln!(
+            f,
+            "{}:{}:{}: {}",
+            self.file_path, self.line, self.column, self.message
+        )?;
+        writeln!(f, "{:>5} | {}", self.line, self.snippet)?;
+        write!(
+            f,
+            "{:>5} | {}^",
+            "",
+            " ".repeat(self.column.saturating_sub(1))
+        )
+    }
+}
+
+/// Language of a [`ShaderSource`]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ShaderLanguage {
+    #[default]
+    Wgsl,
+    /// GLSL of a single stage, requires the `glsl` feature
+    #[cfg(feature = "glsl")]
+    Glsl(wgpu::naga::ShaderStage),
 }
 
+impl ShaderLanguage {
+    /// Extensions of files that can be included
+    fn extensions(self) -> &'static [&'static str] {
+        match self {
+            ShaderLanguage::Wgsl => &["wgsl"],
+            #[cfg(feature = "glsl")]
+            ShaderLanguage::Glsl(_) => &["glsl", "vert", "frag", "comp"],
+        }
+    }
+}
+
+/// Values of `#define`s applied when a [`ShaderSource`] is parsed. Empty values only mark the
+/// name as defined.
+pub type ShaderDefines = HashMap<String, String>;
+
 impl std::fmt::Display for ShaderError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let s = match self {
             ShaderError::FileReadError(e) => {
-                format!("ShaderError::FileReadError: {}", e)
+                format!("{}: {}", message(Message::ShaderFileReadError), e)
             }
             ShaderError::InvalidExtension(e) => {
-                format!("ShaderError::InvalidExtension: {}", e)
+                format!("{}: {}", message(Message::ShaderInvalidExtension), e)
             }
             ShaderError::AlreadyIncluded(e) => {
-                format!("ShaderError::AlreadyIncluded: {}", e)
+                format!("{}: {}", message(Message::ShaderAlreadyIncluded), e)
             }
             ShaderError::WgslParseError(e) => {
-                format!("ShaderError::WgslParseError: \n{}", e)
+                format!("{}: \n{}", message(Message::ShaderParseError), e)
+            }
+            ShaderError::PreprocessError(e) => {
+                format!("{}: {}", message(Message::ShaderPreprocessError), e)
+            }
+            ShaderError::GlslParseError(e) => {
+                format!("{}: \n{}", message(Message::ShaderGlslParseError), e)
+            }
+            ShaderError::SpirvParseError(e) => {
+                format!("{}: {}", message(Message::ShaderSpirvParseError), e)
             }
         };
         write!(f, "{}", s)
@@ -48,6 +147,10 @@ pub struct WatchedShaderModule {
     source: ShaderSource,
     _watchers: HashMap<String, Option<RecommendedWatcher>>,
     _receivers: HashMap<String, Receiver<notify::Result<Event>>>,
+    dir_watchers: Vec<(Option<RecommendedWatcher>, Receiver<notify::Result<Event>>)>,
+    debounce: Duration,
+    /// Changed paths not yet returned by `changed_paths` & time of the latest change
+    pending_changes: Mutex<(HashSet<String>, Option<Instant>)>,
 }
 
 impl WatchedShaderModule {
@@ -56,6 +159,25 @@ impl WatchedShaderModule {
         Self::new_from_source(source)
     }
 
+    /// Watch a GLSL shader of `stage` and its includes
+    #[cfg(feature = "glsl")]
+    pub fn new_glsl(
+        path: &Path,
+        stage: wgpu::naga::ShaderStage,
+    ) -> Result<WatchedShaderModule, ShaderError> {
+        let source = ShaderSource::new_glsl(path, stage)?;
+        Self::new_from_source(source)
+    }
+
+    /// Watch a shader that is compiled with `defines`, which are kept across reloads
+    pub fn new_with_defines(
+        path: &Path,
+        defines: ShaderDefines,
+    ) -> Result<WatchedShaderModule, ShaderError> {
+        let source = ShaderSource::new(path)?.with_defines(defines);
+        Self::new_from_source(source)
+    }
+
     pub fn new_with_static_sources(
         root_source_path: &str,
         include_srcs: &HashMap<&'static str, &'static str>,
@@ -82,9 +204,27 @@ impl WatchedShaderModule {
             source,
             _watchers: watchers,
             _receivers: receivers,
+            dir_watchers: vec![],
+            debounce: Duration::ZERO,
+            pending_changes: Mutex::new((HashSet::new(), None)),
         })
     }
 
+    /// Also watch all shader files under `root_dir` recursively, so files that are created or
+    /// renamed, e.g. a new include, trigger a reload. Only files with extensions of the shader's
+    /// language are reported.
+    pub fn watch_dir(&mut self, root_dir: &Path) {
+        let path = root_dir.to_string_lossy();
+        let (receiver, watcher) = start_watcher(&path, RecursiveMode::Recursive);
+        self.dir_watchers.push((watcher, receiver));
+    }
+
+    /// Report changes only once no file has changed for `debounce`, so editors writing a file
+    /// several times on save cause one reload. Zero by default.
+    pub fn set_debounce(&mut self, debounce: Duration) {
+        self.debounce = debounce;
+    }
+
     fn paths_to_watch(source: &ShaderSource) -> HashSet<String> {
         let mut paths_to_watch = HashSet::new();
         let path_buf = PathBuf::from(&source.path);
@@ -102,7 +242,9 @@ impl WatchedShaderModule {
 
     pub fn reload(&mut self) -> Result<(), ShaderError> {
         if !self.source.is_static {
-            let source = ShaderSource::new(&PathBuf::from(&self.source.path))?;
+            let source =
+                ShaderSource::load(&PathBuf::from(&self.source.path), self.source.language)?
+                    .with_defines(self.source.defines.clone());
             let new_watches = Self::paths_to_watch(&source);
             let mut removes = vec![];
             // Find if we need to remove old watchers
@@ -139,51 +281,104 @@ impl WatchedShaderModule {
         modify_fn(&mut self.source)
     }
 
+    /// Path of the root source
+    pub fn path(&self) -> &str {
+        &self.source.path
+    }
+
+    /// Replace the defines, e.g. to switch to another variant. Takes effect in the next
+    /// [`WatchedShaderModule::module`].
+    pub fn set_defines(&mut self, defines: ShaderDefines) {
+        self.source.defines = defines;
+    }
+
+    pub fn defines(&self) -> &ShaderDefines {
+        &self.source.defines
+    }
+
+    /// Paths changed since the last call, see [`WatchedShaderModule::set_debounce`]
     pub fn changed_paths(&self) -> HashSet<String> {
-        let mut paths: HashSet<String> = HashSet::default();
-        for (_path, receiver) in self._receivers.iter() {
-            for event in receiver.try_iter().flatten() {
-                for path in event.paths.iter() {
-                    if let Some(p) = path.to_str() {
-                        if !p.ends_with('~') {
-                            paths.insert(p.to_string());
-                        }
-                    }
-                }
-            }
+        let mut pending = self.pending_changes.lock().unwrap();
+        let (paths, last_change) = &mut *pending;
+        let extensions = self.source.language.extensions();
+        let file_events = Self::source_events(self._receivers.values());
+        let dir_events = Self::source_events(
+            self.dir_watchers.iter().map(|(_, receiver)| receiver),
+        )
+        .filter(|path| {
+            Path::new(path)
+                .extension()
+                .is_some_and(|ext| extensions.contains(&&*ext.to_string_lossy()))
+        });
+        for path in file_events.chain(dir_events) {
+            paths.insert(path);
+            *last_change = Some(Instant::now());
+        }
+        if last_change.is_some_and(|time| time.elapsed() >= self.debounce) {
+            *last_change = None;
+            std::mem::take(paths)
+        } else {
+            HashSet::default()
         }
+    }
 
-        paths
+    /// Paths of modifying events in `receivers`. Opening or reading a file isn't a change.
+    fn source_events<'a>(
+        receivers: impl Iterator<Item = &'a Receiver<notify::Result<Event>>> + 'a,
+    ) -> impl Iterator<Item = String> + 'a {
+        receivers
+            .flat_map(|receiver| receiver.try_iter().flatten().collect::<Vec<_>>())
+            .filter(|event| {
+                !matches!(event.kind, EventKind::Access(kind)
+                    if kind != AccessKind::Close(AccessMode::Write))
+            })
+            .flat_map(|event| event.paths)
+            .filter_map(|path| path.to_str().map(str::to_string))
+            .filter(|path| !path.ends_with('~'))
     }
 
     pub fn module(&self) -> Result<ShaderModule, ShaderError> {
         ShaderModule::new_from_source(self.source.clone())
     }
+
+    /// Like [`WatchedShaderModule::module`], but logs errors and returns
+    /// [`ShaderModule::fallback`] instead, so a bad reload doesn't stop the app.
+    pub fn module_or_fallback(&self) -> ShaderModule {
+        ShaderModule::new_from_source_or_fallback(self.source.clone())
+    }
 }
 
 fn file_watcher(
     tx: Sender<notify::Result<Event>>,
     path: &str,
+    mode: RecursiveMode,
 ) -> notify::Result<RecommendedWatcher> {
     let mut watcher = notify::recommended_watcher(move |res| {
         tx.send(res).expect("sending watch event failed");
     })?;
-    watcher.watch(path.as_ref(), RecursiveMode::NonRecursive)?;
+    watcher.watch(path.as_ref(), mode)?;
     Ok(watcher)
 }
 
 pub fn start_file_watcher(
     path: &str,
+) -> (Receiver<notify::Result<Event>>, Option<RecommendedWatcher>) {
+    start_watcher(path, RecursiveMode::NonRecursive)
+}
+
+fn start_watcher(
+    path: &str,
+    mode: RecursiveMode,
 ) -> (Receiver<notify::Result<Event>>, Option<RecommendedWatcher>) {
     let (rx, watcher) = {
         let (tx, rx) = unbounded::<notify::Result<Event>>();
-        match file_watcher(tx, path) {
+        match file_watcher(tx, path, mode) {
             Ok(watcher) => {
                 info!("Watching {} for changes", path);
                 (rx, Some(watcher))
             }
             Err(e) => {
-                error!("Shader {} file watcher failed: {:?}", path, e);
+                error!("File watcher for {} failed: {:?}", path, e);
                 (rx, None)
             }
         }
@@ -208,6 +403,36 @@ impl ShaderModule {
         Self::new_from_source(source)
     }
 
+    /// Parse a GLSL shader of `stage`. `#include`s are resolved like in WGSL, other directives
+    /// are handled by the GLSL preprocessor with [`ShaderSource::defines`] as predefined macros.
+    #[cfg(feature = "glsl")]
+    pub fn from_glsl(
+        path: &Path,
+        stage: wgpu::naga::ShaderStage,
+    ) -> Result<ShaderModule, ShaderError> {
+        let source = ShaderSource::new_glsl(path, stage)?;
+        Self::new_from_source(source)
+    }
+
+    /// Parse a SPIR-V binary
+    #[cfg(feature = "spirv")]
+    pub fn from_spirv(bytes: &[u8]) -> Result<ShaderModule, ShaderError> {
+        wgpu::naga::front::spv::parse_u8_slice(bytes, &Default::default())
+            .map(|module| ShaderModule {
+                module,
+            })
+            .map_err(|e| ShaderError::SpirvParseError(e.to_string()))
+    }
+
+    /// Parse a shader variant, see [`ShaderSource::with_defines`]
+    pub fn new_with_defines(
+        path: &Path,
+        defines: ShaderDefines,
+    ) -> Result<ShaderModule, ShaderError> {
+        let source = ShaderSource::new(path)?.with_defines(defines);
+        Self::new_from_source(source)
+    }
+
     pub fn new_with_static_sources(
         root_source_path: &str,
         include_srcs: &HashMap<&'static str, &'static str>,
@@ -216,83 +441,217 @@ impl ShaderModule {
         Self::new_from_source(source)
     }
 
+    /// Create a wgpu shader module from the parsed module
+    pub fn create_shader_module(&self, device: &Device, label: Option<&str>) -> wgpu::ShaderModule {
+        device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label,
+            source: wgpu::ShaderSource::Naga(Cow::Owned(self.module.clone())),
+        })
+    }
+
+    /// The magenta [`ERROR_SHADER_SOURCE`] module. It provides `vs_main`, `fs_main` and
+    /// `fragment` entry points and ignores all inputs, so it can be substituted into most
+    /// pipelines.
+    pub fn fallback() -> ShaderModule {
+        let mut wgsl_parser = wgpu::naga::front::wgsl::Frontend::new();
+        ShaderModule {
+            module: wgsl_parser
+                .parse(ERROR_SHADER_SOURCE)
+                .expect("Error shader should parse"),
+        }
+    }
+
+    /// Parse shader source, logging errors and returning [`ShaderModule::fallback`] on failure.
+    pub fn new_from_source_or_fallback(source: ShaderSource) -> ShaderModule {
+        match Self::new_from_source(source) {
+            Ok(module) => module,
+            Err(e) => {
+                error!("{}", e);
+                Self::fallback()
+            }
+        }
+    }
+
+    /// Parse shader source. With the `shader_cache` feature, modules are loaded from and stored
+    /// to the disk cache set by `GlassConfig::shader_cache_dir`.
     pub fn new_from_source(source: ShaderSource) -> Result<ShaderModule, ShaderError> {
+        #[cfg(feature = "shader_cache")]
+        let key = crate::shader_cache::key(&source);
+        #[cfg(feature = "shader_cache")]
+        if let Some(module) = crate::shader_cache::load(key) {
+            return Ok(ShaderModule {
+                module,
+            });
+        }
+        let module = Self::parse_source(&source)?;
+        #[cfg(feature = "shader_cache")]
+        crate::shader_cache::store(key, &module.module);
+        Ok(module)
+    }
+
+    fn parse_source(source: &ShaderSource) -> Result<ShaderModule, ShaderError> {
+        #[cfg(feature = "glsl")]
+        if let ShaderLanguage::Glsl(stage) = source.language {
+            return Self::new_from_glsl_source(source, stage);
+        }
+        let processed = source.preprocessed()?;
         let mut wgsl_parser = wgpu::naga::front::wgsl::Frontend::new();
-        match wgsl_parser.parse(&source.source) {
+        match wgsl_parser.parse(&processed) {
             Ok(module) => Ok(ShaderModule {
                 module,
             }),
-            Err(parse_error) => {
-                // ToDo: Fix
-                // let mut belonging_parts = vec![];
-                // if let Some(location_in_source) = parse_error.location(&source.source) {
-                //     for part in source.parts.iter() {
-                //         if location_in_source.line_number >= part.start_line as u32
-                //             && location_in_source.line_number <= part.end_line as u32
-                //         {
-                //             belonging_parts.push(part);
-                //         }
-                //     }
-                // }
-                // let error_str = if !belonging_parts.is_empty() {
-                //     let mut error = "".to_string();
-                //     // Take the shallowest matching belonging part
-                //     belonging_parts.sort_by(|a, b| a.depth.cmp(&b.depth));
-                //     for part in belonging_parts {
-                //         error.push_str(
-                //             &parse_error.emit_to_string_with_path(&part.content, &part.file_path),
-                //         );
-                //     }
-                //     error
-                // } else {
-                //     parse_error.emit_to_string_with_path(&source.source, &source.path)
-                // };
-                let mut error_str = "".to_string();
-                error_str
-                    .push_str(&parse_error.emit_to_string_with_path(&source.source, &source.path));
-                error_str.push_str(&format!(
-                    "Included files: {:#?}",
-                    source
-                        .parts
-                        .iter()
-                        .map(|p| p.file_path.to_string())
-                        .collect::<Vec<String>>()
-                ));
-
-                Err(ShaderError::WgslParseError(error_str))
-            }
+            Err(parse_error) => Err(ShaderError::WgslParseError(
+                source.compile_error(
+                    parse_error.message(),
+                    parse_error
+                        .location(&processed)
+                        .map(|l| (l.line_number as usize, l.line_position as usize)),
+                    parse_error.emit_to_string_with_path(&processed, &source.path),
+                ),
+            )),
         }
     }
 }
 
+#[cfg(feature = "glsl")]
+impl ShaderModule {
+    fn new_from_glsl_source(
+        source: &ShaderSource,
+        stage: wgpu::naga::ShaderStage,
+    ) -> Result<ShaderModule, ShaderError> {
+        let options = wgpu::naga::front::glsl::Options {
+            stage,
+            defines: source
+                .defines
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        };
+        wgpu::naga::front::glsl::Frontend::default()
+            .parse(&options, &source.source)
+            .map(|module| ShaderModule {
+                module,
+            })
+            .map_err(|e| ShaderError::GlslParseError(e.emit_to_string(&source.source)))
+    }
+}
+
+/// Shader source with includes resolved. Preprocessor directives are applied with
+/// [`ShaderSource::defines`] when the source is parsed, see [`ShaderSource::with_defines`].
 #[derive(Debug, Default, Clone)]
 pub struct ShaderSource {
     pub path: String,
     pub source: String,
     pub parts: Vec<IncludedPart>,
+    /// Origin of each line of `source`
+    pub lines: Vec<SourceLine>,
     pub is_static: bool,
+    pub defines: ShaderDefines,
+    pub language: ShaderLanguage,
 }
 
 impl ShaderSource {
+    /// Compile the source as a variant selected by `defines`. Supports `#define NAME [value]`,
+    /// `#ifdef NAME`, `#ifndef NAME`, `#if NAME`, `#if NAME == value`, `#if NAME != value`,
+    /// `#else` and `#endif`. Defined names are replaced by their values in the code, so
+    /// e.g. `SAMPLE_COUNT` can be set to `4`. `#if NAME` is false for undefined names and the
+    /// values `0` and `false`. Includes are resolved before conditions, so an `#include` inside
+    /// an inactive block is still included.
+    ///
+    /// ```ignore
+    /// let source = ShaderSource::new(path)?
+    ///     .with_define("MSAA", "")
+    ///     .with_define("SAMPLE_COUNT", "4u");
+    /// ```
+    pub fn with_defines(mut self, defines: ShaderDefines) -> Self {
+        self.defines = defines;
+        self
+    }
+
+    pub fn with_define(mut self, name: &str, value: &str) -> Self {
+        self.defines.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// The source with preprocessor directives applied. Directive and inactive lines are kept as
+    /// empty lines so error line numbers match the source.
+    pub fn preprocessed(&self) -> Result<String, ShaderError> {
+        preprocess(&self.source, &self.defines)
+            .map_err(|e| ShaderError::PreprocessError(format!("{}: {}", self.path, e)))
+    }
+
     pub fn new(path: &Path) -> Result<ShaderSource, ShaderError> {
+        Self::load(path, ShaderLanguage::Wgsl)
+    }
+
+    /// The file and line a 1-based line of [`ShaderSource::source`] was included from
+    pub fn origin(&self, line: usize) -> Option<&SourceLine> {
+        self.lines.get(line.checked_sub(1)?)
+    }
+
+    fn compile_error(
+        &self,
+        message: &str,
+        location: Option<(usize, usize)>,
+        report: String,
+    ) -> ShaderCompileError {
+        let mut error = ShaderCompileError {
+            file_path: self.path.clone(),
+            message: message.to_string(),
+            report,
+            ..Default::default()
+        };
+        if let Some((line, column)) = location {
+            if let Some(origin) = self.origin(line) {
+                error.file_path = origin.file_path.clone();
+                error.line = origin.line;
+                error.column = column;
+                error.snippet = self
+                    .source
+                    .lines()
+                    .nth(line - 1)
+                    .unwrap_or_default()
+                    .to_string();
+            }
+        }
+        error
+    }
+
+    /// GLSL source of `stage`. Includes may have `.glsl`, `.vert`, `.frag` or `.comp`
+    /// extensions.
+    #[cfg(feature = "glsl")]
+    pub fn new_glsl(
+        path: &Path,
+        stage: wgpu::naga::ShaderStage,
+    ) -> Result<ShaderSource, ShaderError> {
+        Self::load(path, ShaderLanguage::Glsl(stage))
+    }
+
+    fn load(path: &Path, language: ShaderLanguage) -> Result<ShaderSource, ShaderError> {
         let mut included_files = HashSet::new();
         let mut file_stack = VecDeque::new();
         let mut included_parts = Vec::new();
         let mut main_file_line_count = 0;
+        let mut lines = Vec::new();
 
         let source = wgsl_source_with_includes(
             path,
+            language.extensions(),
             &mut included_files,
             &mut file_stack,
             &mut included_parts,
             &mut main_file_line_count,
+            &mut lines,
             0,
         )?;
         Ok(ShaderSource {
             path: path.clean().display().to_string(),
             source,
             parts: included_parts,
+            lines,
             is_static: false,
+            defines: ShaderDefines::new(),
+            language,
         })
     }
 
@@ -304,6 +663,7 @@ impl ShaderSource {
         let mut file_stack = VecDeque::new();
         let mut included_parts = Vec::new();
         let mut main_file_line_count = 0;
+        let mut lines = Vec::new();
 
         let path = PathBuf::from(root_source_path);
         let source = wgsl_source_with_static_includes(
@@ -313,17 +673,125 @@ impl ShaderSource {
             &mut file_stack,
             &mut included_parts,
             &mut main_file_line_count,
+            &mut lines,
             0,
         )?;
         Ok(ShaderSource {
             path: root_source_path.to_string(),
             source,
             parts: included_parts,
+            lines,
             is_static: true,
+            defines: ShaderDefines::new(),
+            language: ShaderLanguage::Wgsl,
         })
     }
 }
 
+struct Conditional {
+    active: bool,
+    parent_active: bool,
+    taken: bool,
+    seen_else: bool,
+}
+
+fn preprocess(source: &str, defines: &ShaderDefines) -> Result<String, String> {
+    let mut defines = defines.clone();
+    let mut conditionals: Vec<Conditional> = vec![];
+    let mut result = String::with_capacity(source.len());
+    for (i, line) in source.lines().enumerate() {
+        let active = conditionals.last().is_none_or(|c| c.active);
+        let Some(directive) = line.trim_start().strip_prefix('#') else {
+            if active {
+                result.push_str(&substitute_defines(line, &defines));
+            }
+            result.push('\n');
+            continue;
+        };
+        let error = |msg: &str| format!("line {}: {}", i + 1, msg);
+        let mut words = directive.split_whitespace();
+        match words.next() {
+            Some("define") => {
+                let name = words
+                    .next()
+                    .ok_or_else(|| error("#define without a name"))?;
+                if active {
+                    defines.insert(name.to_string(), words.collect::<Vec<_>>().join(" "));
+                }
+            }
+            Some(keyword @ ("ifdef" | "ifndef" | "if")) => {
+                let name = words
+                    .next()
+                    .ok_or_else(|| error(&format!("#{} without a name", keyword)))?;
+                let value = defines.get(name);
+                let condition = match keyword {
+                    "ifdef" => value.is_some(),
+                    "ifndef" => value.is_none(),
+                    _ => match (words.next(), words.next()) {
+                        (None, _) => value.is_some_and(|v| !matches!(v.as_str(), "0" | "false")),
+                        (Some("=="), Some(expected)) => value.is_some_and(|v| v == expected),
+                        (Some("!="), Some(expected)) => value.is_none_or(|v| v != expected),
+                        _ => return Err(error("expected #if NAME [== | != value]")),
+                    },
+                };
+                conditionals.push(Conditional {
+                    active: active && condition,
+                    parent_active: active,
+                    taken: condition,
+                    seen_else: false,
+                });
+            }
+            Some("else") => {
+                let conditional = conditionals
+                    .last_mut()
+                    .ok_or_else(|| error("#else without #if"))?;
+                if conditional.seen_else {
+                    return Err(error("second #else"));
+                }
+                conditional.seen_else = true;
+                conditional.active = conditional.parent_active && !conditional.taken;
+            }
+            Some("endif") => {
+                conditionals
+                    .pop()
+                    .ok_or_else(|| error("#endif without #if"))?;
+            }
+            _ => return Err(error(&format!("unknown directive #{}", directive.trim()))),
+        }
+        result.push('\n');
+    }
+    if !conditionals.is_empty() {
+        return Err("missing #endif".to_string());
+    }
+    Ok(result)
+}
+
+/// Replace identifiers that are defined with a value by the value
+fn substitute_defines(line: &str, defines: &ShaderDefines) -> String {
+    if defines.is_empty() {
+        return line.to_string();
+    }
+    let mut result = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        let (before, from) = rest.split_at(start);
+        result.push_str(before);
+        let end = from
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(from.len());
+        let identifier = &from[..end];
+        // Leave number suffixes like `1u` and member accesses like `a.b` alone
+        let continues_token = before.ends_with(|c: char| c.is_ascii_digit() || c == '.');
+        match defines.get(identifier) {
+            Some(value) if !value.is_empty() && !continues_token => result.push_str(value),
+            _ => result.push_str(identifier),
+        }
+        rest = &from[end..];
+    }
+    result.push_str(rest);
+    result
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct IncludedPart {
     pub content: String,
@@ -333,12 +801,23 @@ pub struct IncludedPart {
     pub depth: usize,
 }
 
+/// A line of a file in a [`ShaderSource`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SourceLine {
+    pub file_path: String,
+    /// 1-based line in `file_path`
+    pub line: usize,
+}
+
+#[allow(clippy::too_many_arguments)]
 fn wgsl_source_with_includes(
     file_path: &Path,
+    extensions: &[&str],
     included_files: &mut HashSet<String>,
     file_stack: &mut VecDeque<String>,
     included_parts: &mut Vec<IncludedPart>,
     main_file_line_count: &mut usize,
+    lines: &mut Vec<SourceLine>,
     depth: usize,
 ) -> Result<String, ShaderError> {
     let mut result = String::new();
@@ -350,9 +829,8 @@ fn wgsl_source_with_includes(
         .map(Cow::into_owned);
 
     if let Some(ext) = ext {
-        match ext.as_str() {
-            "wgsl" => {}
-            e => return Err(ShaderError::InvalidExtension(e.to_string())),
+        if !extensions.contains(&ext.as_str()) {
+            return Err(ShaderError::InvalidExtension(ext));
         }
     }
 
@@ -371,7 +849,7 @@ fn wgsl_source_with_includes(
     let current_part_start_line = *main_file_line_count + 1;
     let mut line_count = 0;
 
-    for line in source.lines() {
+    for (i, line) in source.lines().enumerate() {
         if line.starts_with("#include") {
             let included_file_name = line.trim_start_matches("#include ").trim();
             let included_file_path = std::env::current_dir()
@@ -389,10 +867,12 @@ fn wgsl_source_with_includes(
             if !file_stack.contains(&included_file_path_str) {
                 let included_part = wgsl_source_with_includes(
                     &included_file_path,
+                    extensions,
                     included_files,
                     file_stack,
                     included_parts,
                     main_file_line_count,
+                    lines,
                     depth + 1,
                 )?;
 
@@ -411,6 +891,10 @@ fn wgsl_source_with_includes(
         } else {
             result.push_str(line);
             result.push('\n');
+            lines.push(SourceLine {
+                file_path: file_path_str.clone(),
+                line: i + 1,
+            });
             line_count += 1;
         }
     }
@@ -431,6 +915,7 @@ fn wgsl_source_with_static_includes(
     file_stack: &mut VecDeque<String>,
     included_parts: &mut Vec<IncludedPart>,
     main_file_line_count: &mut usize,
+    lines: &mut Vec<SourceLine>,
     depth: usize,
 ) -> Result<String, ShaderError> {
     let mut result = String::new();
@@ -463,7 +948,7 @@ fn wgsl_source_with_static_includes(
     let current_part_start_line = *main_file_line_count + 1;
     let mut line_count = 0;
 
-    for line in source.lines() {
+    for (i, line) in source.lines().enumerate() {
         if line.starts_with("#include") {
             let included_file_name = line.trim_start_matches("#include ").trim().to_string();
 
@@ -481,6 +966,7 @@ fn wgsl_source_with_static_includes(
                     file_stack,
                     included_parts,
                     main_file_line_count,
+                    lines,
                     depth + 1,
                 )?;
                 let part_count = included_part.lines().count();
@@ -497,6 +983,10 @@ fn wgsl_source_with_static_includes(
         } else {
             result.push_str(line);
             result.push('\n');
+            lines.push(SourceLine {
+                file_path: file_path.clone(),
+                line: i + 1,
+            });
             line_count += 1;
         }
     }
@@ -509,11 +999,1186 @@ fn wgsl_source_with_static_includes(
     Ok(result)
 }
 
+/// Copy the contents of a buffer to CPU memory. Blocks until the copy has completed. The buffer
+/// must have been created with `COPY_SRC` usage.
+pub fn read_buffer(device: &Device, queue: &Queue, buffer: &Buffer) -> Vec<u8> {
+    let staging = device.create_buffer(&BufferDescriptor {
+        label: Some("readback_buffer"),
+        size: buffer.size(),
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("Readback Commands"),
+    });
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, buffer.size());
+    queue.submit(Some(encoder.finish()));
+    map_and_read(device, &staging)
+}
+
+/// Copy mip level 0 of all layers of an uncompressed color texture to CPU memory. Rows are
+/// tightly packed (no padding). Blocks until the copy has completed. The texture must have been
+/// created with `COPY_SRC` usage.
+pub fn read_texture(device: &Device, queue: &Queue, texture: &Texture) -> Vec<u8> {
+    let size = texture.size();
+    let bytes_per_pixel = texture
+        .format()
+        .block_copy_size(None)
+        .expect("Texture readback requires a color format");
+    let unpadded_bytes_per_row = size.width * bytes_per_pixel;
+    let padded_bytes_per_row = padded_bytes_per_row(unpadded_bytes_per_row);
+    let rows = size.height * size.depth_or_array_layers;
+    let staging = device.create_buffer(&BufferDescriptor {
+        label: Some("texture_readback_buffer"),
+        size: (padded_bytes_per_row * rows) as u64,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("Texture Readback Commands"),
+    });
+    encoder.copy_texture_to_buffer(
+        ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: TextureAspect::All,
+        },
+        ImageCopyBuffer {
+            buffer: &staging,
+            layout: ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(size.height),
+            },
+        },
+        size,
+    );
+    queue.submit(Some(encoder.finish()));
+    let padded = map_and_read(device, &staging);
+    let mut data = Vec::with_capacity((unpadded_bytes_per_row * rows) as usize);
+    for row in padded.chunks(padded_bytes_per_row as usize) {
+        data.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+    }
+    data
+}
+
+/// Write tightly packed data (as returned by [`read_texture`]) to mip level 0 of all layers of
+/// a texture. The texture must have been created with `COPY_DST` usage.
+pub fn write_texture(queue: &Queue, texture: &Texture, data: &[u8]) {
+    let size = texture.size();
+    let bytes_per_pixel = texture
+        .format()
+        .block_copy_size(None)
+        .expect("Texture write requires a color format");
+    queue.write_texture(
+        ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: TextureAspect::All,
+        },
+        data,
+        ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(size.width * bytes_per_pixel),
+            rows_per_image: Some(size.height),
+        },
+        size,
+    );
+}
+
+/// Row pitch aligned to [`wgpu::COPY_BYTES_PER_ROW_ALIGNMENT`] as required by buffer copies.
+pub fn padded_bytes_per_row(unpadded_bytes_per_row: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    unpadded_bytes_per_row.div_ceil(align) * align
+}
+
+fn map_and_read(device: &Device, staging: &Buffer) -> Vec<u8> {
+    let slice = staging.slice(..);
+    slice.map_async(MapMode::Read, |_| ());
+    device.poll(Maintain::Wait);
+    let data = slice.get_mapped_range().to_vec();
+    staging.unmap();
+    data
+}
+
+/// GPU time spent in a [`GpuProfiler::scope`]
+#[derive(Debug, Clone)]
+pub struct GpuTiming {
+    pub label: String,
+    pub duration: Duration,
+}
+
+/// Measures GPU time of labeled scopes with timestamp queries. Wrap encoded work in
+/// [`GpuProfiler::scope`] or measure whole passes with [`GpuProfiler::pass_timestamp_writes`],
+/// and call [`GpuProfiler::end_frame`] after the frame has been submitted.
+/// Results arrive asynchronously, [`GpuProfiler::timings`] returns those of the latest resolved
+/// frame. Frames are skipped while the previous readback is still in flight.
+///
+/// ```ignore
+/// let profiler = context.gpu_profiler().unwrap();
+/// profiler.scope("bloom", encoder, |encoder| bloom.bloom(encoder, &target, 1.0));
+/// ```
+pub struct GpuProfiler {
+    query_set: QuerySet,
+    resolve_buffer: Buffer,
+    readback_buffer: Arc<Buffer>,
+    max_scopes: u32,
+    timestamp_period: f32,
+    scopes: Mutex<Vec<String>>,
+    readback_busy: Arc<AtomicBool>,
+    timings: Arc<Mutex<Vec<GpuTiming>>>,
+}
+
+impl GpuProfiler {
+    /// Features the device needs for profiling
+    pub fn required_features() -> Features {
+        Features::TIMESTAMP_QUERY | Features::TIMESTAMP_QUERY_INSIDE_ENCODERS
+    }
+
+    /// Returns `None` if the device lacks [`GpuProfiler::required_features`]. At most
+    /// `max_scopes` scopes are measured per frame.
+    pub fn new(device: &Device, queue: &Queue, max_scopes: u32) -> Option<GpuProfiler> {
+        if !device.features().contains(Self::required_features()) {
+            return None;
+        }
+        let max_scopes = max_scopes.max(1);
+        let size = (max_scopes * 2) as u64 * QUERY_SIZE as u64;
+        let query_set = device.create_query_set(&QuerySetDescriptor {
+            label: Some("gpu_profiler_queries"),
+            ty: QueryType::Timestamp,
+            count: max_scopes * 2,
+        });
+        let resolve_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("gpu_profiler_resolve_buffer"),
+            size,
+            usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = Arc::new(device.create_buffer(&BufferDescriptor {
+            label: Some("gpu_profiler_readback_buffer"),
+            size,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        }));
+        Some(GpuProfiler {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            max_scopes,
+            timestamp_period: queue.get_timestamp_period(),
+            scopes: Mutex::new(vec![]),
+            readback_busy: Arc::new(AtomicBool::new(false)),
+            timings: Arc::new(Mutex::new(vec![])),
+        })
+    }
+
+    /// Measure the GPU time of the work `f` encodes. Scopes beyond `max_scopes` per frame run
+    /// unmeasured.
+    pub fn scope<R>(
+        &self,
+        label: &str,
+        encoder: &mut CommandEncoder,
+        f: impl FnOnce(&mut CommandEncoder) -> R,
+    ) -> R {
+        let Some(index) = self.begin_scope(label) else {
+            return f(encoder);
+        };
+        encoder.write_timestamp(&self.query_set, index * 2);
+        let result = f(encoder);
+        encoder.write_timestamp(&self.query_set, index * 2 + 1);
+        result
+    }
+
+    /// Timestamp writes measuring a whole render pass as a scope. Returns `None` once
+    /// `max_scopes` scopes have been used this frame.
+    ///
+    /// The crate's multi-pass pipelines (e.g. bloom, blur and tonemapping) time their passes
+    /// this way once given a profiler with their `set_profiler` method. Share the runner's with
+    /// [`GlassContext::gpu_profiler_arc`](crate::GlassContext::gpu_profiler_arc), so their
+    /// passes show up as `glass::` scopes in its timings.
+    pub fn pass_timestamp_writes(&self, label: &str) -> Option<RenderPassTimestampWrites<'_>> {
+        let index = self.begin_scope(label)?;
+        Some(RenderPassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(index * 2),
+            end_of_pass_write_index: Some(index * 2 + 1),
+        })
+    }
+
+    /// Timestamp writes measuring a whole compute pass as a scope
+    pub fn compute_pass_timestamp_writes(
+        &self,
+        label: &str,
+    ) -> Option<ComputePassTimestampWrites<'_>> {
+        let index = self.begin_scope(label)?;
+        Some(ComputePassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(index * 2),
+            end_of_pass_write_index: Some(index * 2 + 1),
+        })
+    }
+
+    fn begin_scope(&self, label: &str) -> Option<u32> {
+        let mut scopes = self.scopes.lock().unwrap();
+        if scopes.len() as u32 >= self.max_scopes {
+            return None;
+        }
+        scopes.push(label.to_string());
+        Some(scopes.len() as u32 - 1)
+    }
+
+    /// Resolve this frame's scopes and read them back. Call after the frame's command buffers
+    /// have been submitted. Timings are updated from device polling once the readback completes.
+    pub fn end_frame(&self, device: &Device, queue: &Queue) {
+        let labels = std::mem::take(&mut *self.scopes.lock().unwrap());
+        if labels.is_empty() || self.readback_busy.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let size = (labels.len() * 2) as u64 * QUERY_SIZE as u64;
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Gpu Profiler Resolve"),
+        });
+        encoder.resolve_query_set(
+            &self.query_set,
+            0..(labels.len() * 2) as u32,
+            &self.resolve_buffer,
+            0,
+        );
+        encoder.copy_buffer_to_buffer(&self.resolve_buffer, 0, &self.readback_buffer, 0, size);
+        queue.submit(Some(encoder.finish()));
+
+        let buffer = self.readback_buffer.clone();
+        let readback_busy = self.readback_busy.clone();
+        let timings = self.timings.clone();
+        let timestamp_period = self.timestamp_period as f64;
+        self.readback_buffer
+            .slice(..size)
+            .map_async(MapMode::Read, move |result| {
+                if let Err(e) = result {
+                    error!("Failed to map gpu profiler queries: {}", e);
+                    readback_busy.store(false, Ordering::Release);
+                    return;
+                }
+                let timestamps = {
+                    let data = buffer.slice(..size).get_mapped_range();
+                    data.chunks_exact(QUERY_SIZE as usize)
+                        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+                        .collect::<Vec<_>>()
+                };
+                buffer.unmap();
+                *timings.lock().unwrap() = labels
+                    .into_iter()
+                    .zip(timestamps.chunks_exact(2))
+                    .map(|(label, ticks)| GpuTiming {
+                        label,
+                        duration: Duration::from_nanos(
+                            (ticks[1].saturating_sub(ticks[0]) as f64 * timestamp_period) as u64,
+                        ),
+                    })
+                    .collect();
+                readback_busy.store(false, Ordering::Release);
+            });
+    }
+
+    /// Timings of the latest resolved frame in scope order
+    pub fn timings(&self) -> Vec<GpuTiming> {
+        self.timings.lock().unwrap().clone()
+    }
+}
+
+/// Caller supplied identity of a cached bind group, a name and e.g. a ping-pong index. Use a new
+/// id whenever the bound resources are recreated, or [`BindGroupCache::remove`] the old entry.
+pub type BindGroupKey = (&'static str, u64);
+
+/// Cached bind groups not used within this many frames are dropped
+pub const BIND_GROUP_CACHE_MAX_UNUSED_FRAMES: u64 = 120;
+
+struct CachedBindGroup {
+    bind_group: Arc<BindGroup>,
+    last_used: u64,
+}
+
+/// Hashable form of a [`SamplerDescriptor`], built like one. Used to look up shared samplers with
+/// [`get_or_create_sampler`](crate::device_context::DeviceContext::get_or_create_sampler).
+/// Defaults match [`SamplerDescriptor::default`].
+///
+/// ```ignore
+/// let shadow_sampler = context.get_or_create_sampler(
+///     SamplerKey::new()
+///         .filter(FilterMode::Linear)
+///         .compare(CompareFunction::LessEqual),
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SamplerKey {
+    address_modes: [AddressMode; 3],
+    /// Mag, min and mipmap filters
+    filters: [FilterMode; 3],
+    lod_clamp: [u32; 2],
+    compare: Option<CompareFunction>,
+    anisotropy_clamp: u16,
+    border_color: Option<SamplerBorderColor>,
+}
+
+impl Default for SamplerKey {
+    fn default() -> Self {
+        SamplerKey::from_descriptor(&SamplerDescriptor::default())
+    }
+}
+
+impl SamplerKey {
+    pub fn new() -> SamplerKey {
+        SamplerKey::default()
+    }
+
+    /// Address mode of all axes
+    pub fn address_mode(mut self, mode: AddressMode) -> Self {
+        self.address_modes = [mode; 3];
+        self
+    }
+
+    /// Address modes of u, v and w
+    pub fn address_modes(mut self, modes: [AddressMode; 3]) -> Self {
+        self.address_modes = modes;
+        self
+    }
+
+    /// Mag, min and mipmap filter
+    pub fn filter(mut self, filter: FilterMode) -> Self {
+        self.filters = [filter; 3];
+        self
+    }
+
+    pub fn mag_filter(mut self, filter: FilterMode) -> Self {
+        self.filters[0] = filter;
+        self
+    }
+
+    pub fn min_filter(mut self, filter: FilterMode) -> Self {
+        self.filters[1] = filter;
+        self
+    }
+
+    pub fn mipmap_filter(mut self, filter: FilterMode) -> Self {
+        self.filters[2] = filter;
+        self
+    }
+
+    pub fn lod_clamp(mut self, min: f32, max: f32) -> Self {
+        self.lod_clamp = [min.to_bits(), max.to_bits()];
+        self
+    }
+
+    /// Make a comparison sampler, e.g. for shadow maps
+    pub fn compare(mut self, compare: CompareFunction) -> Self {
+        self.compare = Some(compare);
+        self
+    }
+
+    /// Maximum anisotropy in 1..=16. Anisotropic filtering requires all filters to be linear, it
+    /// is ignored otherwise.
+    pub fn anisotropy(mut self, anisotropy: u16) -> Self {
+        self.anisotropy_clamp = anisotropy.clamp(1, 16);
+        self
+    }
+
+    /// Border color of [`AddressMode::ClampToBorder`]
+    pub fn border_color(mut self, color: SamplerBorderColor) -> Self {
+        self.border_color = Some(color);
+        self
+    }
+
+    pub fn descriptor<'a>(&self, label: Option<&'a str>) -> SamplerDescriptor<'a> {
+        let all_linear = self.filters.iter().all(|f| *f == FilterMode::Linear);
+        SamplerDescriptor {
+            label,
+            address_mode_u: self.address_modes[0],
+            address_mode_v: self.address_modes[1],
+            address_mode_w: self.address_modes[2],
+            mag_filter: self.filters[0],
+            min_filter: self.filters[1],
+            mipmap_filter: self.filters[2],
+            lod_min_clamp: f32::from_bits(self.lod_clamp[0]),
+            lod_max_clamp: f32::from_bits(self.lod_clamp[1]),
+            compare: self.compare,
+            anisotropy_clamp: if all_linear { self.anisotropy_clamp } else { 1 },
+            border_color: self.border_color,
+        }
+    }
+
+    pub fn from_descriptor(desc: &SamplerDescriptor) -> SamplerKey {
+        SamplerKey {
+            address_modes: [
+                desc.address_mode_u,
+                desc.address_mode_v,
+                desc.address_mode_w,
+            ],
+            filters: [desc.mag_filter, desc.min_filter, desc.mipmap_filter],
+            lod_clamp: [desc.lod_min_clamp.to_bits(), desc.lod_max_clamp.to_bits()],
+            compare: desc.compare,
+            anisotropy_clamp: desc.anisotropy_clamp,
+            border_color: desc.border_color,
+        }
+    }
+}
+
+/// Deduplicates bind group creation by caller supplied [`BindGroupKey`]s, so e.g. ping-ponging
+/// between two textures only ever creates two bind groups. Shared samplers are cached by
+/// [`get_or_create_sampler`](crate::device_context::DeviceContext::get_or_create_sampler).
+///
+/// Entries unused for [`BIND_GROUP_CACHE_MAX_UNUSED_FRAMES`] frames are evicted by the runner.
+///
+/// ```ignore
+/// let bind_group = context.bind_group_cache().bind_group(("update", cells.index() as u64), || {
+///     device.create_bind_group(&BindGroupDescriptor { /* src & dst views */ })
+/// });
+/// ```
+#[derive(Default)]
+pub struct BindGroupCache {
+    bind_groups: Mutex<HashMap<BindGroupKey, CachedBindGroup>>,
+    frame: AtomicU64,
+}
+
+impl BindGroupCache {
+    pub fn new() -> BindGroupCache {
+        BindGroupCache::default()
+    }
+
+    /// Return the bind group cached with `key`, creating it with `create` if needed
+    pub fn bind_group(
+        &self,
+        key: BindGroupKey,
+        create: impl FnOnce() -> BindGroup,
+    ) -> Arc<BindGroup> {
+        let frame = self.frame.load(Ordering::Relaxed);
+        if let Some(cached) = self.bind_groups.lock().unwrap().get_mut(&key) {
+            cached.last_used = frame;
+            return cached.bind_group.clone();
+        }
+        // Create outside the lock, `create` may use the cache
+        let bind_group = Arc::new(create());
+        self.bind_groups
+            .lock()
+            .unwrap()
+            .insert(key, CachedBindGroup {
+                bind_group: bind_group.clone(),
+                last_used: frame,
+            });
+        bind_group
+    }
+
+    /// Drop the bind group cached with `key`, e.g. after its resources have been recreated
+    pub fn remove(&self, key: BindGroupKey) {
+        self.bind_groups.lock().unwrap().remove(&key);
+    }
+
+    /// Number of cached bind groups
+    pub fn len(&self) -> usize {
+        self.bind_groups.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop all cached bind groups
+    pub fn clear(&self) {
+        self.bind_groups.lock().unwrap().clear();
+    }
+
+    /// Evict bind groups that haven't been used for [`BIND_GROUP_CACHE_MAX_UNUSED_FRAMES`]
+    pub(crate) fn end_frame(&self) {
+        let frame = self.frame.fetch_add(1, Ordering::Relaxed) + 1;
+        self.bind_groups
+            .lock()
+            .unwrap()
+            .retain(|_, cached| frame - cached.last_used <= BIND_GROUP_CACHE_MAX_UNUSED_FRAMES);
+    }
+}
+
+/// Default size of [`UploadBelt`] staging chunks
+pub const DEFAULT_UPLOAD_CHUNK_SIZE: BufferAddress = 1 << 20;
+
+struct UploadBeltInner {
+    belt: StagingBelt,
+    chunk_size: BufferAddress,
+    /// Mapped staging buffers of this frame's texture writes, unmapped by finish
+    texture_chunks: Vec<TextureChunk>,
+    encoder: Option<CommandEncoder>,
+}
+
+struct TextureChunk {
+    buffer: Buffer,
+    offset: BufferAddress,
+}
+
+/// Batches buffer and texture uploads. Buffer writes go through a [`StagingBelt`], texture
+/// writes are packed into shared staging chunks with rows padded to
+/// [`wgpu::COPY_BYTES_PER_ROW_ALIGNMENT`] and copied with `copy_buffer_to_texture`. All copies
+/// are recorded into an internal encoder which the Glass runner submits after update, before
+/// rendering, and the belt's chunks are recalled once the frame has been submitted. Writes made
+/// during render are submitted with the next frame.
+///
+/// ```ignore
+/// context
+///     .upload_belt()
+///     .write_buffer(context.device(), &self.vertices, 0, bytemuck::cast_slice(&lines));
+/// ```
+pub struct UploadBelt {
+    inner: Mutex<UploadBeltInner>,
+}
+
+impl Default for UploadBelt {
+    fn default() -> Self {
+        Self::new(DEFAULT_UPLOAD_CHUNK_SIZE)
+    }
+}
+
+impl UploadBelt {
+    /// `chunk_size` should be larger than most single writes, larger writes get chunks of their
+    /// own.
+    pub fn new(chunk_size: BufferAddress) -> UploadBelt {
+        UploadBelt {
+            inner: Mutex::new(UploadBeltInner {
+                belt: StagingBelt::new(chunk_size),
+                chunk_size,
+                texture_chunks: vec![],
+                encoder: None,
+            }),
+        }
+    }
+
+    /// Write data to a buffer at offset. The buffer must have `COPY_DST` usage and the offset and
+    /// data length must be multiples of [`wgpu::COPY_BUFFER_ALIGNMENT`].
+    pub fn write_buffer(
+        &self,
+        device: &Device,
+        target: &Buffer,
+        offset: BufferAddress,
+        data: &[u8],
+    ) {
+        let Some(size) = BufferSize::new(data.len() as u64) else {
+            return;
+        };
+        let mut inner = self.inner.lock().unwrap();
+        let UploadBeltInner {
+            belt,
+            encoder,
+            ..
+        } = &mut *inner;
+        let encoder = encoder.get_or_insert_with(|| Self::create_encoder(device));
+        belt.write_buffer(encoder, target, offset, size, device)
+            .copy_from_slice(data);
+    }
+
+    /// Write tightly packed data to a region of a texture. The texture must have `COPY_DST`
+    /// usage and a color format. Writes of a frame share staging chunks of at least the belt's
+    /// chunk size, which are released once submitted.
+    pub fn write_texture(
+        &self,
+        device: &Device,
+        texture: ImageCopyTexture,
+        size: Extent3d,
+        data: &[u8],
+    ) {
+        if data.is_empty() {
+            return;
+        }
+        let bytes_per_pixel = texture
+            .texture
+            .format()
+            .block_copy_size(None)
+            .expect("Texture write requires a color format");
+        let bytes_per_row = size.width * bytes_per_pixel;
+        let padded_bytes_per_row = padded_bytes_per_row(bytes_per_row);
+        let rows = size.height * size.depth_or_array_layers;
+        assert_eq!(
+            data.len(),
+            (bytes_per_row * rows) as usize,
+            "Texture data must be tightly packed"
+        );
+        let staging_size = padded_bytes_per_row as BufferAddress * rows as BufferAddress;
+
+        let mut inner = self.inner.lock().unwrap();
+        let UploadBeltInner {
+            chunk_size,
+            texture_chunks,
+            encoder,
+            ..
+        } = &mut *inner;
+        // Offsets stay multiples of the row alignment, which every texel block size divides
+        let fits = texture_chunks
+            .last()
+            .is_some_and(|chunk| chunk.offset + staging_size <= chunk.buffer.size());
+        if !fits {
+            texture_chunks.push(TextureChunk {
+                buffer: device.create_buffer(&BufferDescriptor {
+                    label: Some("Upload Belt Texture Chunk"),
+                    size: staging_size.max(*chunk_size),
+                    usage: BufferUsages::COPY_SRC,
+                    mapped_at_creation: true,
+                }),
+                offset: 0,
+            });
+        }
+        let chunk = texture_chunks.last_mut().unwrap();
+        let offset = chunk.offset;
+        {
+            let mut staging = chunk
+                .buffer
+                .slice(offset..offset + staging_size)
+                .get_mapped_range_mut();
+            for (src, dst) in data
+                .chunks_exact(bytes_per_row as usize)
+                .zip(staging.chunks_exact_mut(padded_bytes_per_row as usize))
+            {
+                dst[..src.len()].copy_from_slice(src);
+            }
+        }
+        chunk.offset += staging_size;
+        let encoder = encoder.get_or_insert_with(|| Self::create_encoder(device));
+        encoder.copy_buffer_to_texture(
+            ImageCopyBuffer {
+                buffer: &chunk.buffer,
+                layout: ImageDataLayout {
+                    offset,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            texture,
+            size,
+        );
+    }
+
+    fn create_encoder(device: &Device) -> CommandEncoder {
+        device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Upload Belt Commands"),
+        })
+    }
+
+    /// Finish recorded uploads. Returns `None` if nothing was written.
+    pub(crate) fn finish(&self) -> Option<CommandBuffer> {
+        let mut inner = self.inner.lock().unwrap();
+        let encoder = inner.encoder.take()?;
+        inner.belt.finish();
+        for chunk in inner.texture_chunks.drain(..) {
+            chunk.buffer.unmap();
+        }
+        Some(encoder.finish())
+    }
+
+    /// Reclaim staging chunks once their uploads have been submitted
+    pub(crate) fn recall(&self) {
+        self.inner.lock().unwrap().belt.recall();
+    }
+}
+
+/// A GPU buffer of `T` for geometry rebuilt each frame. Elements are collected on the CPU with
+/// [`GrowableBuffer::clear`] & [`GrowableBuffer::extend`] and written with
+/// [`GrowableBuffer::upload`], which reallocates the buffer to the next power of two capacity
+/// when it no longer fits. The buffer is never shrunk. The size of `T` must be a multiple of
+/// [`wgpu::COPY_BUFFER_ALIGNMENT`].
+pub struct GrowableBuffer<T: Pod> {
+    label: Option<String>,
+    usage: BufferUsages,
+    buffer: Buffer,
+    capacity: u64,
+    data: Vec<T>,
+    uploaded_len: u32,
+}
+
+impl<T: Pod> GrowableBuffer<T> {
+    /// `COPY_DST` is added to usage
+    pub fn new(
+        device: &Device,
+        label: Option<&str>,
+        usage: BufferUsages,
+        capacity: u64,
+    ) -> GrowableBuffer<T> {
+        let usage = usage | BufferUsages::COPY_DST;
+        let capacity = capacity.max(1);
+        GrowableBuffer {
+            label: label.map(|l| l.to_string()),
+            usage,
+            buffer: Self::create_buffer(device, label, usage, capacity),
+            capacity,
+            data: vec![],
+            uploaded_len: 0,
+        }
+    }
+
+    fn create_buffer(
+        device: &Device,
+        label: Option<&str>,
+        usage: BufferUsages,
+        capacity: u64,
+    ) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label,
+            size: capacity * std::mem::size_of::<T>() as u64,
+            usage,
+            mapped_at_creation: false,
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+
+    pub fn push(&mut self, element: T) {
+        self.data.push(element);
+    }
+
+    pub fn extend(&mut self, elements: impl IntoIterator<Item = T>) {
+        self.data.extend(elements);
+    }
+
+    /// Elements collected since the last clear
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Capacity of the GPU buffer in elements
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// Write collected elements to the GPU buffer. Returns true if the buffer was reallocated, in
+    /// which case bind groups referring to it must be recreated.
+    pub fn upload(&mut self, device: &Device, queue: &Queue) -> bool {
+        let len = self.data.len() as u64;
+        let grown = len > self.capacity;
+        if grown {
+            self.capacity = len.next_power_of_two();
+            self.buffer =
+                Self::create_buffer(device, self.label.as_deref(), self.usage, self.capacity);
+        }
+        if !self.data.is_empty() {
+            queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&self.data));
+        }
+        self.uploaded_len = len as u32;
+        grown
+    }
+
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Number of elements written by the last upload, e.g. for a draw range
+    pub fn uploaded_len(&self) -> u32 {
+        self.uploaded_len
+    }
+
+    /// Range of all uploaded elements
+    pub fn range(&self) -> Range<u32> {
+        0..self.uploaded_len
+    }
+
+    /// Slice of the GPU buffer covering a range of elements
+    pub fn slice(&self, elements: Range<u32>) -> BufferSlice<'_> {
+        let size = std::mem::size_of::<T>() as u64;
+        self.buffer
+            .slice(elements.start as u64 * size..elements.end as u64 * size)
+    }
+}
+
+/// Values of WGSL `override` constants by name (or `@id`), set when a pipeline is created
+pub type PipelineConstants = HashMap<String, f64>;
+
+/// Compilation options setting `constants`, for the `compilation_options` of pipeline
+/// descriptors
+pub fn compilation_options(constants: &PipelineConstants) -> PipelineCompilationOptions<'_> {
+    PipelineCompilationOptions {
+        constants,
+        ..Default::default()
+    }
+}
+
+/// Creates compute pipelines with override constants and an optional pipeline cache.
+///
+/// ```ignore
+/// let pipeline = ComputePipelineBuilder::new("simulate", &module, "main")
+///     .layout(&layout)
+///     .constant("WORKGROUP_SIZE", 64.0)
+///     .cache(device_context.pipeline_cache())
+///     .build(device);
+/// ```
+pub struct ComputePipelineBuilder<'a> {
+    label: &'a str,
+    module: &'a wgpu::ShaderModule,
+    entry_point: &'a str,
+    layout: Option<&'a PipelineLayout>,
+    constants: PipelineConstants,
+    cache: Option<&'a PipelineCache>,
+}
+
+impl<'a> ComputePipelineBuilder<'a> {
+    pub fn new(
+        label: &'a str,
+        module: &'a wgpu::ShaderModule,
+        entry_point: &'a str,
+    ) -> ComputePipelineBuilder<'a> {
+        ComputePipelineBuilder {
+            label,
+            module,
+            entry_point,
+            layout: None,
+            constants: PipelineConstants::new(),
+            cache: None,
+        }
+    }
+
+    /// Without a layout one is derived from the shader
+    pub fn layout(mut self, layout: &'a PipelineLayout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    /// Cache to create the pipeline with, e.g. [`crate::device_context::DeviceContext::pipeline_cache`]
+    pub fn cache(mut self, cache: Option<&'a PipelineCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Set an `override` constant. Booleans are 0.0 or 1.0.
+    pub fn constant(mut self, name: &str, value: f64) -> Self {
+        self.constants.insert(name.to_string(), value);
+        self
+    }
+
+    pub fn constants(mut self, constants: &PipelineConstants) -> Self {
+        self.constants
+            .extend(constants.iter().map(|(k, v)| (k.clone(), *v)));
+        self
+    }
+
+    pub fn build(&self, device: &Device) -> ComputePipeline {
+        device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some(self.label),
+            layout: self.layout,
+            module: self.module,
+            entry_point: Some(self.entry_point),
+            compilation_options: compilation_options(&self.constants),
+            cache: self.cache,
+        })
+    }
+}
+
+/// Records a mostly static list of draws once into a [`RenderBundle`], which is replayed each
+/// frame with `RenderPass::execute_bundles`. The formats and sample count must match those of
+/// the passes the bundle is executed in. Bundled pipelines record with their `draw_bundle`
+/// methods.
+///
+/// ```ignore
+/// let bundle = RenderBundleBuilder::new("tiles")
+///     .color(surface_format)
+///     .record(device, |encoder| {
+///         for tile in &tiles {
+///             let bind_group = &tile.bind_group;
+///             quad_pipeline.draw_bundle(encoder, bind_group, tile.pos, view_proj, size, 1.0);
+///         }
+///     });
+/// rpass.execute_bundles([&bundle]);
+/// ```
+pub struct RenderBundleBuilder<'a> {
+    label: Option<&'a str>,
+    color_formats: Vec<Option<TextureFormat>>,
+    depth_stencil: Option<RenderBundleDepthStencil>,
+    sample_count: u32,
+}
+
+impl<'a> RenderBundleBuilder<'a> {
+    pub fn new(label: &'a str) -> RenderBundleBuilder<'a> {
+        RenderBundleBuilder {
+            label: Some(label),
+            color_formats: vec![],
+            depth_stencil: None,
+            sample_count: 1,
+        }
+    }
+
+    /// Add the format of the next color attachment
+    pub fn color(mut self, format: TextureFormat) -> Self {
+        self.color_formats.push(Some(format));
+        self
+    }
+
+    /// Set the depth attachment's format. Bundles write depth, stencil is read only.
+    pub fn depth(mut self, format: TextureFormat) -> Self {
+        self.depth_stencil = Some(RenderBundleDepthStencil {
+            format,
+            depth_read_only: false,
+            stencil_read_only: true,
+        });
+        self
+    }
+
+    pub fn sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+
+    /// Record draws into a new bundle
+    pub fn record<'r>(
+        &self,
+        device: &'r Device,
+        draw: impl FnOnce(&mut RenderBundleEncoder<'r>),
+    ) -> RenderBundle {
+        let mut encoder = device.create_render_bundle_encoder(&RenderBundleEncoderDescriptor {
+            label: self.label,
+            color_formats: &self.color_formats,
+            depth_stencil: self.depth_stencil,
+            sample_count: self.sample_count,
+            multiview: None,
+        });
+        draw(&mut encoder);
+        encoder.finish(&RenderBundleDescriptor {
+            label: self.label,
+        })
+    }
+}
+
+/// Gap between atlas regions so linear filtering doesn't bleed neighbouring images in
+const ATLAS_PADDING: u32 = 1;
+
+/// Handle to an image inserted into a [`TextureAtlas`], valid across re-packs
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AtlasId(usize);
+
+/// Where an image lies in a [`TextureAtlas`]. `uv_offset` and `uv_scale` can be passed directly
+/// to [`QuadPipeline::draw_with_uv`](crate::pipelines::QuadPipeline::draw_with_uv).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AtlasRegion {
+    /// `[x, y, width, height]` in pixels
+    pub rect: [u32; 4],
+    pub uv_offset: [f32; 2],
+    pub uv_scale: [f32; 2],
+}
+
+impl AtlasRegion {
+    /// Region of `rect` in an atlas of `atlas_size` pixels. The quad shader samples
+    /// `(uv + uv_offset) / uv_scale`.
+    fn new(rect: [u32; 4], atlas_size: [u32; 2]) -> AtlasRegion {
+        let [x, y, width, height] = rect.map(|v| v as f32);
+        let width = width.max(1.0);
+        let height = height.max(1.0);
+        AtlasRegion {
+            rect,
+            uv_offset: [x / width, y / height],
+            uv_scale: [atlas_size[0] as f32 / width, atlas_size[1] as f32 / height],
+        }
+    }
+}
+
+struct AtlasShelf {
+    y: u32,
+    height: u32,
+    /// Next free x
+    x: u32,
+}
+
+/// Packs many small rgba8 images into one texture with shelf packing. Images can be inserted at
+/// runtime. When an image doesn't fit, all images are re-packed, growing the texture up to the
+/// device's size limit. Re-packing recreates the texture, so bind groups must be recreated when
+/// [`TextureAtlas::version`] changes.
+///
+/// ```ignore
+/// let id = atlas.insert(device, queue, &sprite).unwrap();
+/// let region = atlas.region(id);
+/// quad.draw_with_uv(rpass, &atlas_bind_group, pos, view_proj, size, region.uv_offset,
+///     region.uv_scale, 1.0);
+/// ```
+pub struct TextureAtlas {
+    texture: crate::texture::Texture,
+    format: TextureFormat,
+    size: [u32; 2],
+    shelves: Vec<AtlasShelf>,
+    /// CPU copies for re-packing, indexed by [`AtlasId`]
+    images: Vec<image::RgbaImage>,
+    rects: Vec<[u32; 4]>,
+    version: u64,
+}
+
+impl TextureAtlas {
+    /// `format` is `Rgba8Unorm` or `Rgba8UnormSrgb`
+    pub fn new(device: &Device, size: [u32; 2], format: TextureFormat) -> TextureAtlas {
+        TextureAtlas {
+            texture: Self::create_texture(device, size, format),
+            format,
+            size,
+            shelves: vec![],
+            images: vec![],
+            rects: vec![],
+            version: 0,
+        }
+    }
+
+    fn create_texture(
+        device: &Device,
+        size: [u32; 2],
+        format: TextureFormat,
+    ) -> crate::texture::Texture {
+        crate::texture::Texture::empty(
+            device,
+            "texture_atlas",
+            Extent3d {
+                width: size[0],
+                height: size[1],
+                depth_or_array_layers: 1,
+            },
+            1,
+            format,
+            wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        )
+    }
+
+    /// Insert an image, re-packing or growing the atlas if it doesn't fit. Returns `None` if the
+    /// image can't fit even in the largest texture the device supports.
+    pub fn insert(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        image: &image::RgbaImage,
+    ) -> Option<AtlasId> {
+        let id = AtlasId(self.images.len());
+        let size = [image.width(), image.height()];
+        if let Some(rect) = Self::allocate(&mut self.shelves, self.size, size) {
+            self.images.push(image.clone());
+            self.rects.push(rect);
+            self.write(queue, id);
+            return Some(id);
+        }
+        self.images.push(image.clone());
+        let max_size = device.limits().max_texture_dimension_2d;
+        let mut atlas_size = self.size;
+        loop {
+            if let Some((shelves, rects)) = Self::pack(&self.images, atlas_size) {
+                self.shelves = shelves;
+                self.rects = rects;
+                break;
+            }
+            if atlas_size[0] >= max_size && atlas_size[1] >= max_size {
+                self.images.pop();
+                return None;
+            }
+            // Grow the shorter side first to keep the atlas roughly square
+            if atlas_size[0] <= atlas_size[1] {
+                atlas_size[0] = (atlas_size[0] * 2).min(max_size);
+            } else {
+                atlas_size[1] = (atlas_size[1] * 2).min(max_size);
+            }
+        }
+        self.size = atlas_size;
+        self.texture = Self::create_texture(device, atlas_size, self.format);
+        self.version += 1;
+        for i in 0..self.images.len() {
+            self.write(queue, AtlasId(i));
+        }
+        Some(id)
+    }
+
+    /// Pack all images from scratch, tallest first
+    fn pack(
+        images: &[image::RgbaImage],
+        atlas_size: [u32; 2],
+    ) -> Option<(Vec<AtlasShelf>, Vec<[u32; 4]>)> {
+        let mut order = (0..images.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| std::cmp::Reverse(images[i].height()));
+        let mut shelves = vec![];
+        let mut rects = vec![[0; 4]; images.len()];
+        for i in order {
+            let size = [images[i].width(), images[i].height()];
+            rects[i] = Self::allocate(&mut shelves, atlas_size, size)?;
+        }
+        Some((shelves, rects))
+    }
+
+    /// Place a rect on the first shelf it fits, opening a new shelf below the last if needed
+    fn allocate(
+        shelves: &mut Vec<AtlasShelf>,
+        atlas_size: [u32; 2],
+        size: [u32; 2],
+    ) -> Option<[u32; 4]> {
+        let padded = [size[0] + ATLAS_PADDING, size[1] + ATLAS_PADDING];
+        if padded[0] > atlas_size[0] {
+            return None;
+        }
+        if let Some(shelf) = shelves
+            .iter_mut()
+            .find(|shelf| padded[1] <= shelf.height && shelf.x + padded[0] <= atlas_size[0])
+        {
+            let rect = [shelf.x, shelf.y, size[0], size[1]];
+            shelf.x += padded[0];
+            return Some(rect);
+        }
+        let y = shelves.last().map_or(0, |shelf| shelf.y + shelf.height);
+        if y + padded[1] > atlas_size[1] {
+            return None;
+        }
+        shelves.push(AtlasShelf {
+            y,
+            height: padded[1],
+            x: padded[0],
+        });
+        Some([0, y, size[0], size[1]])
+    }
+
+    fn write(&self, queue: &Queue, id: AtlasId) {
+        let [x, y, width, height] = self.rects[id.0];
+        self.texture.write_region(
+            queue,
+            [x, y],
+            [width, height],
+            &self.images[id.0],
+            4 * width,
+        );
+    }
+
+    pub fn region(&self, id: AtlasId) -> AtlasRegion {
+        AtlasRegion::new(self.rects[id.0], self.size)
+    }
+
+    pub fn texture(&self) -> &crate::texture::Texture {
+        &self.texture
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        self.size
+    }
+
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    /// Incremented whenever a re-pack recreates the texture
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
 
-    use crate::utils::{ShaderError, ShaderModule, ShaderSource};
+    use crate::utils::{
+        preprocess, substitute_defines, AtlasRegion, AtlasShelf, ShaderDefines, ShaderError,
+        ShaderModule, ShaderSource, TextureAtlas, ATLAS_PADDING,
+    };
 
     #[test]
     fn test_sequentially() {
@@ -527,6 +2192,18 @@ mod tests {
         test_shader_parse_error4();
     }
 
+    #[test]
+    fn test_fallback_module() {
+        let module = ShaderModule::fallback();
+        let entry_points = module
+            .module
+            .entry_points
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(entry_points, vec!["vs_main", "fs_main", "fragment"]);
+    }
+
     fn test_shader_source() {
         let includes_file1 = "includes_1.wgsl";
         let includes_file2 = "includes_2.wgsl";
@@ -735,7 +2412,13 @@ const TEST3: u32 = i32(1);
         let _ = std::fs::remove_file(includes_file3);
 
         assert!(result.is_err());
-        assert!(matches!(result, Err(ShaderError::WgslParseError(_))));
+        let Err(ShaderError::WgslParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        // The error is located in the include instead of the resolved source
+        assert!(error.file_path.ends_with(includes_file2));
+        assert_eq!(error.line, 2);
+        assert_eq!(error.snippet, "const TEST1: u32 = i32(1);");
     }
 
     fn test_shader_parse_error3() {
@@ -801,4 +2484,152 @@ const REDEF: u32 = i32(1);
         assert!(result.is_err());
         assert!(matches!(result, Err(ShaderError::WgslParseError(_))));
     }
+
+    fn defines(pairs: &[(&str, &str)]) -> ShaderDefines {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_preprocess_nested() {
+        let source = "#ifdef A\na\n#ifdef B\nab\n#else\na_not_b\n#endif\n#endif\nend";
+        let result = preprocess(source, &defines(&[("A", "")])).unwrap();
+        assert_eq!(result, "\na\n\n\n\na_not_b\n\n\nend\n");
+        let result = preprocess(source, &defines(&[("A", ""), ("B", "")])).unwrap();
+        assert_eq!(result, "\na\n\nab\n\n\n\n\nend\n");
+    }
+
+    #[test]
+    fn test_preprocess_else_in_inactive_parent() {
+        let source = "#ifdef A\n#ifdef B\nb\n#else\nnot_b\n#endif\n#else\nnot_a\n#endif";
+        let result = preprocess(source, &defines(&[])).unwrap();
+        assert_eq!(result, "\n\n\n\n\n\n\nnot_a\n\n");
+        assert!(!result.contains("not_b"));
+    }
+
+    #[test]
+    fn test_preprocess_comparisons() {
+        let source = "#if MODE == 2\ntwo\n#endif\n#if MODE != 2\nnot_two\n#endif";
+        let two = preprocess(source, &defines(&[("MODE", "2")])).unwrap();
+        assert!(two.contains("two") && !two.contains("not_two"));
+        let three = preprocess(source, &defines(&[("MODE", "3")])).unwrap();
+        assert!(three.contains("not_two"));
+        // An undefined name is never equal to a value
+        let undefined = preprocess(source, &defines(&[])).unwrap();
+        assert!(undefined.contains("not_two"));
+        let flag = preprocess("#if FLAG\non\n#endif", &defines(&[("FLAG", "false")])).unwrap();
+        assert!(!flag.contains("on"));
+    }
+
+    #[test]
+    fn test_preprocess_errors() {
+        let no_defines = defines(&[]);
+        assert_eq!(
+            preprocess("#ifdef A\na", &no_defines),
+            Err("missing #endif".to_string())
+        );
+        assert_eq!(
+            preprocess("a\n#endif", &no_defines),
+            Err("line 2: #endif without #if".to_string())
+        );
+        assert_eq!(
+            preprocess("#ifdef A\n#else\n#else\n#endif", &no_defines),
+            Err("line 3: second #else".to_string())
+        );
+        assert!(preprocess("#if A <= 2\n#endif", &no_defines).is_err());
+    }
+
+    #[test]
+    fn test_preprocess_preserves_line_numbers() {
+        let source = "#define N 4\n#ifdef X\nx\n#endif\nlet n = N;";
+        let result = preprocess(source, &defines(&[])).unwrap();
+        assert_eq!(result.lines().count(), source.lines().count());
+        assert_eq!(result.lines().nth(4), Some("let n = 4;"));
+    }
+
+    #[test]
+    fn test_substitute_defines() {
+        let defines = defines(&[("N", "4"), ("u", "x"), ("y", "z"), ("EMPTY", "")]);
+        assert_eq!(
+            substitute_defines("let a = N + 1u;", &defines),
+            "let a = 4 + 1u;"
+        );
+        assert_eq!(substitute_defines("v.y + y", &defines), "v.y + z");
+        assert_eq!(substitute_defines("EMPTY NN N_2", &defines), "EMPTY NN N_2");
+    }
+
+    fn images(sizes: &[[u32; 2]]) -> Vec<image::RgbaImage> {
+        sizes
+            .iter()
+            .map(|size| image::RgbaImage::new(size[0], size[1]))
+            .collect()
+    }
+
+    fn overlaps(a: [u32; 4], b: [u32; 4]) -> bool {
+        a[0] < b[0] + b[2] && b[0] < a[0] + a[2] && a[1] < b[1] + b[3] && b[1] < a[1] + a[3]
+    }
+
+    #[test]
+    fn test_atlas_allocate_on_shelves() {
+        let mut shelves: Vec<AtlasShelf> = vec![];
+        let size = [64, 64];
+        assert_eq!(
+            TextureAtlas::allocate(&mut shelves, size, [16, 16]),
+            Some([0, 0, 16, 16])
+        );
+        // Shorter images share the first shelf
+        assert_eq!(
+            TextureAtlas::allocate(&mut shelves, size, [16, 8]),
+            Some([16 + ATLAS_PADDING, 0, 16, 8])
+        );
+        // Taller images open a new shelf below
+        assert_eq!(
+            TextureAtlas::allocate(&mut shelves, size, [32, 32]),
+            Some([0, 16 + ATLAS_PADDING, 32, 32])
+        );
+        assert_eq!(shelves.len(), 2);
+    }
+
+    #[test]
+    fn test_atlas_full() {
+        let mut shelves = vec![];
+        let size = [32, 32];
+        assert_eq!(TextureAtlas::allocate(&mut shelves, size, [32, 8]), None);
+        assert!(TextureAtlas::allocate(&mut shelves, size, [20, 20]).is_some());
+        assert_eq!(TextureAtlas::allocate(&mut shelves, size, [20, 20]), None);
+        assert!(TextureAtlas::pack(&images(&[[20, 20], [20, 20]]), size).is_none());
+        assert!(TextureAtlas::pack(&images(&[[20, 20], [20, 20]]), [64, 32]).is_some());
+    }
+
+    #[test]
+    fn test_atlas_pack_without_overlaps() {
+        let images = images(&[[8, 4], [16, 16], [10, 12], [30, 2], [5, 5], [12, 16]]);
+        let size = [48, 48];
+        let (_, rects) = TextureAtlas::pack(&images, size).unwrap();
+        for (i, rect) in rects.iter().enumerate() {
+            assert_eq!([rect[2], rect[3]], [images[i].width(), images[i].height()]);
+            assert!(rect[0] + rect[2] <= size[0] && rect[1] + rect[3] <= size[1]);
+            for other in rects.iter().skip(i + 1) {
+                assert!(!overlaps(*rect, *other), "{:?} overlaps {:?}", rect, other);
+            }
+        }
+        // Tallest first, so the 16 pixel images start the first shelf
+        assert_eq!(rects[1][1], 0);
+        assert_eq!(rects[5][1], 0);
+    }
+
+    #[test]
+    fn test_atlas_region_uv() {
+        let region = AtlasRegion::new([16, 32, 8, 16], [64, 128]);
+        let uv = |tex_coords: [f32; 2]| {
+            [
+                (tex_coords[0] + region.uv_offset[0]) / region.uv_scale[0],
+                (tex_coords[1] + region.uv_offset[1]) / region.uv_scale[1],
+            ]
+        };
+        assert_eq!(uv([0.0, 0.0]), [16.0 / 64.0, 32.0 / 128.0]);
+        assert_eq!(uv([1.0, 1.0]), [24.0 / 64.0, 48.0 / 128.0]);
+    }
 }
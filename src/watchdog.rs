@@ -0,0 +1,242 @@
+use std::{
+    collections::VecDeque,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use flume::{Receiver, RecvTimeoutError, Sender};
+use log::error;
+use wgpu::{AdapterInfo, Queue};
+
+#[derive(Debug, Clone)]
+pub struct WatchdogConfig {
+    /// A frame taking longer than this (CPU side, from update to present) is reported as a hang
+    pub frame_timeout: Duration,
+    /// Submitted GPU work not completing within this is reported as a hang
+    pub gpu_timeout: Duration,
+    /// Write a trace of each hang's diagnostics into this directory
+    pub trace_dir: Option<PathBuf>,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            frame_timeout: Duration::from_secs(2),
+            gpu_timeout: Duration::from_secs(2),
+            trace_dir: None,
+        }
+    }
+}
+
+/// Which part of the frame the runner was in
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FrameStage {
+    Idle,
+    Update,
+    Render,
+    Present,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HangKind {
+    /// The frame didn't finish within [`WatchdogConfig::frame_timeout`]
+    LongFrame,
+    /// Submitted work didn't complete within [`WatchdogConfig::gpu_timeout`], e.g. due to an
+    /// infinite loop in a compute shader
+    GpuTimeout,
+}
+
+/// Diagnostics of a detected hang, passed to [`GlassApp::on_hang`](crate::GlassApp::on_hang)
+#[derive(Debug, Clone)]
+pub struct HangInfo {
+    pub kind: HangKind,
+    pub frame: u64,
+    pub stage: FrameStage,
+    /// How long the frame or the oldest pending submission had been running when detected
+    pub duration: Duration,
+    pub pending_submissions: usize,
+}
+
+struct Progress {
+    frame: u64,
+    frame_start: Option<Instant>,
+    /// When the last frame ended. The device is only polled during frames, so idle time (e.g.
+    /// while minimized) isn't counted against pending submissions.
+    idle_start: Option<Instant>,
+    stage: FrameStage,
+    next_submission: u64,
+    pending_submissions: VecDeque<(u64, Instant)>,
+}
+
+/// Monitors frame progress and GPU queue completion from a separate thread. Hangs are logged
+/// immediately from the watchdog thread, since the main thread may be stuck, and delivered to
+/// the app once the runner gets control back.
+pub struct Watchdog {
+    progress: Arc<Mutex<Progress>>,
+    /// Dropping this wakes the watchdog thread up and stops it
+    stop: Option<Sender<()>>,
+    hangs: Receiver<HangInfo>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    pub fn new(config: WatchdogConfig, adapter_info: AdapterInfo) -> Watchdog {
+        let progress = Arc::new(Mutex::new(Progress {
+            frame: 0,
+            frame_start: None,
+            idle_start: None,
+            stage: FrameStage::Idle,
+            next_submission: 0,
+            pending_submissions: VecDeque::new(),
+        }));
+        let (stop, stopped) = flume::bounded(1);
+        let (sender, hangs) = flume::unbounded();
+        let thread = {
+            let progress = progress.clone();
+            std::thread::spawn(move || watch(config, adapter_info, progress, stopped, sender))
+        };
+        Watchdog {
+            progress,
+            stop: Some(stop),
+            hangs,
+            thread: Some(thread),
+        }
+    }
+
+    pub(crate) fn begin_frame(&self) {
+        let mut progress = self.progress.lock().unwrap();
+        let now = Instant::now();
+        if let Some(idle_start) = progress.idle_start.take() {
+            let idle = now - idle_start;
+            for (_, submitted) in progress.pending_submissions.iter_mut() {
+                *submitted += idle;
+            }
+        }
+        progress.frame += 1;
+        progress.frame_start = Some(now);
+        progress.stage = FrameStage::Update;
+    }
+
+    pub(crate) fn set_stage(&self, stage: FrameStage) {
+        self.progress.lock().unwrap().stage = stage;
+    }
+
+    pub(crate) fn end_frame(&self) {
+        let mut progress = self.progress.lock().unwrap();
+        progress.frame_start = None;
+        progress.idle_start = Some(Instant::now());
+        progress.stage = FrameStage::Idle;
+    }
+
+    /// Track completion of the work submitted to the queue so far
+    pub(crate) fn track_submission(&self, queue: &Queue) {
+        let id = {
+            let mut progress = self.progress.lock().unwrap();
+            let id = progress.next_submission;
+            progress.next_submission += 1;
+            progress.pending_submissions.push_back((id, Instant::now()));
+            id
+        };
+        let progress = self.progress.clone();
+        queue.on_submitted_work_done(move || {
+            // Submissions complete in order
+            progress
+                .lock()
+                .unwrap()
+                .pending_submissions
+                .retain(|(pending, _)| *pending > id);
+        });
+    }
+
+    /// Hangs detected since the last call
+    pub(crate) fn take_hangs(&self) -> Vec<HangInfo> {
+        self.hangs.try_iter().collect()
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn watch(
+    config: WatchdogConfig,
+    adapter_info: AdapterInfo,
+    progress: Arc<Mutex<Progress>>,
+    stopped: Receiver<()>,
+    sender: Sender<HangInfo>,
+) {
+    let poll_interval = (config.frame_timeout.min(config.gpu_timeout) / 4)
+        .clamp(Duration::from_millis(10), Duration::from_millis(250));
+    let mut reported_frame = None;
+    let mut reported_submission = None;
+    while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(poll_interval) {
+        let mut hangs = vec![];
+        {
+            let progress = progress.lock().unwrap();
+            if let Some(start) = progress.frame_start {
+                let duration = start.elapsed();
+                if duration > config.frame_timeout && reported_frame != Some(progress.frame) {
+                    reported_frame = Some(progress.frame);
+                    hangs.push(hang_info(&progress, HangKind::LongFrame, duration));
+                }
+            }
+            // Nothing polls the device between frames, so pending submissions can't complete
+            let pending = progress
+                .pending_submissions
+                .front()
+                .filter(|_| progress.frame_start.is_some());
+            if let Some((id, submitted)) = pending {
+                let duration = submitted.elapsed();
+                if duration > config.gpu_timeout && reported_submission != Some(*id) {
+                    reported_submission = Some(*id);
+                    hangs.push(hang_info(&progress, HangKind::GpuTimeout, duration));
+                }
+            }
+        }
+        for hang in hangs {
+            report(&config, &adapter_info, &hang);
+            let _ = sender.send(hang);
+        }
+    }
+}
+
+fn hang_info(progress: &Progress, kind: HangKind, duration: Duration) -> HangInfo {
+    HangInfo {
+        kind,
+        frame: progress.frame,
+        stage: progress.stage,
+        duration,
+        pending_submissions: progress.pending_submissions.len(),
+    }
+}
+
+fn report(config: &WatchdogConfig, adapter_info: &AdapterInfo, hang: &HangInfo) {
+    let diagnostics = format!(
+        "{:?} detected on frame {} during {:?}: running for {:.2?}, {} pending \
+         submissions\nAdapter: {} ({:?}, driver {} {})",
+        hang.kind,
+        hang.frame,
+        hang.stage,
+        hang.duration,
+        hang.pending_submissions,
+        adapter_info.name,
+        adapter_info.backend,
+        adapter_info.driver,
+        adapter_info.driver_info,
+    );
+    error!("Watchdog: {}", diagnostics);
+    if let Some(dir) = &config.trace_dir {
+        let path = dir.join(format!("hang_frame_{}_{:?}.txt", hang.frame, hang.kind));
+        if let Err(e) = fs::create_dir_all(dir).and_then(|_| fs::write(&path, &diagnostics)) {
+            error!("Failed to write hang trace {}: {}", path.display(), e);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Conversions of cursor positions to the spaces things are rendered in. Cursor positions are in
+//! physical pixels from the top left of the window, as reported by winit and
+//! [`Input::cursor_position`](crate::input::Input::cursor_position). Convert logical positions
+//! with [`logical_to_physical`] first.
+
+use winit::dpi::PhysicalPosition;
+
+use crate::{camera::Camera2D, window::Viewport};
+
+/// How a render target of a fixed resolution is shown on a window surface
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ScalingMode {
+    /// Cover the whole surface, ignoring the aspect ratio
+    Stretch,
+    /// Preserve the aspect ratio with letterbox bars, see [`Viewport::fit`]
+    Fit,
+    /// Like `Fit` with whole number scales, see [`Viewport::fit_pixel_perfect`]
+    FitPixelPerfect,
+}
+
+impl ScalingMode {
+    /// Where a target of `target_size` is drawn on a surface of `surface_size`
+    pub fn viewport(&self, target_size: [u32; 2], surface_size: [u32; 2]) -> Viewport {
+        match self {
+            ScalingMode::Stretch => Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: surface_size[0] as f32,
+                height: surface_size[1] as f32,
+                // Only uniform scales are representable, conversions use the size instead
+                scale: surface_size[0] as f32 / target_size[0].max(1) as f32,
+                canvas_size: target_size,
+            },
+            ScalingMode::Fit => Viewport::fit(target_size, surface_size),
+            ScalingMode::FitPixelPerfect => Viewport::fit_pixel_perfect(target_size, surface_size),
+        }
+    }
+}
+
+/// Convert a logical position, e.g. from a UI library, to physical pixels
+pub fn logical_to_physical(logical: [f32; 2], scale_factor: f64) -> [f32; 2] {
+    logical.map(|v| (v as f64 * scale_factor) as f32)
+}
+
+/// Normalized device coordinates of a cursor position, `-1..1` with y pointing up
+pub fn cursor_to_ndc(cursor: [f32; 2], surface_size: [u32; 2]) -> [f32; 2] {
+    let [width, height] = surface_size.map(|s| s.max(1) as f32);
+    [
+        cursor[0] / width * 2.0 - 1.0,
+        1.0 - cursor[1] / height * 2.0,
+    ]
+}
+
+/// World position under the cursor. The camera's size must be the window's surface size.
+pub fn cursor_to_world(cursor: [f32; 2], camera: &Camera2D) -> [f32; 2] {
+    camera.screen_to_world(cursor)
+}
+
+/// Pixel position in a render target of `target_size` shown on the window with `mode`, origin
+/// at the top left. Returns values outside the target if the cursor is on letterbox bars.
+pub fn cursor_to_render_target(
+    cursor: [f32; 2],
+    surface_size: [u32; 2],
+    target_size: [u32; 2],
+    mode: ScalingMode,
+) -> [f32; 2] {
+    if mode == ScalingMode::Stretch {
+        let [width, height] = surface_size.map(|s| s.max(1) as f32);
+        return [
+            cursor[0] / width * target_size[0] as f32,
+            cursor[1] / height * target_size[1] as f32,
+        ];
+    }
+    mode.viewport(target_size, surface_size)
+        .cursor_to_canvas(PhysicalPosition::new(cursor[0] as f64, cursor[1] as f64))
+}
+
+/// [`cursor_to_render_target`] with y pointing up, origin at the bottom left
+pub fn cursor_to_render_target_y_up(
+    cursor: [f32; 2],
+    surface_size: [u32; 2],
+    target_size: [u32; 2],
+    mode: ScalingMode,
+) -> [f32; 2] {
+    let [x, y] = cursor_to_render_target(cursor, surface_size, target_size, mode);
+    [x, target_size[1] as f32 - y]
+}
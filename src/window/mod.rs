@@ -0,0 +1,828 @@
+use std::{borrow::Cow, sync::Arc};
+
+use log::warn;
+use wgpu::{
+    Color, CompositeAlphaMode, CreateSurfaceError, Device, DownlevelFlags, Extent3d, Operations,
+    PresentMode, RenderPassColorAttachment, Surface, SurfaceConfiguration, SurfaceTexture,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView,
+    TextureViewDescriptor,
+};
+use winit::{
+    dpi::{LogicalSize, PhysicalPosition, PhysicalSize},
+    monitor::MonitorHandle,
+    window::{CursorGrabMode, Fullscreen, Window},
+};
+
+pub mod coords;
+
+use crate::{
+    capture::is_capture_format_supported,
+    device_context::DeviceContext,
+    locale::{message, Message},
+};
+
+/// Title of windows that don't set one. Windows with this title use the localized
+/// [`Message::DefaultWindowTitle`].
+pub const DEFAULT_WINDOW_TITLE: &str = "App";
+
+#[derive(Debug, Copy, Clone)]
+pub struct WindowConfig {
+    pub title: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub pos: WindowPos,
+    pub present_mode: PresentMode,
+    pub alpha_mode: CompositeAlphaMode,
+    /// Surface formats in order of preference. The first format supported by the surface is
+    /// used, see [`GlassWindow::surface_format`].
+    pub surface_format_preference: &'static [TextureFormat],
+    /// Prefer HDR surface formats (see [`GlassWindow::hdr_surface_formats`]) over
+    /// `surface_format_preference` when the surface supports them.
+    pub prefer_hdr: bool,
+    /// Additional formats frame views can be created with. They may only differ from the surface
+    /// format by sRGB-ness and need `DownlevelFlags::SURFACE_VIEW_FORMATS`. The sRGB variant of
+    /// the surface format is allowed when the adapter supports it, see
+    /// [`GlassWindow::create_srgb_frame_view`].
+    pub view_formats: &'static [TextureFormat],
+    /// Number of MSAA samples. When > 1, a multisampled color texture is maintained for the window
+    /// and frames should be rendered into it with the swapchain view as resolve target. Falls back
+    /// to 1 if the surface format doesn't support the sample count.
+    pub msaa_samples: u32,
+    /// Format of a depth texture maintained for the window, sized with the surface. `None` for no
+    /// depth texture.
+    pub depth_format: Option<TextureFormat>,
+    pub desired_maximum_frame_latency: u32,
+    pub max_size: Option<LogicalSize<u32>>,
+    pub min_size: Option<LogicalSize<u32>>,
+    pub exit_on_esc: bool,
+    /// When `present_mode` is `AutoVsync`, prefer `Mailbox` if the surface supports it and fall
+    /// back to `Fifo`, which variable refresh rate displays already drive adaptively. wgpu cannot
+    /// detect variable refresh rate, so this never picks the tearing `FifoRelaxed`; set
+    /// `present_mode` to it explicitly to opt in.
+    pub prefer_adaptive_sync: bool,
+    /// Whether dropped & hovered files are passed to [`GlassApp`](crate::GlassApp)
+    pub drag_and_drop: bool,
+    /// Enable input method editors, so text of languages composed from several keys reaches
+    /// [`GlassApp::text_input`](crate::GlassApp::text_input). Can be toggled with
+    /// [`GlassWindow::set_ime_allowed`], e.g. while a text field is focused.
+    pub ime_allowed: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            title: DEFAULT_WINDOW_TITLE,
+            width: 1920,
+            height: 1080,
+            pos: WindowPos::Centered,
+            present_mode: PresentMode::AutoVsync,
+            alpha_mode: CompositeAlphaMode::Auto,
+            surface_format_preference: GlassWindow::default_surface_format_preference(),
+            prefer_hdr: false,
+            view_formats: &[],
+            msaa_samples: 1,
+            depth_format: None,
+            desired_maximum_frame_latency: 2,
+            exit_on_esc: false,
+            max_size: None,
+            min_size: None,
+            prefer_adaptive_sync: false,
+            drag_and_drop: true,
+            ime_allowed: false,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum WindowPos {
+    Centered,
+    FullScreen,
+    SizedFullScreen,
+    FullScreenBorderless,
+    Maximized,
+    Pos(PhysicalPosition<u32>),
+    /// Place the window on the monitor at `index` of the available monitors. `pos` is relative
+    /// to the monitor's top left corner, `None` centers the window on the monitor.
+    OnMonitor {
+        index: usize,
+        pos: Option<PhysicalPosition<i32>>,
+    },
+    /// Borderless fullscreen on the monitor at `index` of the available monitors
+    FullScreenBorderlessOnMonitor(usize),
+}
+
+/// Information about a connected monitor.
+#[derive(Debug, Clone)]
+pub struct MonitorInfo {
+    pub handle: MonitorHandle,
+    pub name: Option<String>,
+    pub size: PhysicalSize<u32>,
+    pub position: PhysicalPosition<i32>,
+    pub refresh_rate_hz: Option<f64>,
+    pub scale_factor: f64,
+}
+
+impl MonitorInfo {
+    pub fn new(handle: MonitorHandle) -> MonitorInfo {
+        MonitorInfo {
+            name: handle.name(),
+            size: handle.size(),
+            position: handle.position(),
+            refresh_rate_hz: handle
+                .refresh_rate_millihertz()
+                .map(|millihertz| millihertz as f64 / 1000.0),
+            scale_factor: handle.scale_factor(),
+            handle,
+        }
+    }
+}
+
+/// A letterboxed (or pillarboxed) sub rectangle of a window surface that displays a canvas of a
+/// fixed resolution with its aspect ratio preserved. All values are in physical pixels, so they
+/// can be passed to [`wgpu::RenderPass::set_viewport`] directly and compared against winit
+/// cursor positions without scale factor conversions.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Surface pixels per canvas pixel
+    pub scale: f32,
+    pub canvas_size: [u32; 2],
+}
+
+impl Viewport {
+    /// Fit a canvas of `canvas_size` centered inside a surface of `surface_size`.
+    pub fn fit(canvas_size: [u32; 2], surface_size: [u32; 2]) -> Viewport {
+        let scale = (surface_size[0] as f32 / canvas_size[0].max(1) as f32)
+            .min(surface_size[1] as f32 / canvas_size[1].max(1) as f32);
+        Viewport::with_scale(canvas_size, surface_size, scale)
+    }
+
+    /// Like [`Viewport::fit`], but the scale is rounded down to a whole number so each canvas
+    /// pixel covers the same amount of surface pixels. Falls back to [`Viewport::fit`] when the
+    /// surface is smaller than the canvas.
+    pub fn fit_pixel_perfect(canvas_size: [u32; 2], surface_size: [u32; 2]) -> Viewport {
+        let viewport = Viewport::fit(canvas_size, surface_size);
+        if viewport.scale < 1.0 {
+            return viewport;
+        }
+        Viewport::with_scale(canvas_size, surface_size, viewport.scale.floor())
+    }
+
+    fn with_scale(canvas_size: [u32; 2], surface_size: [u32; 2], scale: f32) -> Viewport {
+        let width = canvas_size[0] as f32 * scale;
+        let height = canvas_size[1] as f32 * scale;
+        Viewport {
+            x: ((surface_size[0] as f32 - width) / 2.0).floor(),
+            y: ((surface_size[1] as f32 - height) / 2.0).floor(),
+            width,
+            height,
+            scale,
+            canvas_size,
+        }
+    }
+
+    /// Set this viewport on a render pass
+    pub fn apply(&self, rpass: &mut wgpu::RenderPass) {
+        if self.width > 0.0 && self.height > 0.0 {
+            rpass.set_viewport(self.x, self.y, self.width, self.height, 0.0, 1.0);
+        }
+    }
+
+    /// Convert a physical cursor position to canvas coordinates, origin at the top left. Returns
+    /// values outside the canvas if the cursor is on the letterbox bars.
+    pub fn cursor_to_canvas(&self, cursor: PhysicalPosition<f64>) -> [f32; 2] {
+        if self.scale <= 0.0 {
+            return [0.0; 2];
+        }
+        [
+            (cursor.x as f32 - self.x) / self.scale,
+            (cursor.y as f32 - self.y) / self.scale,
+        ]
+    }
+
+    /// Whether a physical cursor position is inside the viewport
+    pub fn contains(&self, cursor: PhysicalPosition<f64>) -> bool {
+        let [x, y] = self.cursor_to_canvas(cursor);
+        x >= 0.0 && y >= 0.0 && x < self.canvas_size[0] as f32 && y < self.canvas_size[1] as f32
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SurfaceError {
+    /// A timeout was encountered while trying to acquire the next frame.
+    Timeout,
+    /// The underlying surface has changed, and therefore the swap chain must be updated.
+    Outdated,
+    /// The swap chain has been lost and needs to be recreated.
+    Lost,
+    /// There is no more memory left to allocate a new frame.
+    OutOfMemory,
+}
+
+pub struct GlassWindow {
+    window: Arc<Window>,
+    surface: Surface<'static>,
+    supported_present_modes: Vec<PresentMode>,
+    supported_surface_formats: Vec<TextureFormat>,
+    present_mode: PresentMode,
+    alpha_mode: CompositeAlphaMode,
+    surface_format: TextureFormat,
+    surface_usage: TextureUsages,
+    view_formats: Vec<TextureFormat>,
+    /// Whether the adapter allows surface views in other formats
+    surface_view_formats_supported: bool,
+    msaa_samples: u32,
+    msaa_view: Option<TextureView>,
+    depth_format: Option<TextureFormat>,
+    depth_view: Option<TextureView>,
+    desired_maximum_frame_latency: u32,
+    exit_on_esc: bool,
+    drag_and_drop: bool,
+    ime_composing: bool,
+    relative_mouse: bool,
+    has_focus: bool,
+    last_surface_size: [u32; 2],
+}
+
+impl GlassWindow {
+    /// Creates a new [`GlassWindow`] that owns the winit [`Window`](winit::window::Window).
+    pub fn new(
+        context: &DeviceContext,
+        config: WindowConfig,
+        window: Arc<Window>,
+    ) -> Result<GlassWindow, CreateSurfaceError> {
+        let size = [window.inner_size().width, window.inner_size().height];
+        let surface = context.instance().create_surface(window.clone())?;
+        let capabilities = surface.get_capabilities(context.adapter());
+        let hdr_format = if config.prefer_hdr {
+            Self::hdr_surface_formats()
+                .iter()
+                .find(|format| capabilities.formats.contains(format))
+                .copied()
+        } else {
+            None
+        };
+        let surface_format = hdr_format.unwrap_or_else(|| {
+            Self::negotiate_surface_format(config.surface_format_preference, &capabilities.formats)
+        });
+        let supported_surface_formats = capabilities.formats;
+        let msaa_samples = if config.msaa_samples > 1
+            && !context
+                .adapter()
+                .get_texture_format_features(surface_format)
+                .flags
+                .sample_count_supported(config.msaa_samples)
+        {
+            warn!(
+                "{} MSAA samples not supported for {:?}, falling back to 1",
+                config.msaa_samples, surface_format
+            );
+            1
+        } else {
+            config.msaa_samples.max(1)
+        };
+        // Allow copying frames for captures when the surface supports it
+        let surface_usage =
+            TextureUsages::RENDER_ATTACHMENT | (capabilities.usages & TextureUsages::COPY_SRC);
+        let supported_present_modes = capabilities.present_modes;
+        let present_mode =
+            if config.prefer_adaptive_sync && config.present_mode == PresentMode::AutoVsync {
+                Self::adaptive_present_mode(&supported_present_modes)
+            } else {
+                config.present_mode
+            };
+        Ok(GlassWindow {
+            window,
+            surface,
+            supported_present_modes,
+            supported_surface_formats,
+            present_mode,
+            alpha_mode: config.alpha_mode,
+            surface_format,
+            surface_usage,
+            view_formats: config.view_formats.to_vec(),
+            surface_view_formats_supported: context
+                .adapter()
+                .get_downlevel_capabilities()
+                .flags
+                .contains(DownlevelFlags::SURFACE_VIEW_FORMATS),
+            msaa_samples,
+            msaa_view: None,
+            depth_format: config.depth_format,
+            depth_view: None,
+            exit_on_esc: config.exit_on_esc,
+            drag_and_drop: config.drag_and_drop,
+            ime_composing: false,
+            relative_mouse: false,
+            desired_maximum_frame_latency: config.desired_maximum_frame_latency,
+            has_focus: false,
+            last_surface_size: size,
+        })
+    }
+
+    /// Configure surface after resize events
+    pub(crate) fn configure_surface_with_size(&mut self, device: &Device, size: PhysicalSize<u32>) {
+        let config = wgpu::SurfaceConfiguration {
+            usage: self.surface_usage,
+            format: self.surface_format,
+            width: size.width,
+            height: size.height,
+            present_mode: self.present_mode,
+            alpha_mode: self.alpha_mode,
+            view_formats: self.surface_view_formats(),
+            desired_maximum_frame_latency: self.desired_maximum_frame_latency,
+        };
+        self.configure_surface(device, &config);
+        self.last_surface_size = [size.width, size.height];
+    }
+
+    /// Configure surface after window has changed. Use this to reconfigure the surface
+    pub(crate) fn configure_surface(&mut self, device: &Device, config: &SurfaceConfiguration) {
+        self.surface.configure(device, config);
+        self.present_mode = config.present_mode;
+        self.alpha_mode = config.alpha_mode;
+        self.surface_format = config.format;
+        self.surface_usage = config.usage;
+        self.view_formats = config.view_formats.clone();
+        self.desired_maximum_frame_latency = config.desired_maximum_frame_latency;
+        self.last_surface_size = [config.width, config.height];
+        self.msaa_view = self.create_msaa_view(device);
+        self.depth_view = self.create_depth_view(device);
+    }
+
+    fn create_depth_view(&self, device: &Device) -> Option<TextureView> {
+        let format = self.depth_format?;
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("window_depth_texture"),
+            size: Extent3d {
+                width: self.last_surface_size[0],
+                height: self.last_surface_size[1],
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: self.msaa_samples,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        Some(texture.create_view(&TextureViewDescriptor::default()))
+    }
+
+    fn create_msaa_view(&self, device: &Device) -> Option<TextureView> {
+        if self.msaa_samples <= 1 {
+            return None;
+        }
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("msaa_texture"),
+            size: Extent3d {
+                width: self.last_surface_size[0],
+                height: self.last_surface_size[1],
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: self.msaa_samples,
+            dimension: TextureDimension::D2,
+            format: self.surface_format,
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        Some(texture.create_view(&TextureViewDescriptor::default()))
+    }
+
+    pub fn set_position(&self, window_position: WindowPos) {
+        match window_position {
+            WindowPos::Maximized => {
+                self.window.set_fullscreen(None);
+                self.window.set_maximized(true)
+            }
+            WindowPos::FullScreen => {
+                if let Some(monitor) = self.window.current_monitor() {
+                    self.window
+                        .set_fullscreen(Some(Fullscreen::Exclusive(get_best_videomode(&monitor))));
+                }
+            }
+            WindowPos::SizedFullScreen => {
+                if let Some(monitor) = self.window.current_monitor() {
+                    let size = self.window.inner_size();
+                    self.window
+                        .set_fullscreen(Some(Fullscreen::Exclusive(get_fitting_videomode(
+                            &monitor,
+                            size.width,
+                            size.height,
+                        ))));
+                }
+            }
+            WindowPos::FullScreenBorderless => self
+                .window
+                .set_fullscreen(Some(Fullscreen::Borderless(self.window.current_monitor()))),
+            WindowPos::FullScreenBorderlessOnMonitor(index) => {
+                if let Some(monitor) = self.window.available_monitors().nth(index) {
+                    self.window
+                        .set_fullscreen(Some(Fullscreen::Borderless(Some(monitor))));
+                }
+            }
+            WindowPos::Pos(pos) => {
+                self.window.set_fullscreen(None);
+                self.window.set_outer_position(pos)
+            }
+            WindowPos::Centered => {
+                if let Some(monitor) = self.window.current_monitor() {
+                    self.window.set_fullscreen(None);
+                    let size = self.window.inner_size();
+                    self.window.set_outer_position(get_centered_window_position(
+                        &monitor,
+                        size.width,
+                        size.height,
+                    ));
+                }
+            }
+            WindowPos::OnMonitor {
+                index,
+                pos,
+            } => {
+                if let Some(monitor) = self.window.available_monitors().nth(index) {
+                    self.window.set_fullscreen(None);
+                    let size = self.window.inner_size();
+                    self.window
+                        .set_outer_position(get_window_position_on_monitor(
+                            &monitor,
+                            pos,
+                            size.width,
+                            size.height,
+                        ));
+                }
+            }
+        };
+    }
+
+    /// Move the window to given monitor. Fullscreen windows stay borderless fullscreen on the new
+    /// monitor, other windows are centered on it.
+    pub fn move_to_monitor(&self, monitor: &MonitorHandle) {
+        if self.window.fullscreen().is_some() {
+            self.window
+                .set_fullscreen(Some(Fullscreen::Borderless(Some(monitor.clone()))));
+        } else {
+            let size = self.window.inner_size();
+            self.window.set_outer_position(get_centered_window_position(
+                monitor,
+                size.width,
+                size.height,
+            ));
+        }
+    }
+
+    /// Return the monitor the window is currently on
+    pub fn current_monitor(&self) -> Option<MonitorInfo> {
+        self.window.current_monitor().map(MonitorInfo::new)
+    }
+
+    /// Return [`Surface`](wgpu::Surface) belonging to the window
+    pub fn surface(&self) -> &Surface<'_> {
+        &self.surface
+    }
+
+    /// Create a view of the frame with the sRGB variant of the surface format. Use this to render
+    /// with sRGB semantics on surfaces that only offer non-sRGB formats (e.g. `Bgra8Unorm`).
+    /// Adapters without `DownlevelFlags::SURFACE_VIEW_FORMATS` (e.g. GL) get a view in the
+    /// surface format instead.
+    pub fn create_srgb_frame_view(&self, frame: &SurfaceTexture) -> TextureView {
+        let format = if self.surface_view_formats_supported {
+            self.surface_format.add_srgb_suffix()
+        } else {
+            self.surface_format
+        };
+        frame.texture.create_view(&TextureViewDescriptor {
+            format: Some(format),
+            ..Default::default()
+        })
+    }
+
+    /// Return the number of MSAA samples frames are rendered with
+    pub fn msaa_samples(&self) -> u32 {
+        self.msaa_samples
+    }
+
+    /// Return the multisampled color view when MSAA is enabled
+    pub fn msaa_view(&self) -> Option<&TextureView> {
+        self.msaa_view.as_ref()
+    }
+
+    /// Return the format of the window's depth texture
+    pub fn depth_format(&self) -> Option<TextureFormat> {
+        self.depth_format
+    }
+
+    /// Return the window's depth view, if a depth format was configured
+    pub fn depth_view(&self) -> Option<&TextureView> {
+        self.depth_view.as_ref()
+    }
+
+    /// Color attachment for rendering into a frame. With MSAA the multisampled view is rendered
+    /// into and resolved to `frame_view`, otherwise `frame_view` is rendered into directly.
+    pub fn color_attachment<'a>(
+        &'a self,
+        frame_view: &'a TextureView,
+        ops: Operations<Color>,
+    ) -> RenderPassColorAttachment<'a> {
+        match &self.msaa_view {
+            Some(msaa_view) => RenderPassColorAttachment {
+                view: msaa_view,
+                resolve_target: Some(frame_view),
+                ops,
+            },
+            None => RenderPassColorAttachment {
+                view: frame_view,
+                resolve_target: None,
+                ops,
+            },
+        }
+    }
+
+    /// Return formats frame views can be created with
+    pub fn view_formats(&self) -> &[TextureFormat] {
+        &self.view_formats
+    }
+
+    fn surface_view_formats(&self) -> Vec<TextureFormat> {
+        let mut view_formats = self.view_formats.clone();
+        let srgb_format = self.surface_format.add_srgb_suffix();
+        if self.surface_view_formats_supported
+            && srgb_format != self.surface_format
+            && !view_formats.contains(&srgb_format)
+        {
+            view_formats.push(srgb_format);
+        }
+        view_formats
+    }
+
+    /// Return [`Window`](winit::window::Window)
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    /// Return [`PresentMode`](wgpu::PresentMode) belonging to the window
+    pub fn present_mode(&self) -> PresentMode {
+        self.present_mode
+    }
+
+    /// Return [`PresentMode`](wgpu::PresentMode)s supported by the window's surface
+    pub fn supported_present_modes(&self) -> &[PresentMode] {
+        &self.supported_present_modes
+    }
+
+    /// Whether the surface supports `FifoRelaxed` (adaptive vsync), which presents late frames
+    /// immediately and tears instead of waiting for the next vblank. This is not variable
+    /// refresh rate, which wgpu cannot detect.
+    pub fn supports_fifo_relaxed(&self) -> bool {
+        self.supported_present_modes
+            .contains(&PresentMode::FifoRelaxed)
+    }
+
+    /// Refresh rate of the monitor the window is currently on, if known
+    pub fn refresh_rate_hz(&self) -> Option<f64> {
+        self.window
+            .current_monitor()
+            .and_then(|monitor| monitor.refresh_rate_millihertz())
+            .map(|millihertz| millihertz as f64 / 1000.0)
+    }
+
+    /// Pick a tear free present mode for adaptive sync displays. Falls back to `Fifo`, which is
+    /// always supported.
+    fn adaptive_present_mode(supported_present_modes: &[PresentMode]) -> PresentMode {
+        if supported_present_modes.contains(&PresentMode::Mailbox) {
+            PresentMode::Mailbox
+        } else {
+            PresentMode::Fifo
+        }
+    }
+
+    /// Return the [`TextureFormat`](wgpu::TextureFormat) the surface was configured with
+    pub fn surface_format(&self) -> TextureFormat {
+        self.surface_format
+    }
+
+    /// Return [`TextureFormat`](wgpu::TextureFormat)s supported by the window's surface
+    pub fn supported_surface_formats(&self) -> &[TextureFormat] {
+        &self.supported_surface_formats
+    }
+
+    /// Whether the surface supports any of [`GlassWindow::hdr_surface_formats`]. wgpu does not
+    /// expose surface color spaces, so this only tells that an HDR capable format is available.
+    pub fn supports_hdr(&self) -> bool {
+        Self::hdr_surface_formats()
+            .iter()
+            .any(|format| self.supported_surface_formats.contains(format))
+    }
+
+    /// Whether the surface was configured with an HDR format
+    pub fn is_hdr(&self) -> bool {
+        Self::hdr_surface_formats().contains(&self.surface_format)
+    }
+
+    /// Return HDR capable surface [`TextureFormat`](wgpu::TextureFormat)s in order of preference
+    pub fn hdr_surface_formats() -> &'static [TextureFormat] {
+        &[TextureFormat::Rgba16Float, TextureFormat::Rgb10a2Unorm]
+    }
+
+    /// Return default [`TextureFormat`](wgpu::TextureFormat) preference for the surface
+    pub fn default_surface_format_preference() -> &'static [TextureFormat] {
+        &[
+            TextureFormat::Bgra8UnormSrgb,
+            TextureFormat::Rgba8UnormSrgb,
+            TextureFormat::Bgra8Unorm,
+            TextureFormat::Rgba8Unorm,
+        ]
+    }
+
+    /// Return default [`TextureFormat`](wgpu::TextureFormat). Prefer
+    /// [`GlassWindow::surface_format`] once a window exists, the surface may not support this.
+    pub fn default_surface_format() -> TextureFormat {
+        TextureFormat::Bgra8UnormSrgb
+    }
+
+    /// Pick the first preferred format the surface supports. If none are supported, fall back to
+    /// the surface's own preferred format.
+    pub fn negotiate_surface_format(
+        preference: &[TextureFormat],
+        supported: &[TextureFormat],
+    ) -> TextureFormat {
+        preference
+            .iter()
+            .find(|format| supported.contains(format))
+            .or_else(|| supported.first())
+            .copied()
+            .unwrap_or_else(Self::default_surface_format)
+    }
+
+    pub(crate) fn exit_on_esc(&self) -> bool {
+        self.exit_on_esc
+    }
+
+    pub fn drag_and_drop(&self) -> bool {
+        self.drag_and_drop
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.has_focus
+    }
+
+    pub(crate) fn set_focus(&mut self, has_focus: bool) {
+        self.has_focus = has_focus;
+    }
+
+    /// Relative mouse mode grabs and hides the cursor, so the mouse can move indefinitely, e.g.
+    /// to look around with [`Input::mouse_delta`](crate::input::Input::mouse_delta). The grab is
+    /// restored when the window regains focus.
+    pub fn set_relative_mouse(&mut self, relative: bool) {
+        self.relative_mouse = relative;
+        if relative {
+            // Locking isn't supported on all platforms, confining keeps the cursor in the window
+            if let Err(e) = self
+                .window
+                .set_cursor_grab(CursorGrabMode::Locked)
+                .or_else(|_| self.window.set_cursor_grab(CursorGrabMode::Confined))
+            {
+                warn!("Failed to grab cursor: {}", e);
+            }
+        } else if let Err(e) = self.window.set_cursor_grab(CursorGrabMode::None) {
+            warn!("Failed to release cursor: {}", e);
+        }
+        self.window.set_cursor_visible(!relative);
+    }
+
+    pub fn is_relative_mouse(&self) -> bool {
+        self.relative_mouse
+    }
+
+    pub fn set_ime_allowed(&mut self, allowed: bool) {
+        self.window.set_ime_allowed(allowed);
+        if !allowed {
+            self.ime_composing = false;
+        }
+    }
+
+    /// Area of the text cursor in physical pixels, where the IME shows its candidate window
+    pub fn set_ime_cursor_area(&self, pos: [u32; 2], size: [u32; 2]) {
+        self.window.set_ime_cursor_area(
+            PhysicalPosition::new(pos[0], pos[1]),
+            PhysicalSize::new(size[0], size[1]),
+        );
+    }
+
+    /// Whether the IME is composing text. Typed text is only committed once composition ends.
+    pub fn is_ime_composing(&self) -> bool {
+        self.ime_composing
+    }
+
+    pub(crate) fn set_ime_composing(&mut self, composing: bool) {
+        self.ime_composing = composing;
+    }
+
+    /// Whether frames of this window can be captured with
+    /// [`GlassContext::capture_frame`](crate::GlassContext::capture_frame)
+    pub fn supports_capture(&self) -> bool {
+        self.surface_usage.contains(TextureUsages::COPY_SRC)
+            && is_capture_format_supported(self.surface_format)
+    }
+
+    pub fn surface_size(&self) -> [u32; 2] {
+        self.last_surface_size
+    }
+
+    /// A [`Viewport`] fitting a canvas of `canvas_size` into the current surface
+    pub fn viewport(&self, canvas_size: [u32; 2]) -> Viewport {
+        Viewport::fit(canvas_size, self.last_surface_size)
+    }
+}
+
+pub(crate) fn window_title(title: &'static str) -> Cow<'static, str> {
+    if title == DEFAULT_WINDOW_TITLE {
+        message(Message::DefaultWindowTitle)
+    } else {
+        Cow::Borrowed(title)
+    }
+}
+
+pub fn get_centered_window_position(
+    monitor: &MonitorHandle,
+    window_width: u32,
+    window_height: u32,
+) -> PhysicalPosition<i32> {
+    let size = monitor.size();
+    let monitor_pos = monitor.position();
+    let window_size = PhysicalSize::new(window_width, window_height);
+    let lt_x = monitor_pos.x + size.width as i32 / 2 - window_size.width as i32 / 2;
+    let lt_y = monitor_pos.y + size.height as i32 / 2 - window_size.height as i32 / 2;
+    PhysicalPosition::new(lt_x, lt_y)
+}
+
+pub fn get_window_position_on_monitor(
+    monitor: &MonitorHandle,
+    pos: Option<PhysicalPosition<i32>>,
+    window_width: u32,
+    window_height: u32,
+) -> PhysicalPosition<i32> {
+    match pos {
+        Some(pos) => {
+            let monitor_pos = monitor.position();
+            PhysicalPosition::new(monitor_pos.x + pos.x, monitor_pos.y + pos.y)
+        }
+        None => get_centered_window_position(monitor, window_width, window_height),
+    }
+}
+
+pub fn get_fitting_videomode(
+    monitor: &winit::monitor::MonitorHandle,
+    width: u32,
+    height: u32,
+) -> winit::monitor::VideoModeHandle {
+    let mut modes = monitor.video_modes().collect::<Vec<_>>();
+
+    fn abs_diff(a: u32, b: u32) -> u32 {
+        if a > b {
+            return a - b;
+        }
+        b - a
+    }
+
+    modes.sort_by(|a, b| {
+        use std::cmp::Ordering::*;
+        match abs_diff(a.size().width, width).cmp(&abs_diff(b.size().width, width)) {
+            Equal => {
+                match abs_diff(a.size().height, height).cmp(&abs_diff(b.size().height, height)) {
+                    Equal => b
+                        .refresh_rate_millihertz()
+                        .cmp(&a.refresh_rate_millihertz()),
+                    default => default,
+                }
+            }
+            default => default,
+        }
+    });
+
+    modes.first().unwrap().clone()
+}
+
+pub fn get_best_videomode(
+    monitor: &winit::monitor::MonitorHandle,
+) -> winit::monitor::VideoModeHandle {
+    let mut modes = monitor.video_modes().collect::<Vec<_>>();
+    modes.sort_by(|a, b| {
+        use std::cmp::Ordering::*;
+        match b.size().width.cmp(&a.size().width) {
+            Equal => match b.size().height.cmp(&a.size().height) {
+                Equal => b
+                    .refresh_rate_millihertz()
+                    .cmp(&a.refresh_rate_millihertz()),
+                default => default,
+            },
+            default => default,
+        }
+    });
+
+    modes.first().unwrap().clone()
+}